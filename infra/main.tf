@@ -44,6 +44,8 @@ resource "azurerm_cosmosdb_sql_container" "todos_container" {
   account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
   database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
   partition_key_paths = ["/created_by"]
+  # Tombstones of deleted todos expire, other todos are kept.
+  default_ttl = -1
 
   indexing_policy {
     indexing_mode = "consistent"
@@ -54,6 +56,168 @@ resource "azurerm_cosmosdb_sql_container" "todos_container" {
   }
 }
 
+# -------------------------------
+# 4a. Create "feed_tokens" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "feed_tokens_container" {
+  name                = var.cosmos_feed_tokens_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+}
+
+# -------------------------------
+# 4b. Create "app_passwords" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "app_passwords_container" {
+  name                = var.cosmos_app_passwords_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+}
+
+# -------------------------------
+# 4c. Create "personal_access_tokens" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "personal_access_tokens_container" {
+  name                = var.cosmos_personal_access_tokens_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+}
+
+# -------------------------------
+# 4d. Create "users" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "users_container" {
+  name                = var.cosmos_users_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+}
+
+# -------------------------------
+# 4e. Create "audit_log" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "audit_log_container" {
+  name                = var.cosmos_audit_log_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/period"]
+}
+
+# -------------------------------
+# 4f. Create "list_members" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "list_members_container" {
+  name                = var.cosmos_list_members_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/member_id"]
+}
+
+# -------------------------------
+# 4g. Create "share_links" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "share_links_container" {
+  name                = var.cosmos_share_links_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+}
+
+# -------------------------------
+# 4h. Create "idempotency_records" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "idempotency_records_container" {
+  name                = var.cosmos_idempotency_records_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/user_id"]
+  # Records carry their own ttl, set from the configured retention window.
+  default_ttl = -1
+}
+
+# -------------------------------
+# 4i. Create "leases" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "leases_container" {
+  name                = var.cosmos_leases_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+  # Change feed leases carry their own ttl, so leases of removed instances expire.
+  default_ttl = -1
+}
+
+# -------------------------------
+# 4j. Create "webhooks" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "webhooks_container" {
+  name                = var.cosmos_webhooks_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/user_id"]
+}
+
+# -------------------------------
+# 4k. Create "webhook_deliveries" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "webhook_deliveries_container" {
+  name                = var.cosmos_webhook_deliveries_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/user_id"]
+  # Deliveries carry their own ttl, so the delivery log only keeps recent deliveries.
+  default_ttl = -1
+}
+
+# -------------------------------
+# 4l. Create "inbound_address_tokens" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "inbound_address_tokens_container" {
+  name                = var.cosmos_inbound_address_tokens_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/id"]
+}
+
+# -------------------------------
+# 4m. Create "notifications" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "notifications_container" {
+  name                = var.cosmos_notifications_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/user_id"]
+  # Notifications carry their own ttl, they are only kept to send each of them once.
+  default_ttl = -1
+}
+
+# -------------------------------
+# 4n. Create "push_subscriptions" Container
+# -------------------------------
+resource "azurerm_cosmosdb_sql_container" "push_subscriptions_container" {
+  name                = var.cosmos_push_subscriptions_container_name
+  resource_group_name = azurerm_resource_group.todo_app_group.name
+  account_name        = azurerm_cosmosdb_account.todo_app_cosmos.name
+  database_name       = azurerm_cosmosdb_sql_database.todo_app_db.name
+  partition_key_paths = ["/user_id"]
+}
+
 # -------------------------------
 # 5. Create Log Analytics Workspace
 # -------------------------------
@@ -104,6 +268,12 @@ resource "azurerm_linux_web_app" "todo_app_service" {
     "APP__COSMOS__ACCOUNT"                           = azurerm_cosmosdb_account.todo_app_cosmos.name
     "APP__COSMOS__DATABASE_NAME"                     = azurerm_cosmosdb_sql_database.todo_app_db.name
     "APP__COSMOS__PRIMARY_KEY"                       = azurerm_cosmosdb_account.todo_app_cosmos.primary_key
+    "APP__ENVIRONMENT"                               = "production"
+    "APP__AUTH__MODE"                                = "app_service"
+    "APP__CHANGE_FEED__ENABLED"                      = "true"
+    "APP__WEBHOOKS__ENABLED"                         = "true"
+    "APP__LIMITS__TRUSTED_PROXY_HOPS"                = "1"
+    "APP__SHARE_LINKS__SIGNING_KEY"                  = var.share_link_signing_key
 
     # Not used directly by the app, just to link the insights resource to the app service in the portal
     "APPINSIGHTS_INSTRUMENTATIONKEY"        = azurerm_application_insights.todo_app_insights.instrumentation_key
@@ -119,9 +289,9 @@ resource "azurerm_linux_web_app" "todo_app_service" {
 
   auth_settings_v2 {
     auth_enabled           = true
-    require_authentication = true
-    unauthenticated_action = "RedirectToLoginPage"
-    excluded_paths         = ["/", "/static/*", "/healthcheck"]
+    require_authentication = false
+    unauthenticated_action = "AllowAnonymous"
+    excluded_paths         = ["/", "/static/*", "/healthcheck", "/feeds/*", "/dav/*", "/.well-known/*"]
     default_provider       = "google"
 
     google_v2 {
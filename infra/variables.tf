@@ -43,7 +43,83 @@ variable "cosmos_todos_container_name" {
   default     = "todos"
 }
 
+variable "cosmos_feed_tokens_container_name" {
+  description = "Name of the calendar feed tokens container"
+  default     = "feed_tokens"
+}
+
+variable "cosmos_app_passwords_container_name" {
+  description = "Name of the CalDAV app passwords container"
+  default     = "app_passwords"
+}
+
+variable "cosmos_personal_access_tokens_container_name" {
+  description = "Name of the personal access tokens container"
+  default     = "personal_access_tokens"
+}
+
+variable "cosmos_list_members_container_name" {
+  description = "Name of the shared todo list memberships container"
+  default     = "list_members"
+}
+
+variable "cosmos_share_links_container_name" {
+  description = "Name of the public read-only share links container"
+  default     = "share_links"
+}
+
+variable "cosmos_idempotency_records_container_name" {
+  description = "Name of the container replaying responses to retried requests"
+  default     = "idempotency_records"
+}
+
+variable "cosmos_leases_container_name" {
+  description = "Name of the container checkpointing each instance's change feed progress"
+  default     = "leases"
+}
+
+variable "cosmos_webhooks_container_name" {
+  description = "Name of the container storing the webhooks registered by users"
+  default     = "webhooks"
+}
+
+variable "cosmos_webhook_deliveries_container_name" {
+  description = "Name of the container queueing and logging webhook deliveries"
+  default     = "webhook_deliveries"
+}
+
+variable "cosmos_inbound_address_tokens_container_name" {
+  description = "Name of the container storing the tokens of inbound email addresses"
+  default     = "inbound_address_tokens"
+}
+
+variable "cosmos_notifications_container_name" {
+  description = "Name of the container recording the notifications sent to users"
+  default     = "notifications"
+}
+
+variable "cosmos_push_subscriptions_container_name" {
+  description = "Name of the container storing the browsers users subscribed to push messages"
+  default     = "push_subscriptions"
+}
+
+variable "cosmos_users_container_name" {
+  description = "Name of the container recording users seen"
+  default     = "users"
+}
+
+variable "cosmos_audit_log_container_name" {
+  description = "Name of the admin audit log container"
+  default     = "audit_log"
+}
+
 variable "google_provider_authentication_secret" {
   description = "Google provider authentication secret"
   type        = string
 }
+
+variable "share_link_signing_key" {
+  description = "Key signing the tokens of share links, rotating it revokes all of them"
+  type        = string
+  sensitive   = true
+}
@@ -0,0 +1,92 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::header::{self, HeaderMap},
+    web, HttpResponse,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use secrecy::SecretString;
+
+use super::{insert_auth_context, AuthContext, ClientPrincipal};
+use crate::{model::Scope, repositories::AppPasswordRepository, tokens};
+
+/// Authenticates clients that cannot go through App Service authentication (e.g. CalDAV
+/// clients) with HTTP Basic credentials, where the password is one of the user's app passwords.
+pub(crate) async fn app_password_middleware<P>(
+    req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error>
+where
+    P: AppPasswordRepository + 'static,
+{
+    let (username, password) = extract_basic_credentials(req.headers())?;
+
+    let app_passwords_repository = req
+        .app_data::<web::Data<P>>()
+        .expect("App password repository is not registered")
+        .clone();
+
+    let app_password = app_passwords_repository
+        .get_ref()
+        .get_by_hash(tokens::hash(&password))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .filter(|app_password| {
+            app_password
+                .principal_name()
+                .eq_ignore_ascii_case(&username)
+        })
+        .ok_or_else(|| basic_auth_challenge("Invalid username or app password"))?;
+
+    let auth_context = AuthContext {
+        principal_id: app_password.user_id(),
+        principal_name: app_password.principal_name().to_string(),
+        verified_email: app_password.verified_email().map(str::to_string),
+        idp: "app_password".to_string(),
+        claims: ClientPrincipal {
+            auth_typ: "basic".to_string(),
+            name_typ: String::new(),
+            role_typ: String::new(),
+            claims: Vec::new(),
+        },
+        scopes: Scope::ALL.to_vec(),
+        roles: Vec::new(),
+    };
+
+    insert_auth_context(&req, auth_context);
+
+    next.call(req).await
+}
+
+fn extract_basic_credentials(
+    headers: &HeaderMap,
+) -> Result<(String, SecretString), actix_web::Error> {
+    let encoded = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .ok_or_else(|| basic_auth_challenge("Missing basic credentials"))?;
+
+    let decoded = BASE64_STANDARD
+        .decode(encoded.trim().as_bytes())
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or_else(|| basic_auth_challenge("Invalid basic credentials"))?;
+
+    let (username, password) = decoded
+        .split_once(':')
+        .ok_or_else(|| basic_auth_challenge("Invalid basic credentials"))?;
+
+    Ok((username.to_string(), SecretString::from(password)))
+}
+
+fn basic_auth_challenge(reason: &'static str) -> actix_web::Error {
+    let response = HttpResponse::Unauthorized()
+        .append_header((
+            header::WWW_AUTHENTICATE,
+            r#"Basic realm="todo_app", charset="UTF-8""#,
+        ))
+        .finish();
+    InternalError::from_response(reason, response).into()
+}
@@ -0,0 +1,107 @@
+use actix_web::{
+    dev::ServiceRequest,
+    error::{ErrorUnauthorized, InternalError},
+    http::{
+        header::{self, HeaderMap},
+        Method,
+    },
+    HttpResponse,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+
+use super::{AuthContext, ClientPrincipal};
+use crate::model::{Scope, UserId};
+
+/// Same provider as the `default_provider` of the App Service authentication settings.
+const LOGIN_PATH: &str = "/.auth/login/google";
+/// Claim types identity providers use for the user's email address.
+const EMAIL_CLAIMS: [&str; 2] = [
+    "email",
+    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress",
+];
+
+pub(super) fn auth_context(req: &ServiceRequest) -> Result<AuthContext, actix_web::Error> {
+    let headers = req.headers();
+    // App Service lets anonymous requests through so that API clients can authenticate
+    // with tokens, browsers without a session are sent to the login page instead.
+    if !headers.contains_key("X-MS-CLIENT-PRINCIPAL") && is_page_navigation(req) {
+        return Err(login_redirect(req.path()));
+    }
+
+    let client_principal = extract_client_principal(headers)?;
+
+    let principal_id = UserId::from(extract_header(headers, "X-MS-CLIENT-PRINCIPAL-ID")?);
+    let principal_name = extract_header(headers, "X-MS-CLIENT-PRINCIPAL-NAME")?;
+    let idp = extract_header(headers, "X-MS-CLIENT-PRINCIPAL-IDP")?;
+
+    Ok(AuthContext {
+        principal_id,
+        principal_name,
+        verified_email: verified_email(&client_principal),
+        idp,
+        claims: client_principal,
+        scopes: Scope::ALL.to_vec(),
+        roles: Vec::new(),
+    })
+}
+
+/// The email claim, if the identity provider also claims to have verified it.
+fn verified_email(client_principal: &ClientPrincipal) -> Option<String> {
+    let claims = &client_principal.claims;
+    let is_verified = claims
+        .iter()
+        .any(|claim| claim.typ == "email_verified" && claim.val.eq_ignore_ascii_case("true"));
+
+    claims
+        .iter()
+        .find(|claim| EMAIL_CLAIMS.contains(&claim.typ.as_str()))
+        .filter(|_| is_verified)
+        .map(|claim| claim.val.clone())
+}
+
+fn extract_client_principal(headers: &HeaderMap) -> Result<ClientPrincipal, actix_web::Error> {
+    let encoded = headers
+        .get("X-MS-CLIENT-PRINCIPAL")
+        .ok_or_else(|| ErrorUnauthorized("Missing X-MS-CLIENT-PRINCIPAL header"))?
+        .to_str()
+        .map_err(|_| ErrorUnauthorized("Invalid X-MS-CLIENT-PRINCIPAL header"))?;
+
+    let decoded = BASE64_STANDARD
+        .decode(encoded.as_bytes())
+        .map_err(|_| ErrorUnauthorized("Failed to decode client principal"))?;
+
+    let decoded_str = String::from_utf8(decoded)
+        .map_err(|_| ErrorUnauthorized("Invalid UTF-8 in client principal"))?;
+
+    serde_json::from_str::<ClientPrincipal>(&decoded_str)
+        .map_err(|_| ErrorUnauthorized("Invalid JSON in client principal"))
+}
+
+fn extract_header(headers: &HeaderMap, key: &str) -> Result<String, actix_web::Error> {
+    let value = headers
+        .get(key)
+        .ok_or_else(|| ErrorUnauthorized(format!("Missing header: {}", key)))?
+        .to_str()
+        .map_err(|_| ErrorUnauthorized(format!("Invalid header: {}", key)))?
+        .to_string();
+    Ok(value)
+}
+
+fn is_page_navigation(req: &ServiceRequest) -> bool {
+    req.method() == Method::GET
+        && req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/html"))
+}
+
+fn login_redirect(path: &str) -> actix_web::Error {
+    let response = HttpResponse::Found()
+        .append_header((
+            header::LOCATION,
+            format!("{}?post_login_redirect_uri={}", LOGIN_PATH, path),
+        ))
+        .finish();
+    InternalError::from_response("Not logged in", response).into()
+}
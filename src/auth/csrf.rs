@@ -0,0 +1,160 @@
+use actix_web::{
+    body::MessageBody,
+    cookie::{Cookie, SameSite},
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorForbidden,
+    http::{header, Method},
+    HttpMessage,
+};
+use secrecy::ExposeSecret;
+
+use crate::{request_body, tokens};
+
+const CSRF_COOKIE: &str = "csrf_token";
+const CSRF_HEADER: &str = "X-CSRF-Token";
+/// Form field carrying the token in plain HTML form submissions.
+const CSRF_FIELD: &str = "csrf_token";
+
+/// The caller's synchronizer token, to be rendered into pages that submit forms or
+/// send unsafe `fetch` requests.
+#[derive(Clone)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Protects cookie authenticated requests against cross-site request forgery.
+///
+/// Every browser session gets a random token in a `SameSite=Strict` cookie. Unsafe
+/// requests have to echo it in the `X-CSRF-Token` header or the `csrf_token` form field,
+/// and must not come from another site according to `Sec-Fetch-Site` or `Origin`.
+/// WebSocket handshakes cannot carry the token, so only their origin is checked.
+/// Requests with a bearer token are exempt, as browsers never attach one on their own.
+/// They still get a token, which is never stored, so that pages render for them too.
+pub(crate) async fn csrf_middleware(
+    mut req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if has_bearer_token(&req) {
+        req.extensions_mut()
+            .insert(CsrfToken(tokens::generate().expose_secret().to_string()));
+        return next.call(req).await;
+    }
+
+    let cookie_token = req.cookie(CSRF_COOKIE).map(|c| c.value().to_string());
+
+    if is_websocket_handshake(&req) && is_cross_site(&req) {
+        tracing::info!("Rejected cross-site WebSocket handshake");
+        return Err(ErrorForbidden("Cross-site requests are not allowed"));
+    }
+
+    if !is_safe_method(req.method()) {
+        if is_cross_site(&req) {
+            tracing::info!("Rejected cross-site request");
+            return Err(ErrorForbidden("Cross-site requests are not allowed"));
+        }
+
+        let submitted_token = submitted_token(&mut req).await?;
+        let is_valid = match (&cookie_token, &submitted_token) {
+            (Some(expected), Some(submitted)) => constant_time_eq(expected, submitted),
+            _ => false,
+        };
+        if !is_valid {
+            tracing::info!("Rejected request with missing or invalid CSRF token");
+            return Err(ErrorForbidden("Missing or invalid CSRF token"));
+        }
+    }
+
+    let (token, is_new) = match cookie_token {
+        Some(token) => (token, false),
+        None => (tokens::generate().expose_secret().to_string(), true),
+    };
+    req.extensions_mut().insert(CsrfToken(token.clone()));
+    let is_https = req.connection_info().scheme() == "https";
+
+    let mut res = next.call(req).await?;
+    if is_new {
+        let cookie = Cookie::build(CSRF_COOKIE, token)
+            .path("/")
+            .http_only(true)
+            .secure(is_https)
+            .same_site(SameSite::Strict)
+            .finish();
+        res.response_mut().add_cookie(&cookie)?;
+    }
+    Ok(res)
+}
+
+fn has_bearer_token(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .get(..7)
+                .is_some_and(|scheme| scheme.eq_ignore_ascii_case("bearer "))
+        })
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn is_websocket_handshake(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// Prefers the `Sec-Fetch-Site` header modern browsers send, and falls back to comparing
+/// `Origin` with the request's own origin. Requests carrying neither are left to the
+/// token check.
+fn is_cross_site(req: &ServiceRequest) -> bool {
+    if let Some(site) = req.headers().get("Sec-Fetch-Site") {
+        return !matches!(site.to_str(), Ok("same-origin") | Ok("none"));
+    }
+
+    match req.headers().get(header::ORIGIN) {
+        Some(origin) => {
+            let connection_info = req.connection_info();
+            let expected = format!("{}://{}", connection_info.scheme(), connection_info.host());
+            origin.to_str().map_or(true, |origin| origin != expected)
+        }
+        None => false,
+    }
+}
+
+/// Reads the token from the header, or from the body of form submissions.
+async fn submitted_token(req: &mut ServiceRequest) -> Result<Option<String>, actix_web::Error> {
+    if let Some(token) = req.headers().get(CSRF_HEADER) {
+        return Ok(token.to_str().ok().map(str::to_string));
+    }
+
+    if req.content_type() != "application/x-www-form-urlencoded" {
+        return Ok(None);
+    }
+
+    let body = request_body::peek_body(req).await?;
+    let token = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body)
+        .ok()
+        .and_then(|fields| {
+            fields
+                .into_iter()
+                .find(|(name, _)| name == CSRF_FIELD)
+                .map(|(_, value)| value)
+        });
+
+    Ok(token)
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
@@ -0,0 +1,77 @@
+use actix_web::{cookie::Cookie, dev::ServiceRequest};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+use super::{AuthContext, ClientPrincipal};
+use crate::{
+    configuration::DevelopmentAuthSettings,
+    model::{Scope, UserId},
+};
+
+pub const DEVELOPMENT_PRINCIPAL_COOKIE: &str = "dev_principal";
+
+/// Identity picked on the local login form, kept in a plain cookie. Only ever trusted
+/// when development authentication is enabled, which is refused in production.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DevelopmentPrincipal {
+    pub principal_id: String,
+    pub principal_name: String,
+}
+
+impl DevelopmentPrincipal {
+    pub fn to_cookie(&self) -> Cookie<'static> {
+        let value = serde_json::to_vec(self).expect("Failed to serialize development principal");
+        Cookie::build(
+            DEVELOPMENT_PRINCIPAL_COOKIE,
+            BASE64_URL_SAFE_NO_PAD.encode(value),
+        )
+        .path("/")
+        .http_only(true)
+        .finish()
+    }
+
+    fn from_cookie(cookie: &Cookie) -> Option<Self> {
+        let decoded = BASE64_URL_SAFE_NO_PAD.decode(cookie.value()).ok()?;
+        serde_json::from_slice(&decoded).ok()
+    }
+}
+
+impl From<&DevelopmentAuthSettings> for DevelopmentPrincipal {
+    fn from(settings: &DevelopmentAuthSettings) -> Self {
+        Self {
+            principal_id: settings.principal_id.clone(),
+            principal_name: settings.principal_name.clone(),
+        }
+    }
+}
+
+/// Authenticates as the user chosen on the local login form, or as the configured
+/// default user when nobody has logged in. Locally users are whoever they say they are,
+/// so a principal name that is an email address counts as verified.
+pub(super) fn auth_context(
+    req: &ServiceRequest,
+    settings: &DevelopmentAuthSettings,
+) -> AuthContext {
+    let principal = req
+        .cookie(DEVELOPMENT_PRINCIPAL_COOKIE)
+        .and_then(|cookie| DevelopmentPrincipal::from_cookie(&cookie))
+        .unwrap_or_else(|| DevelopmentPrincipal::from(settings));
+
+    AuthContext {
+        principal_id: UserId::from(principal.principal_id),
+        verified_email: principal
+            .principal_name
+            .contains('@')
+            .then(|| principal.principal_name.clone()),
+        principal_name: principal.principal_name,
+        idp: "development".to_string(),
+        claims: ClientPrincipal {
+            auth_typ: "development".to_string(),
+            name_typ: String::new(),
+            role_typ: String::new(),
+            claims: Vec::new(),
+        },
+        scopes: Scope::ALL.to_vec(),
+        roles: Vec::new(),
+    }
+}
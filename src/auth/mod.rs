@@ -0,0 +1,126 @@
+mod app_password;
+mod app_service;
+mod csrf;
+mod development;
+mod oidc;
+mod personal_access_token;
+mod roles;
+mod user_tracking;
+
+pub(crate) use app_password::*;
+pub(crate) use csrf::csrf_middleware;
+pub use csrf::CsrfToken;
+pub use development::*;
+pub use oidc::JwtValidator;
+pub(crate) use personal_access_token::*;
+pub use roles::{RequireRole, Role};
+pub(crate) use user_tracking::*;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    web, HttpMessage,
+};
+use serde::Deserialize;
+
+use crate::{
+    configuration::{AuthSettings, AuthorizationSettings, DevelopmentAuthSettings},
+    model::{Scope, UserId},
+};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClientPrincipal {
+    pub auth_typ: String,
+    pub name_typ: String,
+    pub role_typ: String,
+    pub claims: Vec<Claim>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Claim {
+    pub typ: String,
+    pub val: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub principal_id: UserId,
+    pub principal_name: String,
+    /// The email address the identity provider vouches for. Unlike the principal name,
+    /// which may be any display or user name, only this is matched against email domains.
+    pub verified_email: Option<String>,
+    pub idp: String,
+    pub claims: ClientPrincipal,
+    pub scopes: Vec<Scope>,
+    pub roles: Vec<Role>,
+}
+
+impl AuthContext {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+}
+
+/// The authentication strategy selected in [`AuthSettings`], together with any state
+/// the strategy needs at runtime.
+pub enum Authenticator {
+    AppService,
+    Development(DevelopmentAuthSettings),
+    Oidc(JwtValidator),
+}
+
+impl From<AuthSettings> for Authenticator {
+    fn from(settings: AuthSettings) -> Self {
+        match settings {
+            AuthSettings::AppService => Authenticator::AppService,
+            AuthSettings::Development(settings) => Authenticator::Development(settings),
+            AuthSettings::Oidc(settings) => Authenticator::Oidc(JwtValidator::new(settings)),
+        }
+    }
+}
+
+pub async fn auth_middleware(
+    req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    // Requests carrying a personal access token were already authenticated.
+    if req.extensions().contains::<AuthContext>() {
+        return next.call(req).await;
+    }
+
+    let authenticator = req
+        .app_data::<web::Data<Authenticator>>()
+        .expect("Authenticator is not registered")
+        .clone();
+
+    let auth_context = match authenticator.get_ref() {
+        Authenticator::AppService => app_service::auth_context(&req)?,
+        Authenticator::Development(settings) => development::auth_context(&req, settings),
+        Authenticator::Oidc(validator) => {
+            let token = oidc::extract_bearer_token(req.headers())?;
+            validator.validate(token).await.map_err(|e| {
+                tracing::info!(error = ?e, "Rejected bearer token");
+                oidc::bearer_challenge("Invalid bearer token", Some("invalid_token"))
+            })?
+        }
+    };
+
+    insert_auth_context(&req, auth_context);
+
+    next.call(req).await
+}
+
+/// Makes the authenticated principal, together with the roles granted to it, available
+/// to the rest of the request pipeline.
+fn insert_auth_context(req: &ServiceRequest, mut auth_context: AuthContext) {
+    let authorization_settings = req
+        .app_data::<web::Data<AuthorizationSettings>>()
+        .expect("Authorization settings are not registered");
+
+    auth_context.roles = roles::resolve_roles(&auth_context, authorization_settings);
+    req.extensions_mut().insert(auth_context);
+}
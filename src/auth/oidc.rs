@@ -0,0 +1,228 @@
+use std::time::{Duration, Instant};
+
+use actix_web::{
+    error::InternalError,
+    http::header::{self, HeaderMap},
+    HttpResponse,
+};
+use anyhow::Context;
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde_json::{Map, Value};
+use tokio::sync::RwLock;
+
+use super::{AuthContext, Claim, ClientPrincipal};
+use crate::{
+    configuration::OidcSettings,
+    model::{Scope, UserId},
+};
+
+/// Refetching the key set for an unknown `kid` is rate limited, so that tokens with made-up
+/// key ids cannot be used to hammer the identity provider.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Claims tried in order when looking for a human readable principal name.
+const NAME_CLAIMS: [&str; 3] = ["email", "preferred_username", "name"];
+
+/// Validates bearer JWTs against the keys published by the configured identity provider.
+pub struct JwtValidator {
+    settings: OidcSettings,
+    client: reqwest::Client,
+    jwks: RwLock<Option<CachedJwks>>,
+}
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+impl JwtValidator {
+    pub fn new(settings: OidcSettings) -> Self {
+        Self {
+            settings,
+            client: reqwest::Client::new(),
+            jwks: RwLock::new(None),
+        }
+    }
+
+    #[tracing::instrument(name = "Validate bearer token", skip(self, token))]
+    pub async fn validate(&self, token: &str) -> anyhow::Result<AuthContext> {
+        let header = jsonwebtoken::decode_header(token).context("Malformed token header")?;
+        let kid = header.kid.context("Token header is missing a key id")?;
+        let jwk = self.key(&kid).await?;
+
+        let key = DecodingKey::from_jwk(&jwk).context("Unsupported signing key")?;
+        let mut validation = Validation::new(header.alg);
+        validation.algorithms = allowed_algorithms(&jwk);
+        validation.set_issuer(&[&self.settings.issuer]);
+        validation.set_audience(&[&self.settings.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.leeway = self.settings.leeway_seconds;
+
+        let claims = jsonwebtoken::decode::<Map<String, Value>>(token, &key, &validation)
+            .context("Invalid token")?
+            .claims;
+
+        Ok(auth_context(&self.settings.issuer, claims))
+    }
+
+    async fn key(&self, kid: &str) -> anyhow::Result<Jwk> {
+        let ttl = Duration::from_secs(self.settings.jwks_cache_ttl_seconds);
+
+        if let Some(cached) = self.jwks.read().await.as_ref() {
+            let stale = cached.fetched_at.elapsed() > ttl;
+            let may_refresh = cached.fetched_at.elapsed() > MIN_REFRESH_INTERVAL;
+            match cached.keys.find(kid) {
+                Some(jwk) if !stale => return Ok(jwk.clone()),
+                None if !stale && !may_refresh => {
+                    return Err(anyhow::anyhow!("Unknown signing key: {}", kid))
+                }
+                _ => {}
+            }
+        }
+
+        let mut cache = self.jwks.write().await;
+        // Another request might have refreshed the key set while this one was waiting.
+        let refreshed = cache
+            .as_ref()
+            .is_some_and(|cached| cached.fetched_at.elapsed() <= MIN_REFRESH_INTERVAL);
+        if !refreshed {
+            let keys = self.fetch_jwks().await?;
+            *cache = Some(CachedJwks {
+                keys,
+                fetched_at: Instant::now(),
+            });
+        }
+
+        cache
+            .as_ref()
+            .and_then(|cached| cached.keys.find(kid))
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown signing key: {}", kid))
+    }
+
+    #[tracing::instrument(name = "Fetch JWKS", skip(self), fields(url = %self.settings.jwks_url))]
+    async fn fetch_jwks(&self) -> anyhow::Result<JwkSet> {
+        let body = self
+            .client
+            .get(&self.settings.jwks_url)
+            .send()
+            .await
+            .context("Failed to fetch JWKS")?
+            .error_for_status()
+            .context("JWKS endpoint returned an error")?
+            .bytes()
+            .await
+            .context("Failed to read JWKS")?;
+
+        serde_json::from_slice(&body).context("Invalid JWKS document")
+    }
+}
+
+/// Only asymmetric algorithms of the key's own family are accepted, which rules out
+/// algorithm confusion attacks such as signing with the public key as an HMAC secret.
+fn allowed_algorithms(jwk: &Jwk) -> Vec<Algorithm> {
+    use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve};
+
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => vec![
+            Algorithm::RS256,
+            Algorithm::RS384,
+            Algorithm::RS512,
+            Algorithm::PS256,
+            Algorithm::PS384,
+            Algorithm::PS512,
+        ],
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => vec![Algorithm::ES256],
+            EllipticCurve::P384 => vec![Algorithm::ES384],
+            _ => Vec::new(),
+        },
+        AlgorithmParameters::OctetKeyPair(_) => vec![Algorithm::EdDSA],
+        AlgorithmParameters::OctetKey(_) => Vec::new(),
+    }
+}
+
+fn auth_context(issuer: &str, claims: Map<String, Value>) -> AuthContext {
+    let subject = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let principal_name = NAME_CLAIMS
+        .iter()
+        .find_map(|name| claims.get(*name).and_then(Value::as_str))
+        .unwrap_or(&subject)
+        .to_string();
+    // Some identity providers send `email_verified` as a string.
+    let email_verified = claims
+        .get("email_verified")
+        .is_some_and(|value| *value == true || *value == "true");
+    let verified_email = claims
+        .get("email")
+        .and_then(Value::as_str)
+        .filter(|_| email_verified)
+        .map(str::to_string);
+
+    let claims = claims
+        .into_iter()
+        .flat_map(|(typ, value)| match value {
+            Value::Array(values) => values
+                .into_iter()
+                .map(|value| Claim {
+                    typ: typ.clone(),
+                    val: claim_value(value),
+                })
+                .collect::<Vec<_>>(),
+            value => vec![Claim {
+                typ,
+                val: claim_value(value),
+            }],
+        })
+        .collect();
+
+    AuthContext {
+        principal_id: UserId::from(subject),
+        principal_name,
+        verified_email,
+        idp: issuer.to_string(),
+        claims: ClientPrincipal {
+            auth_typ: "oidc".to_string(),
+            name_typ: "email".to_string(),
+            role_typ: "roles".to_string(),
+            claims,
+        },
+        scopes: Scope::ALL.to_vec(),
+        roles: Vec::new(),
+    }
+}
+
+fn claim_value(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+pub(super) fn extract_bearer_token(headers: &HeaderMap) -> Result<&str, actix_web::Error> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| bearer_challenge("Missing bearer token", None))
+}
+
+pub(super) fn bearer_challenge(reason: &'static str, error: Option<&str>) -> actix_web::Error {
+    let challenge = match error {
+        Some(error) => format!(r#"Bearer realm="todo_app", error="{}""#, error),
+        None => r#"Bearer realm="todo_app""#.to_string(),
+    };
+    let response = HttpResponse::Unauthorized()
+        .append_header((header::WWW_AUTHENTICATE, challenge))
+        .finish();
+    InternalError::from_response(reason, response).into()
+}
@@ -0,0 +1,133 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::{
+        header::{self, HeaderMap},
+        Method,
+    },
+    web, HttpResponse,
+};
+use secrecy::SecretString;
+
+use super::{insert_auth_context, AuthContext, ClientPrincipal};
+use crate::{model::Scope, repositories::PersonalAccessTokenRepository, tokens};
+
+/// Every personal access token starts with this prefix, which tells them apart from
+/// identity provider JWTs and makes leaked tokens easy to spot.
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "todo_pat_";
+
+/// Personal access tokens are meant for scripts working with todos, so they are only
+/// accepted on the todo routes: `/me/todos`, the routes below it and its exports.
+/// Everything else under `/me`, from credentials and sharing to webhooks, push
+/// subscriptions and the inbound email address, stays reserved for interactive sessions,
+/// so a leaked token can neither mint new credentials nor open new ways in or out. Routes
+/// added later are session-only until they are listed here.
+const PERSONAL_ACCESS_TOKEN_PATHS: [&str; 1] = ["/me/todos"];
+
+fn accepts_personal_access_tokens(path: &str) -> bool {
+    PERSONAL_ACCESS_TOKEN_PATHS.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '.']))
+    })
+}
+
+/// Authenticates requests carrying `Authorization: Bearer <personal access token>`.
+/// Requests without one are passed on untouched to the configured authentication.
+pub(crate) async fn personal_access_token_middleware<P>(
+    req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error>
+where
+    P: PersonalAccessTokenRepository + 'static,
+{
+    let Some(token) = extract_personal_access_token(req.headers()) else {
+        return next.call(req).await;
+    };
+
+    let personal_access_tokens_repository = req
+        .app_data::<web::Data<P>>()
+        .expect("Personal access token repository is not registered")
+        .clone();
+
+    let personal_access_token = personal_access_tokens_repository
+        .get_ref()
+        .get_by_hash(tokens::hash(&token))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| {
+            bearer_error(
+                HttpResponse::Unauthorized(),
+                "Invalid personal access token",
+                "invalid_token",
+            )
+        })?;
+
+    if !accepts_personal_access_tokens(req.path()) {
+        return Err(bearer_error(
+            HttpResponse::Forbidden(),
+            "Personal access tokens can only be used on todos",
+            "insufficient_scope",
+        ));
+    }
+
+    let required_scope = required_scope(req.method());
+    if !personal_access_token.scopes().contains(&required_scope) {
+        return Err(bearer_error(
+            HttpResponse::Forbidden(),
+            "Personal access token is missing the required scope",
+            "insufficient_scope",
+        ));
+    }
+
+    let auth_context = AuthContext {
+        principal_id: personal_access_token.user_id(),
+        principal_name: personal_access_token.principal_name().to_string(),
+        verified_email: personal_access_token.verified_email().map(str::to_string),
+        idp: "personal_access_token".to_string(),
+        claims: ClientPrincipal {
+            auth_typ: "bearer".to_string(),
+            name_typ: String::new(),
+            role_typ: String::new(),
+            claims: Vec::new(),
+        },
+        scopes: personal_access_token.scopes().to_vec(),
+        roles: Vec::new(),
+    };
+
+    insert_auth_context(&req, auth_context);
+
+    next.call(req).await
+}
+
+/// Reading needs the `read` scope, anything that may change data needs `write`.
+fn required_scope(method: &Method) -> Scope {
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => Scope::Read,
+        _ => Scope::Write,
+    }
+}
+
+fn extract_personal_access_token(headers: &HeaderMap) -> Option<SecretString> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX))
+        .map(SecretString::from)
+}
+
+fn bearer_error(
+    mut response: actix_web::HttpResponseBuilder,
+    reason: &'static str,
+    error: &str,
+) -> actix_web::Error {
+    let response = response
+        .append_header((
+            header::WWW_AUTHENTICATE,
+            format!(r#"Bearer realm="todo_app", error="{}""#, error),
+        ))
+        .finish();
+    InternalError::from_response(reason, response).into()
+}
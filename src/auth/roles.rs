@@ -0,0 +1,136 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::ErrorForbidden,
+    HttpMessage,
+};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use serde::Serialize;
+
+use super::AuthContext;
+use crate::configuration::{AuthorizationSettings, RoleRule};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Admin,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::User => write!(f, "user"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+pub(super) fn resolve_roles(auth_ctx: &AuthContext, settings: &AuthorizationSettings) -> Vec<Role> {
+    let is_admin = matches_rule(auth_ctx, &settings.admin);
+    let is_user = is_admin
+        || settings
+            .user
+            .as_ref()
+            .is_none_or(|rule| matches_rule(auth_ctx, rule));
+
+    let mut roles = Vec::new();
+    if is_user {
+        roles.push(Role::User);
+    }
+    if is_admin {
+        roles.push(Role::Admin);
+    }
+    roles
+}
+
+fn matches_rule(auth_ctx: &AuthContext, rule: &RoleRule) -> bool {
+    let claims = &auth_ctx.claims.claims;
+    let role_typ = &auth_ctx.claims.role_typ;
+
+    let has_role = !role_typ.is_empty()
+        && claims
+            .iter()
+            .filter(|claim| &claim.typ == role_typ)
+            .any(|claim| rule.roles.contains(&claim.val));
+
+    let has_claim = rule.claims.iter().any(|expected| {
+        claims
+            .iter()
+            .any(|claim| claim.typ == expected.typ && claim.val == expected.val)
+    });
+
+    let has_email_domain = auth_ctx
+        .verified_email
+        .as_deref()
+        .and_then(|email| email.rsplit_once('@'))
+        .is_some_and(|(_, domain)| {
+            rule.email_domains
+                .iter()
+                .any(|expected| expected.eq_ignore_ascii_case(domain))
+        });
+
+    has_role || has_claim || has_email_domain
+}
+
+/// Rejects requests whose principal lacks the role with `403 Forbidden`. It has to be
+/// wrapped inside one of the authentication middlewares.
+pub struct RequireRole(Role);
+
+impl RequireRole {
+    pub fn new(role: Role) -> Self {
+        Self(role)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware {
+            service,
+            role: self.0,
+        }))
+    }
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: S,
+    role: Role,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let has_role = req
+            .extensions()
+            .get::<AuthContext>()
+            .is_some_and(|auth_ctx| auth_ctx.has_role(self.role));
+
+        if !has_role {
+            tracing::info!(role = %self.role, "Denied request without required role");
+            let reason = format!("Missing required role: {}", self.role);
+            return Box::pin(ready(Err(ErrorForbidden(reason))));
+        }
+
+        Box::pin(self.service.call(req))
+    }
+}
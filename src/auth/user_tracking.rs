@@ -0,0 +1,61 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    web, HttpMessage,
+};
+
+use super::AuthContext;
+use crate::{model::User, repositories::UserRepository};
+
+/// How stale a user's last seen timestamp may get before it is written again, so that
+/// not every request costs a write.
+const LAST_SEEN_RESOLUTION: chrono::TimeDelta = chrono::TimeDelta::hours(1);
+
+/// Records the authenticated user, so that admins can list the users who have used the
+/// app. Failures are logged rather than failing the request.
+pub(crate) async fn user_tracking_middleware<U>(
+    req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error>
+where
+    U: UserRepository + 'static,
+{
+    let auth_context = req.extensions().get::<AuthContext>().cloned();
+
+    if let Some(auth_context) = auth_context {
+        let users_repository = req
+            .app_data::<web::Data<U>>()
+            .expect("User repository is not registered")
+            .clone();
+
+        if let Err(e) = record_user(users_repository.get_ref(), auth_context).await {
+            tracing::warn!(error = ?e, "Failed to record user");
+        }
+    }
+
+    next.call(req).await
+}
+
+async fn record_user<U: UserRepository>(
+    users_repository: &U,
+    auth_context: AuthContext,
+) -> anyhow::Result<()> {
+    let user = match users_repository
+        .get_by_id(auth_context.principal_id.clone())
+        .await?
+    {
+        Some(user)
+            if user.principal_name() == auth_context.principal_name
+                && chrono::Utc::now() - user.last_seen_at() < LAST_SEEN_RESOLUTION =>
+        {
+            return Ok(());
+        }
+        Some(mut user) => {
+            user.seen(auth_context.principal_name);
+            user
+        }
+        None => User::new(auth_context.principal_id, auth_context.principal_name),
+    };
+
+    users_repository.save(user).await
+}
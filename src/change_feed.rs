@@ -0,0 +1,213 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{TodoEvent, TodoEventBus},
+    model::Todo,
+};
+
+/// Leases of instances that stopped checkpointing, e.g. after scaling in, expire after a week.
+const LEASE_TTL_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Deleted todos are kept as long as leases, so that an instance resuming from its lease
+/// still reads their deletion.
+pub const TOMBSTONE_TTL_SECONDS: u64 = LEASE_TTL_SECONDS as u64;
+
+/// How much earlier than a read a todo may be stamped as created, and still have been
+/// written too late for that read: the write takes a while, and the clocks of the
+/// instances differ.
+const CREATION_MARGIN: chrono::TimeDelta = chrono::TimeDelta::seconds(60);
+
+/// Changes read from one partition key range of the change feed.
+pub struct ChangeFeedPage {
+    /// The latest versions of the changed documents, in the order they were written.
+    pub documents: Vec<serde_json::Value>,
+    /// Where to continue reading after these documents, `None` when nothing changed.
+    pub continuation: Option<String>,
+}
+
+/// The change feed of the `todos` container. It carries the latest version of every
+/// created, updated or deleted todo, coalescing the writes in between reads. Deleted todos
+/// are tombstones, see [`Todo::delete`]. Like
+/// the processor, implementations run on the actix runtime, which does not need `Send`
+/// futures.
+#[allow(async_fn_in_trait)]
+pub trait ChangeFeedSource {
+    async fn partition_key_ranges(&self) -> anyhow::Result<Vec<String>>;
+    /// Reads the changes after `continuation`. Without a continuation reading starts at the
+    /// current end of the feed, so the page holds no documents, just the continuation.
+    async fn read(
+        &self,
+        partition_key_range_id: &str,
+        continuation: Option<&str>,
+    ) -> anyhow::Result<ChangeFeedPage>;
+}
+
+/// How far an instance has read the change feed, per partition key range.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Lease {
+    pub id: String,
+    pub continuations: BTreeMap<String, String>,
+    /// When the last page of every partition key range was requested.
+    #[serde(default)]
+    pub read_at: BTreeMap<String, chrono::DateTime<chrono::Utc>>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    ttl: i64,
+}
+
+impl Lease {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            continuations: BTreeMap::new(),
+            read_at: BTreeMap::new(),
+            updated_at: chrono::Utc::now(),
+            ttl: LEASE_TTL_SECONDS,
+        }
+    }
+}
+
+#[allow(async_fn_in_trait)]
+pub trait LeaseStore {
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Lease>>;
+    async fn save(&self, lease: Lease) -> anyhow::Result<()>;
+}
+
+/// What the processor does with the todo events it reads from the change feed.
+#[allow(async_fn_in_trait)]
+pub trait ChangeFeedHandler {
+    /// Failing keeps the lease from advancing, so the whole page, including the events
+    /// handled before, is read again on the next poll.
+    async fn handle(&self, event: TodoEvent) -> anyhow::Result<()>;
+}
+
+/// Republishes the todos changed through any instance of the app to the local event bus.
+impl ChangeFeedHandler for Arc<TodoEventBus> {
+    async fn handle(&self, event: TodoEvent) -> anyhow::Result<()> {
+        self.republish(event);
+        Ok(())
+    }
+}
+
+/// Hands the todos changed through any instance of the app to a handler. Every instance
+/// reads the whole feed under a lease of its own, checkpointing after every page, so that
+/// a restarted instance continues where it stopped.
+pub struct ChangeFeedProcessor<S, L, H> {
+    lease_id: String,
+    source: S,
+    leases: L,
+    handler: H,
+}
+
+impl<S, L, H> ChangeFeedProcessor<S, L, H>
+where
+    S: ChangeFeedSource,
+    L: LeaseStore,
+    H: ChangeFeedHandler,
+{
+    pub fn new(lease_id: String, source: S, leases: L, handler: H) -> Self {
+        Self {
+            lease_id,
+            source,
+            leases,
+            handler,
+        }
+    }
+
+    /// Polls the change feed until the process exits. Failed polls are retried on the next
+    /// tick.
+    pub async fn run(self, poll_interval: Duration) {
+        let mut interval = tokio::time::interval(poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll().await {
+                tracing::error!(error = ?e, lease_id = %self.lease_id, "Failed to read the change feed");
+            }
+        }
+    }
+
+    /// Reads all changes since the last checkpoint and hands them to the handler. Partition
+    /// key ranges without a checkpoint, i.e. on the first run or after a split, are read
+    /// from the current end of the feed. Returns the number of handled events.
+    #[tracing::instrument(name = "Poll todo change feed", skip(self), fields(lease_id = %self.lease_id))]
+    pub async fn poll(&self) -> anyhow::Result<usize> {
+        let mut lease = self
+            .leases
+            .get(&self.lease_id)
+            .await?
+            .unwrap_or_else(|| Lease::new(self.lease_id.clone()));
+        let partition_key_ranges = self.source.partition_key_ranges().await?;
+        lease
+            .continuations
+            .retain(|range_id, _| partition_key_ranges.contains(range_id));
+        lease
+            .read_at
+            .retain(|range_id, _| partition_key_ranges.contains(range_id));
+
+        let mut handled = 0;
+        for range_id in partition_key_ranges {
+            loop {
+                let continuation = lease.continuations.get(&range_id).map(String::as_str);
+                let read_at = chrono::Utc::now();
+                let page = self.source.read(&range_id, continuation).await?;
+                let Some(next) = page.continuation else {
+                    break;
+                };
+                let caught_up = page.documents.is_empty();
+                let previous_read_at = lease.read_at.get(&range_id).copied();
+                for document in page.documents {
+                    for event in events(document, previous_read_at) {
+                        self.handler.handle(event).await?;
+                        handled += 1;
+                    }
+                }
+
+                lease.continuations.insert(range_id.clone(), next);
+                lease.read_at.insert(range_id.clone(), read_at);
+                lease.updated_at = chrono::Utc::now();
+                self.leases.save(lease.clone()).await?;
+                if caught_up {
+                    break;
+                }
+            }
+        }
+
+        Ok(handled)
+    }
+}
+
+/// Announces a tombstone as a deletion, and a todo created since the previous read of its
+/// range as a creation, followed by an update when the feed coalesced it with later edits.
+/// Erring towards announcing a creation twice, subscribers tolerate repeated creations but
+/// would miss skipped ones. Documents that are not todos are skipped, rather than blocking
+/// the feed.
+fn events(
+    document: serde_json::Value,
+    previous_read_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Vec<TodoEvent> {
+    let events = serde_json::from_value::<Todo>(document)
+        .map_err(anyhow::Error::from)
+        .and_then(|todo| {
+            if todo.is_deleted() {
+                return Ok(vec![TodoEvent::deleted(todo.created_by(), todo.id())?]);
+            }
+
+            let edited = todo.last_modified() != todo.created_at();
+            let created = match previous_read_at {
+                Some(read_at) => todo.created_at() + CREATION_MARGIN > read_at,
+                None => !edited,
+            };
+            match (created, edited) {
+                (true, true) => Ok(vec![TodoEvent::created(&todo)?, TodoEvent::updated(&todo)?]),
+                (true, false) => Ok(vec![TodoEvent::created(&todo)?]),
+                (false, _) => Ok(vec![TodoEvent::updated(&todo)?]),
+            }
+        });
+
+    events.unwrap_or_else(|e| {
+        tracing::warn!(error = ?e, "Skipping change feed document");
+        Vec::new()
+    })
+}
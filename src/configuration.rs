@@ -1,18 +1,39 @@
-use config::Case;
 use secrecy::SecretString;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Settings {
+    #[serde(default)]
+    pub environment: Environment,
     pub cosmos: CosmosSettings,
     pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    pub authorization: AuthorizationSettings,
+    #[serde(default)]
+    pub limits: LimitSettings,
+    #[serde(default)]
+    pub idempotency: IdempotencySettings,
+    #[serde(default)]
+    pub change_feed: ChangeFeedSettings,
+    #[serde(default)]
+    pub webhooks: WebhookSettings,
+    #[serde(default)]
+    pub inbound_email: InboundEmailSettings,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub push: PushSettings,
+    #[serde(default)]
+    pub share_links: ShareLinkSettings,
 }
 
 impl Settings {
     pub fn load() -> Result<Self, config::ConfigError> {
         let config_dir = std::env::current_dir().expect("Failed to determine current directory");
 
-        config::Config::builder()
+        let settings: Self = config::Config::builder()
             .add_source(config::File::from(config_dir.join("configuration")).required(false))
             .add_source(
                 config::Environment::with_prefix("APP")
@@ -21,8 +42,50 @@ impl Settings {
                     .keep_prefix(false),
             )
             .build()?
-            .try_deserialize()
+            .try_deserialize()?;
+
+        settings.validate()?;
+        Ok(settings)
     }
+
+    pub fn validate(&self) -> Result<(), config::ConfigError> {
+        if self.environment == Environment::Production
+            && matches!(self.auth, AuthSettings::Development(_))
+        {
+            return Err(config::ConfigError::Message(
+                "Development authentication cannot be enabled in production".to_string(),
+            ));
+        }
+
+        if self.environment == Environment::Production && self.share_links.signing_key.is_none() {
+            return Err(config::ConfigError::Message(
+                "Share links need a signing key in production".to_string(),
+            ));
+        }
+
+        for (name, limit) in [
+            ("limits.reads", self.limits.reads),
+            ("limits.writes", self.limits.writes),
+        ] {
+            if limit.burst == 0 || !(limit.per_second.is_finite() && limit.per_second > 0.0) {
+                return Err(config::ConfigError::Message(format!(
+                    "{name} must allow a burst of at least one request and a positive rate"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Unless configured otherwise the application assumes it runs in production, so that a
+/// missing setting can never enable local-only behaviour in a deployment.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Environment {
+    Local,
+    #[default]
+    Production,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -30,6 +93,10 @@ pub struct CosmosSettings {
     pub account: SecretString,
     pub primary_key: SecretString,
     pub database_name: String,
+    /// Overrides the endpoint derived from the account name, e.g. `https://localhost:8081`
+    /// for the Cosmos DB emulator.
+    #[serde(default)]
+    pub endpoint: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -37,3 +104,467 @@ pub struct TelemetrySettings {
     pub log_level: String,
     pub app_insights_connection_string: SecretString,
 }
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum AuthSettings {
+    /// Identity is provided by App Service authentication through `X-MS-CLIENT-PRINCIPAL*` headers.
+    #[default]
+    AppService,
+    /// Every request is authenticated as a fake user, for running the app on a laptop.
+    Development(DevelopmentAuthSettings),
+    /// Identity is taken from bearer JWTs issued by an OpenID Connect provider.
+    Oidc(OidcSettings),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DevelopmentAuthSettings {
+    pub principal_id: String,
+    pub principal_name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct OidcSettings {
+    pub issuer: String,
+    pub audience: String,
+    pub jwks_url: String,
+    #[serde(default = "default_jwks_cache_ttl_seconds")]
+    pub jwks_cache_ttl_seconds: u64,
+    #[serde(default = "default_leeway_seconds")]
+    pub leeway_seconds: u64,
+}
+
+fn default_jwks_cache_ttl_seconds() -> u64 {
+    3600
+}
+
+fn default_leeway_seconds() -> u64 {
+    60
+}
+
+/// Maps authenticated principals to application roles.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AuthorizationSettings {
+    /// When set, only principals matching the rule may use the app. Everybody may otherwise.
+    pub user: Option<RoleRule>,
+    /// Admins are always users as well. Nobody is an admin unless a rule matches.
+    #[serde(default)]
+    pub admin: RoleRule,
+}
+
+/// A principal matches the rule when any one of its conditions holds.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RoleRule {
+    /// Values of the identity provider's role claims, i.e. claims of the principal's `role_typ`.
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub claims: Vec<ClaimRule>,
+    /// Domains of the principal's email address, e.g. `example.com`.
+    #[serde(default)]
+    pub email_domains: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClaimRule {
+    pub typ: String,
+    pub val: String,
+}
+
+/// Keeps single users from exhausting the Cosmos DB request unit budget.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LimitSettings {
+    #[serde(default = "default_read_rate_limit")]
+    pub reads: RateLimitSettings,
+    #[serde(default = "default_write_rate_limit")]
+    pub writes: RateLimitSettings,
+    #[serde(default = "default_max_todos_per_user")]
+    pub max_todos_per_user: u64,
+    /// Reverse proxies in front of the app that append the client address to
+    /// `X-Forwarded-For`, one on App Service. Anonymous clients are told apart by the
+    /// address the outermost of them saw, or by the peer address when there are none.
+    #[serde(default)]
+    pub trusted_proxy_hops: usize,
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self {
+            reads: default_read_rate_limit(),
+            writes: default_write_rate_limit(),
+            max_todos_per_user: default_max_todos_per_user(),
+            trusted_proxy_hops: 0,
+        }
+    }
+}
+
+/// A token bucket holding up to `burst` requests, refilled at `per_second` requests a second.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct RateLimitSettings {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+fn default_read_rate_limit() -> RateLimitSettings {
+    RateLimitSettings {
+        burst: 60,
+        per_second: 5.0,
+    }
+}
+
+fn default_write_rate_limit() -> RateLimitSettings {
+    RateLimitSettings {
+        burst: 20,
+        per_second: 1.0,
+    }
+}
+
+fn default_max_todos_per_user() -> u64 {
+    10_000
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct IdempotencySettings {
+    /// How long responses are kept for replaying retries with the same `Idempotency-Key`.
+    #[serde(default = "default_idempotency_retention_seconds")]
+    pub retention_seconds: u64,
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        Self {
+            retention_seconds: default_idempotency_retention_seconds(),
+        }
+    }
+}
+
+fn default_idempotency_retention_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ShareLinkSettings {
+    /// Signs the tokens of share links. Without one, a key is generated on start and the
+    /// links stop working when the instance restarts, which is only acceptable locally.
+    pub signing_key: Option<SecretString>,
+}
+
+/// Instances scaled out behind App Service learn about each other's todo changes from the
+/// change feed of the `todos` container. A single instance does not need it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChangeFeedSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_change_feed_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Names the lease document the instance checkpoints its progress in.
+    #[serde(default = "default_change_feed_lease_id")]
+    pub lease_id: String,
+}
+
+impl Default for ChangeFeedSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_ms: default_change_feed_poll_interval_ms(),
+            lease_id: default_change_feed_lease_id(),
+        }
+    }
+}
+
+fn default_change_feed_poll_interval_ms() -> u64 {
+    1000
+}
+
+/// App Service gives every instance an id that survives restarts.
+fn default_change_feed_lease_id() -> String {
+    std::env::var("WEBSITE_INSTANCE_ID").unwrap_or_else(|_| "local".to_string())
+}
+
+/// Deliveries of todo events to the webhooks registered by users.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebhookSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Webhook URLs must use HTTPS unless this is turned off, e.g. for local receivers.
+    #[serde(default = "default_true")]
+    pub require_https: bool,
+    /// Webhook URLs may not point at loopback, private or link-local addresses unless this
+    /// is turned on, so that users cannot reach services inside the network through them.
+    #[serde(default)]
+    pub allow_private_networks: bool,
+    #[serde(default = "default_webhook_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Deliveries are given up after this many failed attempts.
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+    /// The wait before the first retry, doubled after every further failed attempt.
+    #[serde(default = "default_webhook_retry_base_seconds")]
+    pub retry_base_seconds: u64,
+    #[serde(default = "default_webhook_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// How long deliveries are kept in the delivery log.
+    #[serde(default = "default_webhook_delivery_retention_seconds")]
+    pub delivery_retention_seconds: u64,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            require_https: true,
+            allow_private_networks: false,
+            poll_interval_ms: default_webhook_poll_interval_ms(),
+            max_attempts: default_webhook_max_attempts(),
+            retry_base_seconds: default_webhook_retry_base_seconds(),
+            timeout_seconds: default_webhook_timeout_seconds(),
+            delivery_retention_seconds: default_webhook_delivery_retention_seconds(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_webhook_poll_interval_ms() -> u64 {
+    5000
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    8
+}
+
+fn default_webhook_retry_base_seconds() -> u64 {
+    30
+}
+
+fn default_webhook_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_webhook_delivery_retention_seconds() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+/// Turns emails sent to a user's inbound address, `todo+<token>@<domain>`, into todos.
+#[derive(Deserialize, Debug, Clone)]
+pub struct InboundEmailSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Where the SMTP listener accepts connections.
+    #[serde(default = "default_inbound_email_listen_address")]
+    pub listen_address: String,
+    /// The domain of the inbound addresses, whose MX record points at the listener.
+    #[serde(default = "default_inbound_email_domain")]
+    pub domain: String,
+    #[serde(default = "default_inbound_email_max_message_bytes")]
+    pub max_message_bytes: usize,
+    #[serde(default = "default_inbound_email_idle_timeout_seconds")]
+    pub idle_timeout_seconds: u64,
+    /// When set, messages must carry an `Authentication-Results` header added by the mail
+    /// server with this id, e.g. the relay in front of the listener, in which DMARC passed.
+    #[serde(default)]
+    pub trusted_authserv_id: Option<String>,
+}
+
+impl Default for InboundEmailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_address: default_inbound_email_listen_address(),
+            domain: default_inbound_email_domain(),
+            max_message_bytes: default_inbound_email_max_message_bytes(),
+            idle_timeout_seconds: default_inbound_email_idle_timeout_seconds(),
+            trusted_authserv_id: None,
+        }
+    }
+}
+
+fn default_inbound_email_listen_address() -> String {
+    "0.0.0.0:2525".to_string()
+}
+
+fn default_inbound_email_domain() -> String {
+    "localhost".to_string()
+}
+
+fn default_inbound_email_max_message_bytes() -> usize {
+    256 * 1024
+}
+
+fn default_inbound_email_idle_timeout_seconds() -> u64 {
+    300
+}
+
+/// Email reminders of todos due soon and the daily digests users opt into.
+#[derive(Deserialize, Debug, Clone)]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_notification_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// How long before their due date todos are reminded of.
+    #[serde(default = "default_notification_reminder_lead_minutes")]
+    pub reminder_lead_minutes: u64,
+    /// The hour of the day, in each user's time zone, from which their digest is sent.
+    #[serde(default = "default_notification_digest_hour")]
+    pub digest_hour: u32,
+    /// Where links in emails point to.
+    #[serde(default = "default_notification_base_url")]
+    pub base_url: String,
+    #[serde(default)]
+    pub smtp: SmtpSettings,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_seconds: default_notification_poll_interval_seconds(),
+            reminder_lead_minutes: default_notification_reminder_lead_minutes(),
+            digest_hour: default_notification_digest_hour(),
+            base_url: default_notification_base_url(),
+            smtp: SmtpSettings::default(),
+        }
+    }
+}
+
+fn default_notification_poll_interval_seconds() -> u64 {
+    60
+}
+
+fn default_notification_reminder_lead_minutes() -> u64 {
+    30
+}
+
+fn default_notification_digest_hour() -> u32 {
+    7
+}
+
+fn default_notification_base_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+/// The mail server notifications are sent through.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SmtpSettings {
+    #[serde(default = "default_smtp_host")]
+    pub host: String,
+    /// Defaults to the usual port of the TLS mode.
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<SecretString>,
+    /// The `From` address of notifications, e.g. `Todo App <todo@example.com>`.
+    #[serde(default = "default_smtp_from")]
+    pub from: String,
+    #[serde(default = "default_smtp_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        Self {
+            host: default_smtp_host(),
+            port: None,
+            tls: SmtpTls::default(),
+            username: None,
+            password: None,
+            from: default_smtp_from(),
+            timeout_seconds: default_smtp_timeout_seconds(),
+        }
+    }
+}
+
+/// How connections to the mail server are encrypted. Unencrypted connections are only
+/// meant for local mail servers.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    /// Upgrades the connection with `STARTTLS`, usually on port 587.
+    #[default]
+    Starttls,
+    /// Connects with TLS from the start, usually on port 465.
+    Tls,
+    None,
+}
+
+fn default_smtp_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_smtp_from() -> String {
+    "Todo App <todo@localhost>".to_string()
+}
+
+fn default_smtp_timeout_seconds() -> u64 {
+    30
+}
+
+/// Web Push messages sent to the browsers of users when their todos fall due.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PushSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The uncompressed P-256 public key of the VAPID key pair, base64url encoded, which
+    /// browsers subscribe with.
+    #[serde(default)]
+    pub vapid_public_key: Option<String>,
+    /// The matching private key, base64url encoded.
+    #[serde(default)]
+    pub vapid_private_key: Option<SecretString>,
+    /// How push services can contact the operator, a `mailto:` or `https:` URL.
+    #[serde(default = "default_push_vapid_subject")]
+    pub vapid_subject: String,
+    #[serde(default = "default_push_poll_interval_seconds")]
+    pub poll_interval_seconds: u64,
+    /// How long push services keep messages for browsers that are offline.
+    #[serde(default = "default_push_message_ttl_seconds")]
+    pub message_ttl_seconds: u64,
+    #[serde(default = "default_push_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Endpoints must use HTTPS unless this is turned off, e.g. for local push services.
+    #[serde(default = "default_true")]
+    pub require_https: bool,
+    /// Endpoints may not point at loopback, private or link-local addresses unless this
+    /// is turned on, as for webhooks.
+    #[serde(default)]
+    pub allow_private_networks: bool,
+}
+
+impl Default for PushSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vapid_public_key: None,
+            vapid_private_key: None,
+            vapid_subject: default_push_vapid_subject(),
+            poll_interval_seconds: default_push_poll_interval_seconds(),
+            message_ttl_seconds: default_push_message_ttl_seconds(),
+            timeout_seconds: default_push_timeout_seconds(),
+            require_https: true,
+            allow_private_networks: false,
+        }
+    }
+}
+
+fn default_push_vapid_subject() -> String {
+    "mailto:admin@localhost".to_string()
+}
+
+fn default_push_poll_interval_seconds() -> u64 {
+    30
+}
+
+fn default_push_message_ttl_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_push_timeout_seconds() -> u64 {
+    10
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use secrecy::SecretString;
+
+use super::{InboxError, Recipient, TodoInbox};
+use crate::{
+    configuration::LimitSettings,
+    events::TodoEventBus,
+    model::{TodoId, UserId},
+    repositories::{InboundAddressTokenRepository, TodoRepository, UserRepository},
+    routes::me::todos::{add_todo, CreateTodoError},
+    tokens,
+};
+
+/// The todo lists of the users, looked up by the tokens of their inbound addresses.
+pub(crate) struct RepositoryInbox<T, K, U> {
+    todos_repository: T,
+    inbound_address_tokens_repository: K,
+    users_repository: U,
+    event_bus: Arc<TodoEventBus>,
+    limits: LimitSettings,
+}
+
+impl<T, K, U> RepositoryInbox<T, K, U> {
+    pub(crate) fn new(
+        todos_repository: T,
+        inbound_address_tokens_repository: K,
+        users_repository: U,
+        event_bus: Arc<TodoEventBus>,
+        limits: LimitSettings,
+    ) -> Self {
+        Self {
+            todos_repository,
+            inbound_address_tokens_repository,
+            users_repository,
+            event_bus,
+            limits,
+        }
+    }
+}
+
+impl<T, K, U> TodoInbox for RepositoryInbox<T, K, U>
+where
+    T: TodoRepository,
+    K: InboundAddressTokenRepository,
+    U: UserRepository,
+{
+    async fn find_recipient(&self, token: &str) -> anyhow::Result<Option<Recipient>> {
+        let token_hash = tokens::hash(&SecretString::from(token.to_string()));
+        let Some(inbound_address_token) = self
+            .inbound_address_tokens_repository
+            .get_by_hash(token_hash)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let user = self
+            .users_repository
+            .get_by_id(inbound_address_token.user_id())
+            .await?;
+        Ok(user.map(|user| Recipient {
+            owner_id: user.id(),
+            email: user.principal_name().to_string(),
+        }))
+    }
+
+    async fn add_todo(
+        &self,
+        owner_id: UserId,
+        content: String,
+        notes: Option<String>,
+    ) -> Result<TodoId, InboxError> {
+        add_todo(
+            &self.todos_repository,
+            &self.event_bus,
+            &self.limits,
+            owner_id,
+            content,
+            notes,
+        )
+        .await
+        .map_err(|e| match e {
+            CreateTodoError::ValidationError(e) => InboxError::Invalid(e.to_string()),
+            CreateTodoError::TooManyTodos(max_todos) => InboxError::Full(max_todos),
+            CreateTodoError::ListAccess(e) => InboxError::Unexpected(e.into()),
+            CreateTodoError::UnexpectedError(e) => InboxError::Unexpected(e),
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use mail_parser::MessageParser;
+
+use super::Recipient;
+use crate::{
+    configuration::InboundEmailSettings,
+    model::{TodoContent, TodoNotes},
+};
+
+/// Prefixes mail clients add to the subjects of forwarded messages and replies.
+const SUBJECT_PREFIXES: [&str; 4] = ["fwd:", "fw:", "re:", "aw:"];
+
+/// A todo read from an email.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EmailTodo {
+    pub content: String,
+    pub notes: Option<String>,
+}
+
+/// Reads the todo from an email to the recipient's inbound address: the subject becomes
+/// its content and the text body, up to the signature, its notes. Only emails the owner
+/// sent themselves are accepted. Errors tell the sender why the email was rejected.
+pub fn read_email_todo(
+    raw_message: &[u8],
+    envelope_sender: &str,
+    recipient: &Recipient,
+    settings: &InboundEmailSettings,
+) -> Result<EmailTodo, String> {
+    let message = MessageParser::default()
+        .parse(raw_message)
+        .ok_or_else(|| "The message could not be parsed".to_string())?;
+
+    let from = message
+        .from()
+        .and_then(|from| from.first())
+        .and_then(|from| from.address())
+        .unwrap_or_default();
+    if !from.eq_ignore_ascii_case(&recipient.email)
+        || !envelope_sender.eq_ignore_ascii_case(&recipient.email)
+    {
+        return Err("Only the owner of the address can send todos to it".to_string());
+    }
+    if let Some(authserv_id) = &settings.trusted_authserv_id {
+        // Relays add their results above the headers of the message, so the first results
+        // of the trusted server are the ones it added rather than forged ones.
+        let results = message
+            .headers_raw()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("Authentication-Results"))
+            .map(|(_, value)| value)
+            .find(|value| authentication_results_id(value).eq_ignore_ascii_case(authserv_id));
+        if !results.is_some_and(dmarc_passed) {
+            return Err("The sender could not be verified".to_string());
+        }
+    }
+
+    let content = strip_subject_prefixes(message.subject().unwrap_or_default());
+    if content.is_empty() {
+        return Err("The subject becomes the todo and cannot be blank".to_string());
+    }
+    let notes = message
+        .body_text(0)
+        .map(|body| strip_signature(&body).trim().replace("\r\n", "\n"))
+        .filter(|notes| !notes.is_empty())
+        .map(|notes| truncate(&notes, TodoNotes::MAX_LEN).to_string());
+
+    Ok(EmailTodo {
+        content: truncate(content, TodoContent::MAX_LEN).to_string(),
+        notes,
+    })
+}
+
+/// The `authserv-id` an `Authentication-Results` header starts with, without its version.
+fn authentication_results_id(value: &str) -> &str {
+    let id = value.split(';').next().unwrap_or_default().trim();
+    id.split_whitespace().next().unwrap_or_default()
+}
+
+fn dmarc_passed(value: &str) -> bool {
+    value.split(';').skip(1).any(|result| {
+        result
+            .split_whitespace()
+            .next()
+            .is_some_and(|method| method.eq_ignore_ascii_case("dmarc=pass"))
+    })
+}
+
+fn strip_subject_prefixes(mut subject: &str) -> &str {
+    loop {
+        subject = subject.trim();
+        let Some(prefix) = SUBJECT_PREFIXES.iter().find(|prefix| {
+            subject
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        }) else {
+            return subject;
+        };
+        subject = &subject[prefix.len()..];
+    }
+}
+
+/// Cuts the body at the signature delimiter, a line of `-- `.
+fn strip_signature(body: &str) -> &str {
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == "-- " {
+            return &body[..offset];
+        }
+        offset += line.len();
+    }
+    body
+}
+
+/// Shortens the text to at most `max_len` bytes, without splitting a character.
+fn truncate(text: &str, max_len: usize) -> &str {
+    if text.len() <= max_len {
+        return text;
+    }
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].trim_end()
+}
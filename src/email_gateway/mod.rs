@@ -0,0 +1,51 @@
+mod inbox;
+mod message;
+mod smtp;
+
+pub(crate) use inbox::*;
+pub use message::*;
+pub use smtp::*;
+
+pub use crate::model::{TodoId, UserId};
+
+/// Inbound addresses are `todo+<token>@<domain>`.
+const ADDRESS_PREFIX: &str = "todo+";
+
+/// The inbound address of the user with the token.
+pub fn inbound_address(token: &str, domain: &str) -> String {
+    format!("{}{}@{}", ADDRESS_PREFIX, token, domain)
+}
+
+/// The owner of an inbound address.
+#[derive(Debug, Clone)]
+pub struct Recipient {
+    pub owner_id: UserId,
+    /// The email address the owner signs in with, the only sender accepted for them.
+    pub email: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InboxError {
+    /// The message can never become a todo, e.g. because the subject is too long.
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Todo lists are limited to {0} todos")]
+    Full(u64),
+    #[error("Something went wrong")]
+    Unexpected(#[source] anyhow::Error),
+}
+
+/// Where the todos of emails end up. Like the change feed, implementations run on the
+/// actix runtime, which does not need `Send` futures.
+#[allow(async_fn_in_trait)]
+pub trait TodoInbox {
+    /// The owner of the inbound address with the token, if there is one.
+    async fn find_recipient(&self, token: &str) -> anyhow::Result<Option<Recipient>>;
+    /// Adds a todo to the owner's list, within the same limits as todos created on the web.
+    async fn add_todo(
+        &self,
+        owner_id: UserId,
+        content: String,
+        notes: Option<String>,
+    ) -> Result<TodoId, InboxError>;
+}
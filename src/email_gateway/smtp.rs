@@ -0,0 +1,355 @@
+use std::{rc::Rc, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+
+use super::{read_email_todo, InboxError, Recipient, TodoInbox, ADDRESS_PREFIX};
+use crate::configuration::InboundEmailSettings;
+
+/// Connections beyond this many are turned away until others close.
+const MAX_CONNECTIONS: usize = 100;
+/// Commands are at most 512 characters long, see RFC 5321, with room for extensions.
+const MAX_COMMAND_LEN: usize = 2048;
+/// Clients sending this many invalid commands are disconnected.
+const MAX_ERRORS: usize = 10;
+
+/// Accepts emails to inbound addresses over SMTP and adds their todos to the owners'
+/// lists. Nothing is ever relayed, every recipient must be an inbound address. Every
+/// message takes a single recipient, further ones are deferred by the client to another
+/// transaction, so that a message is either wholly accepted or rejected.
+pub struct EmailGateway<I> {
+    inbox: I,
+    settings: InboundEmailSettings,
+}
+
+struct Transaction {
+    sender: String,
+    recipient: Option<Recipient>,
+}
+
+enum Line {
+    Complete(Vec<u8>),
+    TooLong,
+    Closed,
+}
+
+enum Data {
+    Complete(Vec<u8>),
+    TooLarge,
+    Closed,
+}
+
+impl<I> EmailGateway<I>
+where
+    I: TodoInbox + 'static,
+{
+    pub fn new(inbox: I, settings: InboundEmailSettings) -> Self {
+        Self { inbox, settings }
+    }
+
+    /// Serves connections until the process exits. Like the other background tasks, the
+    /// connections are served on the actix runtime.
+    pub async fn run(self, listener: TcpListener) {
+        let gateway = Rc::new(self);
+        let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+        loop {
+            let (mut stream, peer) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to accept SMTP connection");
+                    continue;
+                }
+            };
+
+            let gateway = gateway.clone();
+            let connections = connections.clone();
+            actix_web::rt::spawn(async move {
+                let Ok(_permit) = connections.try_acquire_owned() else {
+                    let _ = stream
+                        .write_all(b"421 4.3.2 Too many connections, try again later\r\n")
+                        .await;
+                    return;
+                };
+                if let Err(e) = gateway.serve(stream).await {
+                    tracing::warn!(error = ?e, %peer, "SMTP connection failed");
+                }
+            });
+        }
+    }
+
+    #[tracing::instrument(name = "Serve SMTP connection", skip(self, stream), fields(peer = ?stream.peer_addr().ok()))]
+    async fn serve(&self, stream: TcpStream) -> std::io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let domain = &self.settings.domain;
+
+        reply(&mut writer, &format!("220 {} ESMTP ready", domain)).await?;
+        let mut greeted = false;
+        let mut transaction: Option<Transaction> = None;
+        let mut errors = 0;
+        loop {
+            let line = match self.read(read_line(&mut reader, MAX_COMMAND_LEN)).await? {
+                Some(Line::Complete(line)) => line,
+                Some(Line::TooLong) => {
+                    errors += 1;
+                    reply(&mut writer, "500 5.5.2 Line too long").await?;
+                    continue;
+                }
+                Some(Line::Closed) => return Ok(()),
+                None => return reply(&mut writer, "421 4.4.2 Timeout, closing connection").await,
+            };
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (verb, args) = line.split_once(' ').unwrap_or((line, ""));
+
+            let response = match verb.to_ascii_uppercase().as_str() {
+                "EHLO" => {
+                    greeted = true;
+                    transaction = None;
+                    format!(
+                        "250-{}\r\n250-SIZE {}\r\n250-8BITMIME\r\n250 ENHANCEDSTATUSCODES",
+                        domain, self.settings.max_message_bytes
+                    )
+                }
+                "HELO" => {
+                    greeted = true;
+                    transaction = None;
+                    format!("250 {}", domain)
+                }
+                "MAIL" if !greeted => "503 5.5.1 Send EHLO first".to_string(),
+                "MAIL" if transaction.is_some() => "503 5.5.1 Nested MAIL command".to_string(),
+                "MAIL" => match parse_path(args, "FROM:") {
+                    None => "501 5.5.4 Syntax: MAIL FROM:<address>".to_string(),
+                    Some((_, params)) if self.exceeds_size(params) => {
+                        "552 5.3.4 Message too large".to_string()
+                    }
+                    Some((sender, _)) => {
+                        transaction = Some(Transaction {
+                            sender,
+                            recipient: None,
+                        });
+                        "250 2.1.0 OK".to_string()
+                    }
+                },
+                "RCPT" => match (&mut transaction, parse_path(args, "TO:")) {
+                    (None, _) => "503 5.5.1 Send MAIL first".to_string(),
+                    (_, None) => "501 5.5.4 Syntax: RCPT TO:<address>".to_string(),
+                    (
+                        Some(Transaction {
+                            recipient: Some(_), ..
+                        }),
+                        _,
+                    ) => "452 4.5.3 Too many recipients".to_string(),
+                    (Some(transaction), Some((address, _))) => {
+                        match self.find_recipient(&address).await {
+                            Ok(Some(recipient)) => {
+                                transaction.recipient = Some(recipient);
+                                "250 2.1.5 OK".to_string()
+                            }
+                            Ok(None) => "550 5.1.1 No such mailbox".to_string(),
+                            Err(e) => {
+                                tracing::error!(error = ?e, "Failed to look up SMTP recipient");
+                                "451 4.3.0 Try again later".to_string()
+                            }
+                        }
+                    }
+                },
+                "DATA" => match transaction.take() {
+                    Some(Transaction {
+                        sender,
+                        recipient: Some(recipient),
+                    }) => {
+                        reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+                        let data = read_data(&mut reader, self.settings.max_message_bytes);
+                        match self.read(data).await? {
+                            Some(Data::Complete(message)) => {
+                                self.deliver(&message, &sender, recipient).await
+                            }
+                            Some(Data::TooLarge) => "552 5.3.4 Message too large".to_string(),
+                            Some(Data::Closed) => return Ok(()),
+                            None => {
+                                return reply(&mut writer, "421 4.4.2 Timeout, closing connection")
+                                    .await
+                            }
+                        }
+                    }
+                    other => {
+                        transaction = other;
+                        "503 5.5.1 No valid recipients".to_string()
+                    }
+                },
+                "RSET" => {
+                    transaction = None;
+                    "250 2.0.0 OK".to_string()
+                }
+                "NOOP" => "250 2.0.0 OK".to_string(),
+                "VRFY" => "252 2.5.0 Cannot verify addresses".to_string(),
+                "QUIT" => return reply(&mut writer, "221 2.0.0 Bye").await,
+                _ => {
+                    errors += 1;
+                    "500 5.5.2 Command not recognized".to_string()
+                }
+            };
+            reply(&mut writer, &response).await?;
+
+            if errors >= MAX_ERRORS {
+                return reply(&mut writer, "421 4.7.0 Too many errors, closing connection").await;
+            }
+        }
+    }
+
+    /// Reads with the idle timeout, `None` when it ran out.
+    async fn read<T>(
+        &self,
+        read: impl std::future::Future<Output = std::io::Result<T>>,
+    ) -> std::io::Result<Option<T>> {
+        let timeout = Duration::from_secs(self.settings.idle_timeout_seconds);
+        match tokio::time::timeout(timeout, read).await {
+            Ok(result) => result.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn exceeds_size(&self, params: &str) -> bool {
+        params
+            .split_whitespace()
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.eq_ignore_ascii_case("SIZE"))
+            .and_then(|(_, size)| size.parse::<usize>().ok())
+            .is_some_and(|size| size > self.settings.max_message_bytes)
+    }
+
+    async fn find_recipient(&self, address: &str) -> anyhow::Result<Option<Recipient>> {
+        let Some((local_part, domain)) = address.rsplit_once('@') else {
+            return Ok(None);
+        };
+        if !domain.eq_ignore_ascii_case(&self.settings.domain) {
+            return Ok(None);
+        }
+        let Some(token) = local_part
+            .get(..ADDRESS_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(ADDRESS_PREFIX))
+            .map(|_| &local_part[ADDRESS_PREFIX.len()..])
+        else {
+            return Ok(None);
+        };
+
+        self.inbox.find_recipient(&token.to_ascii_lowercase()).await
+    }
+
+    #[tracing::instrument(name = "Deliver email to todo inbox", skip(self, message, recipient), fields(owner_id = %recipient.owner_id))]
+    async fn deliver(&self, message: &[u8], sender: &str, recipient: Recipient) -> String {
+        let todo = match read_email_todo(message, sender, &recipient, &self.settings) {
+            Ok(todo) => todo,
+            Err(reason) => {
+                tracing::info!(reason, "Rejected email to todo inbox");
+                return format!("550 5.7.1 {}", reason);
+            }
+        };
+
+        match self
+            .inbox
+            .add_todo(recipient.owner_id, todo.content, todo.notes)
+            .await
+        {
+            Ok(todo_id) => format!("250 2.0.0 Created todo {}", todo_id),
+            Err(e @ InboxError::Invalid(_)) => format!("550 5.6.0 {}", e),
+            Err(e @ InboxError::Full(_)) => format!("552 5.2.2 {}", e),
+            Err(InboxError::Unexpected(e)) => {
+                tracing::error!(error = ?e, "Failed to add todo from email");
+                "451 4.3.0 Try again later".to_string()
+            }
+        }
+    }
+}
+
+async fn reply(writer: &mut (impl AsyncWrite + Unpin), response: &str) -> std::io::Result<()> {
+    writer.write_all(response.as_bytes()).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await
+}
+
+/// Splits `FROM:<address> PARAMS` into the address and the parameters. The null sender
+/// `<>` is an empty address.
+fn parse_path<'a>(args: &'a str, keyword: &str) -> Option<(String, &'a str)> {
+    let args = args.trim_start();
+    let rest = args
+        .get(..keyword.len())
+        .filter(|start| start.eq_ignore_ascii_case(keyword))
+        .map(|_| args[keyword.len()..].trim_start())?;
+    let rest = rest.strip_prefix('<')?;
+    let (address, params) = rest.split_once('>')?;
+    Some((address.trim().to_string(), params))
+}
+
+/// Reads a line of at most `max_len` bytes. Longer lines are skipped.
+async fn read_line(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    max_len: usize,
+) -> std::io::Result<Line> {
+    let mut line = Vec::new();
+    let mut too_long = false;
+    loop {
+        let mut chunk = Vec::new();
+        let read = (&mut *reader)
+            .take(max_len as u64 + 1)
+            .read_until(b'\n', &mut chunk)
+            .await?;
+        if read == 0 {
+            return Ok(Line::Closed);
+        }
+        if !too_long && line.len() + chunk.len() <= max_len {
+            line.extend_from_slice(&chunk);
+        } else {
+            too_long = true;
+            line.clear();
+        }
+        if chunk.ends_with(b"\n") {
+            return Ok(if too_long {
+                Line::TooLong
+            } else {
+                Line::Complete(line)
+            });
+        }
+    }
+}
+
+/// Reads a message up to the line with a single dot, removing the dots added in front of
+/// lines starting with one. Messages over `max_len` bytes are read to the end, but dropped.
+async fn read_data(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    max_len: usize,
+) -> std::io::Result<Data> {
+    let mut message = Vec::new();
+    let mut too_large = false;
+    loop {
+        let line = match read_line(reader, max_len).await? {
+            Line::Complete(line) => line,
+            Line::TooLong => {
+                too_large = true;
+                continue;
+            }
+            Line::Closed => return Ok(Data::Closed),
+        };
+        if line == b".\r\n" || line == b".\n" {
+            return Ok(if too_large {
+                Data::TooLarge
+            } else {
+                Data::Complete(message)
+            });
+        }
+        if too_large {
+            continue;
+        }
+        let line = line.strip_prefix(b".").unwrap_or(&line);
+        if message.len() + line.len() > max_len {
+            too_large = true;
+            message.clear();
+        } else {
+            message.extend_from_slice(line);
+        }
+    }
+}
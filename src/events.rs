@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::model::{Todo, TodoId};
+
+pub use crate::model::UserId;
+
+/// Events a subscriber has not received yet when this many more were published are
+/// dropped, and the subscriber is told to resynchronize.
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoChange {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl TodoChange {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TodoChange::Created => "created",
+            TodoChange::Updated => "updated",
+            TodoChange::Deleted => "deleted",
+        }
+    }
+}
+
+/// A change of a todo on the list of `owner_id`. The payload is serialized once when the
+/// event is published, rather than for every subscriber.
+#[derive(Debug, Clone)]
+pub struct TodoEvent {
+    pub owner_id: UserId,
+    pub change: TodoChange,
+    /// The todo as JSON, or just its id for deleted todos.
+    pub data: Arc<str>,
+    /// Numbers the events of a list in the order they were published, starting at 1.
+    /// Assigned by the bus, so that subscribers can tell when they missed events.
+    pub seq: u64,
+}
+
+#[derive(Serialize)]
+struct DeletedTodo {
+    id: TodoId,
+}
+
+impl TodoEvent {
+    pub fn created(todo: &Todo) -> anyhow::Result<Self> {
+        Self::new(todo.created_by(), TodoChange::Created, todo)
+    }
+
+    pub fn updated(todo: &Todo) -> anyhow::Result<Self> {
+        Self::new(todo.created_by(), TodoChange::Updated, todo)
+    }
+
+    pub fn deleted(owner_id: UserId, todo_id: TodoId) -> anyhow::Result<Self> {
+        Self::new(owner_id, TodoChange::Deleted, &DeletedTodo { id: todo_id })
+    }
+
+    fn new(owner_id: UserId, change: TodoChange, data: &impl Serialize) -> anyhow::Result<Self> {
+        Ok(Self {
+            owner_id,
+            change,
+            data: serde_json::to_string(data)?.into(),
+            seq: 0,
+        })
+    }
+}
+
+/// The channel of one list, with the sequence number of the last event sent on it.
+struct ListChannel {
+    sender: broadcast::Sender<TodoEvent>,
+    seq: u64,
+}
+
+type ListChannels = Arc<Mutex<HashMap<UserId, ListChannel>>>;
+
+/// Broadcasts todo changes to the event streams of the users looking at the changed
+/// list. Every list has a channel of its own while anyone on this instance is subscribed
+/// to it, so that a busy list cannot make the subscribers of other lists fall behind.
+/// Subscriptions live in memory. Without a change feed every instance of the app only
+/// sees the changes it made itself.
+pub struct TodoEventBus {
+    channels: ListChannels,
+    change_feed: bool,
+}
+
+impl TodoEventBus {
+    pub fn new() -> Self {
+        Self {
+            channels: Arc::new(Mutex::new(HashMap::new())),
+            change_feed: false,
+        }
+    }
+
+    /// A bus whose changes are all republished from the change feed, see
+    /// [`crate::change_feed::ChangeFeedProcessor`], whichever instance made them.
+    pub fn with_change_feed() -> Self {
+        Self {
+            change_feed: true,
+            ..Self::new()
+        }
+    }
+
+    /// Publishing never fails, nobody may be listening. With a change feed nothing is
+    /// published directly, the change reaches every instance through the feed instead.
+    pub fn publish(&self, event: TodoEvent) {
+        if self.change_feed {
+            return;
+        }
+        self.republish(event);
+    }
+
+    /// Publishes the event even with a change feed, for changes read from it.
+    pub(crate) fn republish(&self, mut event: TodoEvent) {
+        // Numbering and sending under the same lock keeps the channel in sequence order.
+        let mut channels = self.channels.lock().unwrap();
+        let Some(channel) = channels.get_mut(&event.owner_id) else {
+            return;
+        };
+        channel.seq += 1;
+        event.seq = channel.seq;
+
+        tracing::debug!(owner_id = %event.owner_id, change = event.change.as_str(), seq = event.seq, "Publishing todo event");
+        let _ = channel.sender.send(event);
+    }
+
+    /// The sequence number of the last event published for the list, 0 if there was none.
+    /// Sequence numbers are kept by each instance, and only while the list has
+    /// subscribers on it, so they are only comparable within one subscription.
+    pub fn last_seq(&self, owner_id: &UserId) -> u64 {
+        self.channels
+            .lock()
+            .unwrap()
+            .get(owner_id)
+            .map(|channel| channel.seq)
+            .unwrap_or_default()
+    }
+
+    pub fn subscribe(&self, owner_id: &UserId) -> TodoEventReceiver {
+        let mut channels = self.channels.lock().unwrap();
+        let channel = channels
+            .entry(owner_id.clone())
+            .or_insert_with(|| ListChannel {
+                sender: broadcast::channel(CHANNEL_CAPACITY).0,
+                seq: 0,
+            });
+        TodoEventReceiver {
+            owner_id: owner_id.clone(),
+            receiver: channel.sender.subscribe(),
+            channels: self.channels.clone(),
+        }
+    }
+
+    /// The number of lists with subscribers on this instance.
+    pub fn subscribed_lists(&self) -> usize {
+        self.channels.lock().unwrap().len()
+    }
+}
+
+/// What a subscriber receives next.
+#[derive(Debug)]
+pub enum Received {
+    Event(TodoEvent),
+    /// The subscriber fell behind and events of the list were dropped, it has to load the
+    /// list again.
+    Resync,
+}
+
+/// A subscription to the events of one list. The channel of the list is removed along
+/// with its last subscription.
+pub struct TodoEventReceiver {
+    owner_id: UserId,
+    receiver: broadcast::Receiver<TodoEvent>,
+    channels: ListChannels,
+}
+
+impl TodoEventReceiver {
+    /// Waits for the next event of the list. Cancelling the wait loses nothing. Ends with
+    /// `None` if the channel closes, which its subscriptions prevent.
+    pub async fn recv(&mut self) -> Option<Received> {
+        match self.receiver.recv().await {
+            Ok(event) => Some(Received::Event(event)),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::info!(missed, owner_id = %self.owner_id, "Todo event subscriber fell behind");
+                Some(Received::Resync)
+            }
+            Err(broadcast::error::RecvError::Closed) => None,
+        }
+    }
+}
+
+impl Drop for TodoEventReceiver {
+    fn drop(&mut self) {
+        let mut channels = self.channels.lock().unwrap();
+        // Still counts the receiver of this subscription.
+        if channels
+            .get(&self.owner_id)
+            .is_some_and(|channel| channel.sender.receiver_count() <= 1)
+        {
+            channels.remove(&self.owner_id);
+        }
+    }
+}
+
+impl Default for TodoEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,315 @@
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::model::{Frequency, Priority, Todo};
+
+const PRODUCT_ID: &str = "-//todo_app//Todo App//EN";
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Renders todos as an RFC 5545 calendar containing one VTODO component per todo.
+pub fn render_calendar<'a>(name: &str, todos: impl IntoIterator<Item = &'a Todo>) -> String {
+    let mut calendar = Calendar::default();
+
+    calendar.begin();
+    calendar.line("X-WR-CALNAME", &escape_text(name));
+    for todo in todos {
+        calendar.todo(todo);
+    }
+    calendar.line("END", "VCALENDAR");
+
+    calendar.0
+}
+
+/// Renders a single todo as a standalone calendar object resource, as served over CalDAV.
+pub fn render_calendar_object(todo: &Todo) -> String {
+    let mut calendar = Calendar::default();
+
+    calendar.begin();
+    calendar.todo(todo);
+    calendar.line("END", "VCALENDAR");
+
+    calendar.0
+}
+
+pub fn uid(todo: &Todo) -> String {
+    todo.calendar_object()
+        .map(|object| object.uid.clone())
+        .unwrap_or_else(|| todo.id().to_string())
+}
+
+#[derive(Default)]
+struct Calendar(String);
+
+impl Calendar {
+    fn begin(&mut self) {
+        self.line("BEGIN", "VCALENDAR");
+        self.line("VERSION", "2.0");
+        self.line("PRODID", PRODUCT_ID);
+        self.line("CALSCALE", "GREGORIAN");
+    }
+
+    fn todo(&mut self, todo: &Todo) {
+        self.line("BEGIN", "VTODO");
+        self.line("UID", &escape_text(&uid(todo)));
+        self.line("DTSTAMP", &format_date_time(todo.last_modified()));
+        self.line("CREATED", &format_date_time(todo.created_at()));
+        self.line("LAST-MODIFIED", &format_date_time(todo.last_modified()));
+        self.line("SUMMARY", &escape_text(todo.content().as_ref()));
+
+        if let Some(due_at) = todo.due_at() {
+            self.line("DUE", &format_date_time(due_at));
+        }
+        if !todo.tags().is_empty() {
+            let categories = todo
+                .tags()
+                .iter()
+                .map(|tag| escape_text(tag))
+                .collect::<Vec<_>>();
+            self.line("CATEGORIES", &categories.join(","));
+        }
+        // PRIORITY runs from 1, the highest, to 9, the lowest.
+        if let Some(priority) = todo.priority() {
+            let value = match priority {
+                Priority::High => "1",
+                Priority::Medium => "5",
+                Priority::Low => "9",
+            };
+            self.line("PRIORITY", value);
+        }
+        if let Some(recurrence) = todo.recurrence() {
+            let frequency = match recurrence.frequency {
+                Frequency::Daily => "DAILY",
+                Frequency::Weekly => "WEEKLY",
+                Frequency::Monthly => "MONTHLY",
+                Frequency::Yearly => "YEARLY",
+            };
+            self.line(
+                "RRULE",
+                &format!("FREQ={};INTERVAL={}", frequency, recurrence.interval),
+            );
+        }
+
+        if todo.is_done() {
+            self.line("STATUS", "COMPLETED");
+            self.line("PERCENT-COMPLETE", "100");
+            if let Some(completed_at) = todo.completed_at() {
+                self.line("COMPLETED", &format_date_time(completed_at));
+            }
+        } else {
+            self.line("STATUS", "NEEDS-ACTION");
+        }
+
+        self.line("END", "VTODO");
+    }
+
+    fn line(&mut self, name: &str, value: &str) {
+        let line = format!("{}:{}", name, value);
+        fold_line(&line, &mut self.0);
+    }
+}
+
+/// Content lines longer than 75 octets are split with CRLF followed by a single space,
+/// never in the middle of a multi-byte UTF-8 sequence.
+fn fold_line(line: &str, out: &mut String) {
+    let mut octets = 0;
+
+    for ch in line.chars() {
+        let len = ch.len_utf8();
+        if octets + len > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            // The leading space of a continuation line counts towards its length.
+            octets = 1;
+        }
+        out.push(ch);
+        octets += len;
+    }
+
+    out.push_str("\r\n");
+}
+
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+fn format_date_time(value: DateTime<Utc>) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// The subset of a VTODO component the todo model can represent.
+#[derive(Debug, Default)]
+pub struct VTodo {
+    pub uid: String,
+    pub summary: String,
+    pub completed: bool,
+    pub due_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Parses the first VTODO component of an iCalendar object. Properties of nested
+/// components (e.g. VALARM) and properties the model cannot represent are ignored.
+pub fn parse_calendar_object(input: &str) -> anyhow::Result<VTodo> {
+    let unfolded = input
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut components = Vec::new();
+    let mut vtodo: Option<VTodo> = None;
+    let mut uid = None;
+
+    for line in unfolded.lines().filter(|line| !line.trim().is_empty()) {
+        let property = ContentLine::parse(line)?;
+
+        match property.name.as_str() {
+            "BEGIN" => {
+                if property.value.eq_ignore_ascii_case("VTODO") && vtodo.is_none() {
+                    vtodo = Some(VTodo::default());
+                }
+                components.push(property.value.to_ascii_uppercase());
+                continue;
+            }
+            "END" => {
+                let component = components.pop();
+                if component.as_deref() == Some("VTODO") {
+                    break;
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some(vtodo) = vtodo.as_mut() else {
+            continue;
+        };
+        if components.last().map(String::as_str) != Some("VTODO") {
+            continue;
+        }
+
+        match property.name.as_str() {
+            "UID" => uid = Some(unescape_text(property.value)),
+            "SUMMARY" => vtodo.summary = unescape_text(property.value),
+            "STATUS" => vtodo.completed = property.value.eq_ignore_ascii_case("COMPLETED"),
+            "DUE" => vtodo.due_at = Some(parse_date_time(&property)?),
+            "COMPLETED" => vtodo.completed_at = Some(parse_date_time(&property)?),
+            _ => {}
+        }
+    }
+
+    let mut vtodo = vtodo.context("Calendar object does not contain a VTODO component")?;
+    vtodo.uid = uid.context("VTODO component is missing a UID")?;
+    vtodo.completed |= vtodo.completed_at.is_some();
+
+    Ok(vtodo)
+}
+
+struct ContentLine<'a> {
+    name: String,
+    params: Vec<(String, &'a str)>,
+    value: &'a str,
+}
+
+impl<'a> ContentLine<'a> {
+    fn parse(line: &'a str) -> anyhow::Result<Self> {
+        let mut in_quotes = false;
+        let separator = line
+            .char_indices()
+            .find(|(_, ch)| {
+                if *ch == '"' {
+                    in_quotes = !in_quotes;
+                }
+                *ch == ':' && !in_quotes
+            })
+            .map(|(idx, _)| idx)
+            .with_context(|| format!("Invalid content line: {}", line))?;
+
+        let (head, value) = (&line[..separator], &line[separator + 1..]);
+        let mut parts = head.split(';');
+        let name = parts.next().unwrap_or_default().to_ascii_uppercase();
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.to_ascii_uppercase(), value.trim_matches('"')))
+            .collect();
+
+        Ok(Self {
+            name,
+            params,
+            value,
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// DATE values are interpreted as midnight UTC, as are floating DATE-TIME values since
+/// the model has no notion of a local time. TZID-qualified DATE-TIME values are resolved
+/// in their IANA time zone, or taken as UTC if the client uses a name of its own. Local
+/// times skipped by a daylight saving transition are taken with the offset before it.
+fn parse_date_time(property: &ContentLine) -> anyhow::Result<DateTime<Utc>> {
+    let value = property.value.trim();
+
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+            .with_context(|| format!("Invalid DATE value: {}", value));
+    }
+
+    let date_time = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+        .with_context(|| format!("Invalid DATE-TIME value: {}", value))?;
+    let time_zone = property
+        .param("TZID")
+        .filter(|_| !value.ends_with('Z'))
+        .and_then(|tzid| tzid.parse::<Tz>().ok());
+
+    let Some(time_zone) = time_zone else {
+        return Ok(date_time.and_utc());
+    };
+    time_zone
+        .from_local_datetime(&date_time)
+        .earliest()
+        .or_else(|| {
+            let day_before = date_time - chrono::Duration::days(1);
+            let offset = time_zone.offset_from_utc_datetime(&day_before).fix();
+            Some(time_zone.from_utc_datetime(&(date_time - offset)))
+        })
+        .map(|date_time| date_time.with_timezone(&Utc))
+        .with_context(|| format!("Invalid local time: {}", value))
+}
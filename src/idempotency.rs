@@ -0,0 +1,191 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::{ErrorBadRequest, ErrorConflict, ErrorInternalServerError, ErrorUnprocessableEntity},
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    web, HttpMessage, HttpResponse,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::AuthContext,
+    configuration::IdempotencySettings,
+    model::{IdempotencyRecord, StoredResponse, UserId},
+    repositories::IdempotencyRepository,
+    request_body,
+};
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_KEY_LEN: usize = 255;
+
+/// Makes `POST` requests carrying an `Idempotency-Key` header safe to retry. The first
+/// response per user and key is stored and replayed on retries within the retention
+/// window. Reusing a key for a different request is rejected with
+/// `422 Unprocessable Entity`, and retrying while the first request is still being handled
+/// with `409 Conflict`. Server errors are not stored, so that the request can be retried.
+///
+/// Keys belong to the authenticated user, so it has to be wrapped inside the
+/// authentication middlewares.
+pub(crate) async fn idempotency_middleware<R>(
+    mut req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error>
+where
+    R: IdempotencyRepository + 'static,
+{
+    let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(key) if req.method() == Method::POST => key
+            .to_str()
+            .ok()
+            .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+            .ok_or_else(|| {
+                ErrorBadRequest(format!(
+                    "{} must be between 1 and {} visible ASCII characters long",
+                    IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN
+                ))
+            })?
+            .to_string(),
+        _ => return next.call(req).await.map(|res| res.map_into_boxed_body()),
+    };
+    let Some(user_id) = req
+        .extensions()
+        .get::<AuthContext>()
+        .map(|auth_ctx| auth_ctx.principal_id.clone())
+    else {
+        return next.call(req).await.map(|res| res.map_into_boxed_body());
+    };
+
+    let idempotency_repository = req
+        .app_data::<web::Data<R>>()
+        .expect("Idempotency repository is not registered")
+        .clone();
+    let settings = req
+        .app_data::<web::Data<IdempotencySettings>>()
+        .expect("Idempotency settings are not registered")
+        .clone();
+
+    let body = request_body::peek_body(&mut req).await?;
+    let request_hash = request_hash(&req, &body);
+
+    let record = IdempotencyRecord::new(
+        user_id.clone(),
+        &key,
+        request_hash.clone(),
+        settings.retention_seconds,
+    );
+    let is_first = idempotency_repository
+        .get_ref()
+        .try_create(record)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    if !is_first {
+        let record = idempotency_repository
+            .get_ref()
+            .get(user_id, &key)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .ok_or_else(|| ErrorConflict("The idempotency key expired, retry the request"))?;
+
+        if record.request_hash() != request_hash {
+            return Err(ErrorUnprocessableEntity(
+                "The idempotency key was used for a different request",
+            ));
+        }
+
+        let response = record.response().ok_or_else(|| {
+            ErrorConflict("A request with this idempotency key is still being handled")
+        })?;
+
+        tracing::info!("Replaying response for idempotency key");
+        return Ok(req.into_response(replay(response)?));
+    }
+
+    let res = match next.call(req).await {
+        Ok(res) => res,
+        Err(e) => {
+            forget(idempotency_repository.get_ref(), user_id, &key).await;
+            return Err(e);
+        }
+    };
+
+    if res.status().is_server_error() {
+        forget(idempotency_repository.get_ref(), user_id, &key).await;
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, res_body) = res.into_parts();
+    let res_body = body::to_bytes(res_body)
+        .await
+        .map_err(|e| ErrorInternalServerError(e.into().to_string()))?;
+
+    let stored_response = StoredResponse {
+        status: res.status().as_u16(),
+        headers: res
+            .headers()
+            .iter()
+            .filter(|(name, _)| *name != header::SET_COOKIE)
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: BASE64_STANDARD.encode(&res_body),
+    };
+    let mut record =
+        IdempotencyRecord::new(user_id, &key, request_hash, settings.retention_seconds);
+    record.complete(stored_response);
+    idempotency_repository
+        .get_ref()
+        .save(record)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    let res = res.set_body(res_body).map_into_boxed_body();
+    Ok(ServiceResponse::new(req, res))
+}
+
+/// Identifies the request a key was first used for by method, path, query and body.
+fn request_hash(req: &ServiceRequest, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        req.method().as_str().as_bytes(),
+        req.path().as_bytes(),
+        req.query_string().as_bytes(),
+    ] {
+        hasher.update(part);
+        hasher.update(b"\n");
+    }
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+fn replay(response: &StoredResponse) -> Result<HttpResponse, actix_web::Error> {
+    let status = StatusCode::from_u16(response.status).map_err(ErrorInternalServerError)?;
+    let body = BASE64_STANDARD
+        .decode(&response.body)
+        .map_err(ErrorInternalServerError)?;
+
+    let mut builder = HttpResponse::build(status);
+    for (name, value) in &response.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            builder.append_header((name, value));
+        }
+    }
+    builder.insert_header(("Idempotent-Replayed", "true"));
+
+    Ok(builder.body(body))
+}
+
+/// Drops the record of a request that failed, so that it can be retried. Failures are
+/// logged only, the record expires eventually anyway.
+async fn forget<R: IdempotencyRepository>(idempotency_repository: &R, user_id: UserId, key: &str) {
+    if let Err(e) = idempotency_repository.delete(user_id, key).await {
+        tracing::warn!(error = ?e, "Failed to delete idempotency record");
+    }
+}
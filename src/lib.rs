@@ -1,7 +1,23 @@
-mod auth;
+pub mod auth;
+pub mod change_feed;
 pub mod configuration;
+pub mod email_gateway;
+pub mod events;
+mod ical;
+mod idempotency;
+pub mod markdown;
 mod model;
+pub mod notifications;
+mod openapi;
+mod presence;
+mod problem;
+pub mod push;
+pub mod quick_add;
+mod rate_limit;
 mod repositories;
+mod request_body;
 mod routes;
 pub mod startup;
 pub mod telemetry;
+mod tokens;
+pub mod webhooks;
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use pulldown_cmark::{Options, Parser};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::model::Todo;
+
+const UNTAGGED_GROUP: &str = "Untagged";
+
+/// An inclusive range of calendar days (UTC), open on either side when a bound is missing.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DateRange {
+    /// First day of the range, as `YYYY-MM-DD`.
+    pub from: Option<NaiveDate>,
+    /// Last day of the range, as `YYYY-MM-DD`.
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn is_unbounded(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) if from > to => Err(anyhow::anyhow!(
+                "Range start {} is after range end {}",
+                from,
+                to
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, value: DateTime<Utc>) -> bool {
+        let day = value.date_naive();
+        self.from.is_none_or(|from| day >= from) && self.to.is_none_or(|to| day <= to)
+    }
+
+    /// Done todos are matched by completion date and open ones by due date, so a range
+    /// selects what was finished in it and what was planned for it.
+    fn matches(&self, todo: &Todo) -> bool {
+        if self.is_unbounded() {
+            return true;
+        }
+
+        let date = if todo.is_done() {
+            todo.completed_at()
+        } else {
+            todo.due_at()
+        };
+
+        date.is_some_and(|date| self.contains(date))
+    }
+}
+
+/// Renders the todos of each list as a Markdown checklist, with a section per list and a
+/// subsection per tag. Todos with several tags are listed under each of them. Within a
+/// tag todos are sorted by due date, those without one last, oldest first.
+pub fn render_checklist<'a>(
+    title: &str,
+    lists: impl IntoIterator<Item = (&'a str, &'a [Todo])>,
+    range: &DateRange,
+) -> String {
+    let mut markdown = format!("# {}\n", escape(title));
+    if let Some(period) = describe_range(range) {
+        markdown.push_str(&format!("\n_{}_\n", period));
+    }
+
+    let mut is_empty = true;
+    for (list_name, todos) in lists {
+        let groups = group_by_tag(todos.iter().filter(|todo| range.matches(todo)));
+        if groups.is_empty() {
+            continue;
+        }
+        is_empty = false;
+
+        markdown.push_str(&format!("\n## {}\n", escape(list_name)));
+        for (group, todos) in groups {
+            let heading = group
+                .map(escape)
+                .unwrap_or_else(|| UNTAGGED_GROUP.to_string());
+            markdown.push_str(&format!("\n### {}\n\n", heading));
+            for todo in todos {
+                markdown.push_str(&render_item(todo));
+            }
+        }
+    }
+
+    if is_empty {
+        markdown.push_str("\nNo todos.\n");
+    }
+
+    markdown
+}
+
+/// Groups todos by tag, in tag order with untagged todos last.
+fn group_by_tag<'a>(
+    todos: impl Iterator<Item = &'a Todo>,
+) -> Vec<(Option<&'a str>, Vec<&'a Todo>)> {
+    let mut groups: BTreeMap<Option<&str>, Vec<&Todo>> = BTreeMap::new();
+
+    for todo in todos {
+        if todo.tags().is_empty() {
+            groups.entry(None).or_default().push(todo);
+        }
+        for tag in todo.tags() {
+            groups.entry(Some(tag.as_str())).or_default().push(todo);
+        }
+    }
+
+    let untagged = groups.remove(&None);
+    groups
+        .into_iter()
+        .chain(untagged.map(|todos| (None, todos)))
+        .map(|(group, mut todos)| {
+            todos.sort_by_key(|todo| (todo.due_at().is_none(), todo.due_at(), todo.created_at()));
+            (group, todos)
+        })
+        .collect()
+}
+
+fn render_item(todo: &Todo) -> String {
+    let checkbox = if todo.is_done() { "[x]" } else { "[ ]" };
+    let mut item = format!("- {} {}", checkbox, escape(todo.content().as_ref()));
+
+    let mut details = Vec::new();
+    if let Some(due_at) = todo.due_at() {
+        details.push(format!("due {}", due_at.format("%Y-%m-%d")));
+    }
+    if let Some(completed_at) = todo.completed_at() {
+        details.push(format!("done {}", completed_at.format("%Y-%m-%d")));
+    }
+    if !details.is_empty() {
+        item.push_str(&format!(" ({})", details.join(", ")));
+    }
+
+    item.push('\n');
+    item
+}
+
+fn describe_range(range: &DateRange) -> Option<String> {
+    match (range.from, range.to) {
+        (Some(from), Some(to)) => Some(format!("{} – {}", from, to)),
+        (Some(from), None) => Some(format!("Since {}", from)),
+        (None, Some(to)) => Some(format!("Until {}", to)),
+        (None, None) => None,
+    }
+}
+
+/// Escapes characters that would otherwise turn todo content into Markdown markup, and
+/// collapses line breaks so every todo stays a single list item.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for ch in value.chars() {
+        match ch {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+/// Renders notes written in Markdown as HTML that is safe to embed in a page. Raw HTML
+/// in the notes is kept only as far as it is harmless: scripts, styles, event handlers
+/// and links to other than `http`, `https` and `mailto` URLs or paths on this site are
+/// dropped.
+pub fn render_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    let mut html = String::with_capacity(markdown.len() * 3 / 2);
+    pulldown_cmark::html::push_html(&mut html, Parser::new_ext(markdown, options));
+
+    ammonia::Builder::default()
+        .url_schemes(["http", "https", "mailto"].into())
+        .clean(&html)
+        .to_string()
+}
+
+/// The `markdown` template filter, rendering notes with [`render_html`]. Its output
+/// still has to be marked `safe` to be embedded.
+pub fn markdown_filter(
+    value: &tera::Value,
+    _args: &HashMap<String, tera::Value>,
+) -> tera::Result<tera::Value> {
+    let markdown = tera::from_value::<String>(value.clone())?;
+    Ok(tera::Value::String(render_html(&markdown)))
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, ToSchema)]
 pub struct UserId(String);
 
 impl std::fmt::Display for UserId {
@@ -16,13 +18,13 @@ impl From<String> for UserId {
     }
 }
 
-impl Into<String> for UserId {
-    fn into(self) -> String {
-        self.0
+impl From<UserId> for String {
+    fn from(value: UserId) -> Self {
+        value.0
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ToSchema)]
 pub struct TodoId(Uuid);
 
 impl TodoId {
@@ -31,6 +33,12 @@ impl TodoId {
     }
 }
 
+impl From<Uuid> for TodoId {
+    fn from(value: Uuid) -> Self {
+        Self(value)
+    }
+}
+
 impl Default for TodoId {
     fn default() -> Self {
         Self::new()
@@ -43,29 +51,38 @@ impl std::fmt::Display for TodoId {
     }
 }
 
-impl Into<String> for TodoId {
-    fn into(self) -> String {
-        self.to_string()
+impl From<TodoId> for String {
+    fn from(value: TodoId) -> Self {
+        value.to_string()
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct TodoContent(String);
 
+impl AsRef<str> for TodoContent {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TodoContent {
+    pub const MAX_LEN: usize = 500;
+}
+
 impl TryFrom<String> for TodoContent {
     type Error = anyhow::Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         const MIN_LEN: usize = 1;
-        const MAX_LEN: usize = 500;
         let value = value.trim();
 
         if value.len() < MIN_LEN {
             Err(anyhow::anyhow!("Todo content cannot be blank"))
-        } else if value.len() > MAX_LEN {
+        } else if value.len() > Self::MAX_LEN {
             Err(anyhow::anyhow!(
                 "Todo content cannot be longer than {} characters",
-                MAX_LEN
+                Self::MAX_LEN
             ))
         } else {
             Ok(TodoContent(value.to_string()))
@@ -73,13 +90,119 @@ impl TryFrom<String> for TodoContent {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// Free text kept with a todo, beyond what fits its content.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TodoNotes(String);
+
+impl TodoNotes {
+    pub const MAX_LEN: usize = 20_000;
+}
+
+impl AsRef<str> for TodoNotes {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for TodoNotes {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim();
+
+        if value.is_empty() {
+            Err(anyhow::anyhow!("Todo notes cannot be blank"))
+        } else if value.len() > Self::MAX_LEN {
+            Err(anyhow::anyhow!(
+                "Todo notes cannot be longer than {} characters",
+                Self::MAX_LEN
+            ))
+        } else {
+            Ok(TodoNotes(value.to_string()))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// How often a todo repeats, counted from its due date: every `interval` days, weeks,
+/// months or years.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+}
+
+/// Binds a todo to the resource name and UID a CalDAV client chose for it, so the
+/// client keeps seeing the same href and UID it created the todo with.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct CalendarObject {
+    pub resource_name: String,
+    pub uid: String,
+}
+
+/// A change of a todo's assignee, kept together with who made it.
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct AssignmentChange {
+    pub assignee: Option<UserId>,
+    pub changed_by: UserId,
+    pub changed_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Todo {
     id: TodoId,
     content: TodoContent,
     done: bool,
     created_by: UserId,
     created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    due_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    notes: Option<TodoNotes>,
+    #[serde(default)]
+    priority: Option<Priority>,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    calendar_object: Option<CalendarObject>,
+    #[serde(default)]
+    assignee: Option<UserId>,
+    #[serde(default)]
+    assignment_history: Vec<AssignmentChange>,
+    /// Deleted todos are kept as tombstones until Cosmos DB removes them after `ttl`
+    /// seconds, so that the change feed carries their deletion.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schema(ignore)]
+    deleted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(ignore)]
+    ttl: Option<u64>,
+    /// When Cosmos DB last wrote the todo, in seconds since the epoch.
+    #[serde(rename = "_ts", default, skip_serializing)]
+    #[schema(ignore)]
+    stored_at: Option<i64>,
 }
 
 impl Todo {
@@ -90,6 +213,57 @@ impl Todo {
             done: false,
             created_by,
             created_at: chrono::Utc::now(),
+            updated_at: None,
+            due_at: None,
+            completed_at: None,
+            tags: Vec::new(),
+            notes: None,
+            priority: None,
+            recurrence: None,
+            calendar_object: None,
+            assignee: None,
+            assignment_history: Vec::new(),
+            deleted: false,
+            ttl: None,
+            stored_at: None,
+        }
+    }
+
+    /// Sets the notes of a todo that has not been stored yet.
+    pub fn with_notes(self, notes: Option<TodoNotes>) -> Self {
+        Self { notes, ..self }
+    }
+
+    /// Sets the due date of a todo that has not been stored yet.
+    pub fn with_due_at(self, due_at: Option<chrono::DateTime<chrono::Utc>>) -> Self {
+        Self { due_at, ..self }
+    }
+
+    /// Sets the tags of a todo that has not been stored yet.
+    pub fn with_tags(self, tags: Vec<String>) -> Self {
+        Self { tags, ..self }
+    }
+
+    /// Sets the priority of a todo that has not been stored yet.
+    pub fn with_priority(self, priority: Option<Priority>) -> Self {
+        Self { priority, ..self }
+    }
+
+    /// Sets the recurrence of a todo that has not been stored yet.
+    pub fn with_recurrence(self, recurrence: Option<Recurrence>) -> Self {
+        Self { recurrence, ..self }
+    }
+
+    pub fn from_calendar_object(
+        id: TodoId,
+        calendar_object: CalendarObject,
+        content: TodoContent,
+        created_by: UserId,
+    ) -> Self {
+        Self {
+            id,
+            calendar_object: Some(calendar_object),
+            ..Self::new(content, created_by)
         }
     }
 
@@ -97,19 +271,1077 @@ impl Todo {
         self.id
     }
 
+    pub fn content(&self) -> &TodoContent {
+        &self.content
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
     pub fn created_by(&self) -> UserId {
         self.created_by.clone()
     }
 
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+
+    pub fn last_modified(&self) -> chrono::DateTime<chrono::Utc> {
+        self.updated_at.unwrap_or(self.created_at)
+    }
+
+    pub fn due_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.due_at
+    }
+
+    pub fn completed_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.completed_at
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn notes(&self) -> Option<&TodoNotes> {
+        self.notes.as_ref()
+    }
+
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    pub fn recurrence(&self) -> Option<Recurrence> {
+        self.recurrence
+    }
+
+    pub fn calendar_object(&self) -> Option<&CalendarObject> {
+        self.calendar_object.as_ref()
+    }
+
+    pub fn assignee(&self) -> Option<&UserId> {
+        self.assignee.as_ref()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+
+    /// `None` for todos that have not been read back from the database.
+    pub fn stored_at(&self) -> Option<i64> {
+        self.stored_at
+    }
+
     pub fn mark_as_done(&mut self) {
+        if !self.done {
+            self.mark_as_done_at(chrono::Utc::now());
+        }
+    }
+
+    pub fn mark_as_done_at(&mut self, completed_at: chrono::DateTime<chrono::Utc>) {
         self.done = true;
+        self.completed_at = Some(completed_at);
+        self.touch();
     }
 
     pub fn mark_as_unfinished(&mut self) {
         self.done = false;
+        self.completed_at = None;
+        self.touch();
     }
 
     pub fn update_content(&mut self, content: TodoContent) {
         self.content = content;
+        self.touch();
+    }
+
+    pub fn update_notes(&mut self, notes: Option<TodoNotes>) {
+        self.notes = notes;
+        self.touch();
+    }
+
+    pub fn update_due_at(&mut self, due_at: Option<chrono::DateTime<chrono::Utc>>) {
+        self.due_at = due_at;
+        self.touch();
+    }
+
+    /// Callers are responsible for checking that the assignee has access to the todo.
+    pub fn assign(&mut self, assignee: Option<UserId>, changed_by: UserId) {
+        if self.assignee == assignee {
+            return;
+        }
+
+        self.assignee = assignee.clone();
+        self.assignment_history.push(AssignmentChange {
+            assignee,
+            changed_by,
+            changed_at: chrono::Utc::now(),
+        });
+        self.touch();
+    }
+
+    /// Turns the todo into a tombstone that Cosmos DB removes after `ttl` seconds.
+    pub fn delete(&mut self, ttl: u64) {
+        self.deleted = true;
+        self.ttl = Some(ttl);
+        self.touch();
+    }
+
+    fn touch(&mut self) {
+        self.updated_at = Some(chrono::Utc::now());
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct TokenHash(String);
+
+impl std::fmt::Display for TokenHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<String> for TokenHash {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TokenHash> for String {
+    fn from(value: TokenHash) -> Self {
+        value.0
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FeedToken {
+    id: TokenHash,
+    user_id: UserId,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl FeedToken {
+    pub fn new(token_hash: TokenHash, user_id: UserId) -> Self {
+        Self {
+            id: token_hash,
+            user_id,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id(&self) -> TokenHash {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+}
+
+/// Identifies the user an email sent to their inbound address belongs to. Like feed
+/// tokens only the hash of the token in the address is stored.
+#[derive(Serialize, Deserialize)]
+pub struct InboundAddressToken {
+    id: TokenHash,
+    user_id: UserId,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl InboundAddressToken {
+    pub fn new(token_hash: TokenHash, user_id: UserId) -> Self {
+        Self {
+            id: token_hash,
+            user_id,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id(&self) -> TokenHash {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AppPassword {
+    id: TokenHash,
+    user_id: UserId,
+    principal_name: String,
+    /// The verified email of the session that created the password, see
+    /// [`crate::auth::AuthContext::verified_email`].
+    #[serde(default)]
+    verified_email: Option<String>,
+    name: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AppPassword {
+    pub fn new(
+        token_hash: TokenHash,
+        user_id: UserId,
+        principal_name: String,
+        verified_email: Option<String>,
+        name: String,
+    ) -> Self {
+        Self {
+            id: token_hash,
+            user_id,
+            principal_name,
+            verified_email,
+            name,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id(&self) -> TokenHash {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn principal_name(&self) -> &str {
+        &self.principal_name
+    }
+
+    pub fn verified_email(&self) -> Option<&str> {
+        self.verified_email.as_deref()
+    }
+}
+
+/// What a personal access token may be used for. Interactive sessions hold every scope.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    Write,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 2] = [Scope::Read, Scope::Write];
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PersonalAccessToken {
+    id: TokenHash,
+    user_id: UserId,
+    principal_name: String,
+    /// The verified email of the session that created the token, see
+    /// [`crate::auth::AuthContext::verified_email`].
+    #[serde(default)]
+    verified_email: Option<String>,
+    name: String,
+    scopes: Vec<Scope>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PersonalAccessToken {
+    pub fn new(
+        token_hash: TokenHash,
+        user_id: UserId,
+        principal_name: String,
+        verified_email: Option<String>,
+        name: String,
+        scopes: Vec<Scope>,
+    ) -> Self {
+        Self {
+            id: token_hash,
+            user_id,
+            principal_name,
+            verified_email,
+            name,
+            scopes,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id(&self) -> TokenHash {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn principal_name(&self) -> &str {
+        &self.principal_name
+    }
+
+    pub fn verified_email(&self) -> Option<&str> {
+        self.verified_email.as_deref()
+    }
+
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct ShareLinkId(Uuid);
+
+impl ShareLinkId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for ShareLinkId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for ShareLinkId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<Uuid> for ShareLinkId {
+    fn from(value: Uuid) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ShareLinkId> for String {
+    fn from(value: ShareLinkId) -> Self {
+        value.to_string()
+    }
+}
+
+/// A public, read-only link to a user's todo list that works without signing in. The link
+/// itself is signed, the record is only kept so that it can be listed and revoked.
+#[derive(Serialize, Deserialize)]
+pub struct ShareLink {
+    id: ShareLinkId,
+    user_id: UserId,
+    name: String,
+    #[serde(default)]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ShareLink {
+    pub fn new(
+        user_id: UserId,
+        name: String,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        Self {
+            id: ShareLinkId::new(),
+            user_id,
+            name,
+            expires_at,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id(&self) -> ShareLinkId {
+        self.id
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.expires_at
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= chrono::Utc::now())
+    }
+}
+
+/// A user who has used the app, recorded so that operators can find them.
+#[derive(Serialize, Deserialize)]
+pub struct User {
+    id: UserId,
+    principal_name: String,
+    first_seen_at: chrono::DateTime<chrono::Utc>,
+    last_seen_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    notification_preferences: NotificationPreferences,
+}
+
+impl User {
+    pub fn new(id: UserId, principal_name: String) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id,
+            principal_name,
+            first_seen_at: now,
+            last_seen_at: now,
+            notification_preferences: NotificationPreferences::default(),
+        }
+    }
+
+    pub fn id(&self) -> UserId {
+        self.id.clone()
+    }
+
+    pub fn principal_name(&self) -> &str {
+        &self.principal_name
+    }
+
+    pub fn last_seen_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.last_seen_at
+    }
+
+    pub fn seen(&mut self, principal_name: String) {
+        self.principal_name = principal_name;
+        self.last_seen_at = chrono::Utc::now();
+    }
+
+    pub fn notification_preferences(&self) -> &NotificationPreferences {
+        &self.notification_preferences
+    }
+
+    pub fn update_notification_preferences(&mut self, preferences: NotificationPreferences) {
+        self.notification_preferences = preferences;
+    }
+}
+
+/// How a user wants to hear about their todos. Emails go to their principal name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct NotificationPreferences {
+    /// Reminders and digests are scheduled and written in the user's time zone.
+    #[serde(default)]
+    pub time_zone: chrono_tz::Tz,
+    /// Reminders of todos due soon, for the todos of the user's list and those assigned
+    /// to them.
+    #[serde(default = "default_true")]
+    pub email_reminders: bool,
+    /// A morning email listing the open todos of the user's list.
+    #[serde(default)]
+    pub daily_digest: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            time_zone: chrono_tz::Tz::UTC,
+            email_reminders: true,
+            daily_digest: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Reminder,
+    DailyDigest,
+    Push,
+}
+
+/// A notification sent to a user, recorded before it is sent so that it is sent once,
+/// however many instances run and however often they restart. Cosmos DB removes the
+/// records after `ttl` seconds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Notification {
+    id: String,
+    user_id: UserId,
+    kind: NotificationKind,
+    created_at: chrono::DateTime<chrono::Utc>,
+    ttl: u64,
+}
+
+impl Notification {
+    /// A reminder of the todo, once per due date so that postponed todos are reminded of
+    /// again.
+    pub fn reminder(
+        user_id: UserId,
+        todo_id: TodoId,
+        due_at: chrono::DateTime<chrono::Utc>,
+        ttl: u64,
+    ) -> Self {
+        let key = format!("{}\n{}", todo_id, due_at.timestamp());
+        Self::new(user_id, NotificationKind::Reminder, &key, ttl)
+    }
+
+    /// The digest of a day in the user's time zone.
+    pub fn daily_digest(user_id: UserId, date: chrono::NaiveDate, ttl: u64) -> Self {
+        let key = date.format("%Y-%m-%d").to_string();
+        Self::new(user_id, NotificationKind::DailyDigest, &key, ttl)
+    }
+
+    /// A push message of the todo to one of the user's subscriptions, once per due date.
+    pub fn push(
+        user_id: UserId,
+        subscription_id: &str,
+        todo_id: TodoId,
+        due_at: chrono::DateTime<chrono::Utc>,
+        ttl: u64,
+    ) -> Self {
+        let key = format!("{}\n{}\n{}", subscription_id, todo_id, due_at.timestamp());
+        Self::new(user_id, NotificationKind::Push, &key, ttl)
+    }
+
+    fn new(user_id: UserId, kind: NotificationKind, key: &str, ttl: u64) -> Self {
+        let name = format!("{:?}\n{}", kind, key);
+        Self {
+            id: Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string(),
+            user_id,
+            kind,
+            created_at: chrono::Utc::now(),
+            ttl,
+        }
+    }
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn kind(&self) -> NotificationKind {
+        self.kind
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    ViewDashboard,
+    ListUsers,
+    ImpersonateUser,
+    DeleteUserData,
+    ViewAuditLog,
+}
+
+/// A record of an action taken by an admin. Entries are partitioned by month, so that
+/// the log of a month can be listed in order without a cross-partition query.
+#[derive(Serialize, Deserialize)]
+pub struct AuditEntry {
+    id: Uuid,
+    period: String,
+    actor_id: UserId,
+    actor_name: String,
+    action: AuditAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target_user_id: Option<UserId>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        actor_id: UserId,
+        actor_name: String,
+        action: AuditAction,
+        target_user_id: Option<UserId>,
+    ) -> Self {
+        let created_at = chrono::Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            period: Self::period_of(created_at),
+            actor_id,
+            actor_name,
+            action,
+            target_user_id,
+            created_at,
+        }
+    }
+
+    pub fn period_of(date: chrono::DateTime<chrono::Utc>) -> String {
+        date.format("%Y-%m").to_string()
+    }
+
+    pub fn period(&self) -> &str {
+        &self.period
+    }
+}
+
+/// Storage used by one Cosmos DB container, as reported by the database.
+#[derive(Serialize)]
+pub struct ContainerUsage {
+    pub name: String,
+    pub documents_count: i64,
+    pub documents_size_kb: u64,
+}
+
+/// What a member of a shared todo list may do with it. The owner may do anything.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ListRole {
+    Viewer,
+    Editor,
+}
+
+/// Grants a user access to the todo list of another user. Memberships are partitioned
+/// by member, so that access checks and the lists shared with someone are cheap reads.
+#[derive(Serialize, Deserialize)]
+pub struct ListMember {
+    id: String,
+    owner_id: UserId,
+    owner_name: String,
+    member_id: UserId,
+    member_name: String,
+    role: ListRole,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ListMember {
+    pub fn new(
+        owner_id: UserId,
+        owner_name: String,
+        member_id: UserId,
+        member_name: String,
+        role: ListRole,
+    ) -> Self {
+        Self {
+            id: Self::id_for(&owner_id, &member_id),
+            owner_id,
+            owner_name,
+            member_id,
+            member_name,
+            role,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    /// A user is a member of a list at most once, so the id is derived from both users.
+    pub fn id_for(owner_id: &UserId, member_id: &UserId) -> String {
+        let name = format!("{}\n{}", owner_id, member_id);
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string()
+    }
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn owner_id(&self) -> UserId {
+        self.owner_id.clone()
+    }
+
+    pub fn owner_name(&self) -> &str {
+        &self.owner_name
+    }
+
+    pub fn member_id(&self) -> UserId {
+        self.member_id.clone()
+    }
+
+    pub fn member_name(&self) -> &str {
+        &self.member_name
+    }
+
+    pub fn role(&self) -> ListRole {
+        self.role
+    }
+}
+
+/// The first request made with an idempotency key, and its response once there is one.
+/// Cosmos DB removes records after `ttl` seconds.
+#[derive(Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    id: String,
+    user_id: UserId,
+    request_hash: String,
+    #[serde(default)]
+    response: Option<StoredResponse>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    ttl: u64,
+}
+
+impl IdempotencyRecord {
+    pub fn new(user_id: UserId, key: &str, request_hash: String, ttl: u64) -> Self {
+        Self {
+            id: Self::id_for(key),
+            user_id,
+            request_hash,
+            response: None,
+            created_at: chrono::Utc::now(),
+            ttl,
+        }
+    }
+
+    /// Keys are chosen by clients and may contain characters Cosmos DB ids cannot, so
+    /// records are stored under a hash of the key.
+    pub fn id_for(key: &str) -> String {
+        format!("{:x}", Sha256::digest(key.as_bytes()))
+    }
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn request_hash(&self) -> &str {
+        &self.request_hash
+    }
+
+    pub fn response(&self) -> Option<&StoredResponse> {
+        self.response.as_ref()
+    }
+
+    pub fn complete(&mut self, response: StoredResponse) {
+        self.response = Some(response);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// Base64 encoded, as bodies need not be text.
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct WebhookId(Uuid);
+
+impl WebhookId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for WebhookId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for WebhookId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<WebhookId> for String {
+    fn from(value: WebhookId) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub enum WebhookEventType {
+    #[serde(rename = "todo.created")]
+    TodoCreated,
+    #[serde(rename = "todo.completed")]
+    TodoCompleted,
+    #[serde(rename = "todo.deleted")]
+    TodoDeleted,
+}
+
+impl WebhookEventType {
+    pub const ALL: [WebhookEventType; 3] = [
+        WebhookEventType::TodoCreated,
+        WebhookEventType::TodoCompleted,
+        WebhookEventType::TodoDeleted,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEventType::TodoCreated => "todo.created",
+            WebhookEventType::TodoCompleted => "todo.completed",
+            WebhookEventType::TodoDeleted => "todo.deleted",
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookEventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == s)
+            .ok_or_else(|| format!("Unknown event type {}", s))
+    }
+}
+
+/// A URL that is sent the user's todo events of the selected types. Deliveries are signed
+/// with the secret, so it is stored as is rather than hashed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    id: WebhookId,
+    user_id: UserId,
+    url: String,
+    event_types: Vec<WebhookEventType>,
+    secret: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Webhook {
+    pub fn new(
+        user_id: UserId,
+        url: String,
+        event_types: Vec<WebhookEventType>,
+        secret: String,
+    ) -> Self {
+        Self {
+            id: WebhookId::new(),
+            user_id,
+            url,
+            event_types,
+            secret,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id(&self) -> WebhookId {
+        self.id
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn event_types(&self) -> &[WebhookEventType] {
+        &self.event_types
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+
+    pub fn is_subscribed_to(&self, event_type: WebhookEventType) -> bool {
+        self.event_types.contains(&event_type)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// One event to be delivered to a webhook, which doubles as its entry in the webhook's
+/// delivery log. Pending deliveries form the retry queue, ordered by `next_attempt_at`.
+/// Cosmos DB removes deliveries after `ttl` seconds.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    id: String,
+    webhook_id: WebhookId,
+    user_id: UserId,
+    event_type: WebhookEventType,
+    /// The request body, kept as sent so that every attempt is signed over the same bytes.
+    payload: String,
+    status: DeliveryStatus,
+    attempts: u32,
+    /// Seconds since the epoch, so that the queue can be queried by comparing numbers.
+    #[serde(with = "chrono::serde::ts_seconds")]
+    next_attempt_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    last_response_status: Option<u16>,
+    #[serde(default)]
+    last_error: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    ttl: u64,
+    /// Set by Cosmos DB, for claiming deliveries with optimistic concurrency.
+    #[serde(rename = "_etag", default, skip_serializing)]
+    etag: Option<String>,
+}
+
+impl WebhookDelivery {
+    /// `dedupe_key` tells events apart, so that the same event read by several instances,
+    /// or seen again in a later change of the todo, is delivered once.
+    pub fn new(
+        webhook: &Webhook,
+        event_type: WebhookEventType,
+        dedupe_key: &str,
+        payload: String,
+        ttl: u64,
+    ) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: Self::id_for(webhook.id(), event_type, dedupe_key),
+            webhook_id: webhook.id(),
+            user_id: webhook.user_id(),
+            event_type,
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_response_status: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+            ttl,
+            etag: None,
+        }
+    }
+
+    pub fn id_for(webhook_id: WebhookId, event_type: WebhookEventType, dedupe_key: &str) -> String {
+        let name = format!("{}\n{}\n{}", webhook_id, event_type.as_str(), dedupe_key);
+        Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string()
+    }
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn webhook_id(&self) -> WebhookId {
+        self.webhook_id
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn event_type(&self) -> WebhookEventType {
+        self.event_type
+    }
+
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    pub fn status(&self) -> DeliveryStatus {
+        self.status
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn next_attempt_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.next_attempt_at
+    }
+
+    pub fn last_response_status(&self) -> Option<u16> {
+        self.last_response_status
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Hides the delivery from other workers until the attempt is over.
+    pub fn lock_until(&mut self, until: chrono::DateTime<chrono::Utc>) {
+        self.next_attempt_at = until;
+    }
+
+    pub fn succeed(&mut self, response_status: u16) {
+        self.attempts += 1;
+        self.status = DeliveryStatus::Succeeded;
+        self.last_response_status = Some(response_status);
+        self.last_error = None;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    /// Schedules another attempt after `backoff`, or gives up when there is none.
+    pub fn fail(
+        &mut self,
+        response_status: Option<u16>,
+        error: String,
+        backoff: Option<chrono::Duration>,
+    ) {
+        let now = chrono::Utc::now();
+        self.attempts += 1;
+        self.last_response_status = response_status;
+        self.last_error = Some(error);
+        self.updated_at = now;
+        match backoff {
+            Some(backoff) => self.next_attempt_at = now + backoff,
+            None => self.status = DeliveryStatus::Failed,
+        }
+    }
+}
+
+/// A browser's subscription to push messages, as created with the Push API. Subscribing
+/// the same browser again replaces its subscription.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PushSubscription {
+    id: String,
+    user_id: UserId,
+    endpoint: String,
+    /// The browser's P-256 public key, base64url encoded.
+    p256dh: String,
+    /// The browser's authentication secret, base64url encoded.
+    auth: String,
+    #[serde(default)]
+    user_agent: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PushSubscription {
+    pub fn new(
+        user_id: UserId,
+        endpoint: String,
+        p256dh: String,
+        auth: String,
+        user_agent: Option<String>,
+    ) -> Self {
+        Self {
+            id: Self::id_for(&endpoint),
+            user_id,
+            endpoint,
+            p256dh,
+            auth,
+            user_agent,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn id_for(endpoint: &str) -> String {
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, endpoint.as_bytes()).to_string()
+    }
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn user_id(&self) -> UserId {
+        self.user_id.clone()
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn p256dh(&self) -> &str {
+        &self.p256dh
+    }
+
+    pub fn auth(&self) -> &str {
+        &self.auth
+    }
+
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
     }
 }
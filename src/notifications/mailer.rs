@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use secrecy::ExposeSecret;
+
+use crate::configuration::{SmtpSettings, SmtpTls};
+
+/// Sends emails through the configured mail server.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(settings: &SmtpSettings) -> anyhow::Result<Self> {
+        let mut builder = match settings.tls {
+            SmtpTls::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)?
+            }
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host)?,
+            SmtpTls::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)
+            }
+        };
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                password.expose_secret().to_string(),
+            ));
+        }
+
+        Ok(Self {
+            transport: builder
+                .timeout(Some(Duration::from_secs(settings.timeout_seconds)))
+                .build(),
+            from: settings
+                .from
+                .parse()
+                .context("The from address of notifications is not valid")?,
+        })
+    }
+
+    /// Sends an email with a plain text and an HTML body.
+    pub async fn send(
+        &self,
+        to: Mailbox,
+        subject: &str,
+        text: String,
+        html: String,
+    ) -> anyhow::Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .multipart(MultiPart::alternative_plain_html(text, html))?;
+
+        self.transport
+            .send(message)
+            .await
+            .context("Failed to send email")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+mod mailer;
+mod scheduler;
+
+pub use mailer::*;
+pub use scheduler::*;
+
+pub use crate::model::{
+    Notification, NotificationKind, NotificationPreferences, Todo, TodoContent, TodoId, User,
+    UserId,
+};
+
+/// Like the change feed, implementations run on the actix runtime, which does not need
+/// `Send` futures.
+#[allow(async_fn_in_trait)]
+pub trait DueTodoRepository {
+    /// Open todos due at or after `from` and before `to`, across all lists.
+    async fn get_open_due_between(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<Vec<Todo>>;
+    /// Open todos of the user's list.
+    async fn get_open_for_user(&self, user_id: UserId) -> anyhow::Result<Vec<Todo>>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait SubscriberRepository {
+    async fn get_subscriber(&self, user_id: UserId) -> anyhow::Result<Option<User>>;
+    /// Users who opted into the daily digest, across all partitions.
+    async fn get_digest_subscribers(&self) -> anyhow::Result<Vec<User>>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait NotificationRepository {
+    /// Records the notification, unless it has been recorded before. Tells whether it was
+    /// recorded.
+    async fn create_if_absent(&self, notification: Notification) -> anyhow::Result<bool>;
+    async fn delete(&self, notification: Notification) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
@@ -0,0 +1,287 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::Timelike;
+use lettre::message::Mailbox;
+use serde::Serialize;
+use tera::Tera;
+
+use super::{DueTodoRepository, NotificationRepository, SmtpMailer, SubscriberRepository};
+use crate::{
+    configuration::NotificationSettings,
+    model::{Notification, Todo, User, UserId},
+};
+
+/// Todos due up to this long ago are still reminded of, so that reminders that fell due
+/// while no instance ran go out late rather than never.
+const REMINDER_CATCH_UP_MINUTES: i64 = 60;
+/// Notifications are recorded for longer than they could be sent again.
+const RECORD_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+const DUE_AT_FORMAT: &str = "%a %-d %b %H:%M %Z";
+
+/// A todo as shown in emails, with its due date in the recipient's time zone.
+#[derive(Serialize)]
+struct TodoView {
+    content: String,
+    due_at: Option<String>,
+}
+
+/// Sends reminders of todos due soon and the daily digests. Every instance runs a
+/// scheduler, they record notifications before sending them so that each is sent once.
+pub struct NotificationScheduler<T, U, N> {
+    todos: T,
+    users: U,
+    notifications: N,
+    mailer: SmtpMailer,
+    tmpl: Tera,
+    settings: NotificationSettings,
+}
+
+impl<T, U, N> NotificationScheduler<T, U, N>
+where
+    T: DueTodoRepository,
+    U: SubscriberRepository,
+    N: NotificationRepository,
+{
+    pub fn new(
+        todos: T,
+        users: U,
+        notifications: N,
+        tmpl: Tera,
+        settings: NotificationSettings,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            todos,
+            users,
+            notifications,
+            mailer: SmtpMailer::new(&settings.smtp)?,
+            tmpl,
+            settings,
+        })
+    }
+
+    /// Polls until the process exits. Failed polls are retried on the next tick.
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.settings.poll_interval_seconds));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll(chrono::Utc::now()).await {
+                tracing::error!(error = ?e, "Failed to send notifications");
+            }
+        }
+    }
+
+    /// Sends the reminders and digests due at `now`. Returns the number of emails sent.
+    #[tracing::instrument(name = "Poll notifications", skip(self))]
+    pub async fn poll(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<usize> {
+        let reminders = self.send_reminders(now).await?;
+        let digests = self.send_digests(now).await?;
+        Ok(reminders + digests)
+    }
+
+    async fn send_reminders(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<usize> {
+        let from = now - chrono::Duration::minutes(REMINDER_CATCH_UP_MINUTES);
+        let to = now + chrono::Duration::minutes(self.settings.reminder_lead_minutes as i64);
+        let todos = self.todos.get_open_due_between(from, to).await?;
+
+        let mut recipients = HashMap::<UserId, Option<User>>::new();
+        let mut sent = 0;
+        for todo in todos {
+            let Some(due_at) = todo.due_at() else {
+                continue;
+            };
+            // Assigned todos are up to their assignee.
+            let recipient_id = todo
+                .assignee()
+                .cloned()
+                .unwrap_or_else(|| todo.created_by());
+            if !recipients.contains_key(&recipient_id) {
+                let recipient = self.users.get_subscriber(recipient_id.clone()).await?;
+                recipients.insert(recipient_id.clone(), recipient);
+            }
+            let Some(recipient) = recipients[&recipient_id]
+                .as_ref()
+                .filter(|recipient| recipient.notification_preferences().email_reminders)
+            else {
+                continue;
+            };
+
+            let notification =
+                Notification::reminder(recipient_id.clone(), todo.id(), due_at, RECORD_TTL_SECONDS);
+            let Some(to) = self.claim(&notification, recipient).await? else {
+                continue;
+            };
+
+            let list_path = if todo.created_by() == recipient_id {
+                "/me/todos"
+            } else {
+                "/me/todos/assigned"
+            };
+            let mut context = tera::Context::new();
+            context.insert(
+                "todo",
+                &todo_view(&todo, recipient.notification_preferences().time_zone),
+            );
+            context.insert("overdue", &(due_at <= now));
+            context.insert(
+                "list_url",
+                &format!("{}{}", self.settings.base_url, list_path),
+            );
+            let subject = format!("Reminder: {}", todo.content().as_ref());
+            if self
+                .deliver(notification, to, &subject, "reminder", &context)
+                .await
+            {
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    async fn send_digests(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<usize> {
+        let mut sent = 0;
+        for subscriber in self.users.get_digest_subscribers().await? {
+            let time_zone = subscriber.notification_preferences().time_zone;
+            let local_now = now.with_timezone(&time_zone);
+            if local_now.hour() < self.settings.digest_hour {
+                continue;
+            }
+
+            let notification = Notification::daily_digest(
+                subscriber.id(),
+                local_now.date_naive(),
+                RECORD_TTL_SECONDS,
+            );
+            let Some(to) = self.claim(&notification, &subscriber).await? else {
+                continue;
+            };
+
+            let mut todos = match self.todos.get_open_for_user(subscriber.id()).await {
+                Ok(todos) => todos,
+                Err(e) => {
+                    self.release(notification).await;
+                    return Err(e);
+                }
+            };
+            // Nothing to do is nothing to write about, the digest of the day is skipped.
+            if todos.is_empty() {
+                continue;
+            }
+            todos.sort_by_key(|todo| (todo.due_at().is_none(), todo.due_at()));
+            let (overdue, open) = todos
+                .iter()
+                .partition::<Vec<_>, _>(|todo| todo.due_at().is_some_and(|due_at| due_at <= now));
+
+            let date = local_now.format("%A %-d %B").to_string();
+            let mut context = tera::Context::new();
+            context.insert("date", &date);
+            context.insert(
+                "overdue",
+                &overdue
+                    .into_iter()
+                    .map(|todo| todo_view(todo, time_zone))
+                    .collect::<Vec<_>>(),
+            );
+            context.insert(
+                "open",
+                &open
+                    .into_iter()
+                    .map(|todo| todo_view(todo, time_zone))
+                    .collect::<Vec<_>>(),
+            );
+            context.insert("list_url", &format!("{}/me/todos", self.settings.base_url));
+            context.insert(
+                "preferences_url",
+                &format!("{}/me/notifications", self.settings.base_url),
+            );
+            let subject = format!("Your todos for {}", date);
+            if self
+                .deliver(notification, to, &subject, "daily_digest", &context)
+                .await
+            {
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Records the notification before it is sent. Returns the address to send it to,
+    /// or `None` when it has been sent before or the user has no email address.
+    async fn claim(
+        &self,
+        notification: &Notification,
+        recipient: &User,
+    ) -> anyhow::Result<Option<Mailbox>> {
+        let Ok(to) = recipient.principal_name().parse::<Mailbox>() else {
+            tracing::debug!(user_id = %recipient.id(), "User has no email address to notify");
+            return Ok(None);
+        };
+
+        if !self
+            .notifications
+            .create_if_absent(notification.clone())
+            .await?
+        {
+            return Ok(None);
+        }
+        Ok(Some(to))
+    }
+
+    /// Sends a claimed notification, rendered from the `emails/<template>.txt` and
+    /// `emails/<template>.html` templates. Failed notifications are released, so that
+    /// they are retried on the next poll. Tells whether it was sent.
+    #[tracing::instrument(
+        name = "Send notification",
+        skip(self, notification, to, subject, context),
+        fields(notification_id = %notification.id(), user_id = %notification.user_id())
+    )]
+    async fn deliver(
+        &self,
+        notification: Notification,
+        to: Mailbox,
+        subject: &str,
+        template: &str,
+        context: &tera::Context,
+    ) -> bool {
+        let result = async {
+            let text = self
+                .tmpl
+                .render(&format!("emails/{}.txt", template), context)?;
+            let html = self
+                .tmpl
+                .render(&format!("emails/{}.html", template), context)?;
+            self.mailer.send(to, subject, text, html).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(error = ?e, "Failed to send notification");
+                self.release(notification).await;
+                false
+            }
+        }
+    }
+
+    async fn release(&self, notification: Notification) {
+        if let Err(e) = self.notifications.delete(notification).await {
+            tracing::error!(error = ?e, "Failed to release notification");
+        }
+    }
+}
+
+fn todo_view(todo: &Todo, time_zone: chrono_tz::Tz) -> TodoView {
+    TodoView {
+        content: todo.content().as_ref().to_string(),
+        due_at: todo.due_at().map(|due_at| {
+            due_at
+                .with_timezone(&time_zone)
+                .format(DUE_AT_FORMAT)
+                .to_string()
+        }),
+    }
+}
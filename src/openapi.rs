@@ -0,0 +1,53 @@
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{problem::Problem, routes};
+
+/// The contract of the todo endpoints, generated from the handlers and the types they
+/// accept and return.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Todo App",
+        description = "Todo lists of signed in users. Outside the browser, requests are authenticated with a personal access token."
+    ),
+    paths(
+        routes::me::todos::get_all_user_todos,
+        routes::me::todos::create_todo,
+        routes::me::todos::get_todo,
+        routes::me::todos::update_todo,
+        routes::me::todos::delete_todo,
+        routes::me::todos::get_todos_assigned_to_me,
+        routes::me::todos::get_todo_events,
+        routes::me::todos::open_todo_socket,
+        routes::me::todos::get_user_todos_calendar,
+        routes::me::todos::get_user_todos_markdown,
+        routes::me::todos::preview_todo,
+    ),
+    // Schemas used by the handlers are collected on their own, problem responses refer
+    // to theirs by name.
+    components(schemas(Problem)),
+    modifiers(&PersonalAccessTokenAuth),
+    security(("personal_access_token" = [])),
+    tags((name = "todos", description = "The caller's todo list, or a list shared with them"))
+)]
+pub(crate) struct ApiDoc;
+
+struct PersonalAccessTokenAuth;
+
+impl Modify for PersonalAccessTokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "personal_access_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("A personal access token created on `/me/tokens`"))
+                    .build(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::model::UserId;
+
+/// Presence changes are only a hint to refresh the viewers, missing some is harmless.
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Viewer {
+    pub user_id: UserId,
+    pub name: String,
+}
+
+/// Keeps track of who has a todo list open over a WebSocket. Like the event bus it lives
+/// in memory, so every instance only knows the viewers connected to it.
+pub struct PresenceRegistry {
+    viewers: Mutex<HashMap<UserId, Vec<(u64, Viewer)>>>,
+    next_connection_id: AtomicU64,
+    /// Sends the owner of every list whose viewers changed.
+    sender: broadcast::Sender<UserId>,
+}
+
+impl PresenceRegistry {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            viewers: Mutex::new(HashMap::new()),
+            next_connection_id: AtomicU64::new(0),
+            sender,
+        }
+    }
+
+    /// Adds the viewer to the list until the returned guard is dropped.
+    pub fn join(registry: Arc<Self>, owner_id: UserId, viewer: Viewer) -> PresenceGuard {
+        let connection_id = registry.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        registry
+            .viewers
+            .lock()
+            .unwrap()
+            .entry(owner_id.clone())
+            .or_default()
+            .push((connection_id, viewer));
+        let _ = registry.sender.send(owner_id.clone());
+
+        PresenceGuard {
+            registry,
+            owner_id,
+            connection_id,
+        }
+    }
+
+    /// Everybody viewing the list, once however many connections they have open.
+    pub fn viewers(&self, owner_id: &UserId) -> Vec<Viewer> {
+        let mut viewers: Vec<Viewer> = Vec::new();
+        for (_, viewer) in self
+            .viewers
+            .lock()
+            .unwrap()
+            .get(owner_id)
+            .into_iter()
+            .flatten()
+        {
+            if !viewers.iter().any(|v| v.user_id == viewer.user_id) {
+                viewers.push(viewer.clone());
+            }
+        }
+        viewers
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<UserId> {
+        self.sender.subscribe()
+    }
+
+    fn leave(&self, owner_id: &UserId, connection_id: u64) {
+        let mut lists = self.viewers.lock().unwrap();
+        if let Some(viewers) = lists.get_mut(owner_id) {
+            viewers.retain(|(id, _)| *id != connection_id);
+            if viewers.is_empty() {
+                lists.remove(owner_id);
+            }
+        }
+        drop(lists);
+        let _ = self.sender.send(owner_id.clone());
+    }
+}
+
+impl Default for PresenceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes the viewer from the list when the connection ends, however it ends.
+pub struct PresenceGuard {
+    registry: Arc<PresenceRegistry>,
+    owner_id: UserId,
+    connection_id: u64,
+}
+
+impl Drop for PresenceGuard {
+    fn drop(&mut self) {
+        self.registry.leave(&self.owner_id, self.connection_id);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, HttpMessage, HttpResponse, ResponseError,
+};
+use opentelemetry::trace::TraceContextExt;
+use serde::Serialize;
+use tera::Tera;
+use tracing_actix_web::RequestId;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utoipa::{
+    openapi::{ContentBuilder, Ref, RefOr, Response, ResponseBuilder},
+    ToSchema,
+};
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// CalDAV clients expect WebDAV error bodies, which are passed through untouched.
+const PASSTHROUGH_PATHS: [&str; 2] = ["/dav", "/.well-known/caldav"];
+
+/// A request field that failed validation. Handlers return it as their validation error,
+/// so that the error response lists the field.
+#[derive(Debug, Clone, Serialize, ToSchema, thiserror::Error)]
+#[error("{message}")]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl std::fmt::Display) -> Self {
+        Self {
+            field,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl ResponseError for FieldError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::new(self.status_code());
+        res.extensions_mut().insert(FieldErrors(vec![self.clone()]));
+        res
+    }
+}
+
+/// Field errors travel to `problem_middleware` in the extensions of the error response.
+#[derive(Clone)]
+struct FieldErrors(Vec<FieldError>);
+
+/// An error response body as described by RFC 7807.
+#[derive(Serialize, ToSchema)]
+pub(crate) struct Problem {
+    #[serde(rename = "type")]
+    #[schema(example = "about:blank")]
+    problem_type: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    /// Fields that failed validation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+    request_id: Option<String>,
+    /// Operation id of the request in Application Insights.
+    trace_id: Option<String>,
+}
+
+/// Renders error responses as `application/problem+json`, or as an error page for
+/// browsers, keeping their status and headers. Every response gets an `X-Request-Id`
+/// header matching the `request_id` of the request's telemetry.
+///
+/// It has to be wrapped inside `TracingLogger`, whose span carries the ids.
+pub(crate) async fn problem_middleware(
+    req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    // The request itself must not be cloned before routing, so what rendering needs is
+    // captured up front.
+    let target = ProblemTarget {
+        request_id: req
+            .extensions()
+            .get::<RequestId>()
+            .map(|request_id| request_id.to_string()),
+        is_passthrough: PASSTHROUGH_PATHS
+            .iter()
+            .any(|prefix| req.path().starts_with(prefix)),
+        tmpl: accepts_html(&req)
+            .then(|| req.app_data::<web::Data<Tera>>().cloned())
+            .flatten(),
+    };
+
+    match next.call(req).await {
+        Ok(res) => {
+            let (req, res) = res.map_into_boxed_body().into_parts();
+            Ok(ServiceResponse::new(req, target.render(res)))
+        }
+        // Errors of other middlewares have no request to build a response with yet, so
+        // they are passed on carrying the rendered response.
+        Err(e) => {
+            let detail = e.to_string();
+            let res = target.render(HttpResponse::from_error(e));
+            Err(InternalError::from_response(detail, res).into())
+        }
+    }
+}
+
+struct ProblemTarget {
+    request_id: Option<String>,
+    is_passthrough: bool,
+    /// Templates, if the client prefers an error page.
+    tmpl: Option<web::Data<Tera>>,
+}
+
+impl ProblemTarget {
+    fn render(&self, mut res: HttpResponse<BoxBody>) -> HttpResponse<BoxBody> {
+        if let Some(request_id) = self
+            .request_id
+            .as_deref()
+            .and_then(|request_id| HeaderValue::from_str(request_id).ok())
+        {
+            res.headers_mut()
+                .insert(header::HeaderName::from_static("x-request-id"), request_id);
+        }
+
+        if self.is_passthrough || !is_problem(&res) {
+            return res;
+        }
+
+        let status = res.status();
+        let detail = match res.error() {
+            // Server errors may describe internals, clients only learn that something failed.
+            _ if status.is_server_error() => "Something went wrong".to_string(),
+            Some(e) => e.to_string(),
+            None => status.canonical_reason().unwrap_or_default().to_string(),
+        };
+        let errors = res
+            .extensions()
+            .get::<FieldErrors>()
+            .map(|errors| errors.0.clone())
+            .unwrap_or_default();
+        let problem = Problem {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail,
+            errors,
+            request_id: self.request_id.clone(),
+            trace_id: trace_id(),
+        };
+
+        let (content_type, body) = match self.render_page(&problem) {
+            Some(html) => ("text/html; charset=utf-8", html),
+            None => match serde_json::to_string(&problem) {
+                Ok(json) => (PROBLEM_CONTENT_TYPE, json),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "Failed to serialize problem");
+                    return res;
+                }
+            },
+        };
+
+        res.headers_mut().remove(header::CONTENT_LENGTH);
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        res.set_body(BoxBody::new(body))
+    }
+
+    /// Falls back to JSON if the page cannot be rendered.
+    fn render_page(&self, problem: &Problem) -> Option<String> {
+        let tmpl = self.tmpl.as_ref()?;
+        let context = tera::Context::from_serialize(problem).ok()?;
+        tmpl.render("error.html", &context)
+            .inspect_err(|e| tracing::warn!(error = ?e, "Failed to render error page"))
+            .ok()
+    }
+}
+
+/// Documents the error statuses of a handler as problem responses. Errors sharing a
+/// status are described in a paragraph each.
+pub(crate) fn responses(
+    errors: impl IntoIterator<Item = (StatusCode, &'static str)>,
+) -> BTreeMap<String, RefOr<Response>> {
+    let mut descriptions = BTreeMap::<String, Vec<&str>>::new();
+    for (status, description) in errors {
+        descriptions
+            .entry(status.as_str().to_string())
+            .or_default()
+            .push(description);
+    }
+
+    descriptions
+        .into_iter()
+        .map(|(status, descriptions)| {
+            let response = ResponseBuilder::new()
+                .description(descriptions.join("\n\n"))
+                .content(
+                    PROBLEM_CONTENT_TYPE,
+                    ContentBuilder::new()
+                        .schema(Some(Ref::from_schema_name("Problem")))
+                        .build(),
+                )
+                .build();
+            (status, response.into())
+        })
+        .collect()
+}
+
+/// Failed requests are rewritten, as are error statuses a handler sent without a body.
+fn is_problem(res: &HttpResponse<BoxBody>) -> bool {
+    let status = res.status();
+    (status.is_client_error() || status.is_server_error())
+        && (res.error().is_some()
+            || matches!(res.body().size(), BodySize::None | BodySize::Sized(0)))
+}
+
+/// Browsers get a page instead of JSON.
+fn accepts_html(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
+
+fn trace_id() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let span_context = context.span().span_context().clone();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id().to_string())
+}
@@ -0,0 +1,94 @@
+use anyhow::{anyhow, ensure};
+use ring::{aead, agreement, hkdf, rand::SecureRandom};
+
+/// The size of the single record messages are sent in.
+const RECORD_SIZE: u32 = 4096;
+const SALT_LEN: usize = 16;
+const PUBLIC_KEY_LEN: usize = 65;
+const AUTH_SECRET_LEN: usize = 16;
+/// The header takes the salt, the record size, the key id length and the public key.
+const HEADER_LEN: usize = SALT_LEN + 4 + 1 + PUBLIC_KEY_LEN;
+/// The record ends in a padding delimiter and the authentication tag.
+pub const MAX_PLAINTEXT_LEN: usize = RECORD_SIZE as usize - HEADER_LEN - 1 - 16;
+
+/// Output lengths of HKDF expansions, which ring types by the keys they make.
+struct OutputLen(usize);
+
+impl hkdf::KeyType for OutputLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// Encrypts a push message for the browser with the `aes128gcm` content encoding of RFC
+/// 8188, keyed as RFC 8291 describes: a fresh key pair of the server is agreed with the
+/// browser's public key, and mixed with the browser's authentication secret.
+pub fn encrypt(
+    plaintext: &[u8],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    rng: &dyn SecureRandom,
+) -> anyhow::Result<Vec<u8>> {
+    ensure!(
+        plaintext.len() <= MAX_PLAINTEXT_LEN,
+        "Push messages may not be longer than {} bytes",
+        MAX_PLAINTEXT_LEN
+    );
+    ensure!(
+        ua_public.len() == PUBLIC_KEY_LEN && auth_secret.len() == AUTH_SECRET_LEN,
+        "The subscription keys are not valid"
+    );
+
+    let mut salt = [0; SALT_LEN];
+    rng.fill(&mut salt)
+        .map_err(|_| anyhow!("Failed to generate salt"))?;
+    let as_private = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, rng)
+        .map_err(|_| anyhow!("Failed to generate key pair"))?;
+    let as_public = as_private
+        .compute_public_key()
+        .map_err(|_| anyhow!("Failed to compute public key"))?;
+
+    let ikm = agreement::agree_ephemeral(
+        as_private,
+        &agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, ua_public),
+        |ecdh_secret| {
+            let info = [b"WebPush: info\0", ua_public, as_public.as_ref()].concat();
+            expand(auth_secret, ecdh_secret, &info, 32)
+        },
+    )
+    .map_err(|_| anyhow!("The subscription's public key is not valid"))??;
+    let cek = expand(&salt, &ikm, b"Content-Encoding: aes128gcm\0", 16)?;
+    let nonce = expand(&salt, &ikm, b"Content-Encoding: nonce\0", 12)?;
+
+    // A single record, which is the last one and so is delimited by 2.
+    let mut record = [plaintext, &[2]].concat();
+    let key = aead::LessSafeKey::new(
+        aead::UnboundKey::new(&aead::AES_128_GCM, &cek)
+            .map_err(|_| anyhow!("Failed to derive content encryption key"))?,
+    );
+    key.seal_in_place_append_tag(
+        aead::Nonce::try_assume_unique_for_key(&nonce)
+            .map_err(|_| anyhow!("Failed to derive nonce"))?,
+        aead::Aad::empty(),
+        &mut record,
+    )
+    .map_err(|_| anyhow!("Failed to encrypt push message"))?;
+
+    let mut body = Vec::with_capacity(HEADER_LEN + record.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(PUBLIC_KEY_LEN as u8);
+    body.extend_from_slice(as_public.as_ref());
+    body.extend_from_slice(&record);
+    Ok(body)
+}
+
+fn expand(salt: &[u8], secret: &[u8], info: &[u8], len: usize) -> anyhow::Result<Vec<u8>> {
+    let mut output = vec![0; len];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+        .extract(secret)
+        .expand(&[info], OutputLen(len))
+        .and_then(|okm| okm.fill(&mut output))
+        .map_err(|_| anyhow!("Failed to derive key"))?;
+    Ok(output)
+}
@@ -0,0 +1,93 @@
+mod encryption;
+mod scheduler;
+mod service;
+mod vapid;
+
+pub use encryption::*;
+pub use scheduler::*;
+pub use service::*;
+pub use vapid::*;
+
+use std::net::IpAddr;
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+pub use crate::model::{PushSubscription, UserId};
+use crate::{configuration::PushSettings, webhooks::is_public};
+
+/// Like the change feed, implementations run on the actix runtime, which does not need
+/// `Send` futures.
+#[allow(async_fn_in_trait)]
+pub trait PushSubscriptionRepository {
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<PushSubscription>> + '_;
+    /// Stores the subscription, replacing an earlier subscription of the same endpoint.
+    async fn save(&self, subscription: PushSubscription) -> anyhow::Result<()>;
+    async fn delete_for_user_by_id(
+        &self,
+        user_id: UserId,
+        subscription_id: String,
+    ) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+/// Sends messages to the push services of browsers, see [`WebPushService`].
+#[allow(async_fn_in_trait)]
+pub trait PushService {
+    async fn send(
+        &self,
+        subscription: &PushSubscription,
+        message: &PushMessage,
+    ) -> anyhow::Result<PushOutcome>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOutcome {
+    Delivered,
+    /// The browser unsubscribed, the subscription is of no further use.
+    Gone,
+}
+
+/// The payload of push messages, which the service worker shows as a notification.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// The page opened when the notification is clicked.
+    pub url: String,
+    /// Notifications with the same tag replace each other.
+    pub tag: String,
+}
+
+/// Checks the endpoint of a subscription before it is stored, the way webhook URLs are
+/// checked. Host names are checked again when they are resolved.
+pub fn check_endpoint(endpoint: &str, settings: &PushSettings) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(endpoint).map_err(|_| "The endpoint is not valid".to_string())?;
+    match url.scheme() {
+        "https" => {}
+        "http" if !settings.require_https => {}
+        _ => return Err("The endpoint must use HTTPS".to_string()),
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("The endpoint may not contain credentials".to_string());
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| "The endpoint must have a host".to_string())?;
+    // IPv6 addresses are enclosed in brackets in URLs.
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>();
+    if let Ok(ip) = ip {
+        if !settings.allow_private_networks && !is_public(ip) {
+            return Err("The endpoint may not point at a private network".to_string());
+        }
+    }
+
+    Ok(url)
+}
@@ -0,0 +1,162 @@
+use std::{collections::HashMap, time::Duration};
+
+use futures::TryStreamExt;
+
+use super::{PushMessage, PushOutcome, PushService, PushSubscriptionRepository};
+use crate::{
+    configuration::PushSettings,
+    model::{Notification, PushSubscription, UserId},
+    notifications::{DueTodoRepository, NotificationRepository},
+};
+
+/// Todos that fell due up to this long ago are still pushed, so that messages that fell
+/// due while no instance ran go out late rather than never.
+const CATCH_UP_MINUTES: i64 = 60;
+/// Messages are recorded for longer than they could be sent again.
+const RECORD_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Pushes todos to the browsers of their assignees, or else their owners, when they
+/// fall due. Every instance runs a scheduler, they record messages before sending them
+/// so that each browser gets each message once.
+pub struct PushScheduler<T, S, N, P> {
+    todos: T,
+    subscriptions: S,
+    notifications: N,
+    service: P,
+    settings: PushSettings,
+}
+
+impl<T, S, N, P> PushScheduler<T, S, N, P>
+where
+    T: DueTodoRepository,
+    S: PushSubscriptionRepository,
+    N: NotificationRepository,
+    P: PushService,
+{
+    pub fn new(
+        todos: T,
+        subscriptions: S,
+        notifications: N,
+        service: P,
+        settings: PushSettings,
+    ) -> Self {
+        Self {
+            todos,
+            subscriptions,
+            notifications,
+            service,
+            settings,
+        }
+    }
+
+    /// Polls until the process exits. Failed polls are retried on the next tick.
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.settings.poll_interval_seconds));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll(chrono::Utc::now()).await {
+                tracing::error!(error = ?e, "Failed to send push messages");
+            }
+        }
+    }
+
+    /// Pushes the todos that fell due by `now`. Returns the number of messages sent.
+    #[tracing::instrument(name = "Poll push messages", skip(self))]
+    pub async fn poll(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<usize> {
+        let from = now - chrono::Duration::minutes(CATCH_UP_MINUTES);
+        let todos = self.todos.get_open_due_between(from, now).await?;
+
+        let mut recipients = HashMap::<UserId, Vec<PushSubscription>>::new();
+        let mut sent = 0;
+        for todo in todos {
+            let Some(due_at) = todo.due_at() else {
+                continue;
+            };
+            // Assigned todos are up to their assignee.
+            let recipient_id = todo
+                .assignee()
+                .cloned()
+                .unwrap_or_else(|| todo.created_by());
+            if !recipients.contains_key(&recipient_id) {
+                let subscriptions = self
+                    .subscriptions
+                    .get_all_for_user(recipient_id.clone())
+                    .try_collect()
+                    .await?;
+                recipients.insert(recipient_id.clone(), subscriptions);
+            }
+
+            let list_path = if todo.created_by() == recipient_id {
+                "/me/todos"
+            } else {
+                "/me/todos/assigned"
+            };
+            let message = PushMessage {
+                title: "Todo due".to_string(),
+                body: todo.content().as_ref().to_string(),
+                url: list_path.to_string(),
+                tag: todo.id().to_string(),
+            };
+            for subscription in &recipients[&recipient_id] {
+                let notification = Notification::push(
+                    recipient_id.clone(),
+                    &subscription.id(),
+                    todo.id(),
+                    due_at,
+                    RECORD_TTL_SECONDS,
+                );
+                if !self
+                    .notifications
+                    .create_if_absent(notification.clone())
+                    .await?
+                {
+                    continue;
+                }
+                if self.deliver(notification, subscription, &message).await {
+                    sent += 1;
+                }
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Sends a claimed message. Failed messages are released, so that they are retried
+    /// on the next poll, and subscriptions the browser gave up are deleted. Tells whether
+    /// it was sent.
+    #[tracing::instrument(
+        name = "Push notification",
+        skip(self, notification, subscription, message),
+        fields(notification_id = %notification.id(), subscription_id = %subscription.id())
+    )]
+    async fn deliver(
+        &self,
+        notification: Notification,
+        subscription: &PushSubscription,
+        message: &PushMessage,
+    ) -> bool {
+        match self.service.send(subscription, message).await {
+            Ok(PushOutcome::Delivered) => true,
+            Ok(PushOutcome::Gone) => {
+                tracing::info!("Deleting push subscription the browser gave up");
+                if let Err(e) = self
+                    .subscriptions
+                    .delete_for_user_by_id(subscription.user_id(), subscription.id())
+                    .await
+                {
+                    tracing::error!(error = ?e, "Failed to delete push subscription");
+                }
+                false
+            }
+            Err(e) => {
+                tracing::warn!(error = ?e, "Failed to send push message");
+                if let Err(e) = self.notifications.delete(notification).await {
+                    tracing::error!(error = ?e, "Failed to release notification");
+                }
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail};
+use reqwest::{header, redirect, StatusCode};
+use ring::rand::SystemRandom;
+use secrecy::ExposeSecret;
+
+use super::{check_endpoint, decode_key, encrypt, PushMessage, PushOutcome, PushService, VapidKey};
+use crate::{
+    configuration::PushSettings, model::PushSubscription, webhooks::PublicAddressResolver,
+};
+
+/// Push services accept tokens that expire within a day.
+const VAPID_TOKEN_LIFETIME_HOURS: i64 = 12;
+
+/// Sends encrypted messages to the push services of browsers, per RFC 8030.
+pub struct WebPushService {
+    client: reqwest::Client,
+    vapid_key: VapidKey,
+    rng: SystemRandom,
+    settings: PushSettings,
+}
+
+impl WebPushService {
+    pub fn new(settings: PushSettings) -> anyhow::Result<Self> {
+        let (Some(public_key), Some(private_key)) =
+            (&settings.vapid_public_key, &settings.vapid_private_key)
+        else {
+            bail!("Push notifications need a VAPID key pair");
+        };
+        let vapid_key = VapidKey::from_base64(public_key, private_key.expose_secret())?;
+
+        // Following redirects or proxies would get around the address checks.
+        let mut client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .timeout(Duration::from_secs(settings.timeout_seconds));
+        if !settings.allow_private_networks {
+            client = client.dns_resolver(Arc::new(PublicAddressResolver));
+        }
+
+        Ok(Self {
+            client: client.build()?,
+            vapid_key,
+            rng: SystemRandom::new(),
+            settings,
+        })
+    }
+}
+
+impl PushService for WebPushService {
+    #[tracing::instrument(
+        name = "Send push message",
+        skip(self, subscription, message),
+        fields(subscription_id = %subscription.id(), user_id = %subscription.user_id())
+    )]
+    async fn send(
+        &self,
+        subscription: &PushSubscription,
+        message: &PushMessage,
+    ) -> anyhow::Result<PushOutcome> {
+        // Subscriptions that no longer pass the checks, e.g. after the settings changed,
+        // cannot be used again.
+        let Ok(endpoint) = check_endpoint(subscription.endpoint(), &self.settings) else {
+            return Ok(PushOutcome::Gone);
+        };
+        let (Ok(ua_public), Ok(auth_secret)) = (
+            decode_key(subscription.p256dh()),
+            decode_key(subscription.auth()),
+        ) else {
+            return Ok(PushOutcome::Gone);
+        };
+
+        let body = encrypt(
+            &serde_json::to_vec(message)?,
+            &ua_public,
+            &auth_secret,
+            &self.rng,
+        )?;
+        let authorization = self.vapid_key.authorization(
+            &endpoint,
+            &self.settings.vapid_subject,
+            chrono::Utc::now() + chrono::Duration::hours(VAPID_TOKEN_LIFETIME_HOURS),
+        )?;
+
+        let response = self
+            .client
+            .post(endpoint)
+            .header(header::AUTHORIZATION, authorization)
+            .header(header::CONTENT_ENCODING, "aes128gcm")
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .header("TTL", self.settings.message_ttl_seconds)
+            .header("Urgency", "high")
+            .body(body)
+            .send()
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(PushOutcome::Delivered),
+            StatusCode::NOT_FOUND | StatusCode::GONE => Ok(PushOutcome::Gone),
+            status => Err(anyhow!("The push service responded with {}", status)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Context};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    aud: &'a str,
+    exp: i64,
+    sub: &'a str,
+}
+
+/// The key pair the server identifies itself to push services with, per RFC 8292.
+/// Browsers only accept messages signed with the key they subscribed with.
+pub struct VapidKey {
+    key_pair: EcdsaKeyPair,
+    rng: SystemRandom,
+}
+
+impl VapidKey {
+    /// Reads the key pair from its base64url encoded raw keys, the uncompressed public
+    /// point and the private scalar, as VAPID key generators print them.
+    pub fn from_base64(public_key: &str, private_key: &str) -> anyhow::Result<Self> {
+        let public_key = decode_key(public_key).context("The VAPID public key is not base64url")?;
+        let private_key =
+            decode_key(private_key).context("The VAPID private key is not base64url")?;
+        let rng = SystemRandom::new();
+        let key_pair = EcdsaKeyPair::from_private_key_and_public_key(
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
+            &private_key,
+            &public_key,
+            &rng,
+        )
+        .map_err(|e| anyhow!("The VAPID key pair is not valid: {}", e))?;
+        Ok(Self { key_pair, rng })
+    }
+
+    /// The base64url encoded public key, which browsers subscribe with as their
+    /// `applicationServerKey`.
+    pub fn public_key(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(self.key_pair.public_key())
+    }
+
+    /// The `Authorization` header for sending to the endpoint: a signed token for the
+    /// origin of the endpoint, and the key to check it with.
+    pub fn authorization(
+        &self,
+        endpoint: &reqwest::Url,
+        subject: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<String> {
+        let header = BASE64_URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&Claims {
+            aud: &endpoint.origin().ascii_serialization(),
+            exp: expires_at.timestamp(),
+            sub: subject,
+        })?);
+        let signing_input = format!("{}.{}", header, claims);
+        let signature = self
+            .key_pair
+            .sign(&self.rng, signing_input.as_bytes())
+            .map_err(|_| anyhow!("Failed to sign VAPID token"))?;
+
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            signing_input,
+            BASE64_URL_SAFE_NO_PAD.encode(signature.as_ref()),
+            self.public_key()
+        ))
+    }
+}
+
+/// Decodes base64url, with or without the padding some clients add.
+pub fn decode_key(value: &str) -> Result<Vec<u8>, base64::DecodeError> {
+    BASE64_URL_SAFE_NO_PAD.decode(value.trim_end_matches('='))
+}
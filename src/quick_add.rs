@@ -0,0 +1,482 @@
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+pub use crate::model::{Frequency, Priority, Recurrence};
+
+/// Todos given a date but no time are due in the morning.
+const DEFAULT_DUE_HOUR: u32 = 9;
+/// `tonight` is due at this hour.
+const EVENING_HOUR: u32 = 20;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LEN: usize = 50;
+/// Larger intervals and offsets are taken for words of the content.
+const MAX_INTERVAL: u32 = 365;
+const MAX_OFFSET: u32 = 1000;
+
+/// The details read from the text of a new todo.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct QuickAdd {
+    /// The words left over, blank when the text only held details.
+    pub content: String,
+    pub due_at: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    /// The name of the list the todo goes on, without the `@`.
+    pub list: Option<String>,
+    pub recurrence: Option<Recurrence>,
+}
+
+/// When a todo is due, in the user's time zone.
+#[derive(Debug, Clone, Copy)]
+enum When {
+    Date(NaiveDate, Option<NaiveTime>),
+    Instant(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Reads the details of a todo out of the text typed into the add form, e.g.
+/// `Pay rent tomorrow 9am #home !high every month`, at `now` by a user in `time_zone`,
+/// in which dates and times are read. Only the first date, time, priority, list and
+/// recurrence are read. Words that are not understood, later details and words escaped
+/// with a backslash are left as the todo's content.
+pub fn parse(input: &str, now: DateTime<Utc>, time_zone: Tz) -> QuickAdd {
+    let local_now = now.with_timezone(&time_zone);
+    let today = local_now.date_naive();
+    let words = input.split_whitespace().collect::<Vec<_>>();
+
+    let mut content = Vec::new();
+    let mut tags = Vec::<String>::new();
+    let mut priority = None;
+    let mut list = None;
+    let mut recurrence = None;
+    let mut when = None;
+    let mut time = None;
+    // `every friday` sets the first due date, unless one is given as well.
+    let mut recurring_from = None;
+
+    let mut i = 0;
+    while i < words.len() {
+        let rest = &words[i..];
+
+        if let Some(literal) = rest[0].strip_prefix('\\').filter(|word| !word.is_empty()) {
+            content.push(literal);
+            i += 1;
+            continue;
+        }
+        if let Some(tag) = parse_tag(rest[0]) {
+            if tags.len() < MAX_TAGS {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+                i += 1;
+                continue;
+            }
+        }
+        if priority.is_none() {
+            if let Some(value) = parse_priority(rest[0]) {
+                priority = Some(value);
+                i += 1;
+                continue;
+            }
+        }
+        if list.is_none() {
+            if let Some(name) = parse_list(rest[0]) {
+                list = Some(name);
+                i += 1;
+                continue;
+            }
+        }
+        if recurrence.is_none() {
+            if let Some((len, value, weekday)) = parse_recurrence(rest) {
+                recurrence = Some(value);
+                recurring_from = weekday.map(|weekday| next_weekday(today, weekday));
+                i += len;
+                continue;
+            }
+        }
+        if when.is_none() {
+            if let Some((len, value)) = parse_date(rest, local_now.naive_local(), now) {
+                when = Some(value);
+                i += len;
+                continue;
+            }
+        }
+        if time.is_none() && !matches!(when, Some(When::Instant(_))) {
+            if let Some((len, value)) = parse_time(rest) {
+                time = Some(value);
+                i += len;
+                continue;
+            }
+        }
+
+        content.push(rest[0]);
+        i += 1;
+    }
+
+    let when = when.or(recurring_from.map(|date| When::Date(date, None)));
+    let due_at = match (when, time) {
+        (Some(When::Instant(instant)), _) => Some(instant),
+        (Some(When::Date(date, default_time)), time) => {
+            let time = time.or(default_time).unwrap_or(hour(DEFAULT_DUE_HOUR));
+            to_utc(time_zone, date.and_time(time))
+        }
+        // A time alone is the next time the clock shows it.
+        (None, Some(time)) => {
+            let mut due_at = today.and_time(time);
+            if due_at <= local_now.naive_local() {
+                due_at += Duration::days(1);
+            }
+            to_utc(time_zone, due_at)
+        }
+        (None, None) => None,
+    };
+
+    QuickAdd {
+        content: content.join(" "),
+        due_at,
+        tags,
+        priority,
+        list,
+        recurrence,
+    }
+}
+
+/// The word as matched against keywords: lowercase, without punctuation after it.
+fn normalize(word: &str) -> String {
+    word.trim_end_matches([',', '.', ';']).to_lowercase()
+}
+
+/// `#name`, starting with a letter and made of letters, digits, `-`, `_` and `/`.
+fn parse_tag(word: &str) -> Option<String> {
+    let tag = normalize(word.strip_prefix('#')?);
+    let valid = tag.chars().next().is_some_and(char::is_alphabetic)
+        && tag.len() <= MAX_TAG_LEN
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'));
+    valid.then_some(tag)
+}
+
+/// `!high`, `!medium` or `!low`, or their first letters.
+fn parse_priority(word: &str) -> Option<Priority> {
+    match normalize(word.strip_prefix('!')?).as_str() {
+        "high" | "h" => Some(Priority::High),
+        "medium" | "med" | "m" => Some(Priority::Medium),
+        "low" | "l" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// `@name`, naming a list by its owner.
+fn parse_list(word: &str) -> Option<String> {
+    let name = word.strip_prefix('@')?.trim_end_matches([',', '.', ';']);
+    name.chars()
+        .next()
+        .is_some_and(char::is_alphanumeric)
+        .then(|| name.to_string())
+}
+
+/// `daily`, `every week`, `every other month`, `every 3 days` or `every monday`, which
+/// also tells the weekday it starts on.
+fn parse_recurrence(words: &[&str]) -> Option<(usize, Recurrence, Option<Weekday>)> {
+    let recurrence = |frequency, interval| Recurrence {
+        frequency,
+        interval,
+    };
+    let first = normalize(words[0]);
+    let frequency = match first.as_str() {
+        "daily" => Some(Frequency::Daily),
+        "weekly" => Some(Frequency::Weekly),
+        "monthly" => Some(Frequency::Monthly),
+        "yearly" | "annually" => Some(Frequency::Yearly),
+        _ => None,
+    };
+    if let Some(frequency) = frequency {
+        return Some((1, recurrence(frequency, 1), None));
+    }
+    if first != "every" || words.len() < 2 {
+        return None;
+    }
+
+    let second = normalize(words[1]);
+    if let Some(weekday) = parse_weekday(&second) {
+        return Some((2, recurrence(Frequency::Weekly, 1), Some(weekday)));
+    }
+    if let Some(frequency) = parse_unit(&second).and_then(frequency_of) {
+        return Some((2, recurrence(frequency, 1), None));
+    }
+
+    let interval = match second.as_str() {
+        "other" => 2,
+        number => number
+            .parse::<u32>()
+            .ok()
+            .filter(|interval| (1..=MAX_INTERVAL).contains(interval))?,
+    };
+    let frequency = parse_unit(&normalize(words.get(2)?)).and_then(frequency_of)?;
+    Some((3, recurrence(frequency, interval), None))
+}
+
+fn frequency_of(unit: Unit) -> Option<Frequency> {
+    match unit {
+        Unit::Day => Some(Frequency::Daily),
+        Unit::Week => Some(Frequency::Weekly),
+        Unit::Month => Some(Frequency::Monthly),
+        Unit::Year => Some(Frequency::Yearly),
+        Unit::Minute | Unit::Hour => None,
+    }
+}
+
+/// A date, optionally after `on`, `by` or `due`: `today`, `tonight`, `tomorrow`, a
+/// weekday, `next friday`, `next week`, `in 3 days`, `in 2 hours`, `2026-11-05`,
+/// `5 nov`, `november 5th` or `nov 5 2027`.
+fn parse_date(
+    words: &[&str],
+    local_now: NaiveDateTime,
+    now: DateTime<Utc>,
+) -> Option<(usize, When)> {
+    let prepositions = words
+        .iter()
+        .take(2)
+        .take_while(|word| matches!(normalize(word).as_str(), "on" | "by" | "due"))
+        .count();
+    let words = &words[prepositions..];
+    let (len, when) = parse_date_words(words, local_now, now)?;
+    Some((prepositions + len, when))
+}
+
+fn parse_date_words(
+    words: &[&str],
+    local_now: NaiveDateTime,
+    now: DateTime<Utc>,
+) -> Option<(usize, When)> {
+    let today = local_now.date();
+    let first = normalize(words.first()?);
+    let second = words.get(1).map(|word| normalize(word));
+    let date = |date| When::Date(date, None);
+
+    match first.as_str() {
+        "today" => return Some((1, date(today))),
+        "tonight" => return Some((1, When::Date(today, Some(hour(EVENING_HOUR))))),
+        "tomorrow" | "tmrw" => return Some((1, date(today.succ_opt()?))),
+        "next" => {
+            let second = second?;
+            // Mondays start the week.
+            let next_monday =
+                today + Duration::days(7 - today.weekday().num_days_from_monday() as i64);
+            if let Some(weekday) = parse_weekday(&second) {
+                let date_of = next_monday + Duration::days(weekday.num_days_from_monday() as i64);
+                return Some((2, date(date_of)));
+            }
+            return match parse_unit(&second)? {
+                Unit::Week => Some((2, date(next_monday))),
+                Unit::Month => Some((
+                    2,
+                    date(today.with_day(1)?.checked_add_months(Months::new(1))?),
+                )),
+                Unit::Year => Some((2, date(NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)?))),
+                _ => None,
+            };
+        }
+        "in" => {
+            let amount = match second?.as_str() {
+                "a" | "an" => 1,
+                number => number
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|amount| *amount <= MAX_OFFSET)?,
+            };
+            let when = match parse_unit(&normalize(words.get(2)?))? {
+                Unit::Minute => When::Instant(now + Duration::minutes(amount as i64)),
+                Unit::Hour => When::Instant(now + Duration::hours(amount as i64)),
+                Unit::Day => date(today + Duration::days(amount as i64)),
+                Unit::Week => date(today + Duration::weeks(amount as i64)),
+                Unit::Month => date(today.checked_add_months(Months::new(amount))?),
+                Unit::Year => date(today.checked_add_months(Months::new(amount * 12))?),
+            };
+            return Some((3, when));
+        }
+        _ => {}
+    }
+
+    if let Some(weekday) = parse_weekday(&first) {
+        return Some((1, date(next_weekday(today, weekday))));
+    }
+    if let Ok(date_of) = NaiveDate::parse_from_str(&first, "%Y-%m-%d") {
+        return Some((1, date(date_of)));
+    }
+
+    // `5 nov` or `nov 5`, followed by an optional year.
+    let second = second?;
+    let (day, month) = match (parse_day(&first), parse_month(&second)) {
+        (Some(day), Some(month)) => (day, month),
+        _ => (parse_day(&second)?, parse_month(&first)?),
+    };
+    let year = words
+        .get(2)
+        .and_then(|word| normalize(word).parse::<i32>().ok())
+        .filter(|year| (today.year()..=today.year() + 100).contains(year));
+    if let Some(year) = year {
+        return Some((3, date(NaiveDate::from_ymd_opt(year, month, day)?)));
+    }
+    // Without a year, it is the next time the date comes around.
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+    let date_of = match this_year {
+        Some(date_of) if date_of >= today => date_of,
+        _ => (1..=8).find_map(|years| NaiveDate::from_ymd_opt(today.year() + years, month, day))?,
+    };
+    Some((2, date(date_of)))
+}
+
+/// A time, optionally after `at`: `9am`, `9:30pm`, `9 am`, `21:00`, `noon`,
+/// `midnight`, or a bare hour after `at`.
+fn parse_time(words: &[&str]) -> Option<(usize, NaiveTime)> {
+    let at = normalize(words[0]) == "at";
+    let words = if at { &words[1..] } else { words };
+    let first = normalize(words.first()?);
+    let offset = at as usize;
+
+    match first.as_str() {
+        "noon" => return Some((offset + 1, hour(12))),
+        "midnight" => return Some((offset + 1, hour(0))),
+        _ => {}
+    }
+    for suffix in ["am", "pm"] {
+        if let Some(clock) = first.strip_suffix(suffix) {
+            return Some((offset + 1, twelve_hour(clock, suffix)?));
+        }
+    }
+    if let Some(suffix) = words
+        .get(1)
+        .map(|word| normalize(word))
+        .filter(|word| word == "am" || word == "pm")
+    {
+        if let Some(time) = twelve_hour(&first, &suffix) {
+            return Some((offset + 2, time));
+        }
+    }
+    if first.contains(':') {
+        return Some((offset + 1, NaiveTime::parse_from_str(&first, "%H:%M").ok()?));
+    }
+    if at {
+        let hours = first.parse::<u32>().ok().filter(|hours| *hours < 24)?;
+        return Some((offset + 1, hour(hours)));
+    }
+    None
+}
+
+/// `9` or `9:30` on a 12-hour clock.
+fn twelve_hour(clock: &str, suffix: &str) -> Option<NaiveTime> {
+    let (hours, minutes) = match clock.split_once(':') {
+        Some((hours, minutes)) if minutes.len() == 2 => (hours, minutes.parse::<u32>().ok()?),
+        Some(_) => return None,
+        None => (clock, 0),
+    };
+    let hours = hours
+        .parse::<u32>()
+        .ok()
+        .filter(|hours| (1..=12).contains(hours))?;
+    let hours = match suffix {
+        "am" => hours % 12,
+        _ => hours % 12 + 12,
+    };
+    NaiveTime::from_hms_opt(hours, minutes, 0)
+}
+
+fn parse_unit(word: &str) -> Option<Unit> {
+    match word {
+        "minute" | "minutes" | "min" | "mins" => Some(Unit::Minute),
+        "hour" | "hours" => Some(Unit::Hour),
+        "day" | "days" => Some(Unit::Day),
+        "week" | "weeks" => Some(Unit::Week),
+        "month" | "months" => Some(Unit::Month),
+        "year" | "years" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+/// Full names only, `sun` or `sat` are more likely meant as words.
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" => Some(Weekday::Mon),
+        "tuesday" => Some(Weekday::Tue),
+        "wednesday" => Some(Weekday::Wed),
+        "thursday" => Some(Weekday::Thu),
+        "friday" => Some(Weekday::Fri),
+        "saturday" => Some(Weekday::Sat),
+        "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    MONTHS
+        .iter()
+        .position(|month| {
+            word == *month || (word.len() >= 3 && month.starts_with(word) && word.len() <= 4)
+        })
+        .map(|index| index as u32 + 1)
+}
+
+/// `5` or `5th`.
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits
+        .parse::<u32>()
+        .ok()
+        .filter(|day| (1..=31).contains(day))
+}
+
+/// The next day that is the weekday, today excluded.
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if days == 0 { 7 } else { days as i64 })
+}
+
+fn hour(hours: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hours, 0, 0).expect("Hours are below 24")
+}
+
+fn to_utc(time_zone: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        // Times skipped when the clocks go forward are taken an hour later.
+        .or_else(|| {
+            time_zone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|due_at| due_at.with_timezone(&Utc))
+}
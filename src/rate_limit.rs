@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    error::InternalError,
+    http::{header, Method},
+    web, HttpMessage, HttpResponse,
+};
+
+use crate::{
+    auth::AuthContext,
+    configuration::{LimitSettings, RateLimitSettings},
+    model::UserId,
+};
+
+/// How often buckets that have refilled completely are dropped, as they behave exactly
+/// like new ones.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The most clients tracked at once. Once reached, the tenth of the buckets updated
+/// longest ago is dropped, letting those clients start over with a full burst.
+const MAX_TRACKED_BUCKETS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RequestKind {
+    Read,
+    Write,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+struct Buckets {
+    buckets: HashMap<(RequestKind, String), Bucket>,
+    swept_at: Instant,
+}
+
+/// Token bucket rate limits for reads and writes, per client. Buckets live in memory, so
+/// every instance of the app enforces the limits on its own.
+pub struct RateLimiter {
+    reads: RateLimitSettings,
+    writes: RateLimitSettings,
+    trusted_proxy_hops: usize,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: &LimitSettings) -> Self {
+        Self {
+            reads: settings.reads,
+            writes: settings.writes,
+            trusted_proxy_hops: settings.trusted_proxy_hops,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                swept_at: Instant::now(),
+            }),
+        }
+    }
+
+    fn limit(&self, kind: RequestKind) -> RateLimitSettings {
+        match kind {
+            RequestKind::Read => self.reads,
+            RequestKind::Write => self.writes,
+        }
+    }
+
+    /// Takes a write token of the user for a write that did not arrive as a request of its
+    /// own, like a mutation sent over a WebSocket.
+    pub(crate) fn acquire_user_write(&self, user_id: &UserId) -> Result<(), Duration> {
+        self.acquire(RequestKind::Write, format!("user:{}", user_id))
+    }
+
+    /// The address of an anonymous client. Entries of `X-Forwarded-For` are only taken from
+    /// trusted proxies, as anything before them can be made up by the client.
+    fn client_address(&self, req: &ServiceRequest) -> String {
+        let peer_address = || {
+            req.peer_addr()
+                .map(|address| address.ip().to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+        };
+        if self.trusted_proxy_hops == 0 {
+            return peer_address();
+        }
+
+        let forwarded_for = req
+            .headers()
+            .get_all(header::X_FORWARDED_FOR)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        forwarded_for
+            .len()
+            .checked_sub(self.trusted_proxy_hops)
+            .and_then(|index| forwarded_for.get(index))
+            .map(|address| address.to_string())
+            .unwrap_or_else(peer_address)
+    }
+
+    /// Takes a token from the client's bucket, or tells how long until one is available.
+    fn acquire(&self, kind: RequestKind, client: String) -> Result<(), Duration> {
+        let limit = self.limit(kind);
+        let burst = f64::from(limit.burst);
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock is poisoned");
+        if now.duration_since(buckets.swept_at) >= SWEEP_INTERVAL {
+            buckets.buckets.retain(|(kind, _), bucket| {
+                let limit = self.limit(*kind);
+                refilled(bucket, limit, now) < f64::from(limit.burst)
+            });
+            buckets.swept_at = now;
+        }
+        let key = (kind, client);
+        if buckets.buckets.len() >= MAX_TRACKED_BUCKETS && !buckets.buckets.contains_key(&key) {
+            evict_oldest(&mut buckets.buckets);
+        }
+
+        let bucket = buckets.buckets.entry(key).or_insert(Bucket {
+            tokens: burst,
+            updated_at: now,
+        });
+        bucket.tokens = refilled(bucket, limit, now).min(burst);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / limit.per_second,
+            ))
+        }
+    }
+}
+
+/// Drops the tenth of the buckets updated longest ago, so that a flood of new clients
+/// only pays for finding them once every so many requests.
+fn evict_oldest(buckets: &mut HashMap<(RequestKind, String), Bucket>) {
+    let mut updated_at = buckets
+        .values()
+        .map(|bucket| bucket.updated_at)
+        .collect::<Vec<_>>();
+    let (_, &mut cutoff, _) = updated_at.select_nth_unstable(buckets.len() / 10);
+    buckets.retain(|_, bucket| bucket.updated_at > cutoff);
+}
+
+fn refilled(bucket: &Bucket, limit: RateLimitSettings, now: Instant) -> f64 {
+    let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+    bucket.tokens + elapsed * limit.per_second
+}
+
+/// Rejects clients exceeding their rate limit with `429 Too Many Requests`. Clients are
+/// told apart by principal, so it has to be wrapped inside the authentication middlewares,
+/// and by IP address on anonymous routes.
+pub(crate) async fn rate_limit_middleware(
+    req: ServiceRequest,
+    next: actix_web::middleware::Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let rate_limiter = req
+        .app_data::<web::Data<RateLimiter>>()
+        .expect("Rate limiter is not registered")
+        .clone();
+
+    let kind = match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => RequestKind::Read,
+        _ => RequestKind::Write,
+    };
+    let client = match req.extensions().get::<AuthContext>() {
+        Some(auth_ctx) => format!("user:{}", auth_ctx.principal_id),
+        None => format!("ip:{}", rate_limiter.client_address(&req)),
+    };
+
+    if let Err(retry_after) = rate_limiter.acquire(kind, client.clone()) {
+        tracing::info!(%client, ?kind, "Rate limited request");
+        let retry_after_seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        let response = HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, retry_after_seconds.to_string()))
+            .body("Too many requests, try again later");
+        return Err(InternalError::from_response("Too many requests", response).into());
+    }
+
+    next.call(req).await
+}
@@ -0,0 +1,109 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{AppPassword, TokenHash, UserId};
+
+pub trait AppPasswordRepository {
+    async fn get_by_hash(&self, token_hash: TokenHash) -> anyhow::Result<Option<AppPassword>>;
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<AppPassword>> + '_;
+    async fn create(&self, app_password: AppPassword) -> anyhow::Result<()>;
+    async fn delete_for_user_by_id(&self, user_id: UserId, id: TokenHash) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for AppPassword {
+    type Entity = TokenHash;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id()
+    }
+}
+
+impl CosmosDocument for AppPassword {
+    const COLLECTION_NAME: &str = "app_passwords";
+    type Id = TokenHash;
+}
+
+pub struct CosmosAppPasswordRepository {
+    cosmos_repository: CosmosDocumentRepository<AppPassword>,
+}
+
+impl CosmosAppPasswordRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl AppPasswordRepository for CosmosAppPasswordRepository {
+    #[tracing::instrument(name = "Fetch app password from db by hash", skip(self, token_hash))]
+    async fn get_by_hash(&self, token_hash: TokenHash) -> anyhow::Result<Option<AppPassword>> {
+        self.cosmos_repository
+            .get_by_id(token_hash.clone(), token_hash)
+            .await
+    }
+
+    #[tracing::instrument(name = "Fetch app passwords from db by user id", skip(self, user_id))]
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<AppPassword>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.user_id = @user_id",
+                AppPassword::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, true)
+    }
+
+    #[tracing::instrument(name = "Create new app password in db", skip(self, app_password))]
+    async fn create(&self, app_password: AppPassword) -> anyhow::Result<()> {
+        self.cosmos_repository.save(app_password, false).await
+    }
+
+    #[tracing::instrument(
+        name = "Delete app password from db by id and user_id",
+        skip(self, user_id, id)
+    )]
+    async fn delete_for_user_by_id(&self, user_id: UserId, id: TokenHash) -> anyhow::Result<()> {
+        let app_password = self.get_by_hash(id).await?;
+
+        match app_password {
+            Some(app_password) if app_password.user_id() == user_id => {
+                self.cosmos_repository
+                    .delete_by_id(app_password.id(), app_password.partition_key())
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "Delete all app passwords from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let existing = self
+            .get_all_for_user(user_id)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for document in existing {
+            self.cosmos_repository
+                .delete_by_id(document.id(), document.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+
+use super::{CosmosDocument, CosmosDocumentRepository, Page};
+use crate::model::AuditEntry;
+
+pub trait AuditLogRepository {
+    async fn create(&self, entry: AuditEntry) -> anyhow::Result<()>;
+    /// Lists the entries of one month, newest first, one page at a time.
+    async fn get_page_for_period(
+        &self,
+        period: String,
+        page_size: i32,
+        continuation: Option<String>,
+    ) -> anyhow::Result<Page<AuditEntry>>;
+}
+
+impl CosmosEntity for AuditEntry {
+    type Entity = String;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.period().to_string()
+    }
+}
+
+impl CosmosDocument for AuditEntry {
+    const COLLECTION_NAME: &str = "audit_log";
+    type Id = String;
+}
+
+pub struct CosmosAuditLogRepository {
+    cosmos_repository: CosmosDocumentRepository<AuditEntry>,
+}
+
+impl CosmosAuditLogRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl AuditLogRepository for CosmosAuditLogRepository {
+    #[tracing::instrument(name = "Create audit log entry in db", skip(self, entry))]
+    async fn create(&self, entry: AuditEntry) -> anyhow::Result<()> {
+        self.cosmos_repository.save(entry, false).await
+    }
+
+    #[tracing::instrument(name = "Fetch page of audit log from db", skip(self, continuation))]
+    async fn get_page_for_period(
+        &self,
+        period: String,
+        page_size: i32,
+        continuation: Option<String>,
+    ) -> anyhow::Result<Page<AuditEntry>> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} a WHERE a.period = @period ORDER BY a.created_at DESC",
+                AuditEntry::COLLECTION_NAME
+            ),
+            vec![Param::new("@period".to_string(), period.clone())],
+        );
+
+        self.cosmos_repository
+            .query_page(query, Some(&period), page_size, continuation)
+            .await
+    }
+}
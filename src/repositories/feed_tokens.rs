@@ -0,0 +1,78 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::TryStreamExt;
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{FeedToken, TokenHash, UserId};
+
+pub trait FeedTokenRepository {
+    async fn get_by_hash(&self, token_hash: TokenHash) -> anyhow::Result<Option<FeedToken>>;
+    async fn replace_for_user(&self, feed_token: FeedToken) -> anyhow::Result<()>;
+    async fn delete_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for FeedToken {
+    type Entity = TokenHash;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id()
+    }
+}
+
+impl CosmosDocument for FeedToken {
+    const COLLECTION_NAME: &str = "feed_tokens";
+    type Id = TokenHash;
+}
+
+pub struct CosmosFeedTokenRepository {
+    cosmos_repository: CosmosDocumentRepository<FeedToken>,
+}
+
+impl CosmosFeedTokenRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl FeedTokenRepository for CosmosFeedTokenRepository {
+    #[tracing::instrument(name = "Fetch feed token from db by hash", skip(self, token_hash))]
+    async fn get_by_hash(&self, token_hash: TokenHash) -> anyhow::Result<Option<FeedToken>> {
+        self.cosmos_repository
+            .get_by_id(token_hash.clone(), token_hash)
+            .await
+    }
+
+    #[tracing::instrument(name = "Replace feed token for user in db", skip(self, feed_token))]
+    async fn replace_for_user(&self, feed_token: FeedToken) -> anyhow::Result<()> {
+        self.delete_for_user(feed_token.user_id()).await?;
+        self.cosmos_repository.save(feed_token, false).await
+    }
+
+    #[tracing::instrument(name = "Delete feed tokens from db by user id", skip(self, user_id))]
+    async fn delete_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.user_id = @user_id",
+                FeedToken::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        let existing = self
+            .cosmos_repository
+            .query(query, true)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for token in existing {
+            self.cosmos_repository
+                .delete_by_id(token.id(), token.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::TryStreamExt;
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{IdempotencyRecord, UserId};
+
+pub trait IdempotencyRepository {
+    async fn get(&self, user_id: UserId, key: &str) -> anyhow::Result<Option<IdempotencyRecord>>;
+    /// Stores a new record, unless the user has used its key already. Tells whether it
+    /// was stored.
+    async fn try_create(&self, record: IdempotencyRecord) -> anyhow::Result<bool>;
+    async fn save(&self, record: IdempotencyRecord) -> anyhow::Result<()>;
+    async fn delete(&self, user_id: UserId, key: &str) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for IdempotencyRecord {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.user_id()
+    }
+}
+
+impl CosmosDocument for IdempotencyRecord {
+    const COLLECTION_NAME: &str = "idempotency_records";
+    type Id = String;
+}
+
+pub struct CosmosIdempotencyRepository {
+    cosmos_repository: CosmosDocumentRepository<IdempotencyRecord>,
+}
+
+impl CosmosIdempotencyRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl IdempotencyRepository for CosmosIdempotencyRepository {
+    #[tracing::instrument(name = "Fetch idempotency record from db", skip(self, user_id, key))]
+    async fn get(&self, user_id: UserId, key: &str) -> anyhow::Result<Option<IdempotencyRecord>> {
+        self.cosmos_repository
+            .get_by_id(IdempotencyRecord::id_for(key), user_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "Create idempotency record in db", skip(self, record))]
+    async fn try_create(&self, record: IdempotencyRecord) -> anyhow::Result<bool> {
+        self.cosmos_repository.create_if_absent(record).await
+    }
+
+    #[tracing::instrument(name = "Save idempotency record in db", skip(self, record))]
+    async fn save(&self, record: IdempotencyRecord) -> anyhow::Result<()> {
+        self.cosmos_repository.save(record, true).await
+    }
+
+    #[tracing::instrument(name = "Delete idempotency record from db", skip(self, user_id, key))]
+    async fn delete(&self, user_id: UserId, key: &str) -> anyhow::Result<()> {
+        self.cosmos_repository
+            .delete_by_id(IdempotencyRecord::id_for(key), user_id)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Delete all idempotency records from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} r WHERE r.user_id = @user_id",
+                IdempotencyRecord::COLLECTION_NAME
+            ),
+            vec![Param::new(
+                "@user_id".to_string(),
+                String::from(user_id.clone()),
+            )],
+        );
+
+        let existing = self
+            .cosmos_repository
+            .query(query, false)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for record in existing {
+            self.cosmos_repository
+                .delete_by_id(record.id(), record.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
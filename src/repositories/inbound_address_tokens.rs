@@ -0,0 +1,102 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::TryStreamExt;
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{InboundAddressToken, TokenHash, UserId};
+
+pub trait InboundAddressTokenRepository {
+    async fn get_by_hash(
+        &self,
+        token_hash: TokenHash,
+    ) -> anyhow::Result<Option<InboundAddressToken>>;
+    async fn replace_for_user(
+        &self,
+        inbound_address_token: InboundAddressToken,
+    ) -> anyhow::Result<()>;
+    async fn delete_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for InboundAddressToken {
+    type Entity = TokenHash;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id()
+    }
+}
+
+impl CosmosDocument for InboundAddressToken {
+    const COLLECTION_NAME: &str = "inbound_address_tokens";
+    type Id = TokenHash;
+}
+
+pub struct CosmosInboundAddressTokenRepository {
+    cosmos_repository: CosmosDocumentRepository<InboundAddressToken>,
+}
+
+impl CosmosInboundAddressTokenRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl InboundAddressTokenRepository for CosmosInboundAddressTokenRepository {
+    #[tracing::instrument(
+        name = "Fetch inbound address token from db by hash",
+        skip(self, token_hash)
+    )]
+    async fn get_by_hash(
+        &self,
+        token_hash: TokenHash,
+    ) -> anyhow::Result<Option<InboundAddressToken>> {
+        self.cosmos_repository
+            .get_by_id(token_hash.clone(), token_hash)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Replace inbound address token for user in db",
+        skip(self, inbound_address_token)
+    )]
+    async fn replace_for_user(
+        &self,
+        inbound_address_token: InboundAddressToken,
+    ) -> anyhow::Result<()> {
+        self.delete_for_user(inbound_address_token.user_id())
+            .await?;
+        self.cosmos_repository
+            .save(inbound_address_token, false)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Delete inbound address tokens from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.user_id = @user_id",
+                InboundAddressToken::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        let existing = self
+            .cosmos_repository
+            .query(query, true)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for token in existing {
+            self.cosmos_repository
+                .delete_by_id(token.id(), token.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use azure_data_cosmos::{prelude::DatabaseClient, CosmosEntity};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::change_feed::{Lease, LeaseStore};
+
+impl CosmosEntity for Lease {
+    type Entity = String;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id.clone()
+    }
+}
+
+impl CosmosDocument for Lease {
+    const COLLECTION_NAME: &str = "leases";
+    type Id = String;
+}
+
+pub struct CosmosLeaseRepository {
+    cosmos_repository: CosmosDocumentRepository<Lease>,
+}
+
+impl CosmosLeaseRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl LeaseStore for CosmosLeaseRepository {
+    #[tracing::instrument(name = "Fetch change feed lease from db", skip(self))]
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Lease>> {
+        self.cosmos_repository
+            .get_by_id(id.to_string(), id.to_string())
+            .await
+    }
+
+    #[tracing::instrument(name = "Save change feed lease in db", skip(self, lease))]
+    async fn save(&self, lease: Lease) -> anyhow::Result<()> {
+        self.cosmos_repository.save(lease, true).await
+    }
+}
@@ -0,0 +1,133 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{ListMember, UserId};
+
+pub trait ListMemberRepository {
+    async fn get(&self, owner_id: UserId, member_id: UserId) -> anyhow::Result<Option<ListMember>>;
+    /// Lists who the owner shares their list with, across all partitions.
+    fn get_all_for_owner(
+        &self,
+        owner_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<ListMember>> + '_;
+    fn get_all_for_member(
+        &self,
+        member_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<ListMember>> + '_;
+    async fn save(&self, list_member: ListMember) -> anyhow::Result<()>;
+    async fn delete(&self, owner_id: UserId, member_id: UserId) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for ListMember {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.member_id()
+    }
+}
+
+impl CosmosDocument for ListMember {
+    const COLLECTION_NAME: &str = "list_members";
+    type Id = String;
+}
+
+pub struct CosmosListMemberRepository {
+    cosmos_repository: CosmosDocumentRepository<ListMember>,
+}
+
+impl CosmosListMemberRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl ListMemberRepository for CosmosListMemberRepository {
+    #[tracing::instrument(name = "Fetch list member from db", skip(self))]
+    async fn get(&self, owner_id: UserId, member_id: UserId) -> anyhow::Result<Option<ListMember>> {
+        self.cosmos_repository
+            .get_by_id(ListMember::id_for(&owner_id, &member_id), member_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "Fetch list members from db by owner id", skip(self, owner_id))]
+    fn get_all_for_owner(
+        &self,
+        owner_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<ListMember>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} m WHERE m.owner_id = @owner_id",
+                ListMember::COLLECTION_NAME
+            ),
+            vec![Param::new("@owner_id".to_string(), String::from(owner_id))],
+        );
+
+        self.cosmos_repository.query(query, true)
+    }
+
+    #[tracing::instrument(
+        name = "Fetch list memberships from db by member id",
+        skip(self, member_id)
+    )]
+    fn get_all_for_member(
+        &self,
+        member_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<ListMember>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} m WHERE m.member_id = @member_id",
+                ListMember::COLLECTION_NAME
+            ),
+            vec![Param::new(
+                "@member_id".to_string(),
+                String::from(member_id),
+            )],
+        );
+
+        self.cosmos_repository.query(query, false)
+    }
+
+    #[tracing::instrument(name = "Save list member in db", skip(self, list_member))]
+    async fn save(&self, list_member: ListMember) -> anyhow::Result<()> {
+        self.cosmos_repository.save(list_member, true).await
+    }
+
+    #[tracing::instrument(name = "Delete list member from db", skip(self))]
+    async fn delete(&self, owner_id: UserId, member_id: UserId) -> anyhow::Result<()> {
+        match self.get(owner_id, member_id).await? {
+            Some(list_member) => {
+                self.cosmos_repository
+                    .delete_by_id(list_member.id(), list_member.partition_key())
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(name = "Delete list members from db by user id", skip(self, user_id))]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let mut list_members = self
+            .get_all_for_owner(user_id.clone())
+            .try_collect::<Vec<_>>()
+            .await?;
+        list_members.extend(
+            self.get_all_for_member(user_id)
+                .try_collect::<Vec<_>>()
+                .await?,
+        );
+
+        for list_member in list_members {
+            self.cosmos_repository
+                .delete_by_id(list_member.id(), list_member.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
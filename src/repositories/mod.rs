@@ -0,0 +1,231 @@
+mod app_passwords;
+mod audit_log;
+mod feed_tokens;
+mod idempotency_records;
+mod inbound_address_tokens;
+mod leases;
+mod list_members;
+mod notifications;
+mod personal_access_tokens;
+mod push_subscriptions;
+mod share_links;
+mod storage_usage;
+mod todos;
+mod user_data;
+mod users;
+mod webhook_deliveries;
+mod webhooks;
+
+pub use app_passwords::*;
+pub use audit_log::*;
+pub use feed_tokens::*;
+pub use idempotency_records::*;
+pub use inbound_address_tokens::*;
+pub use leases::*;
+pub use list_members::*;
+pub use notifications::*;
+pub use personal_access_tokens::*;
+pub use push_subscriptions::*;
+pub use share_links::*;
+pub use storage_usage::*;
+pub use todos::*;
+pub use user_data::*;
+pub use users::*;
+pub use webhook_deliveries::*;
+pub use webhooks::*;
+
+use std::marker::PhantomData;
+
+use anyhow::Context;
+use azure_core::{error::ErrorKind, prelude::Header, StatusCode};
+use azure_data_cosmos::{
+    prelude::{CollectionClient, DatabaseClient, GetDocumentResponse, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// One page of query results, with the token for fetching the next page if there is one.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub continuation: Option<String>,
+}
+
+trait CosmosDocument: CosmosEntity {
+    const COLLECTION_NAME: &str;
+    type Id: Into<String>;
+}
+
+struct CosmosDocumentRepository<T: CosmosEntity + CosmosDocument> {
+    collection_client: CollectionClient,
+    _marker: PhantomData<T>,
+}
+
+impl<T> CosmosDocumentRepository<T>
+where
+    T: CosmosDocument,
+{
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let collection_client = database_client.collection_client(T::COLLECTION_NAME);
+        Self {
+            collection_client,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn collection_client(&self) -> &CollectionClient {
+        &self.collection_client
+    }
+}
+
+impl<T> CosmosDocumentRepository<T>
+where
+    T: CosmosDocument + Send + Sync + DeserializeOwned,
+    <T as CosmosEntity>::Entity: Into<String> + Clone,
+{
+    pub async fn get_by_id(
+        &self,
+        id: T::Id,
+        partition_key: T::Entity,
+    ) -> anyhow::Result<Option<T>> {
+        let result = self
+            .collection_client
+            .document_client(id, &partition_key)?
+            .get_document()
+            .await;
+
+        // The pipeline fails on `404 Not Found` before the response is turned into
+        // `GetDocumentResponse::NotFound`.
+        match result {
+            Ok(GetDocumentResponse::Found(resp)) => Ok(Some(resp.document.document)),
+            Ok(GetDocumentResponse::NotFound(_)) => Ok(None),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::HttpResponse {
+                        status: StatusCode::NotFound,
+                        ..
+                    }
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn delete_by_id(&self, id: T::Id, partition_key: T::Entity) -> anyhow::Result<()> {
+        self.collection_client
+            .document_client(id, &partition_key)?
+            .delete_document()
+            .await
+            .context("Failed to delete document")
+            .map(|_| ())
+    }
+
+    pub fn query(
+        &self,
+        query: Query,
+        cross_partition: bool,
+    ) -> impl StreamExt<Item = anyhow::Result<T>> + '_ {
+        self.collection_client
+            .query_documents(query)
+            .query_cross_partition(cross_partition)
+            .into_stream::<T>()
+            .map_err(anyhow::Error::from)
+            .map_ok(|response| {
+                futures::stream::iter(response.results.into_iter().map(|doc| Ok(doc.0)))
+            })
+            .try_flatten()
+    }
+
+    /// Fetches a single page of results, across all partitions when no partition key is
+    /// given. Cross-partition queries are only served by the gateway when they do not
+    /// aggregate or order the results.
+    pub async fn query_page(
+        &self,
+        query: Query,
+        partition_key: Option<&T::Entity>,
+        page_size: i32,
+        continuation: Option<String>,
+    ) -> anyhow::Result<Page<T>> {
+        let mut request = self
+            .collection_client
+            .query_documents(query)
+            .max_item_count(page_size);
+        request = match partition_key {
+            Some(partition_key) => request.partition_key(partition_key)?,
+            None => request.query_cross_partition(true),
+        };
+        if let Some(continuation) = continuation {
+            request = request.continuation(continuation);
+        }
+
+        let response = request
+            .into_stream::<T>()
+            .next()
+            .await
+            .context("Query returned no response")??;
+
+        Ok(Page {
+            items: response.results.into_iter().map(|doc| doc.0).collect(),
+            continuation: response
+                .continuation_token
+                .map(|token| token.value().as_str().to_string()),
+        })
+    }
+
+    pub async fn count(&self, query: Query, partition_key: &T::Entity) -> anyhow::Result<u64> {
+        self.collection_client
+            .query_documents(query)
+            .partition_key(partition_key)?
+            .into_stream::<u64>()
+            .map_err(anyhow::Error::from)
+            .map_ok(|response| {
+                futures::stream::iter(response.results.into_iter().map(|doc| Ok(doc.0)))
+            })
+            .try_flatten()
+            .try_fold(0, |total, count| async move { Ok(total + count) })
+            .await
+    }
+}
+
+impl<T> CosmosDocumentRepository<T>
+where
+    T: CosmosDocument + Send + Serialize + 'static,
+{
+    async fn save(&self, document: T, is_upsert: bool) -> anyhow::Result<()> {
+        self.collection_client
+            .create_document(document)
+            .is_upsert(is_upsert)
+            .await
+            .context("Failed to store user document")
+            .map(|_| ())
+    }
+
+    /// Creates the document unless one with the same id exists in its partition already.
+    /// Tells whether it was created.
+    async fn create_if_absent(&self, document: T) -> anyhow::Result<bool> {
+        let result = self
+            .collection_client
+            .create_document(document)
+            .is_upsert(false)
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::HttpResponse {
+                        status: StatusCode::Conflict,
+                        ..
+                    }
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(anyhow::Error::from(e).context("Failed to create user document")),
+        }
+    }
+}
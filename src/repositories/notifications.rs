@@ -0,0 +1,77 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::TryStreamExt;
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::{
+    model::{Notification, UserId},
+    notifications::NotificationRepository,
+};
+
+impl CosmosEntity for Notification {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.user_id()
+    }
+}
+
+impl CosmosDocument for Notification {
+    const COLLECTION_NAME: &str = "notifications";
+    type Id = String;
+}
+
+pub struct CosmosNotificationRepository {
+    cosmos_repository: CosmosDocumentRepository<Notification>,
+}
+
+impl CosmosNotificationRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl NotificationRepository for CosmosNotificationRepository {
+    #[tracing::instrument(name = "Create notification in db", skip(self, notification))]
+    async fn create_if_absent(&self, notification: Notification) -> anyhow::Result<bool> {
+        self.cosmos_repository.create_if_absent(notification).await
+    }
+
+    #[tracing::instrument(name = "Delete notification from db", skip(self, notification))]
+    async fn delete(&self, notification: Notification) -> anyhow::Result<()> {
+        self.cosmos_repository
+            .delete_by_id(notification.id(), notification.partition_key())
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Delete all notifications from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} n WHERE n.user_id = @user_id",
+                Notification::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        let notifications = self
+            .cosmos_repository
+            .query(query, false)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for notification in notifications {
+            self.cosmos_repository
+                .delete_by_id(notification.id(), notification.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
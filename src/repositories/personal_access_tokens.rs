@@ -0,0 +1,129 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{PersonalAccessToken, TokenHash, UserId};
+
+pub trait PersonalAccessTokenRepository {
+    async fn get_by_hash(
+        &self,
+        token_hash: TokenHash,
+    ) -> anyhow::Result<Option<PersonalAccessToken>>;
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<PersonalAccessToken>> + '_;
+    async fn create(&self, personal_access_token: PersonalAccessToken) -> anyhow::Result<()>;
+    async fn delete_for_user_by_id(&self, user_id: UserId, id: TokenHash) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for PersonalAccessToken {
+    type Entity = TokenHash;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id()
+    }
+}
+
+impl CosmosDocument for PersonalAccessToken {
+    const COLLECTION_NAME: &str = "personal_access_tokens";
+    type Id = TokenHash;
+}
+
+pub struct CosmosPersonalAccessTokenRepository {
+    cosmos_repository: CosmosDocumentRepository<PersonalAccessToken>,
+}
+
+impl CosmosPersonalAccessTokenRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl PersonalAccessTokenRepository for CosmosPersonalAccessTokenRepository {
+    #[tracing::instrument(
+        name = "Fetch personal access token from db by hash",
+        skip(self, token_hash)
+    )]
+    async fn get_by_hash(
+        &self,
+        token_hash: TokenHash,
+    ) -> anyhow::Result<Option<PersonalAccessToken>> {
+        self.cosmos_repository
+            .get_by_id(token_hash.clone(), token_hash)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Fetch personal access tokens from db by user id",
+        skip(self, user_id)
+    )]
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<PersonalAccessToken>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.user_id = @user_id",
+                PersonalAccessToken::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, true)
+    }
+
+    #[tracing::instrument(
+        name = "Create new personal access token in db",
+        skip(self, personal_access_token)
+    )]
+    async fn create(&self, personal_access_token: PersonalAccessToken) -> anyhow::Result<()> {
+        self.cosmos_repository
+            .save(personal_access_token, false)
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Delete personal access token from db by id and user_id",
+        skip(self, user_id, id)
+    )]
+    async fn delete_for_user_by_id(&self, user_id: UserId, id: TokenHash) -> anyhow::Result<()> {
+        let personal_access_token = self.get_by_hash(id).await?;
+
+        match personal_access_token {
+            Some(personal_access_token) if personal_access_token.user_id() == user_id => {
+                self.cosmos_repository
+                    .delete_by_id(
+                        personal_access_token.id(),
+                        personal_access_token.partition_key(),
+                    )
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "Delete all personal access tokens from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let existing = self
+            .get_all_for_user(user_id)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for document in existing {
+            self.cosmos_repository
+                .delete_by_id(document.id(), document.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::{
+    model::{PushSubscription, UserId},
+    push::PushSubscriptionRepository,
+};
+
+impl CosmosEntity for PushSubscription {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.user_id()
+    }
+}
+
+impl CosmosDocument for PushSubscription {
+    const COLLECTION_NAME: &str = "push_subscriptions";
+    type Id = String;
+}
+
+pub struct CosmosPushSubscriptionRepository {
+    cosmos_repository: CosmosDocumentRepository<PushSubscription>,
+}
+
+impl CosmosPushSubscriptionRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl PushSubscriptionRepository for CosmosPushSubscriptionRepository {
+    #[tracing::instrument(
+        name = "Fetch push subscriptions from db by user id",
+        skip(self, user_id)
+    )]
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<PushSubscription>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} s WHERE s.user_id = @user_id",
+                PushSubscription::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, false)
+    }
+
+    #[tracing::instrument(name = "Save push subscription in db", skip(self, subscription))]
+    async fn save(&self, subscription: PushSubscription) -> anyhow::Result<()> {
+        self.cosmos_repository.save(subscription, true).await
+    }
+
+    #[tracing::instrument(
+        name = "Delete push subscription from db by id and user id",
+        skip(self, user_id, subscription_id)
+    )]
+    async fn delete_for_user_by_id(
+        &self,
+        user_id: UserId,
+        subscription_id: String,
+    ) -> anyhow::Result<()> {
+        if self
+            .cosmos_repository
+            .get_by_id(subscription_id.clone(), user_id.clone())
+            .await?
+            .is_some()
+        {
+            self.cosmos_repository
+                .delete_by_id(subscription_id, user_id)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "Delete all push subscriptions from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let subscriptions = self
+            .get_all_for_user(user_id)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for subscription in subscriptions {
+            self.cosmos_repository
+                .delete_by_id(subscription.id(), subscription.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::model::{ShareLink, ShareLinkId, UserId};
+
+pub trait ShareLinkRepository {
+    async fn get_by_id(&self, id: ShareLinkId) -> anyhow::Result<Option<ShareLink>>;
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<ShareLink>> + '_;
+    async fn create(&self, share_link: ShareLink) -> anyhow::Result<()>;
+    async fn delete_for_user_by_id(&self, user_id: UserId, id: ShareLinkId) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for ShareLink {
+    type Entity = ShareLinkId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id()
+    }
+}
+
+impl CosmosDocument for ShareLink {
+    const COLLECTION_NAME: &str = "share_links";
+    type Id = ShareLinkId;
+}
+
+pub struct CosmosShareLinkRepository {
+    cosmos_repository: CosmosDocumentRepository<ShareLink>,
+}
+
+impl CosmosShareLinkRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl ShareLinkRepository for CosmosShareLinkRepository {
+    #[tracing::instrument(name = "Fetch share link from db by id", skip(self))]
+    async fn get_by_id(&self, id: ShareLinkId) -> anyhow::Result<Option<ShareLink>> {
+        self.cosmos_repository.get_by_id(id, id).await
+    }
+
+    #[tracing::instrument(name = "Fetch share links from db by user id", skip(self, user_id))]
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<ShareLink>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.user_id = @user_id",
+                ShareLink::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, true)
+    }
+
+    #[tracing::instrument(name = "Create new share link in db", skip(self, share_link))]
+    async fn create(&self, share_link: ShareLink) -> anyhow::Result<()> {
+        self.cosmos_repository.save(share_link, false).await
+    }
+
+    #[tracing::instrument(
+        name = "Delete share link from db by id and user_id",
+        skip(self, user_id, id)
+    )]
+    async fn delete_for_user_by_id(&self, user_id: UserId, id: ShareLinkId) -> anyhow::Result<()> {
+        let share_link = self.get_by_id(id).await?;
+
+        match share_link {
+            Some(share_link) if share_link.user_id() == user_id => {
+                self.cosmos_repository
+                    .delete_by_id(share_link.id(), share_link.partition_key())
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "Delete all share links from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let existing = self
+            .get_all_for_user(user_id)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for document in existing {
+            self.cosmos_repository
+                .delete_by_id(document.id(), document.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Query},
+    ResourceQuota,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use crate::model::ContainerUsage;
+
+pub trait StorageUsageRepository {
+    async fn get_usage(&self) -> anyhow::Result<Vec<ContainerUsage>>;
+}
+
+pub struct CosmosStorageUsageRepository {
+    database_client: DatabaseClient,
+}
+
+impl CosmosStorageUsageRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        Self { database_client }
+    }
+}
+
+impl StorageUsageRepository for CosmosStorageUsageRepository {
+    /// Cosmos DB reports the storage used by a container with every response from it,
+    /// so a query for a single document is the cheapest way to find out.
+    #[tracing::instrument(name = "Fetch storage usage from db", skip(self))]
+    async fn get_usage(&self) -> anyhow::Result<Vec<ContainerUsage>> {
+        let collections = self
+            .database_client
+            .list_collections()
+            .into_stream()
+            .map_ok(|response| response.collections)
+            .try_concat()
+            .await
+            .context("Failed to list containers")?;
+
+        let mut usage = Vec::with_capacity(collections.len());
+        for collection in collections {
+            let response = self
+                .database_client
+                .collection_client(collection.id.clone())
+                .query_documents(Query::new("SELECT TOP 1 c.id FROM c".to_string()))
+                .query_cross_partition(true)
+                .max_item_count(1)
+                .into_stream::<serde_json::Value>()
+                .next()
+                .await
+                .context("Query returned no response")?
+                .with_context(|| format!("Failed to query container {}", collection.id))?;
+
+            let mut container_usage = ContainerUsage {
+                name: collection.id,
+                documents_count: 0,
+                documents_size_kb: 0,
+            };
+            for quota in response.resource_usage {
+                match quota {
+                    ResourceQuota::DocumentsCount(count) => container_usage.documents_count = count,
+                    ResourceQuota::DocumentsSize(size) => container_usage.documents_size_kb = size,
+                    _ => {}
+                }
+            }
+            usage.push(container_usage);
+        }
+
+        usage.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(usage)
+    }
+}
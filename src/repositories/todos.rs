@@ -0,0 +1,361 @@
+use anyhow::Context;
+use azure_core::{error::ErrorKind, prelude::IfMatchCondition, StatusCode};
+use azure_data_cosmos::{
+    prelude::{ChangeFeed, CollectionClient, DatabaseClient, Param, PartitionRangeId, Query},
+    CosmosEntity,
+};
+use chrono::SecondsFormat;
+use futures::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::{
+    change_feed::{ChangeFeedPage, ChangeFeedSource, TOMBSTONE_TTL_SECONDS},
+    model::{Todo, TodoId, UserId},
+    notifications::DueTodoRepository,
+};
+
+pub trait TodoRepository {
+    fn get_all_for_user(&self, user_id: UserId)
+        -> impl StreamExt<Item = anyhow::Result<Todo>> + '_;
+    async fn get_one_for_user(
+        &self,
+        user_id: UserId,
+        todo_id: TodoId,
+    ) -> anyhow::Result<Option<Todo>>;
+    /// Lists the todos of the user along with the tombstones of the deleted ones.
+    fn get_all_for_user_with_tombstones(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Todo>> + '_;
+    async fn delete_for_user_by_id(&self, user_id: UserId, todo_id: TodoId) -> anyhow::Result<()>;
+    async fn create(&self, todo: Todo) -> anyhow::Result<()>;
+    async fn save(&self, todo: Todo) -> anyhow::Result<()>;
+    /// Lists todos assigned to the user in any list, across all partitions.
+    fn get_all_assigned_to(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Todo>> + '_;
+    async fn count_for_user(&self, user_id: UserId) -> anyhow::Result<u64>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for Todo {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.created_by()
+    }
+}
+
+impl CosmosDocument for Todo {
+    const COLLECTION_NAME: &str = "todos";
+    type Id = TodoId;
+}
+
+pub struct CosmosTodoRepository {
+    cosmos_repository: CosmosDocumentRepository<Todo>,
+}
+
+impl CosmosTodoRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl TodoRepository for CosmosTodoRepository {
+    #[tracing::instrument(name = "Fetch todos from db by user id", skip(self, user_id))]
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Todo>> + '_ {
+        let statement = format!(
+            "SELECT * FROM {} t WHERE t.created_by = '{}' AND NOT IS_DEFINED(t.deleted)",
+            Todo::COLLECTION_NAME,
+            user_id
+        );
+        let query = Query::new(statement);
+
+        self.cosmos_repository.query(query, false)
+    }
+
+    #[tracing::instrument(name = "Fetch one todo for user from db", skip(self, user_id, todo_id))]
+    async fn get_one_for_user(
+        &self,
+        user_id: UserId,
+        todo_id: TodoId,
+    ) -> anyhow::Result<Option<Todo>> {
+        let todo = self.cosmos_repository.get_by_id(todo_id, user_id).await?;
+
+        Ok(todo.filter(|todo| !todo.is_deleted()))
+    }
+
+    #[tracing::instrument(
+        name = "Fetch todos and tombstones from db by user id",
+        skip(self, user_id)
+    )]
+    fn get_all_for_user_with_tombstones(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Todo>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.created_by = @user_id",
+                Todo::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, false)
+    }
+
+    /// Keeps the todo as a tombstone, which the change feed carries to the other instances.
+    #[tracing::instrument(
+        name = "Delete todo from db by id and user_id",
+        skip(self, user_id, todo_id)
+    )]
+    async fn delete_for_user_by_id(&self, user_id: UserId, todo_id: TodoId) -> anyhow::Result<()> {
+        let Some(mut todo) = self.get_one_for_user(user_id, todo_id).await? else {
+            return Ok(());
+        };
+
+        todo.delete(TOMBSTONE_TTL_SECONDS);
+        self.cosmos_repository.save(todo, true).await
+    }
+
+    #[tracing::instrument(name = "Create new todo in db", skip(self, todo))]
+    async fn create(&self, todo: Todo) -> anyhow::Result<()> {
+        // CalDAV clients choose the ids of the todos they create, and may reuse the id of a
+        // deleted todo whose tombstone is still around.
+        let tombstone = self
+            .cosmos_repository
+            .get_by_id(todo.id(), todo.partition_key())
+            .await?
+            .is_some_and(|existing| existing.is_deleted());
+
+        self.cosmos_repository.save(todo, tombstone).await
+    }
+
+    #[tracing::instrument(name = "Save todo in db", skip(self, todo))]
+    async fn save(&self, todo: Todo) -> anyhow::Result<()> {
+        self.cosmos_repository.save(todo, true).await
+    }
+
+    #[tracing::instrument(name = "Fetch todos from db by assignee", skip(self, user_id))]
+    fn get_all_assigned_to(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Todo>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.assignee = @user_id AND NOT IS_DEFINED(t.deleted)",
+                Todo::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, true)
+    }
+
+    #[tracing::instrument(name = "Count todos in db by user id", skip(self, user_id))]
+    async fn count_for_user(&self, user_id: UserId) -> anyhow::Result<u64> {
+        let query = Query::with_params(
+            format!(
+                "SELECT VALUE COUNT(1) FROM {} t WHERE t.created_by = @user_id AND NOT IS_DEFINED(t.deleted)",
+                Todo::COLLECTION_NAME
+            ),
+            vec![Param::new(
+                "@user_id".to_string(),
+                String::from(user_id.clone()),
+            )],
+        );
+
+        self.cosmos_repository.count(query, &user_id).await
+    }
+
+    /// Removes the tombstones of deleted todos too.
+    #[tracing::instrument(name = "Delete all todos from db by user id", skip(self, user_id))]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.created_by = @user_id",
+                Todo::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+        let todos = self
+            .cosmos_repository
+            .query(query, false)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for todo in todos {
+            self.cosmos_repository
+                .delete_by_id(todo.id(), todo.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DueTodoRepository for CosmosTodoRepository {
+    #[tracing::instrument(name = "Fetch open todos from db by due date", skip(self))]
+    async fn get_open_due_between(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<Vec<Todo>> {
+        // Due dates are stored as RFC 3339 strings in UTC, which sort chronologically.
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.done = false AND t.due_at >= @from AND t.due_at < @to AND NOT IS_DEFINED(t.deleted)",
+                Todo::COLLECTION_NAME
+            ),
+            vec![
+                Param::new(
+                    "@from".to_string(),
+                    from.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                ),
+                Param::new(
+                    "@to".to_string(),
+                    to.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                ),
+            ],
+        );
+
+        self.cosmos_repository
+            .query(query, true)
+            .try_collect()
+            .await
+    }
+
+    #[tracing::instrument(name = "Fetch open todos from db by user id", skip(self, user_id))]
+    async fn get_open_for_user(&self, user_id: UserId) -> anyhow::Result<Vec<Todo>> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} t WHERE t.created_by = @user_id AND t.done = false AND NOT IS_DEFINED(t.deleted)",
+                Todo::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository
+            .query(query, false)
+            .try_collect()
+            .await
+    }
+}
+
+/// Change feed pages are capped, so that catching up after downtime checkpoints regularly.
+const CHANGE_FEED_PAGE_SIZE: i32 = 100;
+
+/// Reads the change feed of the `todos` container one partition key range at a time.
+/// Continuations are log sequence numbers of the range.
+pub struct CosmosTodoChangeFeed {
+    collection_client: CollectionClient,
+}
+
+impl CosmosTodoChangeFeed {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let collection_client = database_client.collection_client(Todo::COLLECTION_NAME);
+        Self { collection_client }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChangeFeedDocument {
+    #[serde(rename = "_lsn")]
+    lsn: u64,
+    #[serde(flatten)]
+    document: serde_json::Value,
+}
+
+impl ChangeFeedSource for CosmosTodoChangeFeed {
+    #[tracing::instrument(name = "Fetch todos partition key ranges from db", skip(self))]
+    async fn partition_key_ranges(&self) -> anyhow::Result<Vec<String>> {
+        let response = self
+            .collection_client
+            .get_partition_key_ranges()
+            .await
+            .context("Failed to list partition key ranges")?;
+
+        Ok(response
+            .partition_key_ranges
+            .into_iter()
+            .map(|range| range.id)
+            .collect())
+    }
+
+    #[tracing::instrument(name = "Read todos change feed from db", skip(self))]
+    async fn read(
+        &self,
+        partition_key_range_id: &str,
+        continuation: Option<&str>,
+    ) -> anyhow::Result<ChangeFeedPage> {
+        let mut request = self
+            .collection_client
+            .list_documents()
+            .a_im(ChangeFeed::Incremental)
+            .partition_range_id(PartitionRangeId::new(partition_key_range_id.to_string()));
+        // Without a continuation the first page is only read for the current log sequence
+        // number of the range.
+        request = match continuation {
+            Some(lsn) => request
+                .max_item_count(CHANGE_FEED_PAGE_SIZE)
+                .if_match_condition(IfMatchCondition::NotMatch(format!("\"{}\"", lsn))),
+            None => request.max_item_count(1),
+        };
+
+        let response = match request
+            .into_stream::<ChangeFeedDocument>()
+            .next()
+            .await
+            .context("Change feed returned no response")?
+        {
+            Ok(response) => response,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::HttpResponse {
+                        status: StatusCode::NotModified,
+                        ..
+                    }
+                ) =>
+            {
+                return Ok(ChangeFeedPage {
+                    documents: Vec::new(),
+                    continuation: None,
+                });
+            }
+            Err(e) => return Err(anyhow::Error::from(e).context("Failed to read change feed")),
+        };
+
+        if continuation.is_none() {
+            return Ok(ChangeFeedPage {
+                documents: Vec::new(),
+                continuation: Some(response.lsn.to_string()),
+            });
+        }
+
+        let documents = response
+            .documents
+            .into_iter()
+            .map(|document| document.document)
+            .collect::<Vec<_>>();
+        let continuation = documents
+            .iter()
+            .map(|document| document.lsn)
+            .max()
+            .map(|lsn| lsn.to_string());
+
+        Ok(ChangeFeedPage {
+            documents: documents
+                .into_iter()
+                .map(|document| document.document)
+                .collect(),
+            continuation,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+
+use super::{
+    AppPasswordRepository, CosmosAppPasswordRepository, CosmosFeedTokenRepository,
+    CosmosIdempotencyRepository, CosmosInboundAddressTokenRepository, CosmosListMemberRepository,
+    CosmosNotificationRepository, CosmosPersonalAccessTokenRepository,
+    CosmosPushSubscriptionRepository, CosmosShareLinkRepository, CosmosTodoRepository,
+    CosmosUserRepository, CosmosWebhookDeliveryRepository, CosmosWebhookRepository,
+    FeedTokenRepository, IdempotencyRepository, InboundAddressTokenRepository,
+    ListMemberRepository, PersonalAccessTokenRepository, ShareLinkRepository, TodoRepository,
+    UserRepository,
+};
+use crate::{
+    notifications::NotificationRepository,
+    push::PushSubscriptionRepository,
+    webhooks::{WebhookDeliveryRepository, WebhookRepository},
+};
+
+/// Every repository that stores data belonging to a user.
+pub trait UserDataRepositories: 'static {
+    type Users: UserRepository + 'static;
+    type Todos: TodoRepository + 'static;
+    type FeedTokens: FeedTokenRepository + 'static;
+    type InboundAddressTokens: InboundAddressTokenRepository + 'static;
+    type AppPasswords: AppPasswordRepository + 'static;
+    type PersonalAccessTokens: PersonalAccessTokenRepository + 'static;
+    type ShareLinks: ShareLinkRepository + 'static;
+    type IdempotencyRecords: IdempotencyRepository + 'static;
+    type ListMembers: ListMemberRepository + 'static;
+    type Webhooks: WebhookRepository + 'static;
+    type WebhookDeliveries: WebhookDeliveryRepository + 'static;
+    type Notifications: NotificationRepository + 'static;
+    type PushSubscriptions: PushSubscriptionRepository + 'static;
+}
+
+pub struct CosmosUserDataRepositories;
+
+impl UserDataRepositories for CosmosUserDataRepositories {
+    type Users = CosmosUserRepository;
+    type Todos = CosmosTodoRepository;
+    type FeedTokens = CosmosFeedTokenRepository;
+    type InboundAddressTokens = CosmosInboundAddressTokenRepository;
+    type AppPasswords = CosmosAppPasswordRepository;
+    type PersonalAccessTokens = CosmosPersonalAccessTokenRepository;
+    type ShareLinks = CosmosShareLinkRepository;
+    type IdempotencyRecords = CosmosIdempotencyRepository;
+    type ListMembers = CosmosListMemberRepository;
+    type Webhooks = CosmosWebhookRepository;
+    type WebhookDeliveries = CosmosWebhookDeliveryRepository;
+    type Notifications = CosmosNotificationRepository;
+    type PushSubscriptions = CosmosPushSubscriptionRepository;
+}
+
+/// The repositories of [`UserDataRepositories`], taken from the app data.
+pub struct UserData<R: UserDataRepositories> {
+    pub users: web::Data<R::Users>,
+    pub todos: web::Data<R::Todos>,
+    pub feed_tokens: web::Data<R::FeedTokens>,
+    pub inbound_address_tokens: web::Data<R::InboundAddressTokens>,
+    pub app_passwords: web::Data<R::AppPasswords>,
+    pub personal_access_tokens: web::Data<R::PersonalAccessTokens>,
+    pub share_links: web::Data<R::ShareLinks>,
+    pub idempotency_records: web::Data<R::IdempotencyRecords>,
+    pub list_members: web::Data<R::ListMembers>,
+    pub webhooks: web::Data<R::Webhooks>,
+    pub webhook_deliveries: web::Data<R::WebhookDeliveries>,
+    pub notifications: web::Data<R::Notifications>,
+    pub push_subscriptions: web::Data<R::PushSubscriptions>,
+}
+
+impl<R: UserDataRepositories> UserData<R> {
+    fn from_app_data(req: &HttpRequest) -> Option<Self> {
+        fn get<T: 'static>(req: &HttpRequest) -> Option<web::Data<T>> {
+            req.app_data::<web::Data<T>>().cloned()
+        }
+
+        Some(Self {
+            users: get(req)?,
+            todos: get(req)?,
+            feed_tokens: get(req)?,
+            inbound_address_tokens: get(req)?,
+            app_passwords: get(req)?,
+            personal_access_tokens: get(req)?,
+            share_links: get(req)?,
+            idempotency_records: get(req)?,
+            list_members: get(req)?,
+            webhooks: get(req)?,
+            webhook_deliveries: get(req)?,
+            notifications: get(req)?,
+            push_subscriptions: get(req)?,
+        })
+    }
+}
+
+impl<R: UserDataRepositories> FromRequest for UserData<R> {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_app_data(req).ok_or_else(|| {
+            tracing::error!("A user data repository is not configured");
+            actix_web::error::ErrorInternalServerError("Something went wrong")
+        }))
+    }
+}
@@ -0,0 +1,126 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository, Page};
+use crate::{
+    model::{User, UserId},
+    notifications::SubscriberRepository,
+};
+
+pub trait UserRepository {
+    async fn get_by_id(&self, user_id: UserId) -> anyhow::Result<Option<User>>;
+    /// Looks a user up by principal name, ignoring case, across all partitions.
+    async fn find_by_principal_name(&self, principal_name: &str) -> anyhow::Result<Option<User>>;
+    /// Lists users across all partitions, one page at a time.
+    async fn get_page(
+        &self,
+        page_size: i32,
+        continuation: Option<String>,
+    ) -> anyhow::Result<Page<User>>;
+    async fn save(&self, user: User) -> anyhow::Result<()>;
+    async fn delete_by_id(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+impl CosmosEntity for User {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.id()
+    }
+}
+
+impl CosmosDocument for User {
+    const COLLECTION_NAME: &str = "users";
+    type Id = UserId;
+}
+
+pub struct CosmosUserRepository {
+    cosmos_repository: CosmosDocumentRepository<User>,
+}
+
+impl CosmosUserRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl UserRepository for CosmosUserRepository {
+    #[tracing::instrument(name = "Fetch user from db by id", skip(self))]
+    async fn get_by_id(&self, user_id: UserId) -> anyhow::Result<Option<User>> {
+        self.cosmos_repository
+            .get_by_id(user_id.clone(), user_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "Find user in db by principal name", skip(self))]
+    async fn find_by_principal_name(&self, principal_name: &str) -> anyhow::Result<Option<User>> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} u WHERE LOWER(u.principal_name) = @principal_name",
+                User::COLLECTION_NAME
+            ),
+            vec![Param::new(
+                "@principal_name".to_string(),
+                principal_name.to_lowercase(),
+            )],
+        );
+
+        self.cosmos_repository
+            .query(query, true)
+            .next()
+            .await
+            .transpose()
+    }
+
+    #[tracing::instrument(name = "Fetch page of users from db", skip(self, continuation))]
+    async fn get_page(
+        &self,
+        page_size: i32,
+        continuation: Option<String>,
+    ) -> anyhow::Result<Page<User>> {
+        let query = Query::new(format!("SELECT * FROM {} u", User::COLLECTION_NAME));
+
+        self.cosmos_repository
+            .query_page(query, None, page_size, continuation)
+            .await
+    }
+
+    #[tracing::instrument(name = "Save user in db", skip(self, user))]
+    async fn save(&self, user: User) -> anyhow::Result<()> {
+        self.cosmos_repository.save(user, true).await
+    }
+
+    #[tracing::instrument(name = "Delete user from db by id", skip(self))]
+    async fn delete_by_id(&self, user_id: UserId) -> anyhow::Result<()> {
+        if self.get_by_id(user_id.clone()).await?.is_none() {
+            return Ok(());
+        }
+
+        self.cosmos_repository
+            .delete_by_id(user_id.clone(), user_id)
+            .await
+    }
+}
+
+impl SubscriberRepository for CosmosUserRepository {
+    async fn get_subscriber(&self, user_id: UserId) -> anyhow::Result<Option<User>> {
+        self.get_by_id(user_id).await
+    }
+
+    #[tracing::instrument(name = "Fetch digest subscribers from db", skip(self))]
+    async fn get_digest_subscribers(&self) -> anyhow::Result<Vec<User>> {
+        let query = Query::new(format!(
+            "SELECT * FROM {} u WHERE u.notification_preferences.daily_digest = true",
+            User::COLLECTION_NAME
+        ));
+
+        self.cosmos_repository
+            .query(query, true)
+            .try_collect()
+            .await
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::Context;
+use azure_core::{error::ErrorKind, prelude::IfMatchCondition, StatusCode};
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::TryStreamExt;
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::{
+    model::{UserId, WebhookDelivery, WebhookId},
+    webhooks::WebhookDeliveryRepository,
+};
+
+impl CosmosEntity for WebhookDelivery {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.user_id()
+    }
+}
+
+impl CosmosDocument for WebhookDelivery {
+    const COLLECTION_NAME: &str = "webhook_deliveries";
+    type Id = String;
+}
+
+pub struct CosmosWebhookDeliveryRepository {
+    cosmos_repository: CosmosDocumentRepository<WebhookDelivery>,
+}
+
+impl CosmosWebhookDeliveryRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl WebhookDeliveryRepository for CosmosWebhookDeliveryRepository {
+    #[tracing::instrument(name = "Create webhook delivery in db", skip(self, delivery))]
+    async fn create_if_absent(&self, delivery: WebhookDelivery) -> anyhow::Result<bool> {
+        self.cosmos_repository.create_if_absent(delivery).await
+    }
+
+    #[tracing::instrument(name = "Fetch due webhook deliveries from db", skip(self))]
+    async fn get_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: i32,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} d WHERE d.status = 'pending' AND d.next_attempt_at <= @now",
+                WebhookDelivery::COLLECTION_NAME
+            ),
+            vec![Param::new("@now".to_string(), now.timestamp())],
+        );
+
+        let page = self
+            .cosmos_repository
+            .query_page(query, None, limit, None)
+            .await?;
+        Ok(page.items)
+    }
+
+    #[tracing::instrument(name = "Claim webhook delivery in db", skip(self, delivery))]
+    async fn try_claim(&self, delivery: WebhookDelivery) -> anyhow::Result<bool> {
+        let etag = delivery
+            .etag()
+            .context("Only deliveries read from the db can be claimed")?
+            .to_string();
+        let result = self
+            .cosmos_repository
+            .collection_client()
+            .document_client(delivery.id(), &delivery.partition_key())?
+            .replace_document(delivery)
+            .if_match_condition(IfMatchCondition::Match(etag))
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::HttpResponse {
+                        status: StatusCode::PreconditionFailed,
+                        ..
+                    }
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(anyhow::Error::from(e).context("Failed to claim webhook delivery")),
+        }
+    }
+
+    #[tracing::instrument(name = "Save webhook delivery in db", skip(self, delivery))]
+    async fn save(&self, delivery: WebhookDelivery) -> anyhow::Result<()> {
+        self.cosmos_repository.save(delivery, true).await
+    }
+
+    #[tracing::instrument(
+        name = "Fetch webhook deliveries from db by webhook id",
+        skip(self, user_id, webhook_id)
+    )]
+    async fn get_latest_for_webhook(
+        &self,
+        user_id: UserId,
+        webhook_id: WebhookId,
+        limit: i32,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let query = Query::with_params(
+            format!(
+                "SELECT TOP @limit * FROM {} d WHERE d.user_id = @user_id AND d.webhook_id = @webhook_id ORDER BY d.created_at DESC",
+                WebhookDelivery::COLLECTION_NAME
+            ),
+            vec![
+                Param::new("@limit".to_string(), limit),
+                Param::new("@user_id".to_string(), String::from(user_id)),
+                Param::new("@webhook_id".to_string(), String::from(webhook_id)),
+            ],
+        );
+
+        self.cosmos_repository
+            .query(query, false)
+            .try_collect()
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "Delete all webhook deliveries from db by user id",
+        skip(self, user_id)
+    )]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} d WHERE d.user_id = @user_id",
+                WebhookDelivery::COLLECTION_NAME
+            ),
+            vec![Param::new(
+                "@user_id".to_string(),
+                String::from(user_id.clone()),
+            )],
+        );
+
+        let deliveries = self
+            .cosmos_repository
+            .query(query, false)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for delivery in deliveries {
+            self.cosmos_repository
+                .delete_by_id(delivery.id(), delivery.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use azure_data_cosmos::{
+    prelude::{DatabaseClient, Param, Query},
+    CosmosEntity,
+};
+use futures::{StreamExt, TryStreamExt};
+
+use super::{CosmosDocument, CosmosDocumentRepository};
+use crate::{
+    model::{UserId, Webhook, WebhookId},
+    webhooks::WebhookRepository,
+};
+
+impl CosmosEntity for Webhook {
+    type Entity = UserId;
+
+    fn partition_key(&self) -> Self::Entity {
+        self.user_id()
+    }
+}
+
+impl CosmosDocument for Webhook {
+    const COLLECTION_NAME: &str = "webhooks";
+    type Id = WebhookId;
+}
+
+pub struct CosmosWebhookRepository {
+    cosmos_repository: CosmosDocumentRepository<Webhook>,
+}
+
+impl CosmosWebhookRepository {
+    pub fn new(database_client: DatabaseClient) -> Self {
+        let cosmos_repository = CosmosDocumentRepository::new(database_client);
+        Self { cosmos_repository }
+    }
+}
+
+impl WebhookRepository for CosmosWebhookRepository {
+    #[tracing::instrument(name = "Fetch webhooks from db by user id", skip(self, user_id))]
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Webhook>> + '_ {
+        let query = Query::with_params(
+            format!(
+                "SELECT * FROM {} w WHERE w.user_id = @user_id",
+                Webhook::COLLECTION_NAME
+            ),
+            vec![Param::new("@user_id".to_string(), String::from(user_id))],
+        );
+
+        self.cosmos_repository.query(query, false)
+    }
+
+    #[tracing::instrument(
+        name = "Fetch webhook from db by id and user id",
+        skip(self, user_id, webhook_id)
+    )]
+    async fn get_for_user_by_id(
+        &self,
+        user_id: UserId,
+        webhook_id: WebhookId,
+    ) -> anyhow::Result<Option<Webhook>> {
+        self.cosmos_repository.get_by_id(webhook_id, user_id).await
+    }
+
+    #[tracing::instrument(name = "Create new webhook in db", skip(self, webhook))]
+    async fn create(&self, webhook: Webhook) -> anyhow::Result<()> {
+        self.cosmos_repository.save(webhook, false).await
+    }
+
+    #[tracing::instrument(
+        name = "Delete webhook from db by id and user id",
+        skip(self, user_id, webhook_id)
+    )]
+    async fn delete_for_user_by_id(
+        &self,
+        user_id: UserId,
+        webhook_id: WebhookId,
+    ) -> anyhow::Result<()> {
+        if self
+            .get_for_user_by_id(user_id.clone(), webhook_id)
+            .await?
+            .is_some()
+        {
+            self.cosmos_repository
+                .delete_by_id(webhook_id, user_id)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Delete all webhooks from db by user id", skip(self, user_id))]
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        let webhooks = self
+            .get_all_for_user(user_id)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for webhook in webhooks {
+            self.cosmos_repository
+                .delete_by_id(webhook.id(), webhook.partition_key())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use std::pin::Pin;
+
+use actix_web::{
+    dev::{Payload, ServiceRequest},
+    error::PayloadError,
+    web,
+};
+use futures::Stream;
+
+type BoxedPayloadStream = Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>>;
+
+/// Reads the whole request body in a middleware and puts it back for the handler.
+pub(crate) async fn peek_body(req: &mut ServiceRequest) -> Result<web::Bytes, actix_web::Error> {
+    let body = req.extract::<web::Bytes>().await?;
+
+    let replayed = body.clone();
+    let stream: BoxedPayloadStream = Box::pin(futures::stream::once(async move { Ok(replayed) }));
+    req.set_payload(Payload::from(stream));
+
+    Ok(body)
+}
@@ -0,0 +1,102 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use tera::Tera;
+
+use super::{audit, PAGE_SIZE};
+use crate::{
+    auth,
+    model::{AuditAction, AuditEntry},
+    problem::FieldError,
+    repositories::AuditLogRepository,
+};
+
+#[derive(Deserialize)]
+pub struct AuditLogQuery {
+    /// Month to list, as `YYYY-MM`. Defaults to the current month.
+    period: Option<String>,
+    continuation: Option<String>,
+}
+
+#[tracing::instrument(
+    name = "Get audit log",
+    skip(query, tmpl, audit_log_repository, auth_ctx)
+)]
+pub async fn get_audit_log<A>(
+    query: web::Query<AuditLogQuery>,
+    tmpl: web::Data<Tera>,
+    audit_log_repository: web::Data<A>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetAuditLogError>
+where
+    A: AuditLogRepository,
+{
+    let AuditLogQuery {
+        period,
+        continuation,
+    } = query.into_inner();
+
+    let period = match period {
+        Some(period) => {
+            NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d").map_err(|_| {
+                GetAuditLogError::ValidationError(FieldError::new(
+                    "period",
+                    "Period must be a month formatted as YYYY-MM",
+                ))
+            })?;
+            period
+        }
+        None => AuditEntry::period_of(chrono::Utc::now()),
+    };
+
+    audit(
+        audit_log_repository.get_ref(),
+        &auth_ctx,
+        AuditAction::ViewAuditLog,
+        None,
+    )
+    .await
+    .map_err(GetAuditLogError::UnexpectedError)?;
+
+    let page = audit_log_repository
+        .get_ref()
+        .get_page_for_period(period.clone(), PAGE_SIZE, continuation)
+        .await
+        .map_err(GetAuditLogError::UnexpectedError)?;
+
+    let mut context = tera::Context::new();
+    context.insert("period", &period);
+    context.insert("entries", &page.items);
+    context.insert("continuation", &page.continuation);
+
+    let html = tmpl
+        .render("admin_audit_log.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(GetAuditLogError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetAuditLogError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for GetAuditLogError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetAuditLogError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            GetAuditLogError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            GetAuditLogError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
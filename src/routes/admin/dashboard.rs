@@ -0,0 +1,57 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use tera::Tera;
+
+use super::audit;
+use crate::{
+    auth,
+    model::AuditAction,
+    repositories::{AuditLogRepository, StorageUsageRepository},
+};
+
+#[tracing::instrument(
+    name = "Get admin dashboard",
+    skip(tmpl, storage_usage_repository, audit_log_repository, auth_ctx)
+)]
+pub async fn get_admin_dashboard<S, A>(
+    tmpl: web::Data<Tera>,
+    storage_usage_repository: web::Data<S>,
+    audit_log_repository: web::Data<A>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetAdminDashboardError>
+where
+    S: StorageUsageRepository,
+    A: AuditLogRepository,
+{
+    audit(
+        audit_log_repository.get_ref(),
+        &auth_ctx,
+        AuditAction::ViewDashboard,
+        None,
+    )
+    .await?;
+
+    let usage = storage_usage_repository.get_ref().get_usage().await?;
+
+    let mut context = tera::Context::new();
+    context.insert("usage", &usage);
+
+    let html = tmpl
+        .render("admin_dashboard.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetAdminDashboardError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetAdminDashboardError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetAdminDashboardError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use super::audit;
+use crate::{
+    auth,
+    model::{AuditAction, UserId},
+    notifications::NotificationRepository,
+    push::PushSubscriptionRepository,
+    repositories::{
+        AppPasswordRepository, AuditLogRepository, FeedTokenRepository, IdempotencyRepository,
+        InboundAddressTokenRepository, ListMemberRepository, PersonalAccessTokenRepository,
+        ShareLinkRepository, TodoRepository, UserData, UserDataRepositories, UserRepository,
+    },
+    webhooks::{WebhookDeliveryRepository, WebhookRepository},
+};
+
+/// Deletes everything stored for a user. Their audit log entries are kept.
+#[tracing::instrument(
+    name = "Delete user data",
+    skip(user_data, audit_log_repository, auth_ctx)
+)]
+pub async fn delete_user_data<R: UserDataRepositories, A: AuditLogRepository>(
+    user_id: web::Path<UserId>,
+    user_data: UserData<R>,
+    audit_log_repository: web::Data<A>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DeleteUserDataError> {
+    let user_id = user_id.into_inner();
+
+    audit(
+        audit_log_repository.get_ref(),
+        &auth_ctx,
+        AuditAction::DeleteUserData,
+        Some(user_id.clone()),
+    )
+    .await?;
+
+    // Credentials go first, so that the user cannot add data while it is being deleted.
+    user_data
+        .personal_access_tokens
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .app_passwords
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .feed_tokens
+        .get_ref()
+        .delete_for_user(user_id.clone())
+        .await?;
+    user_data
+        .inbound_address_tokens
+        .get_ref()
+        .delete_for_user(user_id.clone())
+        .await?;
+    user_data
+        .share_links
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .webhooks
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .webhook_deliveries
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .notifications
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .push_subscriptions
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .idempotency_records
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .list_members
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data
+        .todos
+        .get_ref()
+        .delete_all_for_user(user_id.clone())
+        .await?;
+    user_data.users.get_ref().delete_by_id(user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteUserDataError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DeleteUserDataError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteUserDataError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
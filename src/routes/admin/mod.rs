@@ -0,0 +1,44 @@
+mod audit_log;
+mod dashboard;
+mod delete_user;
+mod user_todos;
+mod users;
+
+pub use audit_log::*;
+pub use dashboard::*;
+pub use delete_user::*;
+pub use user_todos::*;
+pub use users::*;
+
+use serde::Deserialize;
+
+use crate::{
+    auth::AuthContext,
+    model::{AuditAction, AuditEntry, UserId},
+    repositories::AuditLogRepository,
+};
+
+const PAGE_SIZE: i32 = 25;
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    continuation: Option<String>,
+}
+
+/// Writes the admin action to the audit log. Handlers call it before acting, so that an
+/// action that cannot be audited is not carried out either.
+async fn audit<A: AuditLogRepository>(
+    audit_log_repository: &A,
+    auth_ctx: &AuthContext,
+    action: AuditAction,
+    target_user_id: Option<UserId>,
+) -> anyhow::Result<()> {
+    let entry = AuditEntry::new(
+        auth_ctx.principal_id.clone(),
+        auth_ctx.principal_name.clone(),
+        action,
+        target_user_id,
+    );
+
+    audit_log_repository.create(entry).await
+}
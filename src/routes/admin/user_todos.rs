@@ -0,0 +1,84 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use tera::Tera;
+
+use super::audit;
+use crate::{
+    auth,
+    model::{AuditAction, UserId},
+    repositories::{AuditLogRepository, TodoRepository, UserRepository},
+};
+
+/// Shows the todos of a user the way they see them, without any way of changing them.
+#[tracing::instrument(
+    name = "Impersonate user read-only",
+    skip(
+        tmpl,
+        users_repository,
+        todos_repository,
+        audit_log_repository,
+        auth_ctx
+    )
+)]
+pub async fn get_user_todos_as_admin<U, T, A>(
+    user_id: web::Path<UserId>,
+    tmpl: web::Data<Tera>,
+    users_repository: web::Data<U>,
+    todos_repository: web::Data<T>,
+    audit_log_repository: web::Data<A>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetUserTodosAsAdminError>
+where
+    U: UserRepository,
+    T: TodoRepository,
+    A: AuditLogRepository,
+{
+    let user_id = user_id.into_inner();
+
+    audit(
+        audit_log_repository.get_ref(),
+        &auth_ctx,
+        AuditAction::ImpersonateUser,
+        Some(user_id.clone()),
+    )
+    .await?;
+
+    let user = users_repository
+        .get_ref()
+        .get_by_id(user_id.clone())
+        .await?
+        .ok_or(GetUserTodosAsAdminError::UserNotFound)?;
+
+    let todos = todos_repository
+        .get_ref()
+        .get_all_for_user(user_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert("user", &user);
+    context.insert("todos", &todos);
+
+    let html = tmpl
+        .render("admin_user_todos.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetUserTodosAsAdminError {
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetUserTodosAsAdminError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetUserTodosAsAdminError::UserNotFound => StatusCode::NOT_FOUND,
+            GetUserTodosAsAdminError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
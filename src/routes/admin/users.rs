@@ -0,0 +1,87 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use serde::Serialize;
+use tera::Tera;
+
+use super::{audit, PageQuery, PAGE_SIZE};
+use crate::{
+    auth,
+    model::{AuditAction, User},
+    repositories::{AuditLogRepository, TodoRepository, UserRepository},
+};
+
+#[derive(Serialize)]
+struct UserSummary {
+    user: User,
+    todo_count: u64,
+}
+
+#[tracing::instrument(
+    name = "Get page of users",
+    skip(
+        page,
+        tmpl,
+        users_repository,
+        todos_repository,
+        audit_log_repository,
+        auth_ctx,
+        csrf_token
+    )
+)]
+pub async fn get_users<U, T, A>(
+    page: web::Query<PageQuery>,
+    tmpl: web::Data<Tera>,
+    users_repository: web::Data<U>,
+    todos_repository: web::Data<T>,
+    audit_log_repository: web::Data<A>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetUsersError>
+where
+    U: UserRepository,
+    T: TodoRepository,
+    A: AuditLogRepository,
+{
+    audit(
+        audit_log_repository.get_ref(),
+        &auth_ctx,
+        AuditAction::ListUsers,
+        None,
+    )
+    .await?;
+
+    let page = users_repository
+        .get_ref()
+        .get_page(PAGE_SIZE, page.into_inner().continuation)
+        .await?;
+
+    let mut users = Vec::with_capacity(page.items.len());
+    for user in page.items {
+        let todo_count = todos_repository.get_ref().count_for_user(user.id()).await?;
+        users.push(UserSummary { user, todo_count });
+    }
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("users", &users);
+    context.insert("continuation", &page.continuation);
+
+    let html = tmpl
+        .render("admin_users.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetUsersError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetUsersError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetUsersError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
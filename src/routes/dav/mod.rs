@@ -0,0 +1,638 @@
+mod xml;
+
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpRequest, HttpResponse, ResponseError,
+};
+use futures::TryStreamExt;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    auth,
+    change_feed::TOMBSTONE_TTL_SECONDS,
+    configuration::LimitSettings,
+    events::{TodoEvent, TodoEventBus},
+    ical,
+    model::{CalendarObject, Todo, TodoContent, TodoId},
+    repositories::TodoRepository,
+};
+use xml::{Multistatus, PropName, PropValue, CALDAV, CALENDARSERVER, DAV};
+
+const ROOT_HREF: &str = "/dav/";
+const PRINCIPAL_HREF: &str = "/dav/principal/";
+const CALENDAR_HOME_HREF: &str = "/dav/calendars/";
+const CALENDAR_HREF: &str = "/dav/calendars/todos/";
+const CALENDAR_DATA_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+const SYNC_TOKEN_PREFIX: &str = "urn:todo-app:sync:";
+
+pub async fn well_known_caldav() -> HttpResponse {
+    HttpResponse::MovedPermanently()
+        .append_header((header::LOCATION, ROOT_HREF))
+        .finish()
+}
+
+/// Single entry point of the CalDAV server. The handful of WebDAV methods and the
+/// fixed resource layout (principal, calendar home, one calendar of todos) make
+/// dispatching by hand simpler than registering a route per method and path.
+#[tracing::instrument(
+    name = "Handle CalDAV request",
+    skip(req, body, limits, event_bus, todos_repository, auth_ctx),
+    fields(method = %req.method(), path = %req.path())
+)]
+pub async fn dav<T>(
+    req: HttpRequest,
+    body: web::Bytes,
+    limits: web::Data<LimitSettings>,
+    event_bus: web::Data<TodoEventBus>,
+    todos_repository: web::Data<T>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DavError>
+where
+    T: TodoRepository,
+{
+    let resource = DavResource::from_path(req.path()).ok_or(DavError::NotFound)?;
+    let dav = Dav {
+        todos_repository: todos_repository.get_ref(),
+        event_bus: event_bus.get_ref(),
+        auth_ctx: auth_ctx.into_inner(),
+        max_todos_per_user: limits.max_todos_per_user,
+    };
+
+    match (req.method().as_str(), resource) {
+        ("OPTIONS", _) => Ok(HttpResponse::Ok()
+            .append_header(("DAV", "1, 3, calendar-access"))
+            .append_header((
+                header::ALLOW,
+                "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT",
+            ))
+            .finish()),
+        ("PROPFIND", resource) => dav.propfind(&req, &body, resource).await,
+        ("REPORT", DavResource::Calendar) => dav.report(&body).await,
+        ("GET" | "HEAD", DavResource::CalendarObject(name)) => dav.get(&name).await,
+        ("PUT", DavResource::CalendarObject(name)) => dav.put(&req, &body, &name).await,
+        ("DELETE", DavResource::CalendarObject(name)) => dav.delete(&req, &name).await,
+        _ => Err(DavError::MethodNotAllowed),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DavResource {
+    Root,
+    Principal,
+    CalendarHome,
+    Calendar,
+    CalendarObject(String),
+}
+
+impl DavResource {
+    fn from_path(path: &str) -> Option<Self> {
+        let path = path.strip_prefix("/dav")?.trim_matches('/');
+
+        match path.split('/').collect::<Vec<_>>().as_slice() {
+            [""] => Some(Self::Root),
+            ["principal"] => Some(Self::Principal),
+            ["calendars"] => Some(Self::CalendarHome),
+            ["calendars", "todos"] => Some(Self::Calendar),
+            ["calendars", "todos", object] => object
+                .strip_suffix(".ics")
+                .filter(|name| !name.is_empty())
+                .map(|name| Self::CalendarObject(name.to_string())),
+            _ => None,
+        }
+    }
+
+    fn href(&self) -> String {
+        match self {
+            Self::Root => ROOT_HREF.to_string(),
+            Self::Principal => PRINCIPAL_HREF.to_string(),
+            Self::CalendarHome => CALENDAR_HOME_HREF.to_string(),
+            Self::Calendar => CALENDAR_HREF.to_string(),
+            Self::CalendarObject(name) => format!("{}{}.ics", CALENDAR_HREF, name),
+        }
+    }
+
+    /// Properties returned for `allprop` requests.
+    fn default_props(&self) -> Vec<PropName> {
+        let names: &[(&str, &str)] = match self {
+            Self::Root | Self::CalendarHome => &[(DAV, "resourcetype"), (DAV, "displayname")],
+            Self::Principal => &[
+                (DAV, "resourcetype"),
+                (DAV, "displayname"),
+                (CALDAV, "calendar-home-set"),
+            ],
+            Self::Calendar => &[
+                (DAV, "resourcetype"),
+                (DAV, "displayname"),
+                (CALDAV, "supported-calendar-component-set"),
+                (CALENDARSERVER, "getctag"),
+                (DAV, "sync-token"),
+            ],
+            Self::CalendarObject(_) => &[
+                (DAV, "resourcetype"),
+                (DAV, "getetag"),
+                (DAV, "getcontenttype"),
+            ],
+        };
+
+        names
+            .iter()
+            .map(|(namespace, name)| PropName {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Names todos after the resource a CalDAV client created them under, falling back to
+/// the todo id for todos created anywhere else.
+fn resource_name(todo: &Todo) -> String {
+    todo.calendar_object()
+        .map(|object| object.resource_name.clone())
+        .unwrap_or_else(|| todo.id().to_string())
+}
+
+/// Resource names that are canonical todo ids map onto them directly, any other name
+/// a client picks is mapped onto a stable name-based UUID.
+fn todo_id(resource_name: &str) -> TodoId {
+    match Uuid::parse_str(resource_name) {
+        Ok(uuid) if uuid.to_string() == resource_name => TodoId::from(uuid),
+        _ => TodoId::from(Uuid::new_v5(&Uuid::NAMESPACE_URL, resource_name.as_bytes())),
+    }
+}
+
+fn etag(todo: &Todo) -> String {
+    let digest = Sha256::digest(ical::render_calendar_object(todo).as_bytes());
+    format!("\"{:x}\"", digest)
+}
+
+/// The todos of the calendar, and the tombstones of the deleted ones that clients
+/// syncing incrementally still have to learn about.
+#[derive(Default)]
+struct Calendar {
+    todos: Vec<Todo>,
+    tombstones: Vec<Todo>,
+}
+
+impl Calendar {
+    /// When the calendar last changed, in seconds since the epoch.
+    fn changed_at(&self) -> i64 {
+        self.todos
+            .iter()
+            .chain(&self.tombstones)
+            .filter_map(Todo::stored_at)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Names the state of the calendar by when it last changed, which incremental syncs
+    /// start from, followed by a digest of its contents, which tells apart changes made
+    /// within the same second.
+    fn sync_token(&self) -> String {
+        let mut entries = self
+            .todos
+            .iter()
+            .map(|todo| (resource_name(todo), etag(todo)))
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        let mut hasher = Sha256::new();
+        for (name, etag) in entries {
+            hasher.update(name.as_bytes());
+            hasher.update(etag.as_bytes());
+        }
+
+        format!(
+            "{}{}-{:x}",
+            SYNC_TOKEN_PREFIX,
+            self.changed_at(),
+            hasher.finalize()
+        )
+    }
+}
+
+/// When the calendar last changed as of a sync token, as long as every deletion since
+/// is still known. Tombstones expire, so older tokens make the client resync in full.
+fn parse_sync_token(token: &str) -> Option<i64> {
+    let (changed_at, _) = token.strip_prefix(SYNC_TOKEN_PREFIX)?.split_once('-')?;
+    let changed_at = changed_at.parse::<i64>().ok()?;
+    let oldest = chrono::Utc::now().timestamp() - TOMBSTONE_TTL_SECONDS as i64;
+
+    // A calendar that never changed has nothing a client could miss.
+    (changed_at == 0 || changed_at >= oldest).then_some(changed_at)
+}
+
+struct Dav<'a, T> {
+    todos_repository: &'a T,
+    event_bus: &'a TodoEventBus,
+    auth_ctx: auth::AuthContext,
+    max_todos_per_user: u64,
+}
+
+impl<T> Dav<'_, T>
+where
+    T: TodoRepository,
+{
+    async fn calendar(&self) -> anyhow::Result<Calendar> {
+        let (tombstones, todos) = self
+            .todos_repository
+            .get_all_for_user_with_tombstones(self.auth_ctx.principal_id.clone())
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .partition(Todo::is_deleted);
+
+        Ok(Calendar { todos, tombstones })
+    }
+
+    async fn todo(&self, name: &str) -> anyhow::Result<Option<Todo>> {
+        self.todos_repository
+            .get_one_for_user(self.auth_ctx.principal_id.clone(), todo_id(name))
+            .await
+    }
+
+    async fn propfind(
+        &self,
+        req: &HttpRequest,
+        body: &[u8],
+        resource: DavResource,
+    ) -> Result<HttpResponse, DavError> {
+        let request = xml::parse_request(body).map_err(DavError::BadRequest)?;
+        let depth = req
+            .headers()
+            .get("Depth")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("infinity");
+
+        let calendar = match resource {
+            DavResource::Calendar | DavResource::CalendarObject(_) => self.calendar().await?,
+            _ => Calendar::default(),
+        };
+
+        let mut multistatus = Multistatus::new();
+
+        match &resource {
+            DavResource::CalendarObject(name) => {
+                let todo = calendar
+                    .todos
+                    .iter()
+                    .find(|todo| &resource_name(todo) == name)
+                    .ok_or(DavError::NotFound)?;
+                self.respond(&mut multistatus, &resource, &request, Some(todo), &calendar);
+            }
+            _ => self.respond(&mut multistatus, &resource, &request, None, &calendar),
+        }
+
+        if depth != "0" {
+            let children = match resource {
+                DavResource::Root => vec![DavResource::Principal, DavResource::CalendarHome],
+                DavResource::CalendarHome => vec![DavResource::Calendar],
+                _ => Vec::new(),
+            };
+            for child in children {
+                self.respond(&mut multistatus, &child, &request, None, &calendar);
+            }
+
+            if resource == DavResource::Calendar {
+                for todo in &calendar.todos {
+                    let child = DavResource::CalendarObject(resource_name(todo));
+                    self.respond(&mut multistatus, &child, &request, Some(todo), &calendar);
+                }
+            }
+        }
+
+        Ok(multistatus_response(multistatus))
+    }
+
+    async fn report(&self, body: &[u8]) -> Result<HttpResponse, DavError> {
+        let request = xml::parse_request(body).map_err(DavError::BadRequest)?;
+        let report = request
+            .root
+            .clone()
+            .ok_or_else(|| DavError::BadRequest(anyhow::anyhow!("Missing REPORT type")))?;
+        let calendar = self.calendar().await?;
+        let mut multistatus = Multistatus::new();
+
+        if report.is(CALDAV, "calendar-query") {
+            for todo in &calendar.todos {
+                let resource = DavResource::CalendarObject(resource_name(todo));
+                self.respond(&mut multistatus, &resource, &request, Some(todo), &calendar);
+            }
+        } else if report.is(CALDAV, "calendar-multiget") {
+            for href in &request.hrefs {
+                let resource = DavResource::from_path(href);
+                let todo = calendar.todos.iter().find(|todo| {
+                    resource == Some(DavResource::CalendarObject(resource_name(todo)))
+                });
+                match (resource, todo) {
+                    (Some(resource), Some(todo)) => {
+                        self.respond(&mut multistatus, &resource, &request, Some(todo), &calendar)
+                    }
+                    _ => multistatus.not_found(href),
+                }
+            }
+        } else if report.is(DAV, "sync-collection") {
+            let current = calendar.sync_token();
+            // Todos written in the same second as the token was issued are sent again,
+            // as they may have changed after it.
+            let since = match request.sync_token.as_deref() {
+                None | Some("") => None,
+                Some(token) if token == current => Some(i64::MAX),
+                Some(token) => Some(
+                    parse_sync_token(token).ok_or(DavError::Forbidden("<D:valid-sync-token/>"))?,
+                ),
+            };
+            let changed = |todo: &&Todo| {
+                since.is_none_or(|since| todo.stored_at().is_none_or(|at| at >= since))
+            };
+
+            for todo in calendar.todos.iter().filter(changed) {
+                let resource = DavResource::CalendarObject(resource_name(todo));
+                self.respond(&mut multistatus, &resource, &request, Some(todo), &calendar);
+            }
+            // A full sync starts from nothing, so only incremental ones report deletions.
+            if since.is_some() {
+                for tombstone in calendar.tombstones.iter().filter(changed) {
+                    multistatus
+                        .not_found(&DavResource::CalendarObject(resource_name(tombstone)).href());
+                }
+            }
+            multistatus.sync_token(&current);
+        } else {
+            return Err(DavError::Forbidden("<D:supported-report/>"));
+        }
+
+        Ok(multistatus_response(multistatus))
+    }
+
+    fn respond(
+        &self,
+        multistatus: &mut Multistatus,
+        resource: &DavResource,
+        request: &xml::DavRequest,
+        todo: Option<&Todo>,
+        calendar: &Calendar,
+    ) {
+        let requested = if request.all_props || request.props.is_empty() {
+            resource.default_props()
+        } else {
+            request.props.clone()
+        };
+
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for prop in requested {
+            match self.prop(&prop, resource, todo, calendar) {
+                Some(value) => found.push(value),
+                None => missing.push(prop),
+            }
+        }
+
+        multistatus.response(&resource.href(), &found, &missing);
+    }
+
+    fn prop(
+        &self,
+        prop: &PropName,
+        resource: &DavResource,
+        todo: Option<&Todo>,
+        calendar: &Calendar,
+    ) -> Option<PropValue> {
+        let href = |href: &str| format!("<D:href>{}</D:href>", xml::escape(href));
+        let value = |xml: String| Some(PropValue::new(&prop.namespace, &prop.name, xml));
+
+        match (prop.namespace.as_str(), prop.name.as_str(), resource) {
+            (DAV, "current-user-principal", _) => value(href(PRINCIPAL_HREF)),
+            (DAV, "resourcetype", DavResource::Principal) => value("<D:principal/>".into()),
+            (DAV, "resourcetype", DavResource::Calendar) => {
+                value("<D:collection/><C:calendar/>".into())
+            }
+            (DAV, "resourcetype", DavResource::CalendarObject(_)) => value(String::new()),
+            (DAV, "resourcetype", _) => value("<D:collection/>".into()),
+            (DAV, "displayname", DavResource::Principal) => Some(PropValue::text(
+                DAV,
+                "displayname",
+                &self.auth_ctx.principal_name,
+            )),
+            (DAV, "displayname", DavResource::Calendar) => {
+                Some(PropValue::text(DAV, "displayname", "Todos"))
+            }
+            (DAV, "displayname", DavResource::Root | DavResource::CalendarHome) => {
+                Some(PropValue::text(DAV, "displayname", "Todo App"))
+            }
+            (DAV, "principal-URL", DavResource::Principal) => value(href(PRINCIPAL_HREF)),
+            (CALDAV, "calendar-home-set", DavResource::Principal) => {
+                value(href(CALENDAR_HOME_HREF))
+            }
+            (CALDAV, "supported-calendar-component-set", DavResource::Calendar) => {
+                value(r#"<C:comp name="VTODO"/>"#.into())
+            }
+            (DAV, "supported-report-set", DavResource::Calendar) => value(
+                [
+                    "<C:calendar-query/>",
+                    "<C:calendar-multiget/>",
+                    "<D:sync-collection/>",
+                ]
+                .iter()
+                .map(|report| {
+                    format!(
+                        "<D:supported-report><D:report>{}</D:report></D:supported-report>",
+                        report
+                    )
+                })
+                .collect(),
+            ),
+            (DAV, "current-user-privilege-set", DavResource::Calendar) => value(
+                "<D:privilege><D:read/></D:privilege><D:privilege><D:write/></D:privilege>\
+                 <D:privilege><D:write-content/></D:privilege>\
+                 <D:privilege><D:bind/></D:privilege><D:privilege><D:unbind/></D:privilege>"
+                    .into(),
+            ),
+            (CALENDARSERVER, "getctag", DavResource::Calendar)
+            | (DAV, "sync-token", DavResource::Calendar) => Some(PropValue::text(
+                &prop.namespace,
+                &prop.name,
+                &calendar.sync_token(),
+            )),
+            (DAV, "getetag", DavResource::CalendarObject(_)) => {
+                Some(PropValue::text(DAV, "getetag", &etag(todo?)))
+            }
+            (DAV, "getcontenttype", DavResource::CalendarObject(_)) => Some(PropValue::text(
+                DAV,
+                "getcontenttype",
+                "text/calendar; charset=utf-8; component=vtodo",
+            )),
+            (CALDAV, "calendar-data", DavResource::CalendarObject(_)) => Some(PropValue::text(
+                CALDAV,
+                "calendar-data",
+                &ical::render_calendar_object(todo?),
+            )),
+            _ => None,
+        }
+    }
+
+    async fn get(&self, name: &str) -> Result<HttpResponse, DavError> {
+        let todo = self.todo(name).await?.ok_or(DavError::NotFound)?;
+
+        Ok(HttpResponse::Ok()
+            .content_type(CALENDAR_DATA_CONTENT_TYPE)
+            .append_header((header::ETAG, etag(&todo)))
+            .body(ical::render_calendar_object(&todo)))
+    }
+
+    async fn put(
+        &self,
+        req: &HttpRequest,
+        body: &[u8],
+        name: &str,
+    ) -> Result<HttpResponse, DavError> {
+        let body = std::str::from_utf8(body)
+            .map_err(|e| DavError::BadRequest(anyhow::anyhow!("Invalid UTF-8 body: {}", e)))?;
+        let vtodo = ical::parse_calendar_object(body).map_err(DavError::BadRequest)?;
+        let content = TodoContent::try_from(vtodo.summary.clone()).map_err(DavError::BadRequest)?;
+
+        let existing = self.todo(name).await?;
+        check_preconditions(req, existing.as_ref())?;
+
+        let (mut todo, created) = match existing {
+            Some(mut todo) => {
+                todo.update_content(content);
+                (todo, false)
+            }
+            None => {
+                let todo_count = self
+                    .todos_repository
+                    .count_for_user(self.auth_ctx.principal_id.clone())
+                    .await?;
+                if todo_count >= self.max_todos_per_user {
+                    // RFC 4331, section 6
+                    return Err(DavError::Forbidden("<D:quota-not-exceeded/>"));
+                }
+
+                let calendar_object = CalendarObject {
+                    resource_name: name.to_string(),
+                    uid: vtodo.uid.clone(),
+                };
+                let todo = Todo::from_calendar_object(
+                    todo_id(name),
+                    calendar_object,
+                    content,
+                    self.auth_ctx.principal_id.clone(),
+                );
+                (todo, true)
+            }
+        };
+
+        todo.update_due_at(vtodo.due_at);
+        if vtodo.completed {
+            match vtodo.completed_at {
+                Some(completed_at) => todo.mark_as_done_at(completed_at),
+                None => todo.mark_as_done(),
+            }
+        } else {
+            todo.mark_as_unfinished();
+        }
+
+        // The stored representation differs from the one the client sent, so no ETag is
+        // returned and the client refetches the resource (RFC 4791, section 5.3.4).
+        if created {
+            let event = TodoEvent::created(&todo)?;
+            self.todos_repository.create(todo).await?;
+            self.event_bus.publish(event);
+            Ok(HttpResponse::Created().finish())
+        } else {
+            let event = TodoEvent::updated(&todo)?;
+            self.todos_repository.save(todo).await?;
+            self.event_bus.publish(event);
+            Ok(HttpResponse::NoContent().finish())
+        }
+    }
+
+    async fn delete(&self, req: &HttpRequest, name: &str) -> Result<HttpResponse, DavError> {
+        let todo = self.todo(name).await?.ok_or(DavError::NotFound)?;
+        check_preconditions(req, Some(&todo))?;
+
+        let event = TodoEvent::deleted(self.auth_ctx.principal_id.clone(), todo.id())?;
+        self.todos_repository
+            .delete_for_user_by_id(self.auth_ctx.principal_id.clone(), todo.id())
+            .await?;
+        self.event_bus.publish(event);
+
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+fn check_preconditions(req: &HttpRequest, existing: Option<&Todo>) -> Result<(), DavError> {
+    let header = |name: header::HeaderName| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+
+    if let Some(if_match) = header(header::IF_MATCH) {
+        let matches = existing.is_some_and(|todo| {
+            if_match == "*" || if_match.split(',').any(|tag| tag.trim() == etag(todo))
+        });
+        if !matches {
+            return Err(DavError::PreconditionFailed);
+        }
+    }
+
+    if header(header::IF_NONE_MATCH) == Some("*") && existing.is_some() {
+        return Err(DavError::PreconditionFailed);
+    }
+
+    Ok(())
+}
+
+fn multistatus_response(multistatus: Multistatus) -> HttpResponse {
+    HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type("application/xml; charset=utf-8")
+        .body(multistatus.finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DavError {
+    #[error("Resource not found")]
+    NotFound,
+    #[error("Method not allowed")]
+    MethodNotAllowed,
+    #[error("Precondition failed")]
+    PreconditionFailed,
+    #[error("{0}")]
+    Forbidden(&'static str),
+    #[error("{0}")]
+    BadRequest(#[source] anyhow::Error),
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DavError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DavError::NotFound => StatusCode::NOT_FOUND,
+            DavError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            DavError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            DavError::Forbidden(_) => StatusCode::FORBIDDEN,
+            DavError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            DavError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            DavError::Forbidden(precondition) => HttpResponse::Forbidden()
+                .content_type("application/xml; charset=utf-8")
+                .body(xml::error(precondition)),
+            DavError::MethodNotAllowed => HttpResponse::MethodNotAllowed()
+                .append_header((
+                    header::ALLOW,
+                    "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT",
+                ))
+                .finish(),
+            _ => HttpResponse::build(self.status_code()).body(self.to_string()),
+        }
+    }
+}
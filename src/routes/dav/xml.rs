@@ -0,0 +1,231 @@
+use anyhow::Context;
+use quick_xml::{
+    events::Event,
+    name::{Namespace, ResolveResult},
+    NsReader,
+};
+
+pub const DAV: &str = "DAV:";
+pub const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+pub const CALENDARSERVER: &str = "http://calendarserver.org/ns/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropName {
+    pub namespace: String,
+    pub name: String,
+}
+
+impl PropName {
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+}
+
+/// The parts of PROPFIND and REPORT request bodies the server acts upon.
+#[derive(Debug, Default)]
+pub struct DavRequest {
+    pub root: Option<PropName>,
+    pub props: Vec<PropName>,
+    pub all_props: bool,
+    pub hrefs: Vec<String>,
+    pub sync_token: Option<String>,
+}
+
+/// An empty PROPFIND body is equivalent to requesting `allprop`.
+pub fn parse_request(body: &[u8]) -> anyhow::Result<DavRequest> {
+    let mut request = DavRequest::default();
+    if body.iter().all(u8::is_ascii_whitespace) {
+        request.all_props = true;
+        return Ok(request);
+    }
+
+    let mut reader = NsReader::from_reader(body);
+    reader.config_mut().trim_text(true);
+    let mut stack: Vec<PropName> = Vec::new();
+
+    loop {
+        let (namespace, event) = reader
+            .read_resolved_event()
+            .context("Malformed XML request body")?;
+
+        match event {
+            Event::Start(ref element) | Event::Empty(ref element) => {
+                let name = PropName {
+                    namespace: namespace_of(&namespace),
+                    name: String::from_utf8_lossy(element.local_name().as_ref()).into_owned(),
+                };
+
+                let parent = stack.last();
+                if parent.is_some_and(|parent| parent.is(DAV, "prop")) && stack.len() == 2 {
+                    request.props.push(name.clone());
+                } else if name.is(DAV, "allprop") {
+                    request.all_props = true;
+                }
+                if request.root.is_none() {
+                    request.root = Some(name.clone());
+                }
+
+                if matches!(event, Event::Start(_)) {
+                    stack.push(name);
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().context("Malformed XML text")?.into_owned();
+                match stack.last() {
+                    Some(element) if element.is(DAV, "href") => request.hrefs.push(text),
+                    Some(element) if element.is(DAV, "sync-token") => {
+                        request.sync_token = Some(text)
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(_) => {
+                stack.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(request)
+}
+
+fn namespace_of(resolved: &ResolveResult) -> String {
+    match resolved {
+        ResolveResult::Bound(Namespace(namespace)) => {
+            String::from_utf8_lossy(namespace).into_owned()
+        }
+        _ => String::new(),
+    }
+}
+
+/// A property value, already serialized as the XML content of the property element.
+pub struct PropValue {
+    pub name: PropName,
+    pub xml: String,
+}
+
+impl PropValue {
+    pub fn new(namespace: &str, name: &str, xml: impl Into<String>) -> Self {
+        Self {
+            name: PropName {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+            },
+            xml: xml.into(),
+        }
+    }
+
+    pub fn text(namespace: &str, name: &str, text: &str) -> Self {
+        Self::new(namespace, name, escape(text))
+    }
+}
+
+pub struct Multistatus(String);
+
+impl Multistatus {
+    pub fn new() -> Self {
+        Self(format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><D:multistatus xmlns:D="{}" xmlns:C="{}" xmlns:CS="{}">"#,
+            DAV, CALDAV, CALENDARSERVER
+        ))
+    }
+
+    pub fn response(&mut self, href: &str, found: &[PropValue], missing: &[PropName]) {
+        self.0.push_str("<D:response>");
+        self.0
+            .push_str(&format!("<D:href>{}</D:href>", escape(href)));
+
+        if !found.is_empty() {
+            self.0.push_str("<D:propstat><D:prop>");
+            for prop in found {
+                let tag = tag(&prop.name, 0);
+                if prop.xml.is_empty() {
+                    self.0.push_str(&format!("<{}/>", tag.open));
+                } else {
+                    self.0
+                        .push_str(&format!("<{}>{}</{}>", tag.open, prop.xml, tag.close));
+                }
+            }
+            self.0
+                .push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat>");
+        }
+
+        if !missing.is_empty() {
+            self.0.push_str("<D:propstat><D:prop>");
+            for (idx, prop) in missing.iter().enumerate() {
+                self.0.push_str(&format!("<{}/>", tag(prop, idx).open));
+            }
+            self.0
+                .push_str("</D:prop><D:status>HTTP/1.1 404 Not Found</D:status></D:propstat>");
+        }
+
+        self.0.push_str("</D:response>");
+    }
+
+    pub fn not_found(&mut self, href: &str) {
+        self.0.push_str(&format!(
+            "<D:response><D:href>{}</D:href><D:status>HTTP/1.1 404 Not Found</D:status></D:response>",
+            escape(href)
+        ));
+    }
+
+    pub fn sync_token(&mut self, token: &str) {
+        self.0
+            .push_str(&format!("<D:sync-token>{}</D:sync-token>", escape(token)));
+    }
+
+    pub fn finish(mut self) -> String {
+        self.0.push_str("</D:multistatus>");
+        self.0
+    }
+}
+
+struct Tag {
+    open: String,
+    close: String,
+}
+
+fn tag(name: &PropName, idx: usize) -> Tag {
+    let prefix = match name.namespace.as_str() {
+        DAV => "D",
+        CALDAV => "C",
+        CALENDARSERVER => "CS",
+        "" => {
+            return Tag {
+                open: format!("{} xmlns=\"\"", name.name),
+                close: name.name.clone(),
+            };
+        }
+        _ => {
+            let prefix = format!("x{}", idx);
+            return Tag {
+                open: format!(
+                    r#"{}:{} xmlns:{}="{}""#,
+                    prefix,
+                    name.name,
+                    prefix,
+                    escape(&name.namespace)
+                ),
+                close: format!("{}:{}", prefix, name.name),
+            };
+        }
+    };
+
+    let qualified = format!("{}:{}", prefix, name.name);
+    Tag {
+        open: qualified.clone(),
+        close: qualified,
+    }
+}
+
+pub fn error(precondition: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?><D:error xmlns:D="{}" xmlns:C="{}">{}</D:error>"#,
+        DAV, CALDAV, precondition
+    )
+}
+
+pub fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).into_owned()
+}
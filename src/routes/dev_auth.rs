@@ -0,0 +1,160 @@
+use actix_web::{
+    cookie::Cookie,
+    http::{header, StatusCode},
+    web, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use tera::Tera;
+
+use crate::{
+    auth::{Authenticator, DevelopmentPrincipal, DEVELOPMENT_PRINCIPAL_COOKIE},
+    problem::FieldError,
+};
+
+const DEFAULT_LOGIN_REDIRECT: &str = "/me/todos";
+
+#[derive(Deserialize)]
+pub struct LoginRedirect {
+    post_login_redirect_uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LogoutRedirect {
+    post_logout_redirect_uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DevelopmentLogin {
+    principal_id: String,
+    principal_name: String,
+    post_login_redirect_uri: Option<String>,
+}
+
+/// Stands in for the App Service `/.auth/login/{provider}` endpoint when running locally.
+#[tracing::instrument(
+    name = "Show development login form",
+    skip(redirect, tmpl, authenticator)
+)]
+pub async fn dev_login_form(
+    redirect: web::Query<LoginRedirect>,
+    tmpl: web::Data<Tera>,
+    authenticator: web::Data<Authenticator>,
+) -> Result<HttpResponse, DevAuthError> {
+    let Authenticator::Development(settings) = authenticator.get_ref() else {
+        return Err(DevAuthError::Disabled);
+    };
+
+    let mut context = tera::Context::new();
+    context.insert("principal", &DevelopmentPrincipal::from(settings));
+    context.insert(
+        "post_login_redirect_uri",
+        &local_redirect(
+            redirect.into_inner().post_login_redirect_uri,
+            DEFAULT_LOGIN_REDIRECT,
+        ),
+    );
+
+    let html = tmpl
+        .render("dev_login.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[tracing::instrument(name = "Log in with development identity", skip(login, authenticator))]
+pub async fn dev_login(
+    login: web::Form<DevelopmentLogin>,
+    authenticator: web::Data<Authenticator>,
+) -> Result<HttpResponse, DevAuthError> {
+    if !matches!(authenticator.get_ref(), Authenticator::Development(_)) {
+        return Err(DevAuthError::Disabled);
+    }
+
+    let login = login.into_inner();
+    let principal = DevelopmentPrincipal {
+        principal_id: login.principal_id.trim().to_string(),
+        principal_name: login.principal_name.trim().to_string(),
+    };
+    if principal.principal_id.is_empty() || principal.principal_name.is_empty() {
+        let field = if principal.principal_id.is_empty() {
+            "principal_id"
+        } else {
+            "principal_name"
+        };
+        return Err(DevAuthError::ValidationError(FieldError::new(
+            field,
+            "Principal id and name cannot be blank",
+        )));
+    }
+
+    Ok(HttpResponse::SeeOther()
+        .cookie(principal.to_cookie())
+        .append_header((
+            header::LOCATION,
+            local_redirect(login.post_login_redirect_uri, DEFAULT_LOGIN_REDIRECT),
+        ))
+        .finish())
+}
+
+/// Stands in for the App Service `/.auth/logout` endpoint when running locally.
+#[tracing::instrument(name = "Log out development identity", skip(redirect, authenticator))]
+pub async fn dev_logout(
+    redirect: web::Query<LogoutRedirect>,
+    authenticator: web::Data<Authenticator>,
+) -> Result<HttpResponse, DevAuthError> {
+    if !matches!(authenticator.get_ref(), Authenticator::Development(_)) {
+        return Err(DevAuthError::Disabled);
+    }
+
+    let mut cookie = Cookie::build(DEVELOPMENT_PRINCIPAL_COOKIE, "")
+        .path("/")
+        .finish();
+    cookie.make_removal();
+
+    Ok(HttpResponse::SeeOther()
+        .cookie(cookie)
+        .append_header((
+            header::LOCATION,
+            local_redirect(redirect.into_inner().post_logout_redirect_uri, "/"),
+        ))
+        .finish())
+}
+
+/// Only same-origin paths are followed, so the login endpoints cannot be used as open redirects.
+/// Browsers drop tabs and newlines from URLs, which would turn `/\t/host` into `//host`.
+fn local_redirect(uri: Option<String>, default: &str) -> String {
+    uri.filter(|uri| {
+        uri.starts_with('/')
+            && !uri.starts_with("//")
+            && !uri.starts_with("/\\")
+            && !uri.chars().any(char::is_control)
+    })
+    .unwrap_or_else(|| default.to_string())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DevAuthError {
+    #[error("Development authentication is disabled")]
+    Disabled,
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DevAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DevAuthError::Disabled => StatusCode::NOT_FOUND,
+            DevAuthError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            DevAuthError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            DevAuthError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
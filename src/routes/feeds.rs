@@ -0,0 +1,58 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use secrecy::SecretString;
+
+use crate::{
+    ical,
+    repositories::{FeedTokenRepository, TodoRepository},
+    tokens,
+};
+
+#[tracing::instrument(
+    name = "Get todos calendar feed",
+    skip(token, todos_repository, feed_tokens_repository)
+)]
+pub async fn get_todos_calendar_feed<T, F>(
+    token: web::Path<String>,
+    todos_repository: web::Data<T>,
+    feed_tokens_repository: web::Data<F>,
+) -> Result<HttpResponse, GetTodosCalendarFeedError>
+where
+    T: TodoRepository,
+    F: FeedTokenRepository,
+{
+    let token_hash = tokens::hash(&SecretString::from(token.into_inner()));
+
+    let feed_token = feed_tokens_repository
+        .get_ref()
+        .get_by_hash(token_hash)
+        .await?
+        .ok_or(GetTodosCalendarFeedError::UnknownFeed)?;
+
+    let todos = todos_repository
+        .get_ref()
+        .get_all_for_user(feed_token.user_id())
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(ical::render_calendar("Todos", &todos)))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetTodosCalendarFeedError {
+    #[error("Feed not found")]
+    UnknownFeed,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetTodosCalendarFeedError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetTodosCalendarFeedError::UnknownFeed => StatusCode::NOT_FOUND,
+            GetTodosCalendarFeedError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
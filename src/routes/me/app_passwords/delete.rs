@@ -0,0 +1,39 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use crate::{auth, model::TokenHash, repositories::AppPasswordRepository};
+
+#[tracing::instrument(
+    name = "Delete app password",
+    skip(app_password_id, app_passwords_repository, auth_ctx)
+)]
+pub async fn delete_app_password<P>(
+    app_password_id: web::Path<TokenHash>,
+    app_passwords_repository: web::Data<P>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DeleteAppPasswordError>
+where
+    P: AppPasswordRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    app_passwords_repository
+        .get_ref()
+        .delete_for_user_by_id(user_id, app_password_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteAppPasswordError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DeleteAppPasswordError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteAppPasswordError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
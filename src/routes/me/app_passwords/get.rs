@@ -0,0 +1,51 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use tera::Tera;
+
+use crate::{auth, repositories::AppPasswordRepository};
+
+#[tracing::instrument(
+    name = "Get all user app passwords",
+    skip(tmpl, app_passwords_repository, auth_ctx, csrf_token)
+)]
+pub async fn get_all_user_app_passwords<P>(
+    tmpl: web::Data<Tera>,
+    app_passwords_repository: web::Data<P>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetAllUserAppPasswordsError>
+where
+    P: AppPasswordRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let app_passwords = app_passwords_repository
+        .get_ref()
+        .get_all_for_user(user_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("app_passwords", &app_passwords);
+
+    let html = tmpl
+        .render("app_passwords.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetAllUserAppPasswordsError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetAllUserAppPasswordsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetAllUserAppPasswordsError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use tera::Tera;
+
+use crate::{
+    auth, model::AppPassword, problem::FieldError, repositories::AppPasswordRepository, tokens,
+};
+
+const MAX_NAME_LEN: usize = 100;
+
+#[derive(Deserialize)]
+pub struct NewAppPassword {
+    name: String,
+}
+
+#[tracing::instrument(
+    name = "Create app password",
+    skip(req, new_app_password, tmpl, app_passwords_repository, auth_ctx)
+)]
+pub async fn create_app_password<P>(
+    req: HttpRequest,
+    new_app_password: web::Form<NewAppPassword>,
+    tmpl: web::Data<Tera>,
+    app_passwords_repository: web::Data<P>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, CreateAppPasswordError>
+where
+    P: AppPasswordRepository,
+{
+    let name = new_app_password.into_inner().name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(CreateAppPasswordError::ValidationError(FieldError::new(
+            "name",
+            format!(
+                "App password name must be between 1 and {} characters long",
+                MAX_NAME_LEN
+            ),
+        )));
+    }
+
+    let password = tokens::generate();
+    let app_password = AppPassword::new(
+        tokens::hash(&password),
+        auth_ctx.principal_id.clone(),
+        auth_ctx.principal_name.clone(),
+        auth_ctx.verified_email.clone(),
+        name,
+    );
+
+    app_passwords_repository
+        .get_ref()
+        .create(app_password)
+        .await
+        .map_err(CreateAppPasswordError::UnexpectedError)?;
+
+    let connection_info = req.connection_info();
+    let server_url = format!(
+        "{}://{}/dav/",
+        connection_info.scheme(),
+        connection_info.host()
+    );
+
+    let mut context = tera::Context::new();
+    context.insert("server_url", &server_url);
+    context.insert("username", &auth_ctx.principal_name);
+    context.insert("password", password.expose_secret());
+
+    let html = tmpl
+        .render("app_password_created.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(CreateAppPasswordError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateAppPasswordError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for CreateAppPasswordError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CreateAppPasswordError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreateAppPasswordError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            CreateAppPasswordError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
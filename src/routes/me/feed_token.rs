@@ -0,0 +1,58 @@
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use secrecy::ExposeSecret;
+use tera::Tera;
+
+use crate::{auth, model::FeedToken, repositories::FeedTokenRepository, tokens};
+
+#[tracing::instrument(
+    name = "Rotate calendar feed token",
+    skip(req, tmpl, feed_tokens_repository, auth_ctx)
+)]
+pub async fn rotate_feed_token<F>(
+    req: HttpRequest,
+    tmpl: web::Data<Tera>,
+    feed_tokens_repository: web::Data<F>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, RotateFeedTokenError>
+where
+    F: FeedTokenRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+    let token = tokens::generate();
+
+    feed_tokens_repository
+        .get_ref()
+        .replace_for_user(FeedToken::new(tokens::hash(&token), user_id))
+        .await?;
+
+    let connection_info = req.connection_info();
+    let feed_url = format!(
+        "{}://{}/feeds/{}/todos.ics",
+        connection_info.scheme(),
+        connection_info.host(),
+        token.expose_secret()
+    );
+
+    let mut context = tera::Context::new();
+    context.insert("feed_url", &feed_url);
+
+    let html = tmpl
+        .render("feed_token.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RotateFeedTokenError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for RotateFeedTokenError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RotateFeedTokenError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use secrecy::ExposeSecret;
+use tera::Tera;
+
+use crate::{
+    auth, configuration::InboundEmailSettings, email_gateway, model::InboundAddressToken,
+    repositories::InboundAddressTokenRepository, tokens,
+};
+
+#[tracing::instrument(
+    name = "Rotate inbound email address",
+    skip(
+        tmpl,
+        inbound_email_settings,
+        inbound_address_tokens_repository,
+        auth_ctx
+    )
+)]
+pub async fn rotate_inbound_email_address<K>(
+    tmpl: web::Data<Tera>,
+    inbound_email_settings: web::Data<InboundEmailSettings>,
+    inbound_address_tokens_repository: web::Data<K>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, RotateInboundEmailAddressError>
+where
+    K: InboundAddressTokenRepository,
+{
+    if !inbound_email_settings.enabled {
+        return Err(RotateInboundEmailAddressError::Disabled);
+    }
+
+    let user_id = auth_ctx.principal_id.clone();
+    let token = tokens::generate_lowercase();
+
+    inbound_address_tokens_repository
+        .get_ref()
+        .replace_for_user(InboundAddressToken::new(tokens::hash(&token), user_id))
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert(
+        "address",
+        &email_gateway::inbound_address(token.expose_secret(), &inbound_email_settings.domain),
+    );
+    context.insert("sender", &auth_ctx.principal_name);
+
+    let html = tmpl
+        .render("inbound_email_address.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RotateInboundEmailAddressError {
+    #[error("Email to todo is not enabled")]
+    Disabled,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for RotateInboundEmailAddressError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RotateInboundEmailAddressError::Disabled => StatusCode::NOT_FOUND,
+            RotateInboundEmailAddressError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -1 +1,13 @@
+pub mod app_passwords;
+mod feed_token;
+mod inbound_email_address;
+pub mod notifications;
+pub mod push_subscriptions;
+pub mod share_links;
+pub mod sharing;
 pub mod todos;
+pub mod tokens;
+pub mod webhooks;
+
+pub use feed_token::*;
+pub use inbound_email_address::*;
@@ -0,0 +1,109 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use serde::Serialize;
+use tera::Tera;
+
+use crate::{
+    auth,
+    configuration::{NotificationSettings, PushSettings},
+    push::PushSubscriptionRepository,
+    repositories::UserRepository,
+};
+
+/// A browser subscribed to push messages, as listed on the page.
+#[derive(Serialize)]
+struct PushSubscriptionView {
+    id: String,
+    user_agent: Option<String>,
+    created_at: String,
+}
+
+#[tracing::instrument(
+    name = "Get notification preferences",
+    skip(
+        tmpl,
+        notification_settings,
+        push_settings,
+        users_repository,
+        subscriptions_repository,
+        auth_ctx,
+        csrf_token
+    )
+)]
+pub async fn get_notification_preferences<U, S>(
+    tmpl: web::Data<Tera>,
+    notification_settings: web::Data<NotificationSettings>,
+    push_settings: web::Data<PushSettings>,
+    users_repository: web::Data<U>,
+    subscriptions_repository: web::Data<S>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetNotificationPreferencesError>
+where
+    U: UserRepository,
+    S: PushSubscriptionRepository,
+{
+    let preferences = users_repository
+        .get_ref()
+        .get_by_id(auth_ctx.principal_id.clone())
+        .await?
+        .map(|user| user.notification_preferences().clone())
+        .unwrap_or_default();
+
+    let time_zones = chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|time_zone| time_zone.name())
+        .collect::<Vec<_>>();
+
+    // Browsers can only subscribe with the public key of the server.
+    let vapid_public_key = push_settings
+        .vapid_public_key
+        .as_ref()
+        .filter(|_| push_settings.enabled);
+    let push_subscriptions = if vapid_public_key.is_some() {
+        subscriptions_repository
+            .get_ref()
+            .get_all_for_user(auth_ctx.principal_id.clone())
+            .map_ok(|subscription| PushSubscriptionView {
+                id: subscription.id(),
+                user_agent: subscription.user_agent().map(str::to_string),
+                created_at: subscription.created_at().format("%Y-%m-%d").to_string(),
+            })
+            .try_collect::<Vec<_>>()
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("enabled", &notification_settings.enabled);
+    context.insert("email", &auth_ctx.principal_name);
+    context.insert("time_zone", preferences.time_zone.name());
+    context.insert("time_zones", &time_zones);
+    context.insert("preferences", &preferences);
+    context.insert("vapid_public_key", &vapid_public_key);
+    context.insert("push_subscriptions", &push_subscriptions);
+
+    let html = tmpl
+        .render("notifications.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetNotificationPreferencesError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetNotificationPreferencesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetNotificationPreferencesError::UnexpectedError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+
+use crate::{
+    auth,
+    model::{NotificationPreferences, User},
+    problem::FieldError,
+    repositories::UserRepository,
+};
+
+/// Unchecked checkboxes are left out of forms, so the flags are present or missing.
+#[derive(Deserialize)]
+pub struct NotificationPreferencesForm {
+    time_zone: String,
+    #[serde(default)]
+    email_reminders: Option<String>,
+    #[serde(default)]
+    daily_digest: Option<String>,
+}
+
+#[tracing::instrument(
+    name = "Update notification preferences",
+    skip(form, users_repository, auth_ctx)
+)]
+pub async fn update_notification_preferences<U>(
+    form: web::Form<NotificationPreferencesForm>,
+    users_repository: web::Data<U>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, UpdateNotificationPreferencesError>
+where
+    U: UserRepository,
+{
+    let NotificationPreferencesForm {
+        time_zone,
+        email_reminders,
+        daily_digest,
+    } = form.into_inner();
+
+    let time_zone = time_zone.trim().parse::<chrono_tz::Tz>().map_err(|_| {
+        UpdateNotificationPreferencesError::ValidationError(FieldError::new(
+            "time_zone",
+            format!("{} is not a known time zone", time_zone.trim()),
+        ))
+    })?;
+    let preferences = NotificationPreferences {
+        time_zone,
+        email_reminders: email_reminders.is_some(),
+        daily_digest: daily_digest.is_some(),
+    };
+
+    let mut user = users_repository
+        .get_ref()
+        .get_by_id(auth_ctx.principal_id.clone())
+        .await
+        .map_err(UpdateNotificationPreferencesError::UnexpectedError)?
+        .unwrap_or_else(|| {
+            User::new(
+                auth_ctx.principal_id.clone(),
+                auth_ctx.principal_name.clone(),
+            )
+        });
+    user.update_notification_preferences(preferences);
+
+    users_repository
+        .get_ref()
+        .save(user)
+        .await
+        .map_err(UpdateNotificationPreferencesError::UnexpectedError)?;
+
+    Ok(HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/me/notifications"))
+        .finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateNotificationPreferencesError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for UpdateNotificationPreferencesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            UpdateNotificationPreferencesError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            UpdateNotificationPreferencesError::UnexpectedError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            UpdateNotificationPreferencesError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
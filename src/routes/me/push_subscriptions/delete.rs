@@ -0,0 +1,41 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use crate::{auth, push::PushSubscriptionRepository};
+
+/// Unsubscribes one of the user's browsers. The browser keeps its subscription with the
+/// push service until it unsubscribes too, messages are no longer sent to it.
+#[tracing::instrument(
+    name = "Delete push subscription",
+    skip(subscription_id, subscriptions_repository, auth_ctx)
+)]
+pub async fn delete_push_subscription<S>(
+    subscription_id: web::Path<String>,
+    subscriptions_repository: web::Data<S>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DeletePushSubscriptionError>
+where
+    S: PushSubscriptionRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    subscriptions_repository
+        .get_ref()
+        .delete_for_user_by_id(user_id, subscription_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeletePushSubscriptionError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DeletePushSubscriptionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeletePushSubscriptionError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth,
+    configuration::PushSettings,
+    model::PushSubscription,
+    problem::FieldError,
+    push::{self, PushSubscriptionRepository},
+};
+
+const MAX_ENDPOINT_LEN: usize = 2000;
+const MAX_USER_AGENT_LEN: usize = 200;
+const MAX_SUBSCRIPTIONS_PER_USER: usize = 10;
+
+/// A subscription as the Push API serializes it with `PushSubscription.toJSON()`.
+#[derive(Deserialize)]
+pub struct PushSubscriptionBody {
+    endpoint: String,
+    keys: PushSubscriptionKeys,
+}
+
+#[derive(Deserialize)]
+pub struct PushSubscriptionKeys {
+    p256dh: String,
+    auth: String,
+}
+
+#[derive(Serialize)]
+struct CreatedPushSubscription {
+    id: String,
+}
+
+/// Subscribes the browser to push messages of the user's todos. The browser's user agent
+/// is kept to tell the user's devices apart.
+#[tracing::instrument(
+    name = "Create push subscription",
+    skip(req, body, subscriptions_repository, push_settings, auth_ctx)
+)]
+pub async fn create_push_subscription<S>(
+    req: HttpRequest,
+    body: web::Json<PushSubscriptionBody>,
+    subscriptions_repository: web::Data<S>,
+    push_settings: web::Data<PushSettings>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, CreatePushSubscriptionError>
+where
+    S: PushSubscriptionRepository,
+{
+    if !push_settings.enabled {
+        return Err(CreatePushSubscriptionError::Disabled);
+    }
+
+    let PushSubscriptionBody { endpoint, keys } = body.into_inner();
+    if endpoint.len() > MAX_ENDPOINT_LEN {
+        return Err(CreatePushSubscriptionError::ValidationError(
+            FieldError::new(
+                "endpoint",
+                format!(
+                    "The endpoint must be at most {} characters long",
+                    MAX_ENDPOINT_LEN
+                ),
+            ),
+        ));
+    }
+    push::check_endpoint(&endpoint, &push_settings).map_err(|e| {
+        CreatePushSubscriptionError::ValidationError(FieldError::new("endpoint", e))
+    })?;
+    // The browser's key is an uncompressed P-256 point.
+    if !push::decode_key(&keys.p256dh).is_ok_and(|key| key.len() == 65 && key[0] == 0x04) {
+        return Err(CreatePushSubscriptionError::ValidationError(
+            FieldError::new("keys.p256dh", "The key is not a valid P-256 public key"),
+        ));
+    }
+    if !push::decode_key(&keys.auth).is_ok_and(|secret| secret.len() == 16) {
+        return Err(CreatePushSubscriptionError::ValidationError(
+            FieldError::new("keys.auth", "The authentication secret must be 16 bytes"),
+        ));
+    }
+
+    let user_id = auth_ctx.principal_id.clone();
+    let subscription_id = PushSubscription::id_for(&endpoint);
+    let existing = subscriptions_repository
+        .get_ref()
+        .get_all_for_user(user_id.clone())
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(CreatePushSubscriptionError::UnexpectedError)?;
+    let is_new = !existing
+        .iter()
+        .any(|subscription| subscription.id() == subscription_id);
+    if is_new && existing.len() >= MAX_SUBSCRIPTIONS_PER_USER {
+        return Err(CreatePushSubscriptionError::ValidationError(
+            FieldError::new(
+                "endpoint",
+                format!(
+                    "You can subscribe at most {} browsers",
+                    MAX_SUBSCRIPTIONS_PER_USER
+                ),
+            ),
+        ));
+    }
+
+    let user_agent = req
+        .headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(MAX_USER_AGENT_LEN).collect());
+    let subscription = PushSubscription::new(user_id, endpoint, keys.p256dh, keys.auth, user_agent);
+
+    subscriptions_repository
+        .get_ref()
+        .save(subscription)
+        .await
+        .map_err(CreatePushSubscriptionError::UnexpectedError)?;
+
+    Ok(HttpResponse::Created().json(CreatedPushSubscription {
+        id: subscription_id,
+    }))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreatePushSubscriptionError {
+    #[error("Push notifications are not enabled")]
+    Disabled,
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for CreatePushSubscriptionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CreatePushSubscriptionError::Disabled => StatusCode::NOT_FOUND,
+            CreatePushSubscriptionError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreatePushSubscriptionError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            CreatePushSubscriptionError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use crate::{auth, model::ShareLinkId, repositories::ShareLinkRepository};
+
+#[tracing::instrument(
+    name = "Delete share link",
+    skip(share_link_id, share_links_repository, auth_ctx)
+)]
+pub async fn delete_share_link<S>(
+    share_link_id: web::Path<ShareLinkId>,
+    share_links_repository: web::Data<S>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DeleteShareLinkError>
+where
+    S: ShareLinkRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    share_links_repository
+        .get_ref()
+        .delete_for_user_by_id(user_id, share_link_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteShareLinkError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DeleteShareLinkError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteShareLinkError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use tera::Tera;
+
+use crate::{auth, repositories::ShareLinkRepository};
+
+#[tracing::instrument(
+    name = "Get all user share links",
+    skip(tmpl, share_links_repository, auth_ctx, csrf_token)
+)]
+pub async fn get_all_user_share_links<S>(
+    tmpl: web::Data<Tera>,
+    share_links_repository: web::Data<S>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetAllUserShareLinksError>
+where
+    S: ShareLinkRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let share_links = share_links_repository
+        .get_ref()
+        .get_all_for_user(user_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("share_links", &share_links);
+
+    let html = tmpl
+        .render("share_links.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetAllUserShareLinksError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetAllUserShareLinksError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetAllUserShareLinksError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
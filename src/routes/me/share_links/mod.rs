@@ -0,0 +1,7 @@
+mod delete;
+mod get;
+mod post;
+
+pub use delete::*;
+pub use get::*;
+pub use post::*;
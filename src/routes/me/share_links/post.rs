@@ -0,0 +1,119 @@
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use tera::Tera;
+
+use crate::{
+    auth, model::ShareLink, problem::FieldError, repositories::ShareLinkRepository,
+    routes::share_link_token, tokens::Signer,
+};
+
+const MAX_NAME_LEN: usize = 100;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareLinkExpiry {
+    Never,
+    Day,
+    Week,
+    Month,
+}
+
+impl ShareLinkExpiry {
+    fn duration(self) -> Option<chrono::Duration> {
+        match self {
+            ShareLinkExpiry::Never => None,
+            ShareLinkExpiry::Day => Some(chrono::Duration::days(1)),
+            ShareLinkExpiry::Week => Some(chrono::Duration::weeks(1)),
+            ShareLinkExpiry::Month => Some(chrono::Duration::days(30)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewShareLink {
+    name: String,
+    expires_in: ShareLinkExpiry,
+}
+
+#[tracing::instrument(
+    name = "Create share link",
+    skip(req, new_share_link, tmpl, signer, share_links_repository, auth_ctx)
+)]
+pub async fn create_share_link<S>(
+    req: HttpRequest,
+    new_share_link: web::Form<NewShareLink>,
+    tmpl: web::Data<Tera>,
+    signer: web::Data<Signer>,
+    share_links_repository: web::Data<S>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, CreateShareLinkError>
+where
+    S: ShareLinkRepository,
+{
+    let NewShareLink { name, expires_in } = new_share_link.into_inner();
+    let name = name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(CreateShareLinkError::ValidationError(FieldError::new(
+            "name",
+            format!(
+                "Link name must be between 1 and {} characters long",
+                MAX_NAME_LEN
+            ),
+        )));
+    }
+
+    let expires_at = expires_in
+        .duration()
+        .map(|duration| chrono::Utc::now() + duration);
+    let share_link = ShareLink::new(auth_ctx.principal_id.clone(), name, expires_at);
+    let token = share_link_token(&share_link, &signer);
+
+    share_links_repository
+        .get_ref()
+        .create(share_link)
+        .await
+        .map_err(CreateShareLinkError::UnexpectedError)?;
+
+    let connection_info = req.connection_info();
+    let share_url = format!(
+        "{}://{}/shared/{}",
+        connection_info.scheme(),
+        connection_info.host(),
+        token.expose_secret()
+    );
+
+    let mut context = tera::Context::new();
+    context.insert("share_url", &share_url);
+
+    let html = tmpl
+        .render("share_link_created.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(CreateShareLinkError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateShareLinkError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for CreateShareLinkError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CreateShareLinkError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreateShareLinkError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            CreateShareLinkError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use crate::{auth, model::UserId, repositories::ListMemberRepository};
+
+#[tracing::instrument(
+    name = "Revoke todo list access",
+    skip(member_id, list_members_repository, auth_ctx)
+)]
+pub async fn revoke_list_member<M>(
+    member_id: web::Path<UserId>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, RevokeListMemberError>
+where
+    M: ListMemberRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    list_members_repository
+        .get_ref()
+        .delete(user_id, member_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RevokeListMemberError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for RevokeListMemberError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RevokeListMemberError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use tera::Tera;
+
+use crate::{auth, repositories::ListMemberRepository};
+
+#[tracing::instrument(
+    name = "Get todo list members",
+    skip(tmpl, list_members_repository, auth_ctx, csrf_token)
+)]
+pub async fn get_list_members<M>(
+    tmpl: web::Data<Tera>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetListMembersError>
+where
+    M: ListMemberRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let list_members = list_members_repository
+        .get_ref()
+        .get_all_for_owner(user_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("list_members", &list_members);
+
+    let html = tmpl
+        .render("sharing.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetListMembersError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetListMembersError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetListMembersError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
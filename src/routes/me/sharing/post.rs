@@ -0,0 +1,101 @@
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+
+use crate::{
+    auth,
+    model::{ListMember, ListRole},
+    problem::FieldError,
+    repositories::{ListMemberRepository, UserRepository},
+};
+
+#[derive(Deserialize)]
+pub struct NewListMember {
+    principal_name: String,
+    role: ListRole,
+}
+
+/// Shares the caller's todo list with another user, or changes the role of an existing
+/// member. Only users who have signed in before can be found by their principal name.
+#[tracing::instrument(
+    name = "Share todo list",
+    skip(new_list_member, users_repository, list_members_repository, auth_ctx)
+)]
+pub async fn share_list<U, M>(
+    new_list_member: web::Form<NewListMember>,
+    users_repository: web::Data<U>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, ShareListError>
+where
+    U: UserRepository,
+    M: ListMemberRepository,
+{
+    let NewListMember {
+        principal_name,
+        role,
+    } = new_list_member.into_inner();
+
+    let member = users_repository
+        .get_ref()
+        .find_by_principal_name(principal_name.trim())
+        .await
+        .map_err(ShareListError::UnexpectedError)?
+        .ok_or_else(|| {
+            ShareListError::ValidationError(FieldError::new(
+                "principal_name",
+                format!("Nobody named {} has signed in yet", principal_name.trim()),
+            ))
+        })?;
+
+    if member.id() == auth_ctx.principal_id {
+        return Err(ShareListError::ValidationError(FieldError::new(
+            "principal_name",
+            "You cannot share your todo list with yourself",
+        )));
+    }
+
+    let list_member = ListMember::new(
+        auth_ctx.principal_id.clone(),
+        auth_ctx.principal_name.clone(),
+        member.id(),
+        member.principal_name().to_string(),
+        role,
+    );
+
+    list_members_repository
+        .get_ref()
+        .save(list_member)
+        .await
+        .map_err(ShareListError::UnexpectedError)?;
+
+    Ok(HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/me/sharing"))
+        .finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShareListError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for ShareListError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ShareListError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ShareListError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ShareListError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
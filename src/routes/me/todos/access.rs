@@ -0,0 +1,158 @@
+use actix_web::{http::StatusCode, ResponseError};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::{
+    auth::AuthContext,
+    model::{ListRole, UserId},
+    repositories::ListMemberRepository,
+};
+
+/// Selects whose todo list a request works on. Without it, it is the caller's own list.
+#[derive(Deserialize, IntoParams, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Id of the owner of a list shared with the caller.
+    #[param(value_type = Option<String>)]
+    list: Option<UserId>,
+}
+
+/// The list a request is allowed to work on, and what the caller may do with it.
+#[derive(Serialize)]
+pub struct ListAccess {
+    pub owner_id: UserId,
+    pub owner_name: String,
+    pub is_owner: bool,
+    pub can_edit: bool,
+    /// Query string selecting the list again in links and follow-up requests.
+    pub query: String,
+}
+
+/// Resolves the list selected by the query and checks that the caller holds at least
+/// the required role on it. Owners hold every role on their own list.
+pub async fn authorize_list<M>(
+    list_members_repository: &M,
+    auth_ctx: &AuthContext,
+    query: ListQuery,
+    required_role: ListRole,
+) -> Result<ListAccess, ListAccessError>
+where
+    M: ListMemberRepository,
+{
+    let owner_id = match query.list {
+        Some(owner_id) if owner_id != auth_ctx.principal_id => owner_id,
+        _ => {
+            return Ok(ListAccess {
+                owner_id: auth_ctx.principal_id.clone(),
+                owner_name: auth_ctx.principal_name.clone(),
+                is_owner: true,
+                can_edit: true,
+                query: String::new(),
+            })
+        }
+    };
+
+    let list_member = list_members_repository
+        .get(owner_id, auth_ctx.principal_id.clone())
+        .await?
+        .ok_or(ListAccessError::NotShared)?;
+
+    if list_member.role() < required_role {
+        return Err(ListAccessError::ReadOnly);
+    }
+
+    let query = serde_urlencoded::to_string([("list", list_member.owner_id().to_string())])
+        .map_err(anyhow::Error::from)?;
+
+    Ok(ListAccess {
+        owner_id: list_member.owner_id(),
+        owner_name: list_member.owner_name().to_string(),
+        is_owner: false,
+        can_edit: list_member.role() >= ListRole::Editor,
+        query: format!("?{}", query),
+    })
+}
+
+/// Selects the list named `@name` when adding a todo: the caller's own list, or a list
+/// shared with them, by its owner's name or the part of it before the `@`.
+pub async fn find_list_by_name<M>(
+    list_members_repository: &M,
+    auth_ctx: &AuthContext,
+    name: &str,
+) -> Result<ListQuery, ListAccessError>
+where
+    M: ListMemberRepository,
+{
+    let is_named = |owner_name: &str| {
+        owner_name.eq_ignore_ascii_case(name)
+            || owner_name
+                .split_once('@')
+                .is_some_and(|(local_part, _)| local_part.eq_ignore_ascii_case(name))
+    };
+
+    if is_named(&auth_ctx.principal_name) {
+        return Ok(ListQuery { list: None });
+    }
+
+    let shared_lists = list_members_repository
+        .get_all_for_member(auth_ctx.principal_id.clone())
+        .try_collect::<Vec<_>>()
+        .await?;
+    shared_lists
+        .into_iter()
+        .find(|list_member| is_named(list_member.owner_name()))
+        .map(|list_member| ListQuery {
+            list: Some(list_member.owner_id()),
+        })
+        .ok_or(ListAccessError::NotShared)
+}
+
+/// Whether the user owns the list or is one of its members, in any role.
+pub async fn has_list_access<M>(
+    list_members_repository: &M,
+    owner_id: &UserId,
+    user_id: &UserId,
+) -> anyhow::Result<bool>
+where
+    M: ListMemberRepository,
+{
+    if owner_id == user_id {
+        return Ok(true);
+    }
+
+    let list_member = list_members_repository
+        .get(owner_id.clone(), user_id.clone())
+        .await?;
+    Ok(list_member.is_some())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ListAccessError {
+    #[error("This todo list is not shared with you")]
+    NotShared,
+    #[error("You can only view this todo list")]
+    ReadOnly,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ListAccessError {
+    /// Documented statuses of handlers working on a selected list.
+    pub(crate) const RESPONSES: [(StatusCode, &'static str); 2] = [
+        (
+            StatusCode::FORBIDDEN,
+            "This todo list is not shared with you",
+        ),
+        (StatusCode::FORBIDDEN, "You can only view this todo list"),
+    ];
+}
+
+impl ResponseError for ListAccessError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ListAccessError::NotShared | ListAccessError::ReadOnly => StatusCode::FORBIDDEN,
+            ListAccessError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
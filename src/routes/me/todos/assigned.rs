@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, HashMap};
+
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use serde::Serialize;
+use tera::Tera;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
+
+use crate::{
+    auth,
+    model::Todo,
+    problem,
+    repositories::{ListMemberRepository, TodoRepository},
+};
+
+#[derive(Serialize)]
+struct AssignedTodo {
+    todo: Todo,
+    owner_name: String,
+    /// Query string selecting the todo's list, empty for the caller's own list.
+    list_query: String,
+}
+
+/// Shows the todos assigned to the caller, from their own and shared lists.
+#[utoipa::path(
+    get,
+    path = "/me/todos/assigned",
+    tag = "todos",
+    responses(
+        (status = OK, description = "The assigned todos page", content_type = "text/html", body = String),
+        GetTodosAssignedToMeError,
+    )
+)]
+#[tracing::instrument(
+    name = "Get todos assigned to me",
+    skip(todos_repository, list_members_repository, auth_ctx)
+)]
+pub async fn get_todos_assigned_to_me<T, M>(
+    tmpl: web::Data<Tera>,
+    todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetTodosAssignedToMeError>
+where
+    T: TodoRepository,
+    M: ListMemberRepository,
+{
+    let shared_lists = list_members_repository
+        .get_ref()
+        .get_all_for_member(auth_ctx.principal_id.clone())
+        .map_ok(|list_member| (list_member.owner_id(), list_member.owner_name().to_string()))
+        .try_collect::<HashMap<_, _>>()
+        .await?;
+
+    // Assignments stay on the todo when a list stops being shared, so only the lists the
+    // caller can still access are shown.
+    let todos = todos_repository
+        .get_ref()
+        .get_all_assigned_to(auth_ctx.principal_id.clone())
+        .try_collect::<Vec<_>>()
+        .await?
+        .into_iter()
+        .filter_map(|todo| {
+            let owner_id = todo.created_by();
+            if owner_id == auth_ctx.principal_id {
+                return Some(AssignedTodo {
+                    todo,
+                    owner_name: auth_ctx.principal_name.clone(),
+                    list_query: String::new(),
+                });
+            }
+
+            let owner_name = shared_lists.get(&owner_id)?.clone();
+            let list_query = serde_urlencoded::to_string([("list", owner_id.to_string())])
+                .map(|query| format!("?{}", query))
+                .ok()?;
+            Some(AssignedTodo {
+                todo,
+                owner_name,
+                list_query,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut context = tera::Context::new();
+    context.insert("todos", &todos);
+
+    let html = tmpl
+        .render("todos_assigned.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(GetTodosAssignedToMeError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetTodosAssignedToMeError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetTodosAssignedToMeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetTodosAssignedToMeError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponses for GetTodosAssignedToMeError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses([(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")])
+    }
+}
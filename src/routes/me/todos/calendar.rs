@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
+
+use crate::{auth, ical, problem, repositories::TodoRepository};
+
+/// Exports the caller's todos as an iCalendar file.
+#[utoipa::path(
+    get,
+    path = "/me/todos.ics",
+    tag = "todos",
+    responses(
+        (status = OK, description = "The todos as VTODO components", content_type = "text/calendar", body = String),
+        GetUserTodosCalendarError,
+    )
+)]
+#[tracing::instrument(name = "Get user todos calendar", skip(todos_repository, auth_ctx))]
+pub async fn get_user_todos_calendar<T>(
+    todos_repository: web::Data<T>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetUserTodosCalendarError>
+where
+    T: TodoRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let todos = todos_repository
+        .get_ref()
+        .get_all_for_user(user_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(ical::render_calendar("Todos", &todos)))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetUserTodosCalendarError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetUserTodosCalendarError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetUserTodosCalendarError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponses for GetUserTodosCalendarError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses([(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")])
+    }
+}
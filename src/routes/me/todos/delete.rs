@@ -1,29 +1,96 @@
+use std::collections::BTreeMap;
+
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
 
-use crate::{model::TodoId, repositories::TodoRepository};
+use super::{authorize_list, ListAccessError, ListQuery};
+use crate::{
+    events::{TodoEvent, TodoEventBus},
+    model::{ListRole, TodoId, UserId},
+    problem,
+    repositories::{ListMemberRepository, TodoRepository},
+};
 
-#[tracing::instrument(name = "Delete todo", skip(todo_id, todos_repository, auth_ctx))]
-pub async fn delete_todo<T>(
+/// Deletes a todo.
+#[utoipa::path(
+    delete,
+    path = "/me/todos/{todo_id}",
+    tag = "todos",
+    params(("todo_id" = TodoId, Path, description = "Id of the todo"), ListQuery),
+    responses(
+        (status = NO_CONTENT, description = "The todo was deleted, or did not exist"),
+        DeleteTodoError,
+    )
+)]
+#[tracing::instrument(
+    name = "Delete todo",
+    skip(
+        list,
+        todo_id,
+        event_bus,
+        todos_repository,
+        list_members_repository,
+        auth_ctx
+    )
+)]
+pub async fn delete_todo<T, M>(
+    list: web::Query<ListQuery>,
     todo_id: web::Path<TodoId>,
+    event_bus: web::Data<TodoEventBus>,
     todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
     auth_ctx: web::ReqData<crate::auth::AuthContext>,
 ) -> Result<HttpResponse, DeleteTodoError>
 where
     T: TodoRepository,
+    M: ListMemberRepository,
 {
-    let user_id = auth_ctx.principal_id.clone();
-    let todo_id = todo_id.into_inner();
+    let list = authorize_list(
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list.into_inner(),
+        ListRole::Editor,
+    )
+    .await?;
+
+    remove_todo(
+        todos_repository.get_ref(),
+        &event_bus,
+        list.owner_id,
+        todo_id.into_inner(),
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Deletes the todo from the owner's list, and publishes the deletion.
+pub(super) async fn remove_todo<T>(
+    todos_repository: &T,
+    event_bus: &TodoEventBus,
+    owner_id: UserId,
+    todo_id: TodoId,
+) -> Result<(), DeleteTodoError>
+where
+    T: TodoRepository,
+{
+    let event = TodoEvent::deleted(owner_id.clone(), todo_id)?;
 
     todos_repository
-        .get_ref()
-        .delete_for_user_by_id(user_id, todo_id)
+        .delete_for_user_by_id(owner_id, todo_id)
         .await?;
+    event_bus.publish(event);
 
-    Ok(HttpResponse::NoContent().finish())
+    Ok(())
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum DeleteTodoError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
     #[error("Something went wrong")]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -31,7 +98,18 @@ pub enum DeleteTodoError {
 impl ResponseError for DeleteTodoError {
     fn status_code(&self) -> StatusCode {
         match self {
+            DeleteTodoError::ListAccess(e) => e.status_code(),
             DeleteTodoError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
+
+impl IntoResponses for DeleteTodoError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(
+            ListAccessError::RESPONSES
+                .into_iter()
+                .chain([(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")]),
+        )
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use tera::Tera;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
+
+use super::{authorize_list, get::accepts_json, ListAccessError, ListQuery};
+use crate::{
+    auth,
+    model::{ListRole, Todo, TodoId},
+    problem,
+    repositories::{ListMemberRepository, TodoRepository},
+};
+
+/// Shows a todo with its notes, as JSON with the notes in Markdown for clients accepting
+/// `application/json`, and as the todo page with the notes rendered otherwise.
+#[utoipa::path(
+    get,
+    path = "/me/todos/{todo_id}",
+    tag = "todos",
+    params(("todo_id" = TodoId, Path, description = "Id of the todo"), ListQuery),
+    responses(
+        (
+            status = OK,
+            description = "The todo",
+            content((Todo = "application/json"), (String = "text/html"))
+        ),
+        GetTodoError,
+    )
+)]
+#[tracing::instrument(
+    name = "Get todo",
+    skip(
+        req,
+        list,
+        tmpl,
+        todos_repository,
+        list_members_repository,
+        auth_ctx,
+        csrf_token
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_todo<T, M>(
+    req: HttpRequest,
+    list: web::Query<ListQuery>,
+    todo_id: web::Path<TodoId>,
+    tmpl: web::Data<Tera>,
+    todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetTodoError>
+where
+    T: TodoRepository,
+    M: ListMemberRepository,
+{
+    let list = authorize_list(
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list.into_inner(),
+        ListRole::Viewer,
+    )
+    .await?;
+
+    let todo = todos_repository
+        .get_ref()
+        .get_one_for_user(list.owner_id.clone(), todo_id.into_inner())
+        .await?
+        .ok_or(GetTodoError::TodoNotFound)?;
+
+    if accepts_json(&req) {
+        return Ok(HttpResponse::Ok().json(todo));
+    }
+
+    let assignee_name = match todo.assignee() {
+        None => None,
+        Some(assignee) if *assignee == list.owner_id => Some(list.owner_name.clone()),
+        Some(assignee) => list_members_repository
+            .get_ref()
+            .get_all_for_owner(list.owner_id.clone())
+            .try_collect::<Vec<_>>()
+            .await?
+            .into_iter()
+            .find(|member| member.member_id() == *assignee)
+            .map(|member| member.member_name().to_string()),
+    };
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("todo", &todo);
+    context.insert("assignee_name", &assignee_name);
+    context.insert("list", &list);
+
+    let html = tmpl
+        .render("todo.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(GetTodoError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetTodoError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
+    #[error("Todo not found")]
+    TodoNotFound,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetTodoError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetTodoError::ListAccess(e) => e.status_code(),
+            GetTodoError::TodoNotFound => StatusCode::NOT_FOUND,
+            GetTodoError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponses for GetTodoError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(ListAccessError::RESPONSES.into_iter().chain([
+            (StatusCode::NOT_FOUND, "Todo not found"),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        ]))
+    }
+}
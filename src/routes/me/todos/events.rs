@@ -0,0 +1,180 @@
+use std::{collections::BTreeMap, convert::Infallible, time::Duration};
+
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpResponse, ResponseError,
+};
+use futures::{stream, StreamExt};
+use tokio::time::{Instant, Interval};
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
+
+use super::{authorize_list, ListAccessError, ListQuery};
+use crate::{
+    auth,
+    events::{Received, TodoEventBus, TodoEventReceiver},
+    model::ListRole,
+    problem,
+    repositories::ListMemberRepository,
+};
+
+/// Proxies close connections that stay silent for too long, App Service after four minutes.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Access to the list is checked again before sending events once this much time has
+/// passed since the last check, and with every keep-alive.
+const ACCESS_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Streams changes of the todos of a list as server-sent events. `created` and `updated`
+/// events carry the todo, `deleted` events its id. A `resync` event tells the client that
+/// it fell behind and missed events. The stream ends once the list is no longer shared
+/// with the caller.
+#[utoipa::path(
+    get,
+    path = "/me/todos/events",
+    tag = "todos",
+    params(ListQuery),
+    responses(
+        (status = OK, description = "The event stream", content_type = "text/event-stream", body = String),
+        GetTodoEventsError,
+    )
+)]
+#[tracing::instrument(
+    name = "Get todo events",
+    skip(list, event_bus, list_members_repository, auth_ctx)
+)]
+pub async fn get_todo_events<M>(
+    list: web::Query<ListQuery>,
+    event_bus: web::Data<TodoEventBus>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetTodoEventsError>
+where
+    M: ListMemberRepository + 'static,
+{
+    let list_query = list.into_inner();
+    let auth_ctx = auth_ctx.into_inner();
+    let list = authorize_list(
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list_query.clone(),
+        ListRole::Viewer,
+    )
+    .await?;
+
+    let events = EventStream {
+        receiver: event_bus.subscribe(&list.owner_id),
+        keep_alive: tokio::time::interval_at(
+            Instant::now() + KEEP_ALIVE_INTERVAL,
+            KEEP_ALIVE_INTERVAL,
+        ),
+        list_query,
+        auth_ctx,
+        access_checked_at: Instant::now(),
+        list_members_repository,
+    };
+    // Disconnected clients are noticed when writing to them, at the latest on the next
+    // keep-alive.
+    let body = stream::unfold(events, |mut events| async move {
+        let message = events.next_message().await?;
+        Some((message, events))
+    })
+    .map(|message| Ok::<_, Infallible>(web::Bytes::from(message)));
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(body))
+}
+
+/// The open event stream of one client, and what it needs to recheck the client's access.
+struct EventStream<M> {
+    receiver: TodoEventReceiver,
+    keep_alive: Interval,
+    list_query: ListQuery,
+    auth_ctx: auth::AuthContext,
+    access_checked_at: Instant,
+    list_members_repository: web::Data<M>,
+}
+
+impl<M> EventStream<M>
+where
+    M: ListMemberRepository,
+{
+    /// Waits for the next event or keep-alive, or returns `None` when the stream ends.
+    async fn next_message(&mut self) -> Option<String> {
+        let (message, recheck) = tokio::select! {
+            message = next_event(&mut self.receiver) => {
+                (message?, self.access_checked_at.elapsed() >= ACCESS_CHECK_INTERVAL)
+            }
+            _ = self.keep_alive.tick() => (": keep-alive\n\n".to_string(), true),
+        };
+
+        if recheck && !self.has_access().await {
+            return None;
+        }
+        Some(message)
+    }
+
+    async fn has_access(&mut self) -> bool {
+        let access = authorize_list(
+            self.list_members_repository.get_ref(),
+            &self.auth_ctx,
+            self.list_query.clone(),
+            ListRole::Viewer,
+        )
+        .await;
+
+        match access {
+            Ok(_) => {
+                self.access_checked_at = Instant::now();
+                true
+            }
+            Err(ListAccessError::UnexpectedError(e)) => {
+                tracing::error!(error = ?e, "Failed to check todo event stream access");
+                false
+            }
+            Err(e) => {
+                tracing::info!(reason = %e, "Ending todo event stream");
+                false
+            }
+        }
+    }
+}
+
+/// Waits for the next event of the list and formats it as a server-sent event.
+async fn next_event(receiver: &mut TodoEventReceiver) -> Option<String> {
+    match receiver.recv().await? {
+        Received::Event(event) => Some(format!(
+            "event: {}\ndata: {}\n\n",
+            event.change.as_str(),
+            event.data
+        )),
+        Received::Resync => Some("event: resync\ndata: {}\n\n".to_string()),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetTodoEventsError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
+}
+
+impl ResponseError for GetTodoEventsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetTodoEventsError::ListAccess(e) => e.status_code(),
+        }
+    }
+}
+
+impl IntoResponses for GetTodoEventsError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(
+            ListAccessError::RESPONSES
+                .into_iter()
+                .chain([(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")]),
+        )
+    }
+}
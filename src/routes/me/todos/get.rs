@@ -1,28 +1,116 @@
-use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use std::collections::BTreeMap;
+
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpRequest, HttpResponse, ResponseError,
+};
 use futures::TryStreamExt;
+use serde::Serialize;
 use tera::Tera;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
 
-use crate::{auth, repositories::TodoRepository};
+use super::{authorize_list, ListAccessError, ListQuery};
+use crate::{
+    auth,
+    configuration::InboundEmailSettings,
+    model::{ListRole, Todo, UserId},
+    problem,
+    repositories::{ListMemberRepository, TodoRepository},
+};
 
-#[tracing::instrument(name = "Get all user todos", skip(todos_repository, auth_ctx))]
-pub async fn get_all_user_todos<T>(
+/// Lists the todos of a list, as JSON for clients accepting `application/json` and as
+/// the todo list page otherwise.
+#[utoipa::path(
+    get,
+    path = "/me/todos",
+    tag = "todos",
+    params(ListQuery),
+    responses(
+        (
+            status = OK,
+            description = "The todos of the list",
+            content((Vec<Todo> = "application/json"), (String = "text/html"))
+        ),
+        GetAllUserTodosError,
+    )
+)]
+#[tracing::instrument(
+    name = "Get all user todos",
+    skip(
+        req,
+        list,
+        inbound_email_settings,
+        todos_repository,
+        list_members_repository,
+        auth_ctx,
+        csrf_token
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_all_user_todos<T, M>(
+    req: HttpRequest,
+    list: web::Query<ListQuery>,
     tmpl: web::Data<Tera>,
+    inbound_email_settings: web::Data<InboundEmailSettings>,
     todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
     auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
 ) -> Result<HttpResponse, GetAllUserTodosError>
 where
     T: TodoRepository,
+    M: ListMemberRepository,
 {
-    let user_id = auth_ctx.principal_id.clone();
+    let list = authorize_list(
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list.into_inner(),
+        ListRole::Viewer,
+    )
+    .await?;
 
     let todos = todos_repository
         .get_ref()
-        .get_all_for_user(user_id)
+        .get_all_for_user(list.owner_id.clone())
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    if accepts_json(&req) {
+        return Ok(HttpResponse::Ok().json(todos));
+    }
+
+    // Everyone with access to the list can be assigned its todos, the owner first.
+    let members = list_members_repository
+        .get_ref()
+        .get_all_for_owner(list.owner_id.clone())
+        .try_collect::<Vec<_>>()
+        .await?;
+    let assignees = std::iter::once(Assignee {
+        id: list.owner_id.clone(),
+        name: list.owner_name.clone(),
+    })
+    .chain(members.into_iter().map(|member| Assignee {
+        id: member.member_id(),
+        name: member.member_name().to_string(),
+    }))
+    .collect::<Vec<_>>();
+
+    let shared_lists = list_members_repository
+        .get_ref()
+        .get_all_for_member(auth_ctx.principal_id.clone())
         .try_collect::<Vec<_>>()
         .await?;
 
     let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
     context.insert("todos", &todos);
+    context.insert("list", &list);
+    context.insert("assignees", &assignees);
+    context.insert("shared_lists", &shared_lists);
+    context.insert("inbound_email_enabled", &inbound_email_settings.enabled);
 
     let html = tmpl
         .render("todos.html", &context)
@@ -32,8 +120,23 @@ where
     Ok(HttpResponse::Ok().content_type("text/html").body(html))
 }
 
+pub(super) fn accepts_json(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"))
+}
+
+#[derive(Serialize)]
+struct Assignee {
+    id: UserId,
+    name: String,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum GetAllUserTodosError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
     #[error("Something went wrong")]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -41,7 +144,18 @@ pub enum GetAllUserTodosError {
 impl ResponseError for GetAllUserTodosError {
     fn status_code(&self) -> StatusCode {
         match self {
+            GetAllUserTodosError::ListAccess(e) => e.status_code(),
             GetAllUserTodosError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
+
+impl IntoResponses for GetAllUserTodosError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(
+            ListAccessError::RESPONSES
+                .into_iter()
+                .chain([(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")]),
+        )
+    }
+}
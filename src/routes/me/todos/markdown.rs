@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
+
+use crate::{
+    auth,
+    markdown::{self, DateRange},
+    problem::{self, FieldError},
+    repositories::{ListMemberRepository, TodoRepository},
+};
+
+/// Exports the caller's todos and those of the lists shared with them as a Markdown
+/// checklist, grouped by list and tag.
+#[utoipa::path(
+    get,
+    path = "/me/todos.md",
+    tag = "todos",
+    params(DateRange),
+    responses(
+        (status = OK, description = "The todos as a checklist", content_type = "text/markdown", body = String),
+        GetUserTodosMarkdownError,
+    )
+)]
+#[tracing::instrument(
+    name = "Get user todos markdown",
+    skip(range, todos_repository, list_members_repository, auth_ctx)
+)]
+pub async fn get_user_todos_markdown<T, M>(
+    range: web::Query<DateRange>,
+    todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetUserTodosMarkdownError>
+where
+    T: TodoRepository,
+    M: ListMemberRepository,
+{
+    let range = range.into_inner();
+    range
+        .validate()
+        .map_err(|e| GetUserTodosMarkdownError::ValidationError(FieldError::new("to", e)))?;
+
+    let shared_lists = list_members_repository
+        .get_all_for_member(auth_ctx.principal_id.clone())
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(GetUserTodosMarkdownError::UnexpectedError)?;
+
+    let mut owners = vec![(
+        auth_ctx.principal_id.clone(),
+        auth_ctx.principal_name.clone(),
+    )];
+    owners.extend(
+        shared_lists
+            .iter()
+            .map(|list_member| (list_member.owner_id(), list_member.owner_name().to_string())),
+    );
+
+    let mut lists = Vec::with_capacity(owners.len());
+    for (owner_id, owner_name) in owners {
+        let todos = todos_repository
+            .get_all_for_user(owner_id)
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(GetUserTodosMarkdownError::UnexpectedError)?;
+        lists.push((owner_name, todos));
+    }
+
+    let markdown = markdown::render_checklist(
+        "Todos",
+        lists
+            .iter()
+            .map(|(owner_name, todos)| (owner_name.as_str(), todos.as_slice())),
+        &range,
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/markdown; charset=utf-8")
+        .body(markdown))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetUserTodosMarkdownError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for GetUserTodosMarkdownError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetUserTodosMarkdownError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            GetUserTodosMarkdownError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            GetUserTodosMarkdownError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
+
+impl IntoResponses for GetUserTodosMarkdownError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses([
+            (StatusCode::BAD_REQUEST, "The date range is invalid"),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        ])
+    }
+}
@@ -1,9 +1,25 @@
+mod access;
+mod assigned;
+mod calendar;
 mod delete;
+mod detail;
+mod events;
 mod get;
+mod markdown;
 mod patch;
 mod post;
+mod preview;
+mod socket;
 
+pub use access::*;
+pub use assigned::*;
+pub use calendar::*;
 pub use delete::*;
+pub use detail::*;
+pub use events::*;
 pub use get::*;
+pub use markdown::*;
 pub use patch::*;
 pub use post::*;
+pub use preview::*;
+pub use socket::*;
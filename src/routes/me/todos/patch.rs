@@ -1,66 +1,192 @@
+use std::collections::BTreeMap;
+
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses, ToSchema,
+};
 
+use super::{authorize_list, has_list_access, ListAccessError, ListQuery};
 use crate::{
     auth,
-    model::{Todo, TodoId},
-    repositories::TodoRepository,
+    events::{TodoEvent, TodoEventBus},
+    model::{ListRole, Todo, TodoId, TodoNotes, UserId},
+    problem::{self, FieldError},
+    repositories::{ListMemberRepository, TodoRepository},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct TodoUpdate {
-    done: bool,
+    #[serde(default)]
+    done: Option<bool>,
+    /// Missing leaves the assignee as is, `null` unassigns the todo.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    #[schema(value_type = Option<String>)]
+    assignee: Option<Option<UserId>>,
+    /// Notes in Markdown, up to 20000 characters. Missing leaves them as they are,
+    /// `null` or blank removes them.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    #[schema(value_type = Option<String>)]
+    notes: Option<Option<String>>,
 }
 
+/// Tells a field set to `null` apart from a missing one.
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Marks a todo as done or unfinished, or changes its assignee or notes. Fields left
+/// out are not changed.
+#[utoipa::path(
+    patch,
+    path = "/me/todos/{todo_id}",
+    tag = "todos",
+    params(("todo_id" = TodoId, Path, description = "Id of the todo"), ListQuery),
+    request_body = TodoUpdate,
+    responses(
+        (status = NO_CONTENT, description = "The todo was updated, or does not exist"),
+        UpdateTodoError,
+    )
+)]
 #[tracing::instrument(
     name = "Update todo",
-    skip(todo_id, todo_update, todos_repository, auth_ctx)
+    skip(
+        list,
+        todo_id,
+        todo_update,
+        event_bus,
+        todos_repository,
+        list_members_repository,
+        auth_ctx
+    )
 )]
-pub async fn update_todo<T>(
+pub async fn update_todo<T, M>(
+    list: web::Query<ListQuery>,
     todo_id: web::Path<TodoId>,
     todo_update: web::Json<TodoUpdate>,
+    event_bus: web::Data<TodoEventBus>,
     todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
     auth_ctx: web::ReqData<auth::AuthContext>,
 ) -> Result<HttpResponse, UpdateTodoError>
 where
     T: TodoRepository,
+    M: ListMemberRepository,
 {
-    let user_id = auth_ctx.principal_id.clone();
-    let todo_id = todo_id.into_inner();
-    let todo = todos_repository
-        .get_ref()
-        .get_one_for_user(user_id, todo_id)
-        .await
-        .map_err(UpdateTodoError::UnexpectedError)?;
+    let list = authorize_list(
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list.into_inner(),
+        ListRole::Editor,
+    )
+    .await?;
 
-    if let Some(todo) = todo {
-        let todo_update = todo_update.into_inner();
-        let updated_todo =
-            update_todo_object(todo, todo_update).map_err(UpdateTodoError::ValidationError)?;
+    change_todo(
+        todos_repository.get_ref(),
+        list_members_repository.get_ref(),
+        &event_bus,
+        list.owner_id,
+        todo_id.into_inner(),
+        todo_update.into_inner(),
+        auth_ctx.principal_id.clone(),
+    )
+    .await?;
 
-        todos_repository
-            .get_ref()
-            .save(updated_todo)
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Applies the update to the todo on the owner's list, and publishes the change. Todos
+/// that do not exist are left alone.
+pub(super) async fn change_todo<T, M>(
+    todos_repository: &T,
+    list_members_repository: &M,
+    event_bus: &TodoEventBus,
+    owner_id: UserId,
+    todo_id: TodoId,
+    mut todo_update: TodoUpdate,
+    changed_by: UserId,
+) -> Result<(), UpdateTodoError>
+where
+    T: TodoRepository,
+    M: ListMemberRepository,
+{
+    let notes = todo_update
+        .notes
+        .take()
+        .map(|notes| {
+            notes
+                .filter(|notes| !notes.trim().is_empty())
+                .map(TodoNotes::try_from)
+                .transpose()
+        })
+        .transpose()
+        .map_err(|e| UpdateTodoError::ValidationError(FieldError::new("notes", e)))?;
+
+    let Some(todo) = todos_repository
+        .get_one_for_user(owner_id, todo_id)
+        .await
+        .map_err(UpdateTodoError::UnexpectedError)?
+    else {
+        return Ok(());
+    };
+
+    // Todos can only be assigned to users who can see them.
+    if let Some(Some(assignee)) = &todo_update.assignee {
+        let has_access = has_list_access(list_members_repository, &todo.created_by(), assignee)
             .await
             .map_err(UpdateTodoError::UnexpectedError)?;
+        if !has_access {
+            return Err(UpdateTodoError::ValidationError(FieldError::new(
+                "assignee",
+                "The assignee does not have access to this todo list",
+            )));
+        }
     }
 
-    Ok(HttpResponse::NoContent().finish())
+    let updated_todo = update_todo_object(todo, todo_update, notes, changed_by)
+        .map_err(|e| UpdateTodoError::ValidationError(FieldError::new("done", e)))?;
+    let event = TodoEvent::updated(&updated_todo).map_err(UpdateTodoError::UnexpectedError)?;
+
+    todos_repository
+        .save(updated_todo)
+        .await
+        .map_err(UpdateTodoError::UnexpectedError)?;
+    event_bus.publish(event);
+
+    Ok(())
 }
 
-fn update_todo_object(mut current_todo: Todo, todo_update: TodoUpdate) -> anyhow::Result<Todo> {
-    if todo_update.done {
-        current_todo.mark_as_done();
-    } else {
-        current_todo.mark_as_unfinished();
+fn update_todo_object(
+    mut current_todo: Todo,
+    todo_update: TodoUpdate,
+    notes: Option<Option<TodoNotes>>,
+    changed_by: UserId,
+) -> anyhow::Result<Todo> {
+    match todo_update.done {
+        Some(true) => current_todo.mark_as_done(),
+        Some(false) => current_todo.mark_as_unfinished(),
+        None => {}
+    }
+    if let Some(assignee) = todo_update.assignee {
+        current_todo.assign(assignee, changed_by);
+    }
+    if let Some(notes) = notes {
+        current_todo.update_notes(notes);
     }
     Ok(current_todo)
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum UpdateTodoError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
     #[error("{0}")]
-    ValidationError(#[source] anyhow::Error),
+    ValidationError(#[source] FieldError),
     #[error("Something went wrong")]
     UnexpectedError(#[source] anyhow::Error),
 }
@@ -68,8 +194,28 @@ pub enum UpdateTodoError {
 impl ResponseError for UpdateTodoError {
     fn status_code(&self) -> StatusCode {
         match self {
+            UpdateTodoError::ListAccess(e) => e.status_code(),
             UpdateTodoError::ValidationError(_) => StatusCode::BAD_REQUEST,
             UpdateTodoError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            UpdateTodoError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
+
+impl IntoResponses for UpdateTodoError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(ListAccessError::RESPONSES.into_iter().chain([
+            (
+                StatusCode::BAD_REQUEST,
+                "The assignee does not have access to this todo list, or the notes are too long",
+            ),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        ]))
+    }
 }
@@ -1,63 +1,211 @@
+use std::collections::BTreeMap;
+
 use actix_web::{
     http::{header, StatusCode},
     web, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses, ToSchema,
+};
 
+use super::{read_quick_add, ListAccessError, ListQuery, QuickAddedTodo};
 use crate::{
     auth,
-    model::{Todo, TodoContent},
-    repositories::TodoRepository,
+    configuration::LimitSettings,
+    events::{TodoEvent, TodoEventBus},
+    model::{Todo, TodoContent, TodoId, TodoNotes, UserId},
+    problem::{self, FieldError},
+    repositories::{ListMemberRepository, TodoRepository, UserRepository},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct NewTodo {
+    /// The todo as typed into the add form, e.g.
+    /// `Pay rent tomorrow 9am #home !high every month`. Dates and times in it are read
+    /// in the caller's time zone, `@name` puts the todo on the list of that owner, and
+    /// `\word` keeps a word that would be read otherwise. What is left is the content,
+    /// between 1 and 500 characters.
     content: String,
+    /// Notes in Markdown, up to 20000 characters. They are taken as they are.
+    #[serde(default)]
+    notes: Option<String>,
 }
 
-#[tracing::instrument(name = "Create todo", skip(new_todo, todos_repository, auth_ctx))]
-pub async fn create_todo<T>(
+/// Creates a todo and redirects back to the todo list it was added to.
+#[utoipa::path(
+    post,
+    path = "/me/todos",
+    tag = "todos",
+    params(ListQuery),
+    request_body(content = NewTodo, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = SEE_OTHER, description = "The todo was created", headers(("Location" = String, description = "The todo list"))),
+        CreateTodoError,
+    )
+)]
+#[tracing::instrument(
+    name = "Create todo",
+    skip(
+        list,
+        new_todo,
+        limits,
+        event_bus,
+        todos_repository,
+        users_repository,
+        list_members_repository,
+        auth_ctx
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_todo<T, U, M>(
+    list: web::Query<ListQuery>,
     new_todo: web::Form<NewTodo>,
+    limits: web::Data<LimitSettings>,
+    event_bus: web::Data<TodoEventBus>,
     todos_repository: web::Data<T>,
+    users_repository: web::Data<U>,
+    list_members_repository: web::Data<M>,
     auth_ctx: web::ReqData<auth::AuthContext>,
 ) -> Result<HttpResponse, CreateTodoError>
 where
     T: TodoRepository,
+    U: UserRepository,
+    M: ListMemberRepository,
 {
-    let user_id = auth_ctx.principal_id.clone();
+    let QuickAddedTodo { list, details, .. } = read_quick_add(
+        users_repository.get_ref(),
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list.into_inner(),
+        &new_todo.content,
+    )
+    .await?;
 
-    let content: TodoContent = new_todo
-        .into_inner()
+    let content: TodoContent = details
         .content
         .try_into()
-        .map_err(CreateTodoError::ValidationError)?;
+        .map_err(|e| CreateTodoError::ValidationError(FieldError::new("content", e)))?;
+    let notes = parse_notes(new_todo.into_inner().notes)?;
+    let todo = Todo::new(content, list.owner_id)
+        .with_notes(notes)
+        .with_due_at(details.due_at)
+        .with_tags(details.tags)
+        .with_priority(details.priority)
+        .with_recurrence(details.recurrence);
 
-    let todo = Todo::new(content, user_id);
+    store_todo(todos_repository.get_ref(), &event_bus, &limits, todo).await?;
+
+    Ok(HttpResponse::SeeOther()
+        .append_header((header::LOCATION, format!("/me/todos{}", list.query)))
+        .finish())
+}
+
+/// Validates and stores a new todo on the owner's list, and publishes it.
+pub(crate) async fn add_todo<T>(
+    todos_repository: &T,
+    event_bus: &TodoEventBus,
+    limits: &LimitSettings,
+    owner_id: UserId,
+    content: String,
+    notes: Option<String>,
+) -> Result<TodoId, CreateTodoError>
+where
+    T: TodoRepository,
+{
+    let content: TodoContent = content
+        .try_into()
+        .map_err(|e| CreateTodoError::ValidationError(FieldError::new("content", e)))?;
+    let notes = parse_notes(notes)?;
+
+    store_todo(
+        todos_repository,
+        event_bus,
+        limits,
+        Todo::new(content, owner_id).with_notes(notes),
+    )
+    .await
+}
+
+/// Validates the notes of a new todo. Blank notes are no notes.
+fn parse_notes(notes: Option<String>) -> Result<Option<TodoNotes>, CreateTodoError> {
+    notes
+        .filter(|notes| !notes.trim().is_empty())
+        .map(TryInto::try_into)
+        .transpose()
+        .map_err(|e| CreateTodoError::ValidationError(FieldError::new("notes", e)))
+}
+
+/// Stores a new todo on its owner's list, unless the list is full, and publishes it.
+async fn store_todo<T>(
+    todos_repository: &T,
+    event_bus: &TodoEventBus,
+    limits: &LimitSettings,
+    todo: Todo,
+) -> Result<TodoId, CreateTodoError>
+where
+    T: TodoRepository,
+{
+    let todo_count = todos_repository
+        .count_for_user(todo.created_by())
+        .await
+        .map_err(CreateTodoError::UnexpectedError)?;
+    if todo_count >= limits.max_todos_per_user {
+        return Err(CreateTodoError::TooManyTodos(limits.max_todos_per_user));
+    }
+
+    let todo_id = todo.id();
+    let event = TodoEvent::created(&todo).map_err(CreateTodoError::UnexpectedError)?;
 
     todos_repository
-        .get_ref()
         .create(todo)
         .await
         .map_err(CreateTodoError::UnexpectedError)?;
+    event_bus.publish(event);
 
-    Ok(HttpResponse::SeeOther()
-        .append_header((header::LOCATION, "/me/todos"))
-        .finish())
+    Ok(todo_id)
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum CreateTodoError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
     #[error("{0}")]
-    ValidationError(#[source] anyhow::Error),
+    ValidationError(#[source] FieldError),
+    #[error("Todo lists are limited to {0} todos")]
+    TooManyTodos(u64),
     #[error("Something went wrong")]
     UnexpectedError(#[source] anyhow::Error),
 }
 
 impl ResponseError for CreateTodoError {
-    fn status_code(&self) -> actix_web::http::StatusCode {
+    fn status_code(&self) -> StatusCode {
         match self {
+            CreateTodoError::ListAccess(e) => e.status_code(),
             CreateTodoError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreateTodoError::TooManyTodos(_) => StatusCode::CONFLICT,
             CreateTodoError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            CreateTodoError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
+
+impl IntoResponses for CreateTodoError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(ListAccessError::RESPONSES.into_iter().chain([
+            (
+                StatusCode::BAD_REQUEST,
+                "The todo content or notes are invalid",
+            ),
+            (StatusCode::CONFLICT, "The todo list is full"),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        ]))
+    }
 }
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoParams, IntoResponses, ToSchema,
+};
+
+use super::{authorize_list, find_list_by_name, ListAccess, ListAccessError, ListQuery};
+use crate::{
+    auth,
+    model::{ListRole, Priority, Recurrence, TodoContent, UserId},
+    problem::{self, FieldError},
+    quick_add::{self, QuickAdd},
+    repositories::{ListMemberRepository, UserRepository},
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PreviewQuery {
+    /// The text as typed into the add form.
+    content: String,
+}
+
+/// The todo the text would create.
+#[derive(Serialize, ToSchema)]
+pub struct TodoPreview {
+    content: String,
+    due_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The time zone dates and times were read in.
+    time_zone: String,
+    tags: Vec<String>,
+    priority: Option<Priority>,
+    recurrence: Option<Recurrence>,
+    list_owner_id: UserId,
+    list_owner_name: String,
+}
+
+/// A todo read from the text typed into the add form, and the list it goes on.
+pub(crate) struct QuickAddedTodo {
+    pub list: ListAccess,
+    pub details: QuickAdd,
+    pub time_zone: Tz,
+}
+
+/// Reads the text in the caller's time zone. The todo goes on the list it names, or
+/// else on the list of the query, which the caller has to be able to edit.
+pub(crate) async fn read_quick_add<U, M>(
+    users_repository: &U,
+    list_members_repository: &M,
+    auth_ctx: &auth::AuthContext,
+    list: ListQuery,
+    content: &str,
+) -> Result<QuickAddedTodo, ListAccessError>
+where
+    U: UserRepository,
+    M: ListMemberRepository,
+{
+    let time_zone = users_repository
+        .get_by_id(auth_ctx.principal_id.clone())
+        .await?
+        .map(|user| user.notification_preferences().time_zone)
+        .unwrap_or_default();
+    let details = quick_add::parse(content, chrono::Utc::now(), time_zone);
+
+    let list = match &details.list {
+        Some(name) => find_list_by_name(list_members_repository, auth_ctx, name).await?,
+        None => list,
+    };
+    let list = authorize_list(list_members_repository, auth_ctx, list, ListRole::Editor).await?;
+
+    Ok(QuickAddedTodo {
+        list,
+        details,
+        time_zone,
+    })
+}
+
+/// Shows the todo that adding the text would create, without creating it.
+#[utoipa::path(
+    get,
+    path = "/me/todos/preview",
+    tag = "todos",
+    params(PreviewQuery, ListQuery),
+    responses(
+        (status = OK, description = "The todo the text would create", body = TodoPreview),
+        PreviewTodoError,
+    )
+)]
+#[tracing::instrument(
+    name = "Preview todo",
+    skip(query, list, users_repository, list_members_repository, auth_ctx)
+)]
+pub async fn preview_todo<U, M>(
+    query: web::Query<PreviewQuery>,
+    list: web::Query<ListQuery>,
+    users_repository: web::Data<U>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, PreviewTodoError>
+where
+    U: UserRepository,
+    M: ListMemberRepository,
+{
+    let QuickAddedTodo {
+        list,
+        details,
+        time_zone,
+    } = read_quick_add(
+        users_repository.get_ref(),
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list.into_inner(),
+        &query.content,
+    )
+    .await?;
+
+    let content: TodoContent = details
+        .content
+        .try_into()
+        .map_err(|e| PreviewTodoError::ValidationError(FieldError::new("content", e)))?;
+
+    Ok(HttpResponse::Ok().json(TodoPreview {
+        content: content.as_ref().to_string(),
+        due_at: details.due_at,
+        time_zone: time_zone.name().to_string(),
+        tags: details.tags,
+        priority: details.priority,
+        recurrence: details.recurrence,
+        list_owner_id: list.owner_id,
+        list_owner_name: list.owner_name,
+    }))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewTodoError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+}
+
+impl ResponseError for PreviewTodoError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            PreviewTodoError::ListAccess(e) => e.status_code(),
+            PreviewTodoError::ValidationError(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            PreviewTodoError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
+
+impl IntoResponses for PreviewTodoError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(ListAccessError::RESPONSES.into_iter().chain([
+            (StatusCode::BAD_REQUEST, "The todo content is invalid"),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        ]))
+    }
+}
@@ -0,0 +1,559 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, ResponseError};
+use actix_ws::{AggregatedMessage, AggregatedMessageStream, CloseCode, CloseReason, Closed};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use tracing::Instrument;
+use utoipa::{
+    openapi::{RefOr, Response},
+    IntoResponses,
+};
+
+use super::{
+    add_todo, authorize_list, change_todo, remove_todo, ListAccessError, ListQuery, TodoUpdate,
+};
+use crate::{
+    auth::AuthContext,
+    configuration::LimitSettings,
+    events::{Received, TodoEvent, TodoEventBus},
+    model::{ListRole, Scope, Todo, TodoId, UserId},
+    presence::{PresenceRegistry, Viewer},
+    problem,
+    rate_limit::RateLimiter,
+    repositories::{ListMemberRepository, TodoRepository},
+};
+
+/// Clients are pinged this often, and disconnected when they have not answered for two
+/// intervals.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Access to the list is checked again before forwarding events once this much time has
+/// passed since the last check, and on every heartbeat.
+const ACCESS_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Messages are small JSON documents, anything bigger is not a well-behaved client.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Opens a WebSocket on a todo list. The server sends a `snapshot` of the list and its
+/// viewers first, then `created`, `updated` and `deleted` events numbered by `seq`, and
+/// `presence` updates. Clients send `create`, `update` and `delete` mutations, answered
+/// with an `ack` or an `error`, and `resync` to get a fresh snapshot after a gap in `seq`.
+/// Sequence numbers are kept per instance of the app, so they restart when a client
+/// reconnects to another one, which the snapshot it gets then accounts for.
+#[utoipa::path(
+    get,
+    path = "/me/todos/ws",
+    tag = "todos",
+    params(ListQuery),
+    responses(
+        (status = SWITCHING_PROTOCOLS, description = "The WebSocket was opened"),
+        TodoSocketError,
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(
+    name = "Open todo socket",
+    skip(
+        req,
+        body,
+        list,
+        event_bus,
+        presence,
+        rate_limiter,
+        limits,
+        todos_repository,
+        list_members_repository,
+        auth_ctx
+    )
+)]
+pub async fn open_todo_socket<T, M>(
+    req: HttpRequest,
+    body: web::Payload,
+    list: web::Query<ListQuery>,
+    event_bus: web::Data<TodoEventBus>,
+    presence: web::Data<PresenceRegistry>,
+    rate_limiter: web::Data<RateLimiter>,
+    limits: web::Data<LimitSettings>,
+    todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
+    auth_ctx: web::ReqData<AuthContext>,
+) -> Result<HttpResponse, TodoSocketError>
+where
+    T: TodoRepository + 'static,
+    M: ListMemberRepository + 'static,
+{
+    let list_query = list.into_inner();
+    let auth_ctx = auth_ctx.into_inner();
+    let list = authorize_list(
+        list_members_repository.get_ref(),
+        &auth_ctx,
+        list_query.clone(),
+        ListRole::Viewer,
+    )
+    .await?;
+
+    let (response, session, messages) =
+        actix_ws::handle(&req, body).map_err(TodoSocketError::Handshake)?;
+    let messages = messages
+        .max_frame_size(MAX_MESSAGE_SIZE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_MESSAGE_SIZE);
+
+    let socket = TodoSocket {
+        list_query,
+        owner_id: list.owner_id,
+        auth_ctx,
+        access_checked_at: Instant::now(),
+        session,
+        event_bus,
+        presence,
+        rate_limiter,
+        limits,
+        todos_repository,
+        list_members_repository,
+    };
+    actix_web::rt::spawn(socket.run(messages).instrument(tracing::Span::current()));
+
+    Ok(response)
+}
+
+#[derive(Deserialize)]
+struct ClientMessage {
+    /// Echoed in the `ack` or `error` answering a mutation.
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(flatten)]
+    request: ClientRequest,
+}
+
+/// A client asks either for a fresh snapshot, or for a change to the list.
+enum ClientRequest {
+    Resync,
+    Mutation(Mutation),
+}
+
+impl<'de> Deserialize<'de> for ClientRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let request = serde_json::Value::deserialize(deserializer)?;
+        if request.get("type").and_then(|kind| kind.as_str()) == Some("resync") {
+            return Ok(ClientRequest::Resync);
+        }
+        Mutation::deserialize(request)
+            .map(ClientRequest::Mutation)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Mutation {
+    Create {
+        content: String,
+    },
+    Update {
+        todo_id: TodoId,
+        #[serde(flatten)]
+        update: TodoUpdate,
+    },
+    Delete {
+        todo_id: TodoId,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    /// Events up to `seq` are contained in the snapshot, later ones follow it.
+    Snapshot {
+        seq: u64,
+        todos: Vec<Todo>,
+        viewers: Vec<Viewer>,
+    },
+    Presence {
+        viewers: Vec<Viewer>,
+    },
+    Ack {
+        request_id: Option<String>,
+        todo_id: TodoId,
+    },
+    Error {
+        request_id: Option<String>,
+        status: u16,
+        detail: String,
+    },
+    #[serde(untagged)]
+    Change(ChangeMessage<'a>),
+}
+
+/// A todo event, typed `created`, `updated` or `deleted` like the server-sent events.
+#[derive(Serialize)]
+struct ChangeMessage<'a> {
+    #[serde(rename = "type")]
+    change: &'static str,
+    seq: u64,
+    data: &'a RawValue,
+}
+
+/// Why a socket stops being served: the client is gone, or it has to be closed.
+enum Hangup {
+    Disconnected,
+    Close(CloseReason),
+}
+
+impl From<Closed> for Hangup {
+    fn from(_: Closed) -> Self {
+        Hangup::Disconnected
+    }
+}
+
+/// One open WebSocket and everything it needs to serve the list after the handshake
+/// request has finished.
+struct TodoSocket<T, M> {
+    list_query: ListQuery,
+    owner_id: UserId,
+    auth_ctx: AuthContext,
+    access_checked_at: Instant,
+    session: actix_ws::Session,
+    event_bus: web::Data<TodoEventBus>,
+    presence: web::Data<PresenceRegistry>,
+    rate_limiter: web::Data<RateLimiter>,
+    limits: web::Data<LimitSettings>,
+    todos_repository: web::Data<T>,
+    list_members_repository: web::Data<M>,
+}
+
+impl<T, M> TodoSocket<T, M>
+where
+    T: TodoRepository,
+    M: ListMemberRepository,
+{
+    async fn run(mut self, mut messages: AggregatedMessageStream) {
+        // Subscribing before the snapshot is taken makes sure no event falls in between.
+        let mut events = self.event_bus.subscribe(&self.owner_id);
+        let mut presence_changes = self.presence.subscribe();
+        let _presence = PresenceRegistry::join(
+            self.presence.clone().into_inner(),
+            self.owner_id.clone(),
+            Viewer {
+                user_id: self.auth_ctx.principal_id.clone(),
+                name: self.auth_ctx.principal_name.clone(),
+            },
+        );
+        let mut heartbeat =
+            tokio::time::interval_at(Instant::now() + HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL);
+        let mut last_heard_at = Instant::now();
+
+        match self.send_snapshot().await {
+            Ok(()) => {}
+            Err(Hangup::Disconnected) => return,
+            Err(Hangup::Close(reason)) => {
+                let _ = self.session.close(Some(reason)).await;
+                return;
+            }
+        }
+
+        let close_reason = loop {
+            let sent = tokio::select! {
+                message = messages.try_next() => match message {
+                    Ok(Some(AggregatedMessage::Close(reason))) => break reason,
+                    Ok(Some(message)) => {
+                        last_heard_at = Instant::now();
+                        self.handle_message(message).await
+                    }
+                    Ok(None) => return,
+                    Err(e) => {
+                        tracing::info!(error = %e, "Closing todo socket after a protocol error");
+                        break Some(CloseCode::Protocol.into());
+                    }
+                },
+                event = events.recv() => match event {
+                    Some(Received::Event(event)) => self.send_change(&event).await,
+                    // The client would notice the gap in `seq` only with the next event.
+                    Some(Received::Resync) => self.send_snapshot().await,
+                    None => break None,
+                },
+                owner_id = presence_changes.recv() => match owner_id {
+                    Ok(owner_id) if owner_id != self.owner_id => Ok(()),
+                    Ok(_) | Err(RecvError::Lagged(_)) => self.send_presence().await,
+                    Err(RecvError::Closed) => break None,
+                },
+                _ = heartbeat.tick() => {
+                    if last_heard_at.elapsed() > 2 * HEARTBEAT_INTERVAL {
+                        break Some(CloseReason {
+                            code: CloseCode::Away,
+                            description: Some("Heartbeat timed out".to_string()),
+                        });
+                    }
+                    match self.check_access().await {
+                        Ok(()) => self.session.ping(b"").await.map_err(Hangup::from),
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            match sent {
+                Ok(()) => {}
+                Err(Hangup::Disconnected) => return,
+                Err(Hangup::Close(reason)) => break Some(reason),
+            }
+        };
+
+        let _ = self.session.close(close_reason).await;
+    }
+
+    async fn handle_message(&mut self, message: AggregatedMessage) -> Result<(), Hangup> {
+        let text = match message {
+            AggregatedMessage::Text(text) => text,
+            AggregatedMessage::Ping(bytes) => return Ok(self.session.pong(&bytes).await?),
+            AggregatedMessage::Pong(_) | AggregatedMessage::Close(_) => return Ok(()),
+            AggregatedMessage::Binary(_) => {
+                return Ok(self
+                    .send_error(None, StatusCode::BAD_REQUEST, "Messages must be JSON text")
+                    .await?)
+            }
+        };
+
+        let message: ClientMessage = match serde_json::from_str(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                return Ok(self
+                    .send_error(
+                        None,
+                        StatusCode::BAD_REQUEST,
+                        &format!("Invalid message: {e}"),
+                    )
+                    .await?)
+            }
+        };
+
+        match message.request {
+            ClientRequest::Resync => self.send_snapshot().await,
+            ClientRequest::Mutation(mutation) => match self.apply(mutation).await {
+                Ok(todo_id) => Ok(self
+                    .send(&ServerMessage::Ack {
+                        request_id: message.request_id,
+                        todo_id,
+                    })
+                    .await?),
+                Err(e) => {
+                    let status = e.as_response_error().status_code();
+                    if status.is_server_error() {
+                        tracing::error!(error = ?e, "Failed to apply todo socket mutation");
+                    }
+                    Ok(self
+                        .send_error(message.request_id, status, &e.to_string())
+                        .await?)
+                }
+            },
+        }
+    }
+
+    /// Mutations are authorized one by one, like requests, so that revoked access and
+    /// rate limits take effect on open sockets too.
+    async fn apply(&self, mutation: Mutation) -> Result<TodoId, actix_web::Error> {
+        if !self.auth_ctx.has_scope(Scope::Write) {
+            return Err(TodoSocketError::MissingWriteScope.into());
+        }
+        if self
+            .rate_limiter
+            .acquire_user_write(&self.auth_ctx.principal_id)
+            .is_err()
+        {
+            return Err(TodoSocketError::TooManyRequests.into());
+        }
+        let list = authorize_list(
+            self.list_members_repository.get_ref(),
+            &self.auth_ctx,
+            self.list_query.clone(),
+            ListRole::Editor,
+        )
+        .await?;
+
+        let todo_id = match mutation {
+            Mutation::Create { content } => {
+                add_todo(
+                    self.todos_repository.get_ref(),
+                    &self.event_bus,
+                    &self.limits,
+                    list.owner_id,
+                    content,
+                    None,
+                )
+                .await?
+            }
+            Mutation::Update { todo_id, update } => {
+                change_todo(
+                    self.todos_repository.get_ref(),
+                    self.list_members_repository.get_ref(),
+                    &self.event_bus,
+                    list.owner_id,
+                    todo_id,
+                    update,
+                    self.auth_ctx.principal_id.clone(),
+                )
+                .await?;
+                todo_id
+            }
+            Mutation::Delete { todo_id } => {
+                remove_todo(
+                    self.todos_repository.get_ref(),
+                    &self.event_bus,
+                    list.owner_id,
+                    todo_id,
+                )
+                .await?;
+                todo_id
+            }
+        };
+
+        Ok(todo_id)
+    }
+
+    /// Reads and presence are authorized when the socket opens, and again at least every
+    /// [`ACCESS_CHECK_INTERVAL`] while it is open. Once access is gone, the socket is closed
+    /// as a policy violation.
+    async fn check_access(&mut self) -> Result<(), Hangup> {
+        let access = authorize_list(
+            self.list_members_repository.get_ref(),
+            &self.auth_ctx,
+            self.list_query.clone(),
+            ListRole::Viewer,
+        )
+        .await;
+
+        let code = match access {
+            Ok(_) => {
+                self.access_checked_at = Instant::now();
+                return Ok(());
+            }
+            Err(ListAccessError::UnexpectedError(ref e)) => {
+                tracing::error!(error = ?e, "Failed to check todo socket access");
+                CloseCode::Error
+            }
+            Err(_) => CloseCode::Policy,
+        };
+        Err(Hangup::Close(CloseReason {
+            code,
+            description: access.err().map(|e| e.to_string()),
+        }))
+    }
+
+    async fn send_snapshot(&mut self) -> Result<(), Hangup> {
+        self.check_access().await?;
+
+        let seq = self.event_bus.last_seq(&self.owner_id);
+        let todos = self
+            .todos_repository
+            .get_all_for_user(self.owner_id.clone())
+            .try_collect::<Vec<_>>()
+            .await;
+
+        match todos {
+            Ok(todos) => {
+                let viewers = self.presence.viewers(&self.owner_id);
+                Ok(self
+                    .send(&ServerMessage::Snapshot {
+                        seq,
+                        todos,
+                        viewers,
+                    })
+                    .await?)
+            }
+            Err(e) => {
+                tracing::error!(error = ?e, "Failed to load todo socket snapshot");
+                Ok(self
+                    .send_error(
+                        None,
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Something went wrong",
+                    )
+                    .await?)
+            }
+        }
+    }
+
+    async fn send_change(&mut self, event: &TodoEvent) -> Result<(), Hangup> {
+        if self.access_checked_at.elapsed() >= ACCESS_CHECK_INTERVAL {
+            self.check_access().await?;
+        }
+
+        let Ok(data) = serde_json::from_str::<&RawValue>(&event.data) else {
+            return Ok(());
+        };
+        Ok(self
+            .send(&ServerMessage::Change(ChangeMessage {
+                change: event.change.as_str(),
+                seq: event.seq,
+                data,
+            }))
+            .await?)
+    }
+
+    async fn send_presence(&mut self) -> Result<(), Hangup> {
+        let viewers = self.presence.viewers(&self.owner_id);
+        Ok(self.send(&ServerMessage::Presence { viewers }).await?)
+    }
+
+    async fn send_error(
+        &mut self,
+        request_id: Option<String>,
+        status: StatusCode,
+        detail: &str,
+    ) -> Result<(), Closed> {
+        self.send(&ServerMessage::Error {
+            request_id,
+            status: status.as_u16(),
+            detail: detail.to_string(),
+        })
+        .await
+    }
+
+    async fn send(&mut self, message: &ServerMessage<'_>) -> Result<(), Closed> {
+        match serde_json::to_string(message) {
+            Ok(text) => self.session.text(text).await,
+            Err(e) => {
+                tracing::error!(error = ?e, "Failed to serialize todo socket message");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TodoSocketError {
+    #[error(transparent)]
+    ListAccess(#[from] ListAccessError),
+    #[error("{0}")]
+    Handshake(#[source] actix_web::Error),
+    #[error("Personal access token is missing the required scope")]
+    MissingWriteScope,
+    #[error("Too many requests, try again later")]
+    TooManyRequests,
+}
+
+impl ResponseError for TodoSocketError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TodoSocketError::ListAccess(e) => e.status_code(),
+            TodoSocketError::Handshake(e) => e.as_response_error().status_code(),
+            TodoSocketError::MissingWriteScope => StatusCode::FORBIDDEN,
+            TodoSocketError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+}
+
+impl IntoResponses for TodoSocketError {
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        problem::responses(ListAccessError::RESPONSES.into_iter().chain([
+            (
+                StatusCode::BAD_REQUEST,
+                "The request is not a WebSocket handshake",
+            ),
+            (StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong"),
+        ]))
+    }
+}
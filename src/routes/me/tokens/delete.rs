@@ -0,0 +1,39 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use crate::{auth, model::TokenHash, repositories::PersonalAccessTokenRepository};
+
+#[tracing::instrument(
+    name = "Delete personal access token",
+    skip(personal_access_token_id, personal_access_tokens_repository, auth_ctx)
+)]
+pub async fn delete_personal_access_token<P>(
+    personal_access_token_id: web::Path<TokenHash>,
+    personal_access_tokens_repository: web::Data<P>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DeletePersonalAccessTokenError>
+where
+    P: PersonalAccessTokenRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    personal_access_tokens_repository
+        .get_ref()
+        .delete_for_user_by_id(user_id, personal_access_token_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeletePersonalAccessTokenError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DeletePersonalAccessTokenError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeletePersonalAccessTokenError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use tera::Tera;
+
+use crate::{auth, repositories::PersonalAccessTokenRepository};
+
+#[tracing::instrument(
+    name = "Get all user personal access tokens",
+    skip(tmpl, personal_access_tokens_repository, auth_ctx, csrf_token)
+)]
+pub async fn get_all_user_personal_access_tokens<P>(
+    tmpl: web::Data<Tera>,
+    personal_access_tokens_repository: web::Data<P>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetAllUserPersonalAccessTokensError>
+where
+    P: PersonalAccessTokenRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let personal_access_tokens = personal_access_tokens_repository
+        .get_ref()
+        .get_all_for_user(user_id)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("personal_access_tokens", &personal_access_tokens);
+
+    let html = tmpl
+        .render("personal_access_tokens.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetAllUserPersonalAccessTokensError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetAllUserPersonalAccessTokensError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetAllUserPersonalAccessTokensError::UnexpectedError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use tera::Tera;
+
+use crate::{
+    auth,
+    model::{PersonalAccessToken, Scope},
+    problem::FieldError,
+    repositories::PersonalAccessTokenRepository,
+    tokens,
+};
+
+const MAX_NAME_LEN: usize = 100;
+
+#[derive(Deserialize)]
+pub struct NewPersonalAccessToken {
+    name: String,
+    scope: Scope,
+}
+
+#[tracing::instrument(
+    name = "Create personal access token",
+    skip(
+        new_personal_access_token,
+        tmpl,
+        personal_access_tokens_repository,
+        auth_ctx
+    )
+)]
+pub async fn create_personal_access_token<P>(
+    new_personal_access_token: web::Form<NewPersonalAccessToken>,
+    tmpl: web::Data<Tera>,
+    personal_access_tokens_repository: web::Data<P>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, CreatePersonalAccessTokenError>
+where
+    P: PersonalAccessTokenRepository,
+{
+    let NewPersonalAccessToken { name, scope } = new_personal_access_token.into_inner();
+    let name = name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(CreatePersonalAccessTokenError::ValidationError(
+            FieldError::new(
+                "name",
+                format!(
+                    "Token name must be between 1 and {} characters long",
+                    MAX_NAME_LEN
+                ),
+            ),
+        ));
+    }
+
+    // Write access is of little use without being able to read what was written.
+    let scopes = match scope {
+        Scope::Read => vec![Scope::Read],
+        Scope::Write => vec![Scope::Read, Scope::Write],
+    };
+
+    let token = SecretString::from(format!(
+        "{}{}",
+        auth::PERSONAL_ACCESS_TOKEN_PREFIX,
+        tokens::generate().expose_secret()
+    ));
+    let personal_access_token = PersonalAccessToken::new(
+        tokens::hash(&token),
+        auth_ctx.principal_id.clone(),
+        auth_ctx.principal_name.clone(),
+        auth_ctx.verified_email.clone(),
+        name,
+        scopes,
+    );
+
+    personal_access_tokens_repository
+        .get_ref()
+        .create(personal_access_token)
+        .await
+        .map_err(CreatePersonalAccessTokenError::UnexpectedError)?;
+
+    let mut context = tera::Context::new();
+    context.insert("token", token.expose_secret());
+
+    let html = tmpl
+        .render("personal_access_token_created.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(CreatePersonalAccessTokenError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreatePersonalAccessTokenError {
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for CreatePersonalAccessTokenError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CreatePersonalAccessTokenError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreatePersonalAccessTokenError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            CreatePersonalAccessTokenError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
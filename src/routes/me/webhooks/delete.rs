@@ -0,0 +1,41 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+
+use crate::{auth, model::WebhookId, webhooks::WebhookRepository};
+
+/// Deliveries still queued for the webhook fail on their next attempt, its delivery log
+/// expires with the deliveries.
+#[tracing::instrument(
+    name = "Delete webhook",
+    skip(webhook_id, webhooks_repository, auth_ctx)
+)]
+pub async fn delete_webhook<W>(
+    webhook_id: web::Path<WebhookId>,
+    webhooks_repository: web::Data<W>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, DeleteWebhookError>
+where
+    W: WebhookRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    webhooks_repository
+        .get_ref()
+        .delete_for_user_by_id(user_id, webhook_id.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeleteWebhookError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for DeleteWebhookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DeleteWebhookError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
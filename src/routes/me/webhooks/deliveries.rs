@@ -0,0 +1,67 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use tera::Tera;
+
+use crate::{
+    auth,
+    model::WebhookId,
+    webhooks::{WebhookDeliveryRepository, WebhookRepository},
+};
+
+/// How many of the latest deliveries the delivery log shows.
+const DELIVERY_LOG_SIZE: i32 = 50;
+
+#[tracing::instrument(
+    name = "Get webhook deliveries",
+    skip(webhook_id, tmpl, webhooks_repository, deliveries_repository, auth_ctx)
+)]
+pub async fn get_webhook_deliveries<W, D>(
+    webhook_id: web::Path<WebhookId>,
+    tmpl: web::Data<Tera>,
+    webhooks_repository: web::Data<W>,
+    deliveries_repository: web::Data<D>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, GetWebhookDeliveriesError>
+where
+    W: WebhookRepository,
+    D: WebhookDeliveryRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let webhook = webhooks_repository
+        .get_ref()
+        .get_for_user_by_id(user_id.clone(), webhook_id.into_inner())
+        .await?
+        .ok_or(GetWebhookDeliveriesError::WebhookNotFound)?;
+
+    let deliveries = deliveries_repository
+        .get_ref()
+        .get_latest_for_webhook(user_id, webhook.id(), DELIVERY_LOG_SIZE)
+        .await?;
+
+    let mut context = tera::Context::new();
+    context.insert("webhook_url", webhook.url());
+    context.insert("deliveries", &deliveries);
+
+    let html = tmpl
+        .render("webhook_deliveries.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetWebhookDeliveriesError {
+    #[error("Webhook not found")]
+    WebhookNotFound,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetWebhookDeliveriesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetWebhookDeliveriesError::WebhookNotFound => StatusCode::NOT_FOUND,
+            GetWebhookDeliveriesError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
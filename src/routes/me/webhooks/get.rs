@@ -0,0 +1,81 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use serde::Serialize;
+use tera::Tera;
+
+use crate::{
+    auth,
+    model::{WebhookEventType, WebhookId},
+    webhooks::WebhookRepository,
+};
+
+/// A webhook as listed, without its secret.
+#[derive(Serialize)]
+struct WebhookView {
+    id: WebhookId,
+    url: String,
+    event_types: Vec<&'static str>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[tracing::instrument(
+    name = "Get all user webhooks",
+    skip(tmpl, webhooks_repository, auth_ctx, csrf_token)
+)]
+pub async fn get_all_user_webhooks<W>(
+    tmpl: web::Data<Tera>,
+    webhooks_repository: web::Data<W>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+    csrf_token: web::ReqData<auth::CsrfToken>,
+) -> Result<HttpResponse, GetAllUserWebhooksError>
+where
+    W: WebhookRepository,
+{
+    let user_id = auth_ctx.principal_id.clone();
+
+    let webhooks = webhooks_repository
+        .get_ref()
+        .get_all_for_user(user_id)
+        .map_ok(|webhook| WebhookView {
+            id: webhook.id(),
+            url: webhook.url().to_string(),
+            event_types: webhook
+                .event_types()
+                .iter()
+                .map(|event_type| event_type.as_str())
+                .collect(),
+            created_at: webhook.created_at(),
+        })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let event_types = WebhookEventType::ALL
+        .iter()
+        .map(|event_type| event_type.as_str())
+        .collect::<Vec<_>>();
+
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", csrf_token.as_str());
+    context.insert("webhooks", &webhooks);
+    context.insert("event_types", &event_types);
+
+    let html = tmpl
+        .render("webhooks.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetAllUserWebhooksError {
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetAllUserWebhooksError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetAllUserWebhooksError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+mod delete;
+mod deliveries;
+mod get;
+mod post;
+
+pub use delete::*;
+pub use deliveries::*;
+pub use get::*;
+pub use post::*;
@@ -0,0 +1,136 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use secrecy::ExposeSecret;
+use tera::Tera;
+
+use crate::{
+    auth,
+    configuration::WebhookSettings,
+    model::{Webhook, WebhookEventType},
+    problem::FieldError,
+    tokens,
+    webhooks::{self, WebhookRepository},
+};
+
+const MAX_URL_LEN: usize = 2000;
+const MAX_WEBHOOKS_PER_USER: usize = 10;
+
+/// Registers a webhook. The form repeats `event_types` once per checked event type, so it
+/// is read as pairs.
+#[tracing::instrument(
+    name = "Create webhook",
+    skip(form, tmpl, webhooks_repository, webhook_settings, auth_ctx)
+)]
+pub async fn create_webhook<W>(
+    form: web::Form<Vec<(String, String)>>,
+    tmpl: web::Data<Tera>,
+    webhooks_repository: web::Data<W>,
+    webhook_settings: web::Data<WebhookSettings>,
+    auth_ctx: web::ReqData<auth::AuthContext>,
+) -> Result<HttpResponse, CreateWebhookError>
+where
+    W: WebhookRepository,
+{
+    if !webhook_settings.enabled {
+        return Err(CreateWebhookError::Disabled);
+    }
+
+    let mut url = String::new();
+    let mut event_types = Vec::new();
+    for (name, value) in form.into_inner() {
+        match name.as_str() {
+            "url" => url = value.trim().to_string(),
+            "event_types" => {
+                let event_type = value.parse::<WebhookEventType>().map_err(|e| {
+                    CreateWebhookError::ValidationError(FieldError::new("event_types", e))
+                })?;
+                if !event_types.contains(&event_type) {
+                    event_types.push(event_type);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if url.len() > MAX_URL_LEN {
+        return Err(CreateWebhookError::ValidationError(FieldError::new(
+            "url",
+            format!("The URL must be at most {} characters long", MAX_URL_LEN),
+        )));
+    }
+    webhooks::check_url(&url, &webhook_settings)
+        .map_err(|e| CreateWebhookError::ValidationError(FieldError::new("url", e)))?;
+    if event_types.is_empty() {
+        return Err(CreateWebhookError::ValidationError(FieldError::new(
+            "event_types",
+            "Select at least one event type",
+        )));
+    }
+
+    let user_id = auth_ctx.principal_id.clone();
+    let existing = webhooks_repository
+        .get_ref()
+        .get_all_for_user(user_id.clone())
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(CreateWebhookError::UnexpectedError)?;
+    if existing.len() >= MAX_WEBHOOKS_PER_USER {
+        return Err(CreateWebhookError::ValidationError(FieldError::new(
+            "url",
+            format!("You can have at most {} webhooks", MAX_WEBHOOKS_PER_USER),
+        )));
+    }
+
+    let secret = tokens::generate();
+    let webhook = Webhook::new(
+        user_id,
+        url,
+        event_types,
+        secret.expose_secret().to_string(),
+    );
+    let webhook_url = webhook.url().to_string();
+
+    webhooks_repository
+        .get_ref()
+        .create(webhook)
+        .await
+        .map_err(CreateWebhookError::UnexpectedError)?;
+
+    let mut context = tera::Context::new();
+    context.insert("webhook_url", &webhook_url);
+    context.insert("secret", secret.expose_secret());
+
+    let html = tmpl
+        .render("webhook_created.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))
+        .map_err(CreateWebhookError::UnexpectedError)?;
+
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateWebhookError {
+    #[error("Webhooks are not enabled")]
+    Disabled,
+    #[error("{0}")]
+    ValidationError(#[source] FieldError),
+    #[error("Something went wrong")]
+    UnexpectedError(#[source] anyhow::Error),
+}
+
+impl ResponseError for CreateWebhookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CreateWebhookError::Disabled => StatusCode::NOT_FOUND,
+            CreateWebhookError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreateWebhookError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            CreateWebhookError::ValidationError(e) => e.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
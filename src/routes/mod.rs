@@ -1,6 +1,16 @@
+pub mod admin;
+pub mod dav;
+mod dev_auth;
+mod feeds;
 mod healthcheck;
 mod homepage;
 pub mod me;
+mod openapi;
+mod shared;
 
+pub use dev_auth::*;
+pub use feeds::*;
 pub use healthcheck::*;
 pub use homepage::*;
+pub use openapi::*;
+pub use shared::*;
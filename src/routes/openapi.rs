@@ -0,0 +1,9 @@
+use actix_web::{HttpResponse, Responder};
+use utoipa::OpenApi;
+
+use crate::openapi::ApiDoc;
+
+#[tracing::instrument(name = "Get OpenAPI specification")]
+pub async fn get_openapi_spec() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
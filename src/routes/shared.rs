@@ -0,0 +1,143 @@
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use secrecy::SecretString;
+use serde::Serialize;
+use tera::Tera;
+use uuid::Uuid;
+
+use crate::{
+    model::{Priority, Recurrence, ShareLink, ShareLinkId, Todo, TodoContent, TodoNotes},
+    repositories::{ShareLinkRepository, TodoRepository},
+    tokens::Signer,
+};
+
+/// What a share link reveals about a todo. Identifiers, in particular `created_by`,
+/// stay out of it so the page cannot be used to learn anything beyond the list itself.
+#[derive(Serialize)]
+struct SharedTodo<'a> {
+    content: &'a TodoContent,
+    done: bool,
+    due_at: Option<chrono::DateTime<chrono::Utc>>,
+    recurrence: Option<Recurrence>,
+    priority: Option<Priority>,
+    tags: &'a [String],
+    notes: Option<&'a TodoNotes>,
+}
+
+impl<'a> From<&'a Todo> for SharedTodo<'a> {
+    fn from(todo: &'a Todo) -> Self {
+        Self {
+            content: todo.content(),
+            done: todo.is_done(),
+            due_at: todo.due_at(),
+            recurrence: todo.recurrence(),
+            priority: todo.priority(),
+            tags: todo.tags(),
+            notes: todo.notes(),
+        }
+    }
+}
+
+/// Stands in for the list access `todos.html` is rendered with, granting nothing.
+#[derive(Serialize)]
+struct SharedList {
+    is_owner: bool,
+    can_edit: bool,
+    query: &'static str,
+}
+
+/// The token of a share link: its id and expiry, as a unix timestamp or `0` for never,
+/// signed so that links cannot be guessed or extended.
+pub fn share_link_token(share_link: &ShareLink, signer: &Signer) -> SecretString {
+    let expires_at = share_link
+        .expires_at()
+        .map_or(0, |expires_at| expires_at.timestamp());
+    signer.sign(&format!("{}.{}", share_link.id(), expires_at))
+}
+
+/// The id of the share link the token was signed for, unless it is forged or expired.
+fn verify_share_link_token(token: &str, signer: &Signer) -> Option<ShareLinkId> {
+    let (id, expires_at) = signer.verify(token)?.split_once('.')?;
+    let expires_at = expires_at.parse::<i64>().ok()?;
+    if expires_at != 0 && expires_at <= chrono::Utc::now().timestamp() {
+        return None;
+    }
+    Uuid::parse_str(id).ok().map(ShareLinkId::from)
+}
+
+#[tracing::instrument(
+    name = "Get todos through share link",
+    skip(token, tmpl, signer, todos_repository, share_links_repository)
+)]
+pub async fn get_shared_todos<T, S>(
+    token: web::Path<String>,
+    tmpl: web::Data<Tera>,
+    signer: web::Data<Signer>,
+    todos_repository: web::Data<T>,
+    share_links_repository: web::Data<S>,
+) -> Result<HttpResponse, GetSharedTodosError>
+where
+    T: TodoRepository,
+    S: ShareLinkRepository,
+{
+    let share_link_id =
+        verify_share_link_token(&token, &signer).ok_or(GetSharedTodosError::UnknownShareLink)?;
+
+    // Revoked links are deleted, and look exactly like unknown or expired ones.
+    let share_link = share_links_repository
+        .get_ref()
+        .get_by_id(share_link_id)
+        .await?
+        .filter(|share_link| !share_link.is_expired())
+        .ok_or(GetSharedTodosError::UnknownShareLink)?;
+
+    let todos = todos_repository
+        .get_ref()
+        .get_all_for_user(share_link.user_id())
+        .try_collect::<Vec<_>>()
+        .await?;
+    let shared_todos = todos.iter().map(SharedTodo::from).collect::<Vec<_>>();
+
+    let mut context = tera::Context::new();
+    context.insert("read_only", &true);
+    context.insert("name", share_link.name());
+    context.insert("todos", &shared_todos);
+    context.insert(
+        "list",
+        &SharedList {
+            is_owner: false,
+            can_edit: false,
+            query: "",
+        },
+    );
+    context.insert("assignees", &Vec::<()>::new());
+
+    let html = tmpl
+        .render("todos.html", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render web page: {}", e))?;
+
+    // The token is part of the URL, so keep it out of referrers, caches and indexes.
+    Ok(HttpResponse::Ok()
+        .content_type("text/html")
+        .insert_header(("Referrer-Policy", "no-referrer"))
+        .insert_header(("Cache-Control", "no-store"))
+        .insert_header(("X-Robots-Tag", "noindex"))
+        .body(html))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetSharedTodosError {
+    #[error("Share link not found")]
+    UnknownShareLink,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl ResponseError for GetSharedTodosError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GetSharedTodosError::UnknownShareLink => StatusCode::NOT_FOUND,
+            GetSharedTodosError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
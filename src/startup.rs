@@ -1,61 +1,226 @@
+use std::{net::TcpListener, time::Duration};
+
 use crate::auth;
 use actix_files::Files;
-use actix_web::{dev::Server, middleware, web, App, HttpServer};
-use azure_data_cosmos::prelude::{AuthorizationToken, CosmosClient, DatabaseClient};
+use actix_web::{
+    dev::Server, http::Method, middleware, web, App, FromRequest, Handler, HttpServer, Responder,
+    Route,
+};
+use azure_data_cosmos::prelude::{
+    AuthorizationToken, CloudLocation, CosmosClient, CosmosClientBuilder, DatabaseClient,
+};
 use secrecy::ExposeSecret;
 use tera::Tera;
 use tracing_actix_web::TracingLogger;
+use utoipa_redoc::{Redoc, Servable};
 
-use crate::{configuration, repositories, routes};
+use crate::{
+    change_feed, configuration, email_gateway, events, idempotency, markdown, notifications,
+    presence, problem, push, rate_limit, repositories, routes, telemetry, tokens, webhooks,
+};
 
-#[tracing::instrument(name = "Initializing server")]
-pub fn init(settings: configuration::Settings) -> std::io::Result<Server> {
+#[tracing::instrument(name = "Initializing server", skip(listener))]
+pub fn init(settings: configuration::Settings, listener: TcpListener) -> std::io::Result<Server> {
     let database_client = init_database_client(settings.cosmos);
 
+    if let configuration::AuthSettings::Development(dev_settings) = &settings.auth {
+        tracing::warn!(
+            principal_id = %dev_settings.principal_id,
+            "Development authentication is enabled, requests are not authenticated"
+        );
+    }
+    let authenticator = web::Data::new(auth::Authenticator::from(settings.auth));
+    let authorization_settings = web::Data::new(settings.authorization);
+    let rate_limiter = web::Data::new(rate_limit::RateLimiter::new(&settings.limits));
+    let limits = web::Data::new(settings.limits);
+    let idempotency_settings = web::Data::new(settings.idempotency);
+    let event_bus = if settings.change_feed.enabled {
+        let event_bus = web::Data::new(events::TodoEventBus::with_change_feed());
+        let processor = change_feed::ChangeFeedProcessor::new(
+            settings.change_feed.lease_id,
+            repositories::CosmosTodoChangeFeed::new(database_client.clone()),
+            repositories::CosmosLeaseRepository::new(database_client.clone()),
+            event_bus.clone().into_inner(),
+        );
+        actix_web::rt::spawn(
+            processor.run(Duration::from_millis(settings.change_feed.poll_interval_ms)),
+        );
+        event_bus
+    } else {
+        web::Data::new(events::TodoEventBus::new())
+    };
+
+    let presence = web::Data::new(presence::PresenceRegistry::new());
+
+    if settings.webhooks.enabled {
+        let dispatcher = change_feed::ChangeFeedProcessor::new(
+            webhooks::WEBHOOK_LEASE_ID.to_string(),
+            repositories::CosmosTodoChangeFeed::new(database_client.clone()),
+            repositories::CosmosLeaseRepository::new(database_client.clone()),
+            webhooks::WebhookDispatcher::new(
+                repositories::CosmosWebhookRepository::new(database_client.clone()),
+                repositories::CosmosWebhookDeliveryRepository::new(database_client.clone()),
+                settings.webhooks.delivery_retention_seconds,
+            ),
+        );
+        actix_web::rt::spawn(
+            dispatcher.run(Duration::from_millis(settings.webhooks.poll_interval_ms)),
+        );
+        let worker = webhooks::WebhookWorker::new(
+            repositories::CosmosWebhookRepository::new(database_client.clone()),
+            repositories::CosmosWebhookDeliveryRepository::new(database_client.clone()),
+            settings.webhooks.clone(),
+        )
+        .map_err(std::io::Error::other)?;
+        actix_web::rt::spawn(worker.run());
+    }
+    let webhook_settings = web::Data::new(settings.webhooks);
+
+    if settings.inbound_email.enabled {
+        let listener = TcpListener::bind(&settings.inbound_email.listen_address)?;
+        listener.set_nonblocking(true)?;
+        tracing::info!(
+            address = %settings.inbound_email.listen_address,
+            "Receiving todos by email"
+        );
+        let gateway = email_gateway::EmailGateway::new(
+            email_gateway::RepositoryInbox::new(
+                repositories::CosmosTodoRepository::new(database_client.clone()),
+                repositories::CosmosInboundAddressTokenRepository::new(database_client.clone()),
+                repositories::CosmosUserRepository::new(database_client.clone()),
+                event_bus.clone().into_inner(),
+                limits.get_ref().clone(),
+            ),
+            settings.inbound_email.clone(),
+        );
+        actix_web::rt::spawn(async move {
+            match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => gateway.run(listener).await,
+                Err(e) => tracing::error!(error = %e, "Failed to listen for emails"),
+            }
+        });
+    }
+    let inbound_email_settings = web::Data::new(settings.inbound_email);
+
+    let mut tera = Tera::new("templates/**/*").unwrap();
+    tera.register_filter("markdown", markdown::markdown_filter);
+
+    if settings.notifications.enabled {
+        let scheduler = notifications::NotificationScheduler::new(
+            repositories::CosmosTodoRepository::new(database_client.clone()),
+            repositories::CosmosUserRepository::new(database_client.clone()),
+            repositories::CosmosNotificationRepository::new(database_client.clone()),
+            tera.clone(),
+            settings.notifications.clone(),
+        )
+        .map_err(std::io::Error::other)?;
+        actix_web::rt::spawn(scheduler.run());
+    }
+    let notification_settings = web::Data::new(settings.notifications);
+
+    if settings.push.enabled {
+        let service =
+            push::WebPushService::new(settings.push.clone()).map_err(std::io::Error::other)?;
+        let scheduler = push::PushScheduler::new(
+            repositories::CosmosTodoRepository::new(database_client.clone()),
+            repositories::CosmosPushSubscriptionRepository::new(database_client.clone()),
+            repositories::CosmosNotificationRepository::new(database_client.clone()),
+            service,
+            settings.push.clone(),
+        );
+        actix_web::rt::spawn(scheduler.run());
+    }
+    let push_settings = web::Data::new(settings.push);
+
     let todo_repository = web::Data::new(repositories::CosmosTodoRepository::new(
         database_client.clone(),
     ));
+    let feed_token_repository = web::Data::new(repositories::CosmosFeedTokenRepository::new(
+        database_client.clone(),
+    ));
+    let inbound_address_token_repository = web::Data::new(
+        repositories::CosmosInboundAddressTokenRepository::new(database_client.clone()),
+    );
+    let app_password_repository = web::Data::new(repositories::CosmosAppPasswordRepository::new(
+        database_client.clone(),
+    ));
+
+    let personal_access_token_repository = web::Data::new(
+        repositories::CosmosPersonalAccessTokenRepository::new(database_client.clone()),
+    );
 
-    let tera = Tera::new("templates/**/*").unwrap();
+    let idempotency_repository = web::Data::new(repositories::CosmosIdempotencyRepository::new(
+        database_client.clone(),
+    ));
+    let share_link_signer = web::Data::new(
+        settings
+            .share_links
+            .signing_key
+            .map_or_else(tokens::Signer::random, tokens::Signer::new),
+    );
+    let share_link_repository = web::Data::new(repositories::CosmosShareLinkRepository::new(
+        database_client.clone(),
+    ));
+    let list_member_repository = web::Data::new(repositories::CosmosListMemberRepository::new(
+        database_client.clone(),
+    ));
+    let user_repository = web::Data::new(repositories::CosmosUserRepository::new(
+        database_client.clone(),
+    ));
+    let audit_log_repository = web::Data::new(repositories::CosmosAuditLogRepository::new(
+        database_client.clone(),
+    ));
+    let storage_usage_repository = web::Data::new(repositories::CosmosStorageUsageRepository::new(
+        database_client.clone(),
+    ));
+    let webhook_repository = web::Data::new(repositories::CosmosWebhookRepository::new(
+        database_client.clone(),
+    ));
+    let webhook_delivery_repository = web::Data::new(
+        repositories::CosmosWebhookDeliveryRepository::new(database_client.clone()),
+    );
+    let notification_repository = web::Data::new(repositories::CosmosNotificationRepository::new(
+        database_client.clone(),
+    ));
+    let push_subscription_repository = web::Data::new(
+        repositories::CosmosPushSubscriptionRepository::new(database_client.clone()),
+    );
 
     let server = HttpServer::new(move || {
         App::new()
-            .wrap(TracingLogger::default())
+            .wrap(middleware::from_fn(problem::problem_middleware))
+            .wrap(TracingLogger::<telemetry::RedactingRootSpanBuilder>::new())
             .app_data(todo_repository.clone())
+            .app_data(feed_token_repository.clone())
+            .app_data(inbound_address_token_repository.clone())
+            .app_data(app_password_repository.clone())
+            .app_data(personal_access_token_repository.clone())
+            .app_data(share_link_repository.clone())
+            .app_data(share_link_signer.clone())
+            .app_data(list_member_repository.clone())
+            .app_data(user_repository.clone())
+            .app_data(audit_log_repository.clone())
+            .app_data(storage_usage_repository.clone())
+            .app_data(webhook_repository.clone())
+            .app_data(webhook_delivery_repository.clone())
+            .app_data(notification_repository.clone())
+            .app_data(push_subscription_repository.clone())
+            .app_data(authenticator.clone())
+            .app_data(authorization_settings.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(event_bus.clone())
+            .app_data(presence.clone())
+            .app_data(limits.clone())
+            .app_data(idempotency_settings.clone())
+            .app_data(webhook_settings.clone())
+            .app_data(inbound_email_settings.clone())
+            .app_data(notification_settings.clone())
+            .app_data(push_settings.clone())
+            .app_data(idempotency_repository.clone())
             .app_data(web::Data::new(tera.clone()))
-            .service(Files::new("/static", "./static").show_files_listing())
-            .route("/", web::get().to(routes::homepage))
-            .route("/healthcheck", web::get().to(routes::healthcheck))
-            .service(
-                web::scope("/me")
-                    .wrap(middleware::from_fn(auth::auth_middleware))
-                    .route(
-                        "todos",
-                        web::get().to(routes::me::todos::get_all_user_todos::<
-                            repositories::CosmosTodoRepository,
-                        >),
-                    )
-                    .route(
-                        "todos",
-                        web::post().to(routes::me::todos::create_todo::<
-                            repositories::CosmosTodoRepository,
-                        >),
-                    )
-                    .route(
-                        "/todos/{todo_id}",
-                        web::patch().to(routes::me::todos::update_todo::<
-                            repositories::CosmosTodoRepository,
-                        >),
-                    )
-                    .route(
-                        "todos/{todo_id}",
-                        web::delete().to(routes::me::todos::delete_todo::<
-                            repositories::CosmosTodoRepository,
-                        >),
-                    ),
-            )
+            .configure(configure)
     })
-    .bind(("0.0.0.0", 8080))?
+    .listen(listener)?
     .run();
 
     Ok(server)
@@ -65,6 +230,387 @@ fn init_database_client(database_settings: configuration::CosmosSettings) -> Dat
     let auth_token = AuthorizationToken::primary_key(database_settings.primary_key.expose_secret())
         .expect("Invalid cosmos primary key");
 
-    CosmosClient::new(database_settings.account.expose_secret(), auth_token)
-        .database_client(database_settings.database_name)
+    let client = match database_settings.endpoint {
+        Some(uri) => {
+            CosmosClientBuilder::with_location(CloudLocation::Custom { uri, auth_token }).build()
+        }
+        None => CosmosClient::new(database_settings.account.expose_secret(), auth_token),
+    };
+    client.database_client(database_settings.database_name)
+}
+
+/// Registers every route of the app, for [`init`] and for tests.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .service(Files::new("/static", "./static").show_files_listing())
+        .route("/", web::get().to(routes::homepage))
+        .route("/healthcheck", web::get().to(routes::healthcheck))
+        .route("/api/openapi.json", web::get().to(routes::get_openapi_spec))
+        .service(Redoc::with_url("/api/docs", "/api/openapi.json"))
+        .route(
+            "/.auth/login/{provider}",
+            web::get().to(routes::dev_login_form),
+        )
+        .route("/.auth/login/{provider}", web::post().to(routes::dev_login))
+        .route("/.auth/logout", web::get().to(routes::dev_logout))
+        .service(
+            web::resource("/feeds/{token}/todos.ics")
+                .wrap(middleware::from_fn(rate_limit::rate_limit_middleware))
+                .get(
+                    routes::get_todos_calendar_feed::<
+                        repositories::CosmosTodoRepository,
+                        repositories::CosmosFeedTokenRepository,
+                    >,
+                ),
+        )
+        .service(
+            web::resource("/shared/{token}")
+                .wrap(middleware::from_fn(rate_limit::rate_limit_middleware))
+                .get(
+                    routes::get_shared_todos::<
+                        repositories::CosmosTodoRepository,
+                        repositories::CosmosShareLinkRepository,
+                    >,
+                ),
+        )
+        .route(
+            "/.well-known/caldav",
+            web::route().to(routes::dav::well_known_caldav),
+        )
+        .service(
+            web::scope("/dav")
+                .wrap(middleware::from_fn(
+                    auth::user_tracking_middleware::<repositories::CosmosUserRepository>,
+                ))
+                .wrap(middleware::from_fn(rate_limit::rate_limit_middleware))
+                .wrap(auth::RequireRole::new(auth::Role::User))
+                .wrap(middleware::from_fn(
+                    auth::app_password_middleware::<repositories::CosmosAppPasswordRepository>,
+                ))
+                .default_service(web::to(
+                    routes::dav::dav::<repositories::CosmosTodoRepository>,
+                )),
+        )
+        .service(
+            web::scope("/me")
+                .wrap(middleware::from_fn(
+                    idempotency::idempotency_middleware::<
+                        repositories::CosmosIdempotencyRepository,
+                    >,
+                ))
+                .wrap(middleware::from_fn(
+                    auth::user_tracking_middleware::<repositories::CosmosUserRepository>,
+                ))
+                .wrap(middleware::from_fn(rate_limit::rate_limit_middleware))
+                .wrap(auth::RequireRole::new(auth::Role::User))
+                .wrap(middleware::from_fn(auth::auth_middleware))
+                .wrap(middleware::from_fn(
+                    auth::personal_access_token_middleware::<
+                        repositories::CosmosPersonalAccessTokenRepository,
+                    >,
+                ))
+                .wrap(middleware::from_fn(auth::csrf_middleware))
+                .configure(|cfg| {
+                    for api_route in api_routes() {
+                        cfg.route(api_route.path, api_route.route);
+                    }
+                })
+                .route(
+                    "feed-token",
+                    web::post().to(routes::me::rotate_feed_token::<
+                        repositories::CosmosFeedTokenRepository,
+                    >),
+                )
+                .route(
+                    "inbound-email-address",
+                    web::post().to(routes::me::rotate_inbound_email_address::<
+                        repositories::CosmosInboundAddressTokenRepository,
+                    >),
+                )
+                .route(
+                    "notifications",
+                    web::get().to(routes::me::notifications::get_notification_preferences::<
+                        repositories::CosmosUserRepository,
+                        repositories::CosmosPushSubscriptionRepository,
+                    >),
+                )
+                .route(
+                    "notifications",
+                    web::post().to(
+                        routes::me::notifications::update_notification_preferences::<
+                            repositories::CosmosUserRepository,
+                        >,
+                    ),
+                )
+                .route(
+                    "push-subscriptions",
+                    web::post().to(routes::me::push_subscriptions::create_push_subscription::<
+                        repositories::CosmosPushSubscriptionRepository,
+                    >),
+                )
+                .route(
+                    "push-subscriptions/{subscription_id}",
+                    web::delete().to(
+                        routes::me::push_subscriptions::delete_push_subscription::<
+                            repositories::CosmosPushSubscriptionRepository,
+                        >,
+                    ),
+                )
+                .route(
+                    "sharing",
+                    web::get().to(routes::me::sharing::get_list_members::<
+                        repositories::CosmosListMemberRepository,
+                    >),
+                )
+                .route(
+                    "sharing",
+                    web::post().to(routes::me::sharing::share_list::<
+                        repositories::CosmosUserRepository,
+                        repositories::CosmosListMemberRepository,
+                    >),
+                )
+                .route(
+                    "sharing/{member_id}",
+                    web::delete().to(routes::me::sharing::revoke_list_member::<
+                        repositories::CosmosListMemberRepository,
+                    >),
+                )
+                .route(
+                    "app-passwords",
+                    web::get().to(routes::me::app_passwords::get_all_user_app_passwords::<
+                        repositories::CosmosAppPasswordRepository,
+                    >),
+                )
+                .route(
+                    "app-passwords",
+                    web::post().to(routes::me::app_passwords::create_app_password::<
+                        repositories::CosmosAppPasswordRepository,
+                    >),
+                )
+                .route(
+                    "app-passwords/{app_password_id}",
+                    web::delete().to(routes::me::app_passwords::delete_app_password::<
+                        repositories::CosmosAppPasswordRepository,
+                    >),
+                )
+                .route(
+                    "tokens",
+                    web::get().to(routes::me::tokens::get_all_user_personal_access_tokens::<
+                        repositories::CosmosPersonalAccessTokenRepository,
+                    >),
+                )
+                .route(
+                    "tokens",
+                    web::post().to(routes::me::tokens::create_personal_access_token::<
+                        repositories::CosmosPersonalAccessTokenRepository,
+                    >),
+                )
+                .route(
+                    "tokens/{personal_access_token_id}",
+                    web::delete().to(routes::me::tokens::delete_personal_access_token::<
+                        repositories::CosmosPersonalAccessTokenRepository,
+                    >),
+                )
+                .route(
+                    "share-links",
+                    web::get().to(routes::me::share_links::get_all_user_share_links::<
+                        repositories::CosmosShareLinkRepository,
+                    >),
+                )
+                .route(
+                    "share-links",
+                    web::post().to(routes::me::share_links::create_share_link::<
+                        repositories::CosmosShareLinkRepository,
+                    >),
+                )
+                .route(
+                    "share-links/{share_link_id}",
+                    web::delete().to(routes::me::share_links::delete_share_link::<
+                        repositories::CosmosShareLinkRepository,
+                    >),
+                )
+                .route(
+                    "webhooks",
+                    web::get().to(routes::me::webhooks::get_all_user_webhooks::<
+                        repositories::CosmosWebhookRepository,
+                    >),
+                )
+                .route(
+                    "webhooks",
+                    web::post().to(routes::me::webhooks::create_webhook::<
+                        repositories::CosmosWebhookRepository,
+                    >),
+                )
+                .route(
+                    "webhooks/{webhook_id}",
+                    web::delete().to(routes::me::webhooks::delete_webhook::<
+                        repositories::CosmosWebhookRepository,
+                    >),
+                )
+                .route(
+                    "webhooks/{webhook_id}/deliveries",
+                    web::get().to(routes::me::webhooks::get_webhook_deliveries::<
+                        repositories::CosmosWebhookRepository,
+                        repositories::CosmosWebhookDeliveryRepository,
+                    >),
+                ),
+        )
+        // Admin pages are only available to interactive sessions, personal access
+        // tokens are not accepted here.
+        .service(
+            web::scope("/admin")
+                .wrap(middleware::from_fn(rate_limit::rate_limit_middleware))
+                .wrap(auth::RequireRole::new(auth::Role::Admin))
+                .wrap(middleware::from_fn(auth::auth_middleware))
+                .wrap(middleware::from_fn(auth::csrf_middleware))
+                .route(
+                    "",
+                    web::get().to(routes::admin::get_admin_dashboard::<
+                        repositories::CosmosStorageUsageRepository,
+                        repositories::CosmosAuditLogRepository,
+                    >),
+                )
+                .route(
+                    "/users",
+                    web::get().to(routes::admin::get_users::<
+                        repositories::CosmosUserRepository,
+                        repositories::CosmosTodoRepository,
+                        repositories::CosmosAuditLogRepository,
+                    >),
+                )
+                .route(
+                    "/users/{user_id}/todos",
+                    web::get().to(routes::admin::get_user_todos_as_admin::<
+                        repositories::CosmosUserRepository,
+                        repositories::CosmosTodoRepository,
+                        repositories::CosmosAuditLogRepository,
+                    >),
+                )
+                .route(
+                    "/users/{user_id}",
+                    web::delete().to(routes::admin::delete_user_data::<
+                        repositories::CosmosUserDataRepositories,
+                        repositories::CosmosAuditLogRepository,
+                    >),
+                )
+                .route(
+                    "/audit-log",
+                    web::get().to(routes::admin::get_audit_log::<
+                        repositories::CosmosAuditLogRepository,
+                    >),
+                ),
+        );
+}
+
+/// A route of the documented API, registered under `/me`. The routes are kept in a list,
+/// so that the OpenAPI specification can be checked against what is registered.
+pub struct ApiRoute {
+    pub method: Method,
+    /// The path relative to `/me`.
+    pub path: &'static str,
+    route: Route,
+}
+
+impl ApiRoute {
+    fn new<F, Args>(method: Method, path: &'static str, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        Self {
+            route: web::method(method.clone()).to(handler),
+            method,
+            path,
+        }
+    }
+}
+
+/// The routes of the documented API, in the order they are matched in.
+pub fn api_routes() -> Vec<ApiRoute> {
+    vec![
+        ApiRoute::new(
+            Method::GET,
+            "todos",
+            routes::me::todos::get_all_user_todos::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos/assigned",
+            routes::me::todos::get_todos_assigned_to_me::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos/events",
+            routes::me::todos::get_todo_events::<repositories::CosmosListMemberRepository>,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos/ws",
+            routes::me::todos::open_todo_socket::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos.ics",
+            routes::me::todos::get_user_todos_calendar::<repositories::CosmosTodoRepository>,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos.md",
+            routes::me::todos::get_user_todos_markdown::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos/preview",
+            routes::me::todos::preview_todo::<
+                repositories::CosmosUserRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::GET,
+            "todos/{todo_id}",
+            routes::me::todos::get_todo::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::POST,
+            "todos",
+            routes::me::todos::create_todo::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosUserRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::PATCH,
+            "todos/{todo_id}",
+            routes::me::todos::update_todo::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+        ApiRoute::new(
+            Method::DELETE,
+            "todos/{todo_id}",
+            routes::me::todos::delete_todo::<
+                repositories::CosmosTodoRepository,
+                repositories::CosmosListMemberRepository,
+            >,
+        ),
+    ]
 }
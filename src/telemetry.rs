@@ -1,7 +1,12 @@
 use crate::configuration;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Version;
+use actix_web::{Error, HttpMessage};
 use secrecy::ExposeSecret;
 use tracing::subscriber::set_global_default;
-use tracing::Subscriber;
+use tracing::{Span, Subscriber};
+use tracing_actix_web::{DefaultRootSpanBuilder, RequestId, RootSpanBuilder};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
 use tracing_opentelemetry::OpenTelemetryLayer;
@@ -48,3 +53,98 @@ pub fn init_subscriber(subscriber: impl Subscriber + Sync + Send) {
     LogTracer::init().expect("Failed to set logger");
     set_global_default(subscriber).expect("Failed to set subscriber");
 }
+
+/// Path parameters that are secrets, the tokens of calendar feeds and share links.
+const SECRET_PARAMETERS: [&str; 1] = ["{token}"];
+
+/// Builds the root span of a request with the fields of [`DefaultRootSpanBuilder`], except
+/// that secret path parameters are redacted from `http.target`. The default span records
+/// the path as requested, so it cannot be redacted after the fact.
+pub struct RedactingRootSpanBuilder;
+
+impl RootSpanBuilder for RedactingRootSpanBuilder {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let user_agent = request
+            .headers()
+            .get("User-Agent")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let route = request.match_pattern();
+        let target = redact_target(
+            request
+                .uri()
+                .path_and_query()
+                .map(|path_and_query| path_and_query.as_str())
+                .unwrap_or_default(),
+            route.as_deref(),
+        );
+        let route = route.unwrap_or_else(|| "default".to_string());
+        let request_id = request.extensions().get::<RequestId>().copied();
+        let connection_info = request.connection_info();
+
+        tracing::info_span!(
+            "HTTP request",
+            http.method = %request.method(),
+            http.route = %route,
+            http.flavor = http_flavor(request.version()),
+            http.scheme = %connection_info.scheme(),
+            http.host = %connection_info.host(),
+            http.client_ip = connection_info.realip_remote_addr().unwrap_or_default(),
+            http.user_agent = user_agent,
+            http.target = %target,
+            http.status_code = tracing::field::Empty,
+            otel.name = %format!("{} {}", request.method(), route),
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+            trace_id = tracing::field::Empty,
+            request_id = request_id.map(tracing::field::display),
+            exception.message = tracing::field::Empty,
+            exception.details = tracing::field::Empty,
+        )
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+/// Replaces the path segments matched by a secret parameter of the route pattern.
+fn redact_target(target: &str, route: Option<&str>) -> String {
+    let Some(route) = route.filter(|route| {
+        SECRET_PARAMETERS
+            .iter()
+            .any(|parameter| route.contains(parameter))
+    }) else {
+        return target.to_string();
+    };
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+    let path = path
+        .split('/')
+        .zip(route.split('/').map(Some).chain(std::iter::repeat(None)))
+        .map(|(segment, pattern)| match pattern {
+            Some(pattern) if SECRET_PARAMETERS.contains(&pattern) => "[redacted]",
+            _ => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match query {
+        Some(query) => format!("{}?{}", path, query),
+        None => path,
+    }
+}
+
+fn http_flavor(version: Version) -> &'static str {
+    match version {
+        Version::HTTP_09 => "0.9",
+        Version::HTTP_10 => "1.0",
+        Version::HTTP_11 => "1.1",
+        Version::HTTP_2 => "2.0",
+        Version::HTTP_3 => "3.0",
+        _ => "unknown",
+    }
+}
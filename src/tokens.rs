@@ -0,0 +1,77 @@
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+
+use crate::model::TokenHash;
+
+const TOKEN_BYTES: usize = 32;
+/// Keeps hex tokens within the 64 characters allowed in the local part of email addresses.
+const LOWERCASE_TOKEN_BYTES: usize = 20;
+
+/// Generates a random, URL-safe secret token. Only its hash should ever be persisted.
+pub fn generate() -> SecretString {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    SecretString::from(BASE64_URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Like [`generate`], but made of lowercase hex digits, for places that do not preserve
+/// case, like the local part of email addresses.
+pub fn generate_lowercase() -> SecretString {
+    let mut bytes = [0u8; LOWERCASE_TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    SecretString::from(
+        bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>(),
+    )
+}
+
+pub fn hash(token: &SecretString) -> TokenHash {
+    let digest = Sha256::digest(token.expose_secret().as_bytes());
+    TokenHash::from(format!("{:x}", digest))
+}
+
+/// Signs tokens that carry their own payload, so that they can be checked without a lookup.
+/// Signed tokens look like `"{payload}.{signature}"`, with the base64url encoded
+/// HMAC-SHA256 of the payload as signature, so payloads must be URL-safe.
+pub struct Signer {
+    key: SecretString,
+}
+
+impl Signer {
+    pub fn new(key: SecretString) -> Self {
+        Self { key }
+    }
+
+    /// A signer with a key that only lives as long as the process, so its tokens stop
+    /// working on restart. Only meant for local development.
+    pub fn random() -> Self {
+        Self::new(generate())
+    }
+
+    pub fn sign(&self, payload: &str) -> SecretString {
+        let signature = BASE64_URL_SAFE_NO_PAD.encode(self.mac(payload).finalize().into_bytes());
+        SecretString::from(format!("{payload}.{signature}"))
+    }
+
+    /// The payload of the token, if it was signed with this signer's key.
+    pub fn verify<'a>(&self, token: &'a str) -> Option<&'a str> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let signature = BASE64_URL_SAFE_NO_PAD.decode(signature).ok()?;
+        self.mac(payload)
+            .verify_slice(&signature)
+            .ok()
+            .map(|_| payload)
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.expose_secret().as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
@@ -0,0 +1,115 @@
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use super::{WebhookDeliveryRepository, WebhookRepository};
+use crate::{
+    change_feed::ChangeFeedHandler,
+    events::{TodoChange, TodoEvent},
+    model::{TodoId, WebhookDelivery, WebhookEventType},
+};
+
+/// Names the lease the dispatchers of all instances share. Which of them queues a
+/// delivery does not matter, so they need not read the change feed each on their own.
+pub const WEBHOOK_LEASE_ID: &str = "webhooks";
+
+/// The fields of the event data that decide which webhook events a todo event is.
+#[derive(Deserialize)]
+struct EventTodo {
+    id: TodoId,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// The request body sent to webhooks.
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    event_type: WebhookEventType,
+    created_at: chrono::DateTime<chrono::Utc>,
+    data: &'a RawValue,
+}
+
+/// Queues a delivery for every webhook subscribed to the todo events read from the change
+/// feed, see [`crate::change_feed::ChangeFeedProcessor`]. The feed is only checkpointed
+/// once the deliveries of its events are queued, so none are lost to a crash or a failed
+/// write. Deliveries of the same event have the same id, so events read again are still
+/// queued once.
+pub struct WebhookDispatcher<W, D> {
+    webhooks: W,
+    deliveries: D,
+    delivery_ttl: u64,
+}
+
+impl<W, D> WebhookDispatcher<W, D>
+where
+    W: WebhookRepository,
+    D: WebhookDeliveryRepository,
+{
+    pub fn new(webhooks: W, deliveries: D, delivery_ttl: u64) -> Self {
+        Self {
+            webhooks,
+            deliveries,
+            delivery_ttl,
+        }
+    }
+
+    /// Queues the deliveries of one todo event. Returns the number of queued deliveries.
+    #[tracing::instrument(name = "Dispatch todo event to webhooks", skip(self, event), fields(owner_id = %event.owner_id))]
+    pub async fn dispatch(&self, event: &TodoEvent) -> anyhow::Result<usize> {
+        let todo: EventTodo = serde_json::from_str(&event.data)?;
+        let (event_type, dedupe_key) = match event.change {
+            TodoChange::Created => (WebhookEventType::TodoCreated, todo.id.to_string()),
+            TodoChange::Deleted => (WebhookEventType::TodoDeleted, todo.id.to_string()),
+            // Every completion is an event of its own, later edits of a done todo are not.
+            TodoChange::Updated => match (todo.done, todo.completed_at) {
+                (true, Some(completed_at)) => (
+                    WebhookEventType::TodoCompleted,
+                    format!("{}@{}", todo.id, completed_at.to_rfc3339()),
+                ),
+                _ => return Ok(0),
+            },
+        };
+
+        let webhooks = self
+            .webhooks
+            .get_all_for_user(event.owner_id.clone())
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let data = RawValue::from_string(event.data.to_string())?;
+        let mut queued = 0;
+        for webhook in webhooks
+            .iter()
+            .filter(|webhook| webhook.is_subscribed_to(event_type))
+        {
+            let id = WebhookDelivery::id_for(webhook.id(), event_type, &dedupe_key);
+            let payload = serde_json::to_string(&WebhookPayload {
+                id: &id,
+                event_type,
+                created_at: chrono::Utc::now(),
+                data: &data,
+            })?;
+            let delivery =
+                WebhookDelivery::new(webhook, event_type, &dedupe_key, payload, self.delivery_ttl);
+            if self.deliveries.create_if_absent(delivery).await? {
+                queued += 1;
+            }
+        }
+
+        Ok(queued)
+    }
+}
+
+impl<W, D> ChangeFeedHandler for WebhookDispatcher<W, D>
+where
+    W: WebhookRepository,
+    D: WebhookDeliveryRepository,
+{
+    async fn handle(&self, event: TodoEvent) -> anyhow::Result<()> {
+        self.dispatch(&event).await.map(|_| ())
+    }
+}
@@ -0,0 +1,138 @@
+mod dispatcher;
+mod worker;
+
+pub use dispatcher::*;
+pub use worker::*;
+
+use std::net::IpAddr;
+
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::configuration::WebhookSettings;
+pub use crate::model::{
+    DeliveryStatus, UserId, Webhook, WebhookDelivery, WebhookEventType, WebhookId,
+};
+
+/// Like the change feed, implementations run on the actix runtime, which does not need
+/// `Send` futures.
+#[allow(async_fn_in_trait)]
+pub trait WebhookRepository {
+    fn get_all_for_user(
+        &self,
+        user_id: UserId,
+    ) -> impl StreamExt<Item = anyhow::Result<Webhook>> + '_;
+    async fn get_for_user_by_id(
+        &self,
+        user_id: UserId,
+        webhook_id: WebhookId,
+    ) -> anyhow::Result<Option<Webhook>>;
+    async fn create(&self, webhook: Webhook) -> anyhow::Result<()>;
+    async fn delete_for_user_by_id(
+        &self,
+        user_id: UserId,
+        webhook_id: WebhookId,
+    ) -> anyhow::Result<()>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait WebhookDeliveryRepository {
+    /// Queues the delivery, unless it has been queued before. Tells whether it was queued.
+    async fn create_if_absent(&self, delivery: WebhookDelivery) -> anyhow::Result<bool>;
+    /// Pending deliveries whose next attempt is due, across all users.
+    async fn get_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: i32,
+    ) -> anyhow::Result<Vec<WebhookDelivery>>;
+    /// Stores the delivery unless it changed since it was read, so that only one worker
+    /// attempts it. Tells whether it was stored.
+    async fn try_claim(&self, delivery: WebhookDelivery) -> anyhow::Result<bool>;
+    async fn save(&self, delivery: WebhookDelivery) -> anyhow::Result<()>;
+    /// The delivery log of the webhook, newest first.
+    async fn get_latest_for_webhook(
+        &self,
+        user_id: UserId,
+        webhook_id: WebhookId,
+        limit: i32,
+    ) -> anyhow::Result<Vec<WebhookDelivery>>;
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()>;
+}
+
+/// The hex encoded HMAC-SHA256 of `"{timestamp}.{body}"` keyed with the webhook secret,
+/// which receivers compare against the `v1` part of the `X-Webhook-Signature` header.
+/// Signing the timestamp lets receivers reject replayed deliveries.
+pub fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Checks a URL before webhooks are registered for it or events are sent to it. Host
+/// names are checked again when they are resolved, see [`PublicAddressResolver`].
+pub fn check_url(url: &str, settings: &WebhookSettings) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(url).map_err(|_| "The URL is not valid".to_string())?;
+    match url.scheme() {
+        "https" => {}
+        "http" if !settings.require_https => {}
+        _ => return Err("The URL must use HTTPS".to_string()),
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("The URL may not contain credentials".to_string());
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| "The URL must have a host".to_string())?;
+    // IPv6 addresses are enclosed in brackets in URLs.
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>();
+    if let Ok(ip) = ip {
+        if !settings.allow_private_networks && !is_public(ip) {
+            return Err("The URL may not point at a private network".to_string());
+        }
+    }
+
+    Ok(url)
+}
+
+/// Whether the address is reachable over the internet, rather than belonging to the
+/// host, the local network or a reserved range.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // Shared address space of carrier-grade NAT, 100.64.0.0/10.
+                || (a == 100 && (b & 0xc0) == 64)
+                // Reserved for future use, 240.0.0.0/4.
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(ip));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local addresses, fc00::/7.
+                || (first & 0xfe00) == 0xfc00
+                // Link-local addresses, fe80::/10.
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
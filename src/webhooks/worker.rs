@@ -0,0 +1,184 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, redirect,
+};
+
+use super::{check_url, is_public, signature, WebhookDeliveryRepository, WebhookRepository};
+use crate::{configuration::WebhookSettings, model::WebhookDelivery};
+
+/// How many due deliveries a poll picks up at most.
+const BATCH_SIZE: i32 = 50;
+/// Retries wait at most this long, however many attempts failed before.
+const MAX_BACKOFF_SECONDS: u64 = 6 * 60 * 60;
+
+/// Sends the queued deliveries to their webhooks. Every instance runs a worker, they
+/// claim deliveries before attempting them so that each attempt is made once.
+pub struct WebhookWorker<W, D> {
+    webhooks: W,
+    deliveries: D,
+    client: reqwest::Client,
+    settings: WebhookSettings,
+}
+
+impl<W, D> WebhookWorker<W, D>
+where
+    W: WebhookRepository,
+    D: WebhookDeliveryRepository,
+{
+    pub fn new(webhooks: W, deliveries: D, settings: WebhookSettings) -> anyhow::Result<Self> {
+        // Following redirects or proxies would get around the address checks.
+        let mut client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .timeout(Duration::from_secs(settings.timeout_seconds));
+        if !settings.allow_private_networks {
+            client = client.dns_resolver(Arc::new(PublicAddressResolver));
+        }
+
+        Ok(Self {
+            webhooks,
+            deliveries,
+            client: client.build()?,
+            settings,
+        })
+    }
+
+    /// Polls the queue until the process exits. Failed polls are retried on the next tick.
+    pub async fn run(self) {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.settings.poll_interval_ms));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll().await {
+                tracing::error!(error = ?e, "Failed to deliver webhooks");
+            }
+        }
+    }
+
+    /// Attempts the deliveries that are due. Returns the number of attempts made.
+    #[tracing::instrument(name = "Poll webhook delivery queue", skip(self))]
+    pub async fn poll(&self) -> anyhow::Result<usize> {
+        let now = chrono::Utc::now();
+        let due = self.deliveries.get_due(now, BATCH_SIZE).await?;
+
+        // A claimed delivery is hidden from other workers until its attempt timed out,
+        // after which it is retried should this instance have stopped halfway.
+        let locked_until =
+            now + chrono::Duration::seconds(2 * self.settings.timeout_seconds as i64 + 30);
+        let mut attempted = 0;
+        for mut delivery in due {
+            delivery.lock_until(locked_until);
+            if !self.deliveries.try_claim(delivery.clone()).await? {
+                continue;
+            }
+            self.attempt(&mut delivery).await?;
+            self.deliveries.save(delivery).await?;
+            attempted += 1;
+        }
+
+        Ok(attempted)
+    }
+
+    #[tracing::instrument(
+        name = "Attempt webhook delivery",
+        skip(self, delivery),
+        fields(delivery_id = %delivery.id(), webhook_id = %delivery.webhook_id())
+    )]
+    async fn attempt(&self, delivery: &mut WebhookDelivery) -> anyhow::Result<()> {
+        let Some(webhook) = self
+            .webhooks
+            .get_for_user_by_id(delivery.user_id(), delivery.webhook_id())
+            .await?
+        else {
+            delivery.fail(None, "The webhook was deleted".to_string(), None);
+            return Ok(());
+        };
+
+        let url = match check_url(webhook.url(), &self.settings) {
+            Ok(url) => url,
+            Err(e) => {
+                delivery.fail(None, e, None);
+                return Ok(());
+            }
+        };
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let result = self
+            .client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::USER_AGENT, "todo-app-webhooks")
+            .header("X-Webhook-Id", delivery.id())
+            .header("X-Webhook-Event", delivery.event_type().as_str())
+            .header(
+                "X-Webhook-Signature",
+                format!(
+                    "t={},v1={}",
+                    timestamp,
+                    signature(webhook.secret(), timestamp, delivery.payload())
+                ),
+            )
+            .body(delivery.payload().to_string())
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                delivery.succeed(response.status().as_u16());
+            }
+            Ok(response) => {
+                let backoff = self.backoff(delivery);
+                delivery.fail(
+                    Some(response.status().as_u16()),
+                    format!("The receiver responded with {}", response.status()),
+                    backoff,
+                );
+            }
+            Err(e) => {
+                let backoff = self.backoff(delivery);
+                delivery.fail(None, format!("{:#}", anyhow::Error::from(e)), backoff);
+            }
+        }
+        tracing::info!(status = ?delivery.status(), attempts = delivery.attempts(), "Attempted webhook delivery");
+
+        Ok(())
+    }
+
+    /// The wait before the next attempt, doubling with every failed attempt, or `None`
+    /// when the failing attempt is the last one.
+    fn backoff(&self, delivery: &WebhookDelivery) -> Option<chrono::Duration> {
+        let failed_attempts = delivery.attempts() + 1;
+        if failed_attempts >= self.settings.max_attempts {
+            return None;
+        }
+        let seconds = self
+            .settings
+            .retry_base_seconds
+            .saturating_mul(2u64.saturating_pow(failed_attempts - 1))
+            .min(MAX_BACKOFF_SECONDS);
+        Some(chrono::Duration::seconds(seconds as i64))
+    }
+}
+
+/// Resolves host names like the system resolver, but fails for names with addresses that
+/// are not public, so that webhooks cannot be pointed at internal services by name.
+pub struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .collect::<Vec<SocketAddr>>();
+            if addrs.is_empty() || addrs.iter().any(|addr| !is_public(addr.ip())) {
+                return Err(
+                    format!("{} does not resolve to a public address", name.as_str()).into(),
+                );
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
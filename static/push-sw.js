@@ -0,0 +1,22 @@
+// Shows the push messages of due todos, see templates/notifications.html.
+self.addEventListener("push", (event) => {
+    const message = event.data ? event.data.json() : {};
+    event.waitUntil(
+        self.registration.showNotification(message.title || "Todo due", {
+            body: message.body,
+            tag: message.tag,
+            data: { url: message.url || "/me/todos" },
+        }),
+    );
+});
+
+self.addEventListener("notificationclick", (event) => {
+    event.notification.close();
+    const url = new URL(event.notification.data.url, self.location.origin).href;
+    event.waitUntil(
+        self.clients.matchAll({ type: "window" }).then((windows) => {
+            const open = windows.find((window) => window.url === url);
+            return open ? open.focus() : self.clients.openWindow(url);
+        }),
+    );
+});
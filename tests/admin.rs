@@ -0,0 +1,344 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use todo_app::configuration::RoleRule;
+
+const CSRF_TOKEN: &str = "admin-test";
+const CREATED_AT: &str = "2026-10-01T08:00:00Z";
+
+/// Collections holding documents of a user, with the field naming the user. Every user
+/// is seeded with one document per entry.
+const USER_DATA: [(&str, &str); 14] = [
+    ("users", "id"),
+    ("todos", "created_by"),
+    ("feed_tokens", "user_id"),
+    ("inbound_address_tokens", "user_id"),
+    ("app_passwords", "user_id"),
+    ("personal_access_tokens", "user_id"),
+    ("share_links", "user_id"),
+    ("idempotency_records", "user_id"),
+    ("list_members", "owner_id"),
+    ("webhooks", "user_id"),
+    ("webhook_deliveries", "user_id"),
+    ("notifications", "user_id"),
+    ("push_subscriptions", "user_id"),
+    ("list_members", "member_id"),
+];
+
+/// One document of each kind for the user, numbered from `n` so that ids do not clash
+/// between users.
+fn user_data(user_id: &str, n: u128) -> HashMap<&'static str, Vec<Value>> {
+    let id = |offset: u128| uuid::Uuid::from_u128(n + offset);
+    HashMap::from([
+        (
+            "users",
+            vec![json!({
+                "id": user_id,
+                "principal_name": format!("{}@example.com", user_id),
+                "first_seen_at": CREATED_AT,
+                "last_seen_at": CREATED_AT,
+            })],
+        ),
+        (
+            "todos",
+            vec![json!({
+                "id": id(0),
+                "content": "Paint the hall",
+                "done": false,
+                "created_by": user_id,
+                "created_at": CREATED_AT,
+            })],
+        ),
+        (
+            "feed_tokens",
+            vec![
+                json!({ "id": format!("feed-{}", n), "user_id": user_id, "created_at": CREATED_AT }),
+            ],
+        ),
+        (
+            "inbound_address_tokens",
+            vec![
+                json!({ "id": format!("inbound-{}", n), "user_id": user_id, "created_at": CREATED_AT }),
+            ],
+        ),
+        (
+            "app_passwords",
+            vec![json!({
+                "id": format!("app-password-{}", n),
+                "user_id": user_id,
+                "principal_name": format!("{}@example.com", user_id),
+                "name": "Phone",
+                "created_at": CREATED_AT,
+            })],
+        ),
+        (
+            "personal_access_tokens",
+            vec![json!({
+                "id": format!("token-{}", n),
+                "user_id": user_id,
+                "principal_name": format!("{}@example.com", user_id),
+                "name": "Kitchen display",
+                "scopes": ["read"],
+                "created_at": CREATED_AT,
+            })],
+        ),
+        (
+            "share_links",
+            vec![
+                json!({ "id": id(1), "user_id": user_id, "name": "Family", "created_at": CREATED_AT }),
+            ],
+        ),
+        (
+            "idempotency_records",
+            vec![json!({
+                "id": format!("idempotency-{}", n),
+                "user_id": user_id,
+                "request_hash": "hash",
+                "created_at": CREATED_AT,
+                "ttl": 86400,
+            })],
+        ),
+        (
+            "list_members",
+            vec![
+                json!({
+                    "id": id(2),
+                    "owner_id": user_id,
+                    "owner_name": format!("{}@example.com", user_id),
+                    "member_id": format!("{}-friend", user_id),
+                    "member_name": "friend@example.com",
+                    "role": "viewer",
+                    "created_at": CREATED_AT,
+                }),
+                json!({
+                    "id": id(3),
+                    "owner_id": format!("{}-friend", user_id),
+                    "owner_name": "friend@example.com",
+                    "member_id": user_id,
+                    "member_name": format!("{}@example.com", user_id),
+                    "role": "editor",
+                    "created_at": CREATED_AT,
+                }),
+            ],
+        ),
+        (
+            "webhooks",
+            vec![json!({
+                "id": id(4),
+                "user_id": user_id,
+                "url": "https://example.com/hook",
+                "event_types": ["todo.created"],
+                "secret": "secret",
+                "created_at": CREATED_AT,
+            })],
+        ),
+        (
+            "webhook_deliveries",
+            vec![json!({
+                "id": format!("delivery-{}", n),
+                "webhook_id": id(4),
+                "user_id": user_id,
+                "event_type": "todo.created",
+                "payload": "{}",
+                "status": "succeeded",
+                "attempts": 1,
+                "next_attempt_at": 1790000000,
+                "created_at": CREATED_AT,
+                "updated_at": CREATED_AT,
+                "ttl": 86400,
+            })],
+        ),
+        (
+            "notifications",
+            vec![json!({
+                "id": format!("notification-{}", n),
+                "user_id": user_id,
+                "kind": "reminder",
+                "created_at": CREATED_AT,
+                "ttl": 86400,
+            })],
+        ),
+        (
+            "push_subscriptions",
+            vec![json!({
+                "id": format!("push-{}", n),
+                "user_id": user_id,
+                "endpoint": "https://push.example.com/1",
+                "p256dh": "key",
+                "auth": "secret",
+                "created_at": CREATED_AT,
+            })],
+        ),
+    ])
+}
+
+struct TestApp {
+    address: String,
+    cosmos_url: String,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    /// Alice and Bob have data of every kind. The signed in developer is an admin when
+    /// `is_admin` is set.
+    fn spawn(is_admin: bool) -> Self {
+        let mut collections = user_data("alice", 100);
+        for (collection, documents) in user_data("bob", 200) {
+            collections.entry(collection).or_default().extend(documents);
+        }
+        collections.insert("audit_log", vec![]);
+        let cosmos = StubCosmos::spawn(collections);
+
+        let mut settings = common::test_settings(&cosmos.url);
+        if is_admin {
+            settings.authorization.admin = RoleRule {
+                email_domains: vec!["localhost".into()],
+                ..Default::default()
+            };
+        }
+
+        Self {
+            address: common::spawn_app(settings),
+            cosmos_url: cosmos.url.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn call(&self, method: Method, path: &str) -> StatusCode {
+        self.client
+            .request(method, format!("{}{}", self.address, path))
+            .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+            .header("X-CSRF-Token", CSRF_TOKEN)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .expect("Failed to execute request")
+            .status()
+    }
+
+    /// The documents of a collection as stored, read straight from the database.
+    async fn documents(&self, collection: &str) -> Vec<Value> {
+        let response = self
+            .client
+            .post(format!(
+                "{}dbs/todoappdb/colls/{}/docs",
+                self.cosmos_url, collection
+            ))
+            .header("x-ms-documentdb-isquery", "true")
+            .body(json!({ "query": "SELECT * FROM c", "parameters": [] }).to_string())
+            .send()
+            .await
+            .expect("Failed to query the database");
+        let body: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        body["Documents"].as_array().unwrap().clone()
+    }
+
+    async fn audited_actions(&self) -> Vec<String> {
+        self.documents("audit_log")
+            .await
+            .iter()
+            .map(|entry| entry["action"].as_str().unwrap().to_string())
+            .collect()
+    }
+}
+
+const ADMIN_ROUTES: [(&str, &str, &str); 5] = [
+    ("GET", "/admin", "view_dashboard"),
+    ("GET", "/admin/users", "list_users"),
+    ("GET", "/admin/users/alice/todos", "impersonate_user"),
+    ("DELETE", "/admin/users/alice", "delete_user_data"),
+    ("GET", "/admin/audit-log", "view_audit_log"),
+];
+
+#[actix_web::test]
+async fn non_admins_are_forbidden() {
+    let app = TestApp::spawn(false);
+
+    for (method, path, _) in ADMIN_ROUTES {
+        let status = app
+            .call(Method::from_bytes(method.as_bytes()).unwrap(), path)
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} {}", method, path);
+    }
+    assert!(app.audited_actions().await.is_empty());
+    assert_eq!(app.documents("todos").await.len(), 2);
+}
+
+#[actix_web::test]
+async fn every_admin_action_is_audited() {
+    let app = TestApp::spawn(true);
+
+    for (i, (method, path, action)) in ADMIN_ROUTES.into_iter().enumerate() {
+        let status = app
+            .call(Method::from_bytes(method.as_bytes()).unwrap(), path)
+            .await;
+        // The stub cannot list the containers whose usage the dashboard shows, actions
+        // are audited before they are carried out all the same.
+        assert!(
+            status.is_success() || path == "/admin",
+            "{} {}: {}",
+            method,
+            path,
+            status
+        );
+
+        let actions = app.audited_actions().await;
+        assert_eq!(actions.len(), i + 1, "{} {}", method, path);
+        assert!(
+            actions.iter().any(|audited| audited == action),
+            "{}",
+            action
+        );
+    }
+
+    let entries = app.documents("audit_log").await;
+    let deletion = entries
+        .iter()
+        .find(|entry| entry["action"] == "delete_user_data")
+        .unwrap();
+    assert_eq!(deletion["actor_id"], "local-user");
+    assert_eq!(deletion["target_user_id"], "alice");
+}
+
+#[actix_web::test]
+async fn deleting_a_user_removes_all_their_data() {
+    let app = TestApp::spawn(true);
+
+    let status = app.call(Method::DELETE, "/admin/users/alice").await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    for (collection, field) in USER_DATA {
+        let documents = app.documents(collection).await;
+        let owned_by = |user_id: &str| {
+            documents
+                .iter()
+                .filter(|document| document[field] == user_id)
+                .count()
+        };
+        assert_eq!(owned_by("alice"), 0, "{}.{}", collection, field);
+        assert_eq!(owned_by("bob"), 1, "{}.{}", collection, field);
+    }
+
+    // The audit log outlives the user.
+    assert_eq!(app.audited_actions().await, ["delete_user_data"]);
+}
+
+#[actix_web::test]
+async fn deleting_a_user_requires_a_csrf_token() {
+    let app = TestApp::spawn(true);
+
+    let status = app
+        .client
+        .delete(format!("{}/admin/users/alice", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request")
+        .status();
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(app.documents("todos").await.len(), 2);
+}
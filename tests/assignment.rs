@@ -0,0 +1,170 @@
+mod common;
+
+use std::{collections::HashMap, time::Duration};
+
+use common::StubCosmos;
+use futures::{SinkExt, StreamExt};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+
+const CSRF_TOKEN: &str = "assignment-test";
+
+fn todo_id() -> uuid::Uuid {
+    uuid::Uuid::from_u128(1)
+}
+
+struct TestApp {
+    address: String,
+    cosmos_url: String,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    /// The developer's list holds one todo and is shared with Bob. Carol has no access.
+    fn spawn() -> Self {
+        let cosmos = StubCosmos::spawn(HashMap::from([
+            (
+                "todos",
+                vec![json!({
+                    "id": todo_id(),
+                    "content": "Paint the hall",
+                    "done": false,
+                    "created_by": "local-user",
+                    "created_at": "2026-10-01T08:00:00Z",
+                })],
+            ),
+            (
+                "list_members",
+                vec![json!({
+                    "id": uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, b"local-user\nbob"),
+                    "owner_id": "local-user",
+                    "owner_name": "developer@localhost",
+                    "member_id": "bob",
+                    "member_name": "bob@example.com",
+                    "role": "viewer",
+                    "created_at": "2026-10-01T08:00:00Z",
+                })],
+            ),
+        ]));
+
+        Self {
+            address: common::spawn_app(common::test_settings(&cosmos.url)),
+            cosmos_url: cosmos.url.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn patch(&self, update: Value) -> (StatusCode, String) {
+        let response = self
+            .client
+            .patch(format!("{}/me/todos/{}", self.address, todo_id()))
+            .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+            .header("X-CSRF-Token", CSRF_TOKEN)
+            .header("Content-Type", "application/json")
+            .body(update.to_string())
+            .send()
+            .await
+            .expect("Failed to update todo");
+        (response.status(), response.text().await.unwrap())
+    }
+
+    /// The todo as stored, read straight from the database.
+    async fn stored_todo(&self) -> Value {
+        let response = self
+            .client
+            .post(format!("{}dbs/todoappdb/colls/todos/docs", self.cosmos_url))
+            .header("x-ms-documentdb-isquery", "true")
+            .body(json!({ "query": "SELECT * FROM c", "parameters": [] }).to_string())
+            .send()
+            .await
+            .expect("Failed to query the database");
+        let body: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        body["Documents"][0].clone()
+    }
+}
+
+#[actix_web::test]
+async fn todos_are_assigned_to_users_with_access() {
+    let app = TestApp::spawn();
+
+    let (status, _) = app.patch(json!({ "assignee": "bob" })).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let todo = app.stored_todo().await;
+    assert_eq!(todo["assignee"], "bob");
+    assert_eq!(todo["assignment_history"][0]["changed_by"], "local-user");
+
+    // The owner has access to their own list.
+    let (status, _) = app.patch(json!({ "assignee": "local-user" })).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(app.stored_todo().await["assignee"], "local-user");
+
+    let (status, _) = app.patch(json!({ "assignee": null })).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let todo = app.stored_todo().await;
+    assert!(todo["assignee"].is_null());
+    assert_eq!(todo["assignment_history"].as_array().unwrap().len(), 3);
+}
+
+#[actix_web::test]
+async fn assigning_to_users_without_access_is_refused() {
+    let app = TestApp::spawn();
+    app.patch(json!({ "assignee": "bob" })).await;
+
+    let (status, problem) = app
+        .patch(json!({ "assignee": "carol", "done": true }))
+        .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(problem.contains("assignee"), "{}", problem);
+    let todo = app.stored_todo().await;
+    assert_eq!(todo["assignee"], "bob");
+    assert_eq!(todo["done"], false);
+}
+
+#[actix_web::test]
+async fn assigning_to_users_without_access_is_refused_on_the_socket() {
+    let app = TestApp::spawn();
+    let url = format!("{}/me/todos/ws", app.address.replacen("http", "ws", 1));
+    let (mut socket, _) = tokio_tungstenite::connect_async(url)
+        .await
+        .expect("Failed to open socket");
+
+    let mut send_update = async |request_id: &str, assignee: &str| {
+        let update = json!({
+            "type": "update",
+            "request_id": request_id,
+            "todo_id": todo_id(),
+            "assignee": assignee,
+        });
+        socket
+            .send(Message::text(update.to_string()))
+            .await
+            .expect("Failed to send message");
+
+        // The reply to the update, past the snapshot, presence and change messages.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let Message::Text(text) = socket.next().await.unwrap().unwrap() else {
+                    continue;
+                };
+                let message: Value = serde_json::from_str(&text).unwrap();
+                if ["ack", "error"].contains(&message["type"].as_str().unwrap()) {
+                    return message;
+                }
+            }
+        })
+        .await
+        .expect("No reply to the update")
+    };
+
+    let error = send_update("1", "carol").await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["request_id"], "1");
+    assert_eq!(error["status"], 400);
+    assert!(app.stored_todo().await["assignee"].is_null());
+
+    let ack = send_update("2", "bob").await;
+    assert_eq!(ack["type"], "ack");
+    assert_eq!(app.stored_todo().await["assignee"], "bob");
+}
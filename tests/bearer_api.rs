@@ -0,0 +1,189 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::{header, StatusCode};
+use serde_json::{json, Value};
+
+const TODO_ID: &str = "6f1f3bd4-4e4b-4bd4-9a3f-2d4f1e7b1c11";
+const PERSONAL_ACCESS_TOKEN: &str = "todo_pat_bearer-api-test";
+
+fn spawn_app() -> String {
+    let cosmos = StubCosmos::spawn(HashMap::from([
+        (
+            "personal_access_tokens",
+            vec![json!({
+                "id": "token-hash",
+                "user_id": "user-1",
+                "principal_name": "user@example.com",
+                "name": "CLI",
+                "scopes": ["read", "write"],
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+        (
+            "todos",
+            vec![json!({
+                "id": TODO_ID,
+                "content": "Paint the hall",
+                "done": false,
+                "created_by": "user-1",
+                "created_at": "2026-10-01T08:00:00Z",
+                "notes": "Buy **white** paint",
+            })],
+        ),
+    ]));
+
+    common::spawn_app(common::test_settings(&cosmos.url))
+}
+
+async fn get(address: &str, path: &str, accept: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}{}", address, path))
+        .bearer_auth(PERSONAL_ACCESS_TOKEN)
+        .header(header::ACCEPT, accept)
+        .send()
+        .await
+        .expect("Failed to send request")
+}
+
+#[actix_web::test]
+async fn personal_access_tokens_list_todos_as_json() {
+    let address = spawn_app();
+
+    let response = get(&address, "/me/todos", "application/json").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let todos = serde_json::from_str::<Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(todos[0]["id"], TODO_ID);
+    assert_eq!(todos[0]["content"], "Paint the hall");
+}
+
+#[actix_web::test]
+async fn personal_access_tokens_read_a_todo_with_its_raw_notes() {
+    let address = spawn_app();
+
+    let response = get(
+        &address,
+        &format!("/me/todos/{}", TODO_ID),
+        "application/json",
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let todo = serde_json::from_str::<Value>(&response.text().await.unwrap()).unwrap();
+    assert_eq!(todo["notes"], "Buy **white** paint");
+}
+
+#[actix_web::test]
+async fn bearer_requests_render_pages() {
+    let address = spawn_app();
+
+    for path in ["/me/todos".to_string(), format!("/me/todos/{}", TODO_ID)] {
+        let response = get(&address, &path, "text/html").await;
+
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", path);
+        assert!(
+            response.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/html"),
+            "GET {}",
+            path
+        );
+        // The throwaway token is not kept in a cookie.
+        assert!(
+            response.headers().get(header::SET_COOKIE).is_none(),
+            "GET {}",
+            path
+        );
+    }
+}
+
+#[actix_web::test]
+async fn personal_access_tokens_are_refused_outside_the_todo_routes() {
+    let address = spawn_app();
+    let client = reqwest::Client::new();
+
+    for (method, path) in [
+        ("GET", "/me/tokens"),
+        ("POST", "/me/tokens"),
+        ("DELETE", "/me/tokens/some-token"),
+        ("GET", "/me/app-passwords"),
+        ("POST", "/me/app-passwords"),
+        ("GET", "/me/feed-token"),
+        ("POST", "/me/feed-token"),
+        ("GET", "/me/sharing"),
+        ("POST", "/me/sharing"),
+        ("GET", "/me/share-links"),
+        ("POST", "/me/share-links"),
+        ("GET", "/me/webhooks"),
+        ("POST", "/me/webhooks"),
+        ("DELETE", "/me/webhooks/some-webhook"),
+        ("GET", "/me/inbound-email-address"),
+        ("POST", "/me/inbound-email-address"),
+        ("GET", "/me/push-subscriptions"),
+        ("POST", "/me/push-subscriptions"),
+        ("DELETE", "/me/push-subscriptions/some-subscription"),
+        ("GET", "/me/notifications"),
+        // Only starts like the todo routes.
+        ("GET", "/me/todosettings"),
+    ] {
+        let response = client
+            .request(method.parse().unwrap(), format!("{}{}", address, path))
+            .bearer_auth(PERSONAL_ACCESS_TOKEN)
+            .form(&[("url", "https://example.com/hook")])
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(
+            response.status(),
+            StatusCode::FORBIDDEN,
+            "{} {}",
+            method,
+            path
+        );
+        assert!(
+            response.headers()[header::WWW_AUTHENTICATE]
+                .to_str()
+                .unwrap()
+                .contains("insufficient_scope"),
+            "{} {}",
+            method,
+            path
+        );
+    }
+}
+
+#[actix_web::test]
+async fn personal_access_tokens_are_accepted_on_the_todo_routes() {
+    let address = spawn_app();
+    let client = reqwest::Client::new();
+
+    for path in [
+        "/me/todos".to_string(),
+        format!("/me/todos/{}", TODO_ID),
+        "/me/todos/assigned".to_string(),
+        "/me/todos.ics".to_string(),
+        "/me/todos.md".to_string(),
+    ] {
+        let response = get(&address, &path, "application/json").await;
+        assert_eq!(response.status(), StatusCode::OK, "GET {}", path);
+    }
+
+    let response = client
+        .post(format!("{}/me/todos", address))
+        .bearer_auth(PERSONAL_ACCESS_TOKEN)
+        .header(header::ACCEPT, "application/json")
+        .form(&[("content", "Ship the release")])
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert!(
+        response.status().is_success(),
+        "POST /me/todos: {}",
+        response.status()
+    );
+}
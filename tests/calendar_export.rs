@@ -0,0 +1,105 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+/// The limit RFC 5545 puts on content lines, without their CRLF.
+const MAX_LINE_OCTETS: usize = 75;
+
+fn todo(id: u128, content: &str, tags: &[&str]) -> Value {
+    json!({
+        "id": uuid::Uuid::from_u128(id),
+        "content": content,
+        "done": false,
+        "created_by": "local-user",
+        "created_at": "2026-10-01T08:00:00Z",
+        "tags": tags,
+    })
+}
+
+/// Exports the todos of the signed in developer as they are sent, byte for byte.
+async fn export(todos: Vec<Value>) -> Vec<u8> {
+    let cosmos = StubCosmos::spawn(HashMap::from([("todos", todos)]));
+    let address = common::spawn_app(common::test_settings(&cosmos.url));
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/me/todos.ics", address))
+        .send()
+        .await
+        .expect("Failed to export todos");
+    assert_eq!(response.status(), StatusCode::OK);
+    response.bytes().await.unwrap().to_vec()
+}
+
+/// Joins folded lines back together.
+fn unfold(calendar: &str) -> String {
+    calendar.replace("\r\n ", "")
+}
+
+#[actix_web::test]
+async fn long_lines_are_folded_between_characters() {
+    // Multi-byte characters straddle every possible offset of the fold.
+    let contents = [
+        "a".repeat(200),
+        "ü".repeat(100),
+        "€".repeat(80),
+        format!("a{}", "🎨".repeat(60)),
+        format!("ab{}", "🎨".repeat(60)),
+    ];
+    let calendar = export(
+        contents
+            .iter()
+            .enumerate()
+            .map(|(i, content)| todo(i as u128, content, &[]))
+            .collect(),
+    )
+    .await;
+
+    let calendar = String::from_utf8(calendar).expect("Export is not UTF-8");
+    assert!(calendar.ends_with("\r\n"));
+    for line in calendar.trim_end_matches("\r\n").split("\r\n") {
+        assert!(
+            line.len() <= MAX_LINE_OCTETS,
+            "{} octets: {:?}",
+            line.len(),
+            line
+        );
+    }
+    assert!(calendar.contains("\r\n "));
+
+    let unfolded = unfold(&calendar);
+    for content in &contents {
+        assert!(unfolded.contains(&format!("SUMMARY:{}\r\n", content)));
+    }
+}
+
+#[actix_web::test]
+async fn short_lines_are_not_folded() {
+    let content = "a".repeat(MAX_LINE_OCTETS - "SUMMARY:".len());
+    let calendar = String::from_utf8(export(vec![todo(1, &content, &[])]).await).unwrap();
+
+    assert!(calendar.contains(&format!("\r\nSUMMARY:{}\r\n", content)));
+    assert!(!calendar.contains("\r\n "));
+}
+
+#[actix_web::test]
+async fn text_is_escaped() {
+    let calendar = export(vec![todo(
+        1,
+        "Buy milk, eggs; flour\\sugar\nand butter\r\n",
+        &["home, garden", "a;b"],
+    )])
+    .await;
+
+    let calendar = unfold(&String::from_utf8(calendar).unwrap());
+    assert!(
+        calendar.contains("\r\nSUMMARY:Buy milk\\, eggs\\; flour\\\\sugar\\nand butter\\n\r\n"),
+        "{}",
+        calendar
+    );
+    // Categories are separated by unescaped commas.
+    assert!(calendar.contains("\r\nCATEGORIES:home\\, garden,a\\;b\r\n"));
+}
@@ -0,0 +1,488 @@
+mod common;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use common::StubCosmos;
+use futures::FutureExt;
+use serde_json::{json, Value};
+use todo_app::{
+    change_feed::{
+        ChangeFeedHandler, ChangeFeedPage, ChangeFeedProcessor, ChangeFeedSource, Lease, LeaseStore,
+    },
+    configuration::ChangeFeedSettings,
+    events::{Received, TodoChange, TodoEvent, TodoEventBus, TodoEventReceiver},
+};
+
+const LEASE_ID: &str = "instance-1";
+const CSRF_TOKEN: &str = "change-feed-test";
+const PAGE_SIZE: usize = 2;
+
+/// Documents of each partition key range with the log sequence number of their last write.
+type Ranges = BTreeMap<String, Vec<(u64, Value)>>;
+
+/// Keeps the latest version of every document per partition key range, tagged with the
+/// log sequence number of its last write, like the latest-version change feed.
+#[derive(Clone, Default)]
+struct FakeChangeFeed {
+    ranges: Rc<RefCell<Ranges>>,
+    lsn: Rc<Cell<u64>>,
+}
+
+impl FakeChangeFeed {
+    fn with_ranges(range_ids: &[&str]) -> Self {
+        let feed = Self::default();
+        for range_id in range_ids {
+            feed.ranges
+                .borrow_mut()
+                .insert(range_id.to_string(), Vec::new());
+        }
+        feed
+    }
+
+    fn write(&self, range_id: &str, document: Value) {
+        self.lsn.set(self.lsn.get() + 1);
+        let mut ranges = self.ranges.borrow_mut();
+        let documents = ranges
+            .get_mut(range_id)
+            .expect("Unknown partition key range");
+        documents.retain(|(_, existing)| existing["id"] != document["id"]);
+        documents.push((self.lsn.get(), document));
+    }
+
+    fn split(&self, range_id: &str, into: &[&str]) {
+        let mut ranges = self.ranges.borrow_mut();
+        ranges.remove(range_id);
+        for child_id in into {
+            ranges.insert(child_id.to_string(), Vec::new());
+        }
+    }
+}
+
+impl ChangeFeedSource for FakeChangeFeed {
+    async fn partition_key_ranges(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self.ranges.borrow().keys().cloned().collect())
+    }
+
+    async fn read(
+        &self,
+        partition_key_range_id: &str,
+        continuation: Option<&str>,
+    ) -> anyhow::Result<ChangeFeedPage> {
+        let Some(continuation) = continuation else {
+            return Ok(ChangeFeedPage {
+                documents: Vec::new(),
+                continuation: Some(self.lsn.get().to_string()),
+            });
+        };
+
+        let after: u64 = continuation.parse()?;
+        let ranges = self.ranges.borrow();
+        let changes = ranges[partition_key_range_id]
+            .iter()
+            .filter(|(lsn, _)| *lsn > after)
+            .take(PAGE_SIZE)
+            .collect::<Vec<_>>();
+
+        Ok(ChangeFeedPage {
+            documents: changes
+                .iter()
+                .map(|(_, document)| document.clone())
+                .collect(),
+            continuation: changes.last().map(|(lsn, _)| lsn.to_string()),
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+struct FakeLeaseStore {
+    leases: Rc<RefCell<BTreeMap<String, Lease>>>,
+}
+
+impl FakeLeaseStore {
+    fn continuations(&self) -> BTreeMap<String, String> {
+        self.leases.borrow()[LEASE_ID].continuations.clone()
+    }
+}
+
+impl LeaseStore for FakeLeaseStore {
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Lease>> {
+        Ok(self.leases.borrow().get(id).cloned())
+    }
+
+    async fn save(&self, lease: Lease) -> anyhow::Result<()> {
+        self.leases.borrow_mut().insert(lease.id.clone(), lease);
+        Ok(())
+    }
+}
+
+/// Subscriptions to the lists of all users in the tests.
+struct Subscriptions(Vec<TodoEventReceiver>);
+
+impl Subscriptions {
+    fn to(event_bus: &TodoEventBus) -> Self {
+        Self(
+            ["alice", "bob"]
+                .into_iter()
+                .map(|owner| event_bus.subscribe(&serde_json::from_value(json!(owner)).unwrap()))
+                .collect(),
+        )
+    }
+}
+
+fn processor(
+    feed: &FakeChangeFeed,
+    leases: &FakeLeaseStore,
+) -> (
+    ChangeFeedProcessor<FakeChangeFeed, FakeLeaseStore, Arc<TodoEventBus>>,
+    Subscriptions,
+) {
+    let event_bus = Arc::new(TodoEventBus::with_change_feed());
+    let receiver = Subscriptions::to(&event_bus);
+    let processor = ChangeFeedProcessor::new(
+        LEASE_ID.to_string(),
+        feed.clone(),
+        leases.clone(),
+        event_bus,
+    );
+    (processor, receiver)
+}
+
+/// Records the contents of the todos it is handed, failing once on the one with
+/// `fail_on` as content.
+#[derive(Clone, Default)]
+struct FlakyHandler {
+    fail_on: Rc<RefCell<Option<String>>>,
+    handled: Rc<RefCell<Vec<String>>>,
+}
+
+impl ChangeFeedHandler for FlakyHandler {
+    async fn handle(&self, event: TodoEvent) -> anyhow::Result<()> {
+        let todo: Value = serde_json::from_str(&event.data)?;
+        let content = todo["content"].as_str().unwrap().to_string();
+        if self.fail_on.borrow().as_ref() == Some(&content) {
+            self.fail_on.take();
+            anyhow::bail!("Failed to handle {content}");
+        }
+        self.handled.borrow_mut().push(content);
+        Ok(())
+    }
+}
+
+fn todo(
+    id: u128,
+    owner: &str,
+    content: &str,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+) -> Value {
+    json!({
+        "id": uuid::Uuid::from_u128(id),
+        "content": content,
+        "done": false,
+        "created_by": owner,
+        "created_at": created_at,
+        "updated_at": updated_at,
+        "_lsn": 1,
+        "_ts": 1790000000,
+    })
+}
+
+/// A todo created just now, and not edited since.
+fn new_todo(id: u128, owner: &str, content: &str) -> Value {
+    todo(id, owner, content, Utc::now(), None)
+}
+
+/// A todo created long before the previous read of the feed, and edited just now.
+fn edited_todo(id: u128, owner: &str, content: &str) -> Value {
+    todo(
+        id,
+        owner,
+        content,
+        Utc::now() - TimeDelta::hours(1),
+        Some(Utc::now()),
+    )
+}
+
+/// The events received so far, list by list.
+fn received(subscriptions: &mut Subscriptions) -> Vec<(String, TodoChange, Value)> {
+    let mut events = Vec::new();
+    for receiver in &mut subscriptions.0 {
+        while let Some(received) = receiver.recv().now_or_never() {
+            match received {
+                Some(Received::Event(event)) => events.push((
+                    serde_json::to_value(&event.owner_id)
+                        .unwrap()
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                    event.change,
+                    serde_json::from_str(&event.data).unwrap(),
+                )),
+                other => panic!("Unexpected receive: {other:?}"),
+            }
+        }
+    }
+    events
+}
+
+#[actix_web::test]
+async fn first_poll_starts_at_the_current_end_of_the_feed() {
+    let feed = FakeChangeFeed::with_ranges(&["0", "1"]);
+    feed.write(
+        "0",
+        new_todo(1, "alice", "Written before the instance started"),
+    );
+    let leases = FakeLeaseStore::default();
+    let (processor, mut receiver) = processor(&feed, &leases);
+
+    assert_eq!(processor.poll().await.unwrap(), 0);
+    assert!(received(&mut receiver).is_empty());
+    assert_eq!(
+        leases.continuations(),
+        BTreeMap::from([
+            ("0".to_string(), "1".to_string()),
+            ("1".to_string(), "1".to_string())
+        ])
+    );
+
+    feed.write("1", new_todo(2, "bob", "Written afterwards"));
+
+    assert_eq!(processor.poll().await.unwrap(), 1);
+    let events = received(&mut receiver);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, "bob");
+    assert_eq!(events[0].1, TodoChange::Created);
+    assert_eq!(events[0].2["content"], "Written afterwards");
+}
+
+#[actix_web::test]
+async fn pages_are_read_again_until_their_events_are_handled() {
+    let feed = FakeChangeFeed::with_ranges(&["0"]);
+    let leases = FakeLeaseStore::default();
+    let handler = FlakyHandler::default();
+    let processor = ChangeFeedProcessor::new(
+        LEASE_ID.to_string(),
+        feed.clone(),
+        leases.clone(),
+        handler.clone(),
+    );
+    processor.poll().await.unwrap();
+    let checkpoint = leases.continuations();
+
+    feed.write("0", new_todo(1, "alice", "First"));
+    feed.write("0", new_todo(2, "alice", "Second"));
+    handler.fail_on.replace(Some("Second".to_string()));
+
+    assert!(processor.poll().await.is_err());
+    assert_eq!(leases.continuations(), checkpoint);
+
+    assert_eq!(processor.poll().await.unwrap(), 2);
+    assert_eq!(*handler.handled.borrow(), ["First", "First", "Second"]);
+    assert_ne!(leases.continuations(), checkpoint);
+}
+
+#[actix_web::test]
+async fn changes_are_republished_once_across_pages_and_restarts() {
+    let feed = FakeChangeFeed::with_ranges(&["0"]);
+    let leases = FakeLeaseStore::default();
+    let (processor, mut receiver) = processor(&feed, &leases);
+    processor.poll().await.unwrap();
+
+    for id in 1..=5 {
+        feed.write("0", new_todo(id, "alice", &format!("Todo {id}")));
+    }
+    assert_eq!(processor.poll().await.unwrap(), 5);
+    assert_eq!(received(&mut receiver).len(), 5);
+    assert_eq!(processor.poll().await.unwrap(), 0);
+
+    feed.write("0", edited_todo(3, "alice", "Todo 3, edited"));
+    let (restarted, mut receiver) = self::processor(&feed, &leases);
+
+    assert_eq!(restarted.poll().await.unwrap(), 1);
+    let events = received(&mut receiver);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].1, TodoChange::Updated);
+    assert_eq!(events[0].2["content"], "Todo 3, edited");
+    assert_eq!(leases.continuations()["0"], "6");
+}
+
+#[actix_web::test]
+async fn documents_that_are_not_todos_are_skipped() {
+    let feed = FakeChangeFeed::with_ranges(&["0"]);
+    let leases = FakeLeaseStore::default();
+    let (processor, mut receiver) = processor(&feed, &leases);
+    processor.poll().await.unwrap();
+
+    feed.write("0", json!({ "id": "not-a-todo" }));
+    feed.write("0", new_todo(1, "alice", "After the broken document"));
+
+    assert_eq!(processor.poll().await.unwrap(), 1);
+    assert_eq!(received(&mut receiver).len(), 1);
+    assert_eq!(leases.continuations()["0"], "2");
+}
+
+#[actix_web::test]
+async fn split_ranges_are_read_from_the_current_end() {
+    let feed = FakeChangeFeed::with_ranges(&["0"]);
+    let leases = FakeLeaseStore::default();
+    let (processor, mut receiver) = processor(&feed, &leases);
+    processor.poll().await.unwrap();
+
+    feed.split("0", &["1", "2"]);
+    processor.poll().await.unwrap();
+    assert_eq!(
+        leases.continuations().keys().collect::<Vec<_>>(),
+        vec!["1", "2"]
+    );
+
+    feed.write("2", new_todo(1, "alice", "Written after the split"));
+    assert_eq!(processor.poll().await.unwrap(), 1);
+    assert_eq!(received(&mut receiver).len(), 1);
+}
+
+#[actix_web::test]
+async fn creations_coalesced_with_updates_are_announced_as_both() {
+    let feed = FakeChangeFeed::with_ranges(&["0"]);
+    let leases = FakeLeaseStore::default();
+    let (processor, mut receiver) = processor(&feed, &leases);
+    processor.poll().await.unwrap();
+
+    // Created and edited again before the next read.
+    let created = new_todo(1, "alice", "Paint the hall");
+    let mut edited = created.clone();
+    edited["content"] = json!("Paint the hall white");
+    edited["updated_at"] = json!(Utc::now());
+    feed.write("0", created);
+    feed.write("0", edited);
+
+    assert_eq!(processor.poll().await.unwrap(), 2);
+    let events = received(&mut receiver)
+        .into_iter()
+        .map(|(_, change, todo)| (change, todo["content"].as_str().unwrap().to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        vec![
+            (TodoChange::Created, "Paint the hall white".to_string()),
+            (TodoChange::Updated, "Paint the hall white".to_string()),
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn tombstones_are_announced_as_deletions() {
+    let feed = FakeChangeFeed::with_ranges(&["0"]);
+    let leases = FakeLeaseStore::default();
+    let (processor, mut receiver) = processor(&feed, &leases);
+    processor.poll().await.unwrap();
+
+    let mut tombstone = edited_todo(1, "alice", "Paint the hall");
+    tombstone["deleted"] = json!(true);
+    tombstone["ttl"] = json!(604800);
+    feed.write("0", tombstone);
+
+    assert_eq!(processor.poll().await.unwrap(), 1);
+    assert_eq!(
+        received(&mut receiver),
+        vec![(
+            "alice".to_string(),
+            TodoChange::Deleted,
+            json!({ "id": uuid::Uuid::from_u128(1) })
+        )]
+    );
+}
+
+#[test]
+fn nothing_is_published_directly_with_a_change_feed() {
+    let event_bus = TodoEventBus::with_change_feed();
+    let mut receiver = Subscriptions::to(&event_bus);
+
+    for change in [
+        TodoChange::Created,
+        TodoChange::Updated,
+        TodoChange::Deleted,
+    ] {
+        event_bus.publish(TodoEvent {
+            owner_id: serde_json::from_value(json!("alice")).unwrap(),
+            change,
+            data: "{}".into(),
+            seq: 0,
+        });
+    }
+
+    assert!(received(&mut receiver).is_empty());
+}
+
+/// Starts an instance of the app reading the change feed of the shared database under a
+/// lease of its own.
+fn spawn_instance(cosmos: &StubCosmos, lease_id: &str) -> String {
+    let mut settings = common::test_settings(&cosmos.url);
+    settings.change_feed = ChangeFeedSettings {
+        enabled: true,
+        poll_interval_ms: 50,
+        lease_id: lease_id.to_string(),
+    };
+    common::spawn_app(settings)
+}
+
+#[actix_web::test]
+async fn deletions_reach_the_other_instances() {
+    let todo_id = uuid::Uuid::from_u128(1);
+    let cosmos = StubCosmos::spawn(HashMap::from([(
+        "todos",
+        vec![edited_todo(1, "local-user", "Paint the hall")],
+    )]));
+    let first = spawn_instance(&cosmos, "instance-1");
+    let second = spawn_instance(&cosmos, "instance-2");
+    let client = reqwest::Client::new();
+    let mut events = client
+        .get(format!("{}/me/todos/events", second))
+        .send()
+        .await
+        .expect("Failed to subscribe to events");
+    assert_eq!(events.status(), reqwest::StatusCode::OK);
+    // Instances start reading at the current end of the feed on their first poll.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let response = client
+        .delete(format!("{}/me/todos/{}", first, todo_id))
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .header("X-CSRF-Token", CSRF_TOKEN)
+        .send()
+        .await
+        .expect("Failed to delete todo");
+    assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+    let mut stream = String::new();
+    let expected = format!("event: deleted\ndata: {{\"id\":\"{}\"}}", todo_id);
+    let received = tokio::time::timeout(Duration::from_secs(5), async {
+        while !stream.contains(&expected) {
+            let chunk = events.chunk().await.unwrap().expect("Event stream ended");
+            stream.push_str(&String::from_utf8_lossy(&chunk));
+        }
+    })
+    .await;
+    assert!(
+        received.is_ok(),
+        "No deletion on the other instance: {}",
+        stream
+    );
+
+    let todos = client
+        .get(format!("{}/me/todos", second))
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .expect("Failed to list todos")
+        .text()
+        .await
+        .unwrap();
+    assert!(!todos.contains(&todo_id.to_string()), "{}", todos);
+}
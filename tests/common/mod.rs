@@ -0,0 +1,375 @@
+use std::{collections::HashMap, net::TcpListener, sync::Mutex};
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use serde_json::{json, Value};
+use todo_app::{configuration::Settings, startup};
+
+/// Settings for starting the app in tests, independent of `configuration.yml`. The
+/// database is the one at `cosmos_endpoint`, e.g. a stub or a closed port.
+pub fn test_settings(cosmos_endpoint: &str) -> Settings {
+    let yaml = format!(
+        r#"
+environment: local
+cosmos:
+  account: test
+  primary_key: dGVzdC1rZXk=
+  database_name: todoappdb
+  endpoint: "{}"
+telemetry:
+  log_level: info
+  app_insights_connection_string: test
+auth:
+  mode: development
+  principal_id: local-user
+  principal_name: developer@localhost
+limits:
+  reads:
+    burst: 1000
+    per_second: 100.0
+  writes:
+    burst: 1000
+    per_second: 100.0
+"#,
+        cosmos_endpoint
+    );
+
+    config::Config::builder()
+        .add_source(config::File::from_str(&yaml, config::FileFormat::Yaml))
+        .build()
+        .and_then(|config| config.try_deserialize())
+        .expect("Failed to build test settings")
+}
+
+/// Starts the app as `startup::init` registers it and returns its address.
+pub fn spawn_app(settings: Settings) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let port = listener.local_addr().unwrap().port();
+    let server = startup::init(settings, listener).expect("Failed to start server");
+    actix_web::rt::spawn(server);
+
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// Answers the Cosmos DB REST calls the app makes from documents kept per collection,
+/// seeded with fixed ones. Queries return the documents matching the equality conditions
+/// of their `WHERE` clause, or their number for `SELECT VALUE COUNT(1)`. Point reads find
+/// documents by id, except that the only personal access token is found under any id, as
+/// ids are hashes of the tokens. Writes are kept, stamped with the time in `_ts` and
+/// numbered by a log sequence number, and the change feed of a collection lists the
+/// documents written after the one a client has read to, in a single partition key range.
+pub struct StubCosmos {
+    pub url: String,
+}
+
+/// Documents of every collection with the log sequence number of their last write.
+#[derive(Default)]
+struct Store {
+    collections: HashMap<String, Vec<(u64, Value)>>,
+    lsn: u64,
+}
+
+impl Store {
+    /// Keeps the document and returns it as stored.
+    fn write(&mut self, collection: &str, mut document: Value) -> Value {
+        self.lsn += 1;
+        document["_ts"] = json!(chrono::Utc::now().timestamp());
+        let documents = self.collections.entry(collection.to_string()).or_default();
+        documents.retain(|(_, existing)| existing["id"] != document["id"]);
+        documents.push((self.lsn, document.clone()));
+        document
+    }
+
+    fn documents(&self, collection: &str) -> impl Iterator<Item = &(u64, Value)> {
+        self.collections.get(collection).into_iter().flatten()
+    }
+}
+
+impl StubCosmos {
+    pub fn spawn(collections: HashMap<&'static str, Vec<Value>>) -> Self {
+        let mut store = Store::default();
+        for (collection, documents) in collections {
+            for document in documents {
+                store.write(collection, document);
+            }
+        }
+        let store = web::Data::new(Mutex::new(store));
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(store.clone())
+                .default_service(web::to(handle))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("Failed to bind stub Cosmos DB");
+
+        let port = server.addrs()[0].port();
+        tokio::spawn(server.run());
+
+        Self {
+            url: format!("http://127.0.0.1:{}/", port),
+        }
+    }
+}
+
+async fn handle(
+    req: HttpRequest,
+    body: web::Bytes,
+    store: web::Data<Mutex<Store>>,
+) -> HttpResponse {
+    // dbs/{database}/colls/{collection}/{docs[/{id}]|pkranges}
+    let segments = req.path().trim_matches('/').split('/').collect::<Vec<_>>();
+    let collection = segments.get(3).copied().unwrap_or_default();
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim_matches('"').to_string())
+    };
+    let mut store = store.lock().unwrap();
+    let lsn = store.lsn;
+
+    if segments.get(4) == Some(&"pkranges") {
+        let range = json!({
+            "_rid": "stub",
+            "id": "0",
+            "_etag": "\"0\"",
+            "minInclusive": "",
+            "maxExclusive": "FF",
+            "ridPrefix": 0,
+            "_self": "stub",
+            "throughputFraction": 1.0,
+            "status": "online",
+            "_ts": 0,
+        });
+        return cosmos_response(HttpResponse::Ok(), 1, lsn)
+            .insert_header(("server", "stub"))
+            .json(json!({ "_rid": "stub", "PartitionKeyRanges": [range], "_count": 1 }));
+    }
+
+    if req.headers().contains_key("a-im") {
+        let Some(after) = header("if-none-match") else {
+            return cosmos_response(HttpResponse::Ok(), 0, lsn)
+                .json(json!({ "_rid": "stub", "Documents": [], "_count": 0 }));
+        };
+        let after = after.parse::<u64>().unwrap_or_default();
+        let max_items = header("x-ms-max-item-count")
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(usize::MAX);
+        let mut changes = store
+            .documents(collection)
+            .filter(|(document_lsn, _)| *document_lsn > after)
+            .cloned()
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|(document_lsn, _)| *document_lsn);
+        changes.truncate(max_items);
+        if changes.is_empty() {
+            return cosmos_response(HttpResponse::NotModified(), 0, lsn).finish();
+        }
+
+        let count = changes.len();
+        let documents = changes
+            .into_iter()
+            .map(|(document_lsn, document)| {
+                let mut document = with_attributes(document);
+                document["_lsn"] = json!(document_lsn);
+                document
+            })
+            .collect::<Vec<_>>();
+        return cosmos_response(HttpResponse::Ok(), count, lsn).json(json!({
+            "_rid": "stub",
+            "Documents": documents,
+            "_count": count,
+        }));
+    }
+
+    if req.headers().contains_key("x-ms-documentdb-isquery") {
+        let query = serde_json::from_slice(&body).unwrap_or_else(|_| json!({}));
+        let documents = store
+            .documents(collection)
+            .map(|(_, document)| document)
+            .filter(|document| matches_query(document, &query))
+            .cloned()
+            .collect::<Vec<_>>();
+        if query["query"]
+            .as_str()
+            .is_some_and(|statement| statement.starts_with("SELECT VALUE COUNT(1)"))
+        {
+            return cosmos_response(HttpResponse::Ok(), 1, lsn).json(json!({
+                "_rid": "stub",
+                "Documents": [documents.len()],
+                "_count": 1,
+            }));
+        }
+        let count = documents.len();
+        return cosmos_response(HttpResponse::Ok(), count, lsn).json(json!({
+            "_rid": "stub",
+            "Documents": documents.into_iter().map(with_attributes).collect::<Vec<_>>(),
+            "_count": count,
+        }));
+    }
+
+    let found = |id: &str| {
+        store
+            .documents(collection)
+            .map(|(_, document)| document)
+            .find(|document| document["id"] == id || collection == "personal_access_tokens")
+            .cloned()
+    };
+    match (req.method().as_str(), segments.get(5)) {
+        ("GET", Some(id)) => match found(id) {
+            Some(document) => {
+                cosmos_response(HttpResponse::Ok(), 1, lsn).json(with_attributes(document))
+            }
+            None => not_found(lsn),
+        },
+        ("DELETE", Some(id)) => {
+            if found(id).is_none() {
+                return not_found(lsn);
+            }
+            if let Some(documents) = store.collections.get_mut(collection) {
+                documents.retain(|(_, document)| document["id"] != *id);
+            }
+            cosmos_response(HttpResponse::NoContent(), 0, lsn).finish()
+        }
+        (method, _) => {
+            let document = serde_json::from_slice::<Value>(&body).unwrap_or_else(|_| json!({}));
+            let is_upsert = method == "PUT"
+                || header("x-ms-documentdb-is-upsert").is_some_and(|upsert| upsert == "true");
+            let exists = store
+                .documents(collection)
+                .any(|(_, existing)| existing["id"] == document["id"]);
+            if exists && !is_upsert {
+                return cosmos_response(HttpResponse::Conflict(), 0, lsn)
+                    .json(json!({ "code": "Conflict", "message": "Entity already exists" }));
+            }
+
+            let document = store.write(collection, document);
+            cosmos_response(HttpResponse::Created(), 1, store.lsn).json(with_attributes(document))
+        }
+    }
+}
+
+fn not_found(lsn: u64) -> HttpResponse {
+    cosmos_response(HttpResponse::NotFound(), 0, lsn)
+        .json(json!({ "code": "NotFound", "message": "Entity not found" }))
+}
+
+/// Applies the `field = value`, `LOWER(field) = value` and `NOT IS_DEFINED(field)`
+/// conditions of a query, other conditions match anything.
+fn matches_query(document: &Value, query: &Value) -> bool {
+    let statement = query["query"].as_str().unwrap_or_default();
+    let statement = statement.split(" ORDER BY ").next().unwrap_or_default();
+    let Some((_, conditions)) = statement.split_once(" WHERE ") else {
+        return true;
+    };
+
+    conditions.split(" AND ").all(|condition| {
+        if let Some(field) = condition
+            .trim()
+            .strip_prefix("NOT IS_DEFINED(")
+            .and_then(|field| field.strip_suffix(')'))
+            .and_then(|field| field.split_once('.'))
+            .map(|(_, field)| field)
+        {
+            return document
+                .pointer(&format!("/{}", field.replace('.', "/")))
+                .is_none();
+        }
+        let Some((field, value)) = condition.split_once(" = ") else {
+            return true;
+        };
+        let (field, lowercase) = match field
+            .trim()
+            .strip_prefix("LOWER(")
+            .and_then(|field| field.strip_suffix(')'))
+        {
+            Some(field) => (field, true),
+            None => (field.trim(), false),
+        };
+        let Some((_, field)) = field.split_once('.') else {
+            return true;
+        };
+        if field.contains('(') || field.contains(')') {
+            return true;
+        }
+
+        let value = value.trim();
+        let expected = if value.starts_with('@') {
+            query["parameters"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|parameter| parameter["name"] == value)
+                .map(|parameter| parameter["value"].clone())
+                .unwrap_or_default()
+        } else if let Some(text) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            Value::from(text)
+        } else {
+            serde_json::from_str(value).unwrap_or_default()
+        };
+        let mut actual = document
+            .pointer(&format!("/{}", field.replace('.', "/")))
+            .cloned()
+            .unwrap_or_default();
+        if let (true, Some(text)) = (lowercase, actual.as_str()) {
+            actual = Value::from(text.to_lowercase());
+        }
+        actual == expected
+    })
+}
+
+fn with_attributes(mut document: Value) -> Value {
+    let attributes = json!({
+        "_rid": "stub",
+        "_self": "stub",
+        "_etag": "\"0\"",
+        "_attachments": "attachments/",
+    });
+    for (name, value) in attributes.as_object().unwrap() {
+        document[name] = value.clone();
+    }
+    document
+}
+
+/// The headers the Cosmos DB SDK requires on every response.
+fn cosmos_response(
+    mut response: actix_web::HttpResponseBuilder,
+    item_count: usize,
+    lsn: u64,
+) -> actix_web::HttpResponseBuilder {
+    let now = chrono::Utc::now();
+    for (name, value) in [
+        ("date", now.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+        (
+            "x-ms-last-state-change-utc",
+            now.format("%a, %d %b %Y %H:%M:%S%.3f GMT").to_string(),
+        ),
+        ("etag", "\"0\"".to_string()),
+        ("x-ms-activity-id", uuid::Uuid::new_v4().to_string()),
+        ("x-ms-session-token", "0:1#1".to_string()),
+        ("x-ms-request-charge", "1".to_string()),
+        ("x-ms-item-count", item_count.to_string()),
+        ("x-ms-resource-quota", "documentSize=10240;".to_string()),
+        ("x-ms-resource-usage", "documentSize=0;".to_string()),
+        ("x-ms-schemaversion", "1.0".to_string()),
+        ("x-ms-serviceversion", "1.0".to_string()),
+        ("x-ms-gatewayversion", "1.0".to_string()),
+        ("x-ms-content-path", "stub".to_string()),
+        ("x-ms-alt-content-path", "stub".to_string()),
+        ("x-ms-xp-role", "1".to_string()),
+        ("x-ms-number-of-read-regions", "0".to_string()),
+        ("lsn", lsn.to_string()),
+        ("x-ms-global-committed-lsn", "1".to_string()),
+        ("x-ms-item-lsn", "1".to_string()),
+        ("x-ms-transport-request-id", "1".to_string()),
+        ("x-ms-cosmos-llsn", "1".to_string()),
+        ("x-ms-cosmos-item-llsn", "1".to_string()),
+        ("x-ms-quorum-acked-lsn", lsn.to_string()),
+        ("x-ms-current-write-quorum", "1".to_string()),
+        ("x-ms-current-replica-set-size", "1".to_string()),
+        ("x-ms-cosmos-quorum-acked-llsn", lsn.to_string()),
+    ] {
+        response.insert_header((name, value));
+    }
+    response
+}
@@ -0,0 +1,356 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+const USERNAME: &str = "developer@localhost";
+const APP_PASSWORD: &str = "dav-test-app-password";
+const CALENDAR: &str = "/dav/calendars/todos/";
+const SEEDED: u128 = 1;
+
+fn todo(id: u128, owner: &str, content: &str) -> Value {
+    json!({
+        "id": uuid::Uuid::from_u128(id),
+        "content": content,
+        "done": false,
+        "created_by": owner,
+        "created_at": "2026-10-01T08:00:00Z",
+    })
+}
+
+fn seeded_href() -> String {
+    format!("{}{}.ics", CALENDAR, uuid::Uuid::from_u128(SEEDED))
+}
+
+fn vtodo(uid: &str, summary: &str, due: &str) -> String {
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//Test//EN\r\nBEGIN:VTODO\r\n\
+         UID:{uid}\r\nSUMMARY:{summary}\r\n{due}END:VTODO\r\nEND:VCALENDAR\r\n"
+    )
+}
+
+struct TestApp {
+    address: String,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    /// Signs in with an app password of the developer, whose list holds one todo next to
+    /// someone else's.
+    fn spawn() -> Self {
+        let app_password = json!({
+            "id": format!("{:x}", Sha256::digest(APP_PASSWORD.as_bytes())),
+            "user_id": "local-user",
+            "principal_name": USERNAME,
+            "name": "Phone",
+            "created_at": "2026-10-01T08:00:00Z",
+        });
+        let cosmos = StubCosmos::spawn(HashMap::from([
+            (
+                "todos",
+                vec![
+                    todo(SEEDED, "local-user", "Paint the hall"),
+                    todo(2, "someone-else", "Not on the calendar"),
+                ],
+            ),
+            ("app_passwords", vec![app_password]),
+        ]));
+
+        Self {
+            address: common::spawn_app(common::test_settings(&cosmos.url)),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> RequestBuilder {
+        self.client
+            .request(
+                Method::from_bytes(method.as_bytes()).unwrap(),
+                format!("{}{}", self.address, path),
+            )
+            .basic_auth(USERNAME, Some(APP_PASSWORD))
+    }
+
+    async fn send(&self, request: RequestBuilder) -> (StatusCode, String) {
+        let response = request.send().await.expect("Failed to execute request");
+        (response.status(), response.text().await.unwrap())
+    }
+
+    async fn put(&self, name: &str, body: String) -> StatusCode {
+        self.request("PUT", &format!("{}{}.ics", CALENDAR, name))
+            .header("Content-Type", "text/calendar")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to put calendar object")
+            .status()
+    }
+
+    async fn etag(&self, href: &str) -> String {
+        let response = self
+            .request("GET", href)
+            .send()
+            .await
+            .expect("Failed to get calendar object");
+        assert_eq!(response.status(), StatusCode::OK);
+        response.headers()["ETag"].to_str().unwrap().to_string()
+    }
+
+    async fn sync(&self, token: &str) -> (StatusCode, String) {
+        let body = format!(
+            r#"<?xml version="1.0"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>{token}</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop><D:getetag/></D:prop>
+</D:sync-collection>"#
+        );
+        self.send(self.request("REPORT", CALENDAR).body(body)).await
+    }
+}
+
+fn sync_token(multistatus: &str) -> String {
+    let start = multistatus.find("<D:sync-token>").expect("No sync token") + "<D:sync-token>".len();
+    let end = start + multistatus[start..].find('<').unwrap();
+    multistatus[start..end].to_string()
+}
+
+#[actix_web::test]
+async fn propfind_lists_the_todos_of_the_calendar() {
+    let app = TestApp::spawn();
+    let body = r#"<?xml version="1.0"?>
+<D:propfind xmlns:D="DAV:" xmlns:CS="http://calendarserver.org/ns/">
+  <D:prop><D:displayname/><D:getetag/><CS:getctag/></D:prop>
+</D:propfind>"#;
+
+    let (status, multistatus) = app
+        .send(
+            app.request("PROPFIND", CALENDAR)
+                .header("Depth", "1")
+                .body(body),
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(multistatus.contains("Todos"));
+    assert!(multistatus.contains("getctag"));
+    assert!(multistatus.contains(&seeded_href()));
+    assert!(!multistatus.contains(&uuid::Uuid::from_u128(2).to_string()));
+
+    let (status, multistatus) = app
+        .send(
+            app.request("PROPFIND", CALENDAR)
+                .header("Depth", "0")
+                .body(body),
+        )
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(!multistatus.contains(&seeded_href()));
+}
+
+#[actix_web::test]
+async fn puts_are_conditional_on_etags() {
+    let app = TestApp::spawn();
+    let href = format!("{}call-mum.ics", CALENDAR);
+
+    let status = app
+        .send(
+            app.request("PUT", &href)
+                .header("If-None-Match", "*")
+                .body(vtodo("call-mum", "Call mum", "")),
+        )
+        .await
+        .0;
+    assert_eq!(status, StatusCode::CREATED);
+    let status = app
+        .send(
+            app.request("PUT", &href)
+                .header("If-None-Match", "*")
+                .body(vtodo("call-mum", "Call dad", "")),
+        )
+        .await
+        .0;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+    let etag = app.etag(&href).await;
+    let status = app
+        .send(
+            app.request("PUT", &href)
+                .header("If-Match", "\"outdated\"")
+                .body(vtodo("call-mum", "Call dad", "")),
+        )
+        .await
+        .0;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    let status = app
+        .send(
+            app.request("PUT", &href)
+                .header("If-Match", &etag)
+                .body(vtodo("call-mum", "Call dad", "")),
+        )
+        .await
+        .0;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, calendar_object) = app.send(app.request("GET", &href)).await;
+    assert!(calendar_object.contains("SUMMARY:Call dad"));
+    assert_ne!(app.etag(&href).await, etag);
+}
+
+#[actix_web::test]
+async fn deletes_are_conditional_and_remove_the_todo() {
+    let app = TestApp::spawn();
+    let href = seeded_href();
+
+    let (status, _) = app
+        .send(
+            app.request("DELETE", &href)
+                .header("If-Match", "\"outdated\""),
+        )
+        .await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+    let etag = app.etag(&href).await;
+    let (status, _) = app
+        .send(app.request("DELETE", &href).header("If-Match", &etag))
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    assert_eq!(
+        app.send(app.request("GET", &href)).await.0,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.send(app.request("DELETE", &href)).await.0,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[actix_web::test]
+async fn reports_return_calendar_data() {
+    let app = TestApp::spawn();
+
+    let query = r#"<?xml version="1.0"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/><C:calendar-data/></D:prop>
+  <C:filter><C:comp-filter name="VCALENDAR"><C:comp-filter name="VTODO"/></C:comp-filter></C:filter>
+</C:calendar-query>"#;
+    let (status, multistatus) = app.send(app.request("REPORT", CALENDAR).body(query)).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(multistatus.contains(&seeded_href()));
+    assert!(multistatus.contains("SUMMARY:Paint the hall"));
+    assert!(!multistatus.contains("Not on the calendar"));
+
+    let missing = format!("{}missing.ics", CALENDAR);
+    let multiget = format!(
+        r#"<?xml version="1.0"?>
+<C:calendar-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data/></D:prop>
+  <D:href>{}</D:href>
+  <D:href>{}</D:href>
+</C:calendar-multiget>"#,
+        seeded_href(),
+        missing
+    );
+    let (status, multistatus) = app
+        .send(app.request("REPORT", CALENDAR).body(multiget))
+        .await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(multistatus.contains("SUMMARY:Paint the hall"));
+    let missing_response = &multistatus[multistatus.find(&missing).unwrap()..];
+    assert!(missing_response.contains("404 Not Found"));
+}
+
+#[actix_web::test]
+async fn sync_collection_reports_changes_and_deletions_since_a_token() {
+    let app = TestApp::spawn();
+
+    let (status, multistatus) = app.sync("").await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(multistatus.contains(&seeded_href()));
+    let token = sync_token(&multistatus);
+
+    let (_, multistatus) = app.sync(&token).await;
+    assert!(!multistatus.contains("<D:response>"));
+    assert_eq!(sync_token(&multistatus), token);
+
+    assert_eq!(
+        app.put("call-mum", vtodo("call-mum", "Call mum", "")).await,
+        StatusCode::CREATED
+    );
+    let (status, _) = app.send(app.request("DELETE", &seeded_href())).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, multistatus) = app.sync(&token).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert!(multistatus.contains(&format!("{}call-mum.ics", CALENDAR)));
+    let deleted = &multistatus[multistatus.find(&seeded_href()).expect("No deletion")..];
+    assert!(deleted.contains("404 Not Found"));
+    assert_ne!(sync_token(&multistatus), token);
+
+    // Full syncs only list what is left.
+    let (_, multistatus) = app.sync("").await;
+    assert!(!multistatus.contains(&seeded_href()));
+}
+
+#[actix_web::test]
+async fn unknown_and_outdated_sync_tokens_are_refused() {
+    let app = TestApp::spawn();
+    let outdated = (chrono::Utc::now() - chrono::Duration::days(30)).timestamp();
+
+    for token in [
+        "garbage".to_string(),
+        format!("urn:todo-app:sync:{}-0", outdated),
+    ] {
+        let (status, error) = app.sync(&token).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "token: {}", token);
+        assert!(error.contains("valid-sync-token"));
+    }
+}
+
+#[actix_web::test]
+async fn due_times_in_a_time_zone_are_stored_in_utc() {
+    let app = TestApp::spawn();
+    let cases = [
+        (
+            "DUE;TZID=Europe/Berlin:20261020T090000\r\n",
+            "DUE:20261020T070000Z",
+        ),
+        (
+            "DUE;TZID=\"America/New_York\":20261220T090000\r\n",
+            "DUE:20261220T140000Z",
+        ),
+        // Skipped by the switch to daylight saving time.
+        (
+            "DUE;TZID=Europe/Berlin:20270328T023000\r\n",
+            "DUE:20270328T013000Z",
+        ),
+        ("DUE:20261020T090000Z\r\n", "DUE:20261020T090000Z"),
+        ("DUE:20261020T090000\r\n", "DUE:20261020T090000Z"),
+        (
+            "DUE;TZID=Custom Zone:20261020T090000\r\n",
+            "DUE:20261020T090000Z",
+        ),
+    ];
+
+    for (i, (due, expected)) in cases.into_iter().enumerate() {
+        let name = format!("due-{}", i);
+        let status = app.put(&name, vtodo(&name, "Water the plants", due)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (_, calendar_object) = app
+            .send(app.request("GET", &format!("{}{}.ics", CALENDAR, name)))
+            .await;
+        assert!(
+            calendar_object.contains(expected),
+            "{} should be stored as {}: {}",
+            due.trim(),
+            expected,
+            calendar_object
+        );
+    }
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::{redirect::Policy, Method, Response, StatusCode};
+use todo_app::configuration::AuthSettings;
+
+/// Starts the app, authenticating through App Service instead of development logins when
+/// `development` is not set.
+fn spawn_app(development: bool) -> (String, reqwest::Client) {
+    let cosmos = StubCosmos::spawn(HashMap::new());
+    let mut settings = common::test_settings(&cosmos.url);
+    if !development {
+        settings.auth = AuthSettings::AppService;
+    }
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap();
+
+    (common::spawn_app(settings), client)
+}
+
+async fn log_in(address: &str, client: &reqwest::Client, redirect: &str) -> Response {
+    client
+        .post(format!("{}/.auth/login/development", address))
+        .form(&[
+            ("principal_id", "alice"),
+            ("principal_name", "alice@example.com"),
+            ("post_login_redirect_uri", redirect),
+        ])
+        .send()
+        .await
+        .expect("Failed to log in")
+}
+
+async fn log_out(address: &str, client: &reqwest::Client, redirect: &str) -> Response {
+    client
+        .get(format!("{}/.auth/logout", address))
+        .query(&[("post_logout_redirect_uri", redirect)])
+        .send()
+        .await
+        .expect("Failed to log out")
+}
+
+fn location(response: &Response) -> &str {
+    response.headers()["Location"].to_str().unwrap()
+}
+
+#[actix_web::test]
+async fn development_logins_are_not_found_outside_development_mode() {
+    let (address, client) = spawn_app(false);
+
+    for (method, path) in [
+        (Method::GET, "/.auth/login/development"),
+        (Method::POST, "/.auth/login/development"),
+        (Method::GET, "/.auth/logout"),
+    ] {
+        let response = client
+            .request(method.clone(), format!("{}{}", address, path))
+            .form(&[("principal_id", "alice"), ("principal_name", "alice")])
+            .send()
+            .await
+            .expect("Failed to execute request");
+
+        assert_eq!(
+            response.status(),
+            StatusCode::NOT_FOUND,
+            "{} {}",
+            method,
+            path
+        );
+        assert!(response.headers().get("Set-Cookie").is_none());
+    }
+}
+
+#[actix_web::test]
+async fn development_logins_set_the_principal_and_redirect() {
+    let (address, client) = spawn_app(true);
+
+    let form = client
+        .get(format!("{}/.auth/login/development", address))
+        .send()
+        .await
+        .expect("Failed to get login form");
+    assert_eq!(form.status(), StatusCode::OK);
+
+    let response = log_in(&address, &client, "/me/todos?list=bob").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), "/me/todos?list=bob");
+    let cookie = response.headers()["Set-Cookie"].to_str().unwrap();
+    assert!(cookie.starts_with("dev_principal="), "{}", cookie);
+
+    let response = log_out(&address, &client, "/signed-out").await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), "/signed-out");
+}
+
+#[actix_web::test]
+async fn redirects_away_from_the_app_are_ignored() {
+    let (address, client) = spawn_app(true);
+
+    for redirect in [
+        "https://evil.example/",
+        "//evil.example/",
+        "/\\evil.example/",
+        "/\t/evil.example/",
+        "javascript:alert(1)",
+        "",
+    ] {
+        let response = log_in(&address, &client, redirect).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&response), "/me/todos", "{:?}", redirect);
+
+        let response = log_out(&address, &client, redirect).await;
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(location(&response), "/", "{:?}", redirect);
+    }
+}
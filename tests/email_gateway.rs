@@ -0,0 +1,274 @@
+use std::{cell::RefCell, rc::Rc};
+
+use lettre::{
+    address::Envelope, message::header::ContentType, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+use todo_app::{
+    configuration::InboundEmailSettings,
+    email_gateway::{
+        inbound_address, EmailGateway, InboxError, Recipient, TodoId, TodoInbox, UserId,
+    },
+};
+
+const TOKEN: &str = "0123456789abcdef";
+const OWNER_EMAIL: &str = "alice@example.com";
+const DOMAIN: &str = "todo.example.com";
+
+/// A todo added to the fake inbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AddedTodo {
+    owner_id: UserId,
+    content: String,
+    notes: Option<String>,
+}
+
+/// An inbox with a single address, owned by alice.
+#[derive(Clone, Default)]
+struct FakeInbox {
+    todos: Rc<RefCell<Vec<AddedTodo>>>,
+}
+
+impl TodoInbox for FakeInbox {
+    async fn find_recipient(&self, token: &str) -> anyhow::Result<Option<Recipient>> {
+        Ok((token == TOKEN).then(|| Recipient {
+            owner_id: UserId::from("alice".to_string()),
+            email: OWNER_EMAIL.to_string(),
+        }))
+    }
+
+    async fn add_todo(
+        &self,
+        owner_id: UserId,
+        content: String,
+        notes: Option<String>,
+    ) -> Result<TodoId, InboxError> {
+        self.todos.borrow_mut().push(AddedTodo {
+            owner_id,
+            content,
+            notes,
+        });
+        Ok(TodoId::new())
+    }
+}
+
+struct TestGateway {
+    inbox: FakeInbox,
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl TestGateway {
+    async fn spawn(settings: InboundEmailSettings) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let inbox = FakeInbox::default();
+        let gateway = EmailGateway::new(inbox.clone(), settings);
+        actix_web::rt::spawn(gateway.run(listener));
+
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+            .port(port)
+            .build();
+        Self { inbox, mailer }
+    }
+
+    fn added_todos(&self) -> Vec<AddedTodo> {
+        self.inbox.todos.borrow().clone()
+    }
+
+    async fn send_raw(&self, from: &str, to: &str, message: &str) -> Result<String, String> {
+        let envelope =
+            Envelope::new(Some(from.parse().unwrap()), vec![to.parse().unwrap()]).unwrap();
+        self.mailer
+            .send_raw(&envelope, message.as_bytes())
+            .await
+            .map(|response| response.message().collect::<Vec<_>>().join("\n"))
+            .map_err(|e| e.status().map(|code| code.to_string()).unwrap_or_default())
+    }
+}
+
+fn settings() -> InboundEmailSettings {
+    InboundEmailSettings {
+        enabled: true,
+        domain: DOMAIN.to_string(),
+        ..Default::default()
+    }
+}
+
+fn address() -> String {
+    inbound_address(TOKEN, DOMAIN)
+}
+
+fn raw_message(from: &str, subject: &str, body: &str) -> String {
+    format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+        from,
+        address(),
+        subject,
+        body
+    )
+}
+
+#[actix_web::test]
+async fn forwarded_email_becomes_a_todo_with_notes() {
+    let gateway = TestGateway::spawn(settings()).await;
+    let message = Message::builder()
+        .from(OWNER_EMAIL.parse().unwrap())
+        .to(address().to_uppercase().parse().unwrap())
+        .subject("Fwd: Renew the passport")
+        .header(ContentType::TEXT_PLAIN)
+        .body("The appointment is on Monday.\n.Bring photos.\n\n-- \nAlice".to_string())
+        .unwrap();
+
+    let response = gateway.mailer.send(message).await.unwrap();
+
+    assert!(response.message().any(|line| line.contains("Created todo")));
+    assert_eq!(
+        gateway.added_todos(),
+        vec![AddedTodo {
+            owner_id: UserId::from("alice".to_string()),
+            content: "Renew the passport".to_string(),
+            notes: Some("The appointment is on Monday.\n.Bring photos.".to_string()),
+        }]
+    );
+}
+
+#[actix_web::test]
+async fn unknown_address_is_rejected() {
+    let gateway = TestGateway::spawn(settings()).await;
+
+    for to in [
+        inbound_address("fedcba9876543210", DOMAIN),
+        inbound_address(TOKEN, "example.com"),
+        format!("alice@{}", DOMAIN),
+    ] {
+        let result = gateway
+            .send_raw(
+                OWNER_EMAIL,
+                &to,
+                &raw_message(OWNER_EMAIL, "Buy milk", "Skimmed"),
+            )
+            .await;
+
+        assert_eq!(result, Err("550".to_string()), "sent to {}", to);
+    }
+    assert!(gateway.added_todos().is_empty());
+}
+
+#[actix_web::test]
+async fn email_from_another_sender_is_rejected() {
+    let gateway = TestGateway::spawn(settings()).await;
+
+    let forged_header = gateway
+        .send_raw(
+            OWNER_EMAIL,
+            &address(),
+            &raw_message("mallory@example.com", "Buy milk", ""),
+        )
+        .await;
+    let forged_envelope = gateway
+        .send_raw(
+            "mallory@example.com",
+            &address(),
+            &raw_message(OWNER_EMAIL, "Buy milk", ""),
+        )
+        .await;
+
+    assert_eq!(forged_header, Err("550".to_string()));
+    assert_eq!(forged_envelope, Err("550".to_string()));
+    assert!(gateway.added_todos().is_empty());
+}
+
+#[actix_web::test]
+async fn blank_subject_is_rejected() {
+    let gateway = TestGateway::spawn(settings()).await;
+
+    let result = gateway
+        .send_raw(
+            OWNER_EMAIL,
+            &address(),
+            &raw_message(OWNER_EMAIL, "Fwd: ", "Buy milk"),
+        )
+        .await;
+
+    assert_eq!(result, Err("550".to_string()));
+    assert!(gateway.added_todos().is_empty());
+}
+
+#[actix_web::test]
+async fn oversized_email_is_rejected() {
+    let gateway = TestGateway::spawn(InboundEmailSettings {
+        max_message_bytes: 1024,
+        ..settings()
+    })
+    .await;
+
+    let result = gateway
+        .send_raw(
+            OWNER_EMAIL,
+            &address(),
+            &raw_message(OWNER_EMAIL, "Buy milk", &"Skimmed\r\n".repeat(200)),
+        )
+        .await;
+
+    assert_eq!(result, Err("552".to_string()));
+    assert!(gateway.added_todos().is_empty());
+}
+
+#[actix_web::test]
+async fn long_body_is_truncated_into_notes() {
+    let gateway = TestGateway::spawn(settings()).await;
+    let body = "é".repeat(15_000);
+
+    gateway
+        .send_raw(
+            OWNER_EMAIL,
+            &address(),
+            &raw_message(OWNER_EMAIL, "Read the book", &body),
+        )
+        .await
+        .unwrap();
+
+    let notes = gateway.added_todos()[0].notes.clone().unwrap();
+    assert!(notes.len() <= 20_000);
+    assert!(notes.chars().all(|c| c == 'é'));
+}
+
+#[actix_web::test]
+async fn trusted_authentication_results_are_required_when_configured() {
+    let gateway = TestGateway::spawn(InboundEmailSettings {
+        trusted_authserv_id: Some("mx.example.com".to_string()),
+        ..settings()
+    })
+    .await;
+    let message = raw_message(OWNER_EMAIL, "Buy milk", "");
+
+    let unverified = gateway.send_raw(OWNER_EMAIL, &address(), &message).await;
+    // Results of other servers, e.g. added by the sender, are ignored.
+    let forged = gateway
+        .send_raw(
+            OWNER_EMAIL,
+            &address(),
+            &format!(
+                "Authentication-Results: mx.example.com; dmarc=fail\r\n\
+                 Authentication-Results: evil.example.com; dmarc=pass\r\n{}",
+                message
+            ),
+        )
+        .await;
+    let verified = gateway
+        .send_raw(
+            OWNER_EMAIL,
+            &address(),
+            &format!(
+                "Authentication-Results: mx.example.com 1; spf=pass smtp.mailfrom=example.com; \
+                 dkim=pass header.d=example.com; dmarc=pass header.from=example.com\r\n{}",
+                message
+            ),
+        )
+        .await;
+
+    assert_eq!(unverified, Err("550".to_string()));
+    assert_eq!(forged, Err("550".to_string()));
+    assert!(verified.is_ok());
+    assert_eq!(gateway.added_todos().len(), 1);
+}
@@ -0,0 +1,108 @@
+use futures::FutureExt;
+use serde_json::json;
+use todo_app::events::{Received, TodoChange, TodoEvent, TodoEventBus, TodoEventReceiver, UserId};
+
+/// More events than a channel holds for a subscriber that does not keep up.
+const FLOOD: usize = 2000;
+
+fn user(id: &str) -> UserId {
+    serde_json::from_value(json!(id)).unwrap()
+}
+
+fn event(owner: &str, content: &str) -> TodoEvent {
+    TodoEvent {
+        owner_id: user(owner),
+        change: TodoChange::Updated,
+        data: json!({ "content": content }).to_string().into(),
+        seq: 0,
+    }
+}
+
+/// The contents and sequence numbers of the events received so far, or `None` for a
+/// resync.
+fn received(receiver: &mut TodoEventReceiver) -> Vec<Option<(String, u64)>> {
+    let mut received = Vec::new();
+    while let Some(next) = receiver.recv().now_or_never() {
+        received.push(match next.expect("Channel closed") {
+            Received::Event(event) => {
+                let data: serde_json::Value = serde_json::from_str(&event.data).unwrap();
+                Some((data["content"].as_str().unwrap().to_string(), event.seq))
+            }
+            Received::Resync => None,
+        });
+    }
+    received
+}
+
+fn event_of(content: &str, seq: u64) -> Option<(String, u64)> {
+    Some((content.to_string(), seq))
+}
+
+#[test]
+fn events_only_reach_the_subscribers_of_their_list() {
+    let event_bus = TodoEventBus::new();
+    let mut alice = event_bus.subscribe(&user("alice"));
+    let mut bob = event_bus.subscribe(&user("bob"));
+
+    event_bus.publish(event("alice", "Paint the hall"));
+    event_bus.publish(event("bob", "Book the venue"));
+    event_bus.publish(event("alice", "Fix the fence"));
+
+    assert_eq!(
+        received(&mut alice),
+        vec![event_of("Paint the hall", 1), event_of("Fix the fence", 2)]
+    );
+    assert_eq!(received(&mut bob), vec![event_of("Book the venue", 1)]);
+    assert_eq!(event_bus.last_seq(&user("alice")), 2);
+}
+
+#[test]
+fn subscribers_that_fall_behind_resync_without_holding_up_other_lists() {
+    let event_bus = TodoEventBus::new();
+    let mut alice = event_bus.subscribe(&user("alice"));
+    let mut bob = event_bus.subscribe(&user("bob"));
+
+    for i in 0..FLOOD {
+        event_bus.publish(event("alice", &format!("Todo {i}")));
+    }
+    event_bus.publish(event("bob", "Book the venue"));
+
+    let alice_received = received(&mut alice);
+    assert_eq!(alice_received[0], None);
+    // After the resync the subscriber continues with the events still in the channel.
+    assert_eq!(
+        alice_received.last().unwrap(),
+        &event_of(&format!("Todo {}", FLOOD - 1), FLOOD as u64)
+    );
+    assert!(alice_received.len() < FLOOD);
+    assert_eq!(received(&mut bob), vec![event_of("Book the venue", 1)]);
+
+    event_bus.publish(event("alice", "After the resync"));
+    assert_eq!(
+        received(&mut alice),
+        vec![event_of("After the resync", FLOOD as u64 + 1)]
+    );
+}
+
+#[test]
+fn channels_are_removed_with_their_last_subscription() {
+    let event_bus = TodoEventBus::new();
+    let first = event_bus.subscribe(&user("alice"));
+    let second = event_bus.subscribe(&user("alice"));
+    let bob = event_bus.subscribe(&user("bob"));
+    event_bus.publish(event("alice", "Paint the hall"));
+    assert_eq!(event_bus.subscribed_lists(), 2);
+
+    drop(first);
+    assert_eq!(event_bus.subscribed_lists(), 2);
+    assert_eq!(event_bus.last_seq(&user("alice")), 1);
+
+    drop(second);
+    drop(bob);
+    assert_eq!(event_bus.subscribed_lists(), 0);
+
+    // Nobody listens, so nothing is kept for the list.
+    event_bus.publish(event("alice", "Fix the fence"));
+    assert_eq!(event_bus.subscribed_lists(), 0);
+    assert_eq!(event_bus.last_seq(&user("alice")), 0);
+}
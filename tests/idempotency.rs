@@ -0,0 +1,169 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::{redirect::Policy, Response, StatusCode};
+use serde_json::{json, Value};
+
+const CSRF_TOKEN: &str = "idempotency-test";
+const KEY: &str = "3f1c9a2e-retry";
+
+struct TestApp {
+    address: String,
+    cosmos_url: String,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    /// The developer's list is empty. Alice shares hers with them, unless her list member
+    /// document is left incomplete by `broken_membership`, which fails every lookup of the
+    /// developer's shared lists.
+    fn spawn(broken_membership: bool) -> Self {
+        let mut list_member = alice_list_member();
+        if broken_membership {
+            list_member.as_object_mut().unwrap().remove("owner_name");
+        }
+        let cosmos = StubCosmos::spawn(HashMap::from([("list_members", vec![list_member])]));
+
+        Self {
+            address: common::spawn_app(common::test_settings(&cosmos.url)),
+            cosmos_url: cosmos.url.clone(),
+            client: reqwest::Client::builder()
+                .redirect(Policy::none())
+                .build()
+                .unwrap(),
+        }
+    }
+
+    async fn create_todo(&self, key: &str, content: &str) -> Response {
+        self.client
+            .post(format!("{}/me/todos", self.address))
+            .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+            .header("Idempotency-Key", key)
+            .form(&[("content", content), ("csrf_token", CSRF_TOKEN)])
+            .send()
+            .await
+            .expect("Failed to create todo")
+    }
+
+    fn collection_url(&self, collection: &str) -> String {
+        format!("{}dbs/todoappdb/colls/{}/docs", self.cosmos_url, collection)
+    }
+
+    /// The documents of a collection as stored, read straight from the database.
+    async fn documents(&self, collection: &str) -> Vec<Value> {
+        let response = self
+            .client
+            .post(self.collection_url(collection))
+            .header("x-ms-documentdb-isquery", "true")
+            .body(json!({ "query": "SELECT * FROM c", "parameters": [] }).to_string())
+            .send()
+            .await
+            .expect("Failed to query the database");
+        let body: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        body["Documents"].as_array().unwrap().clone()
+    }
+
+    /// Replaces a document behind the app's back.
+    async fn upsert(&self, collection: &str, document: Value) {
+        let response = self
+            .client
+            .post(self.collection_url(collection))
+            .header("x-ms-documentdb-is-upsert", "true")
+            .body(document.to_string())
+            .send()
+            .await
+            .expect("Failed to write to the database");
+        assert!(response.status().is_success(), "{}", response.status());
+    }
+}
+
+fn alice_list_member() -> Value {
+    json!({
+        "id": uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, b"alice\nlocal-user"),
+        "owner_id": "alice",
+        "owner_name": "alice@example.com",
+        "member_id": "local-user",
+        "member_name": "developer@localhost",
+        "role": "editor",
+        "created_at": "2026-10-01T08:00:00Z",
+    })
+}
+
+fn location(response: &Response) -> &str {
+    response.headers()["Location"].to_str().unwrap()
+}
+
+#[actix_web::test]
+async fn retries_replay_the_stored_response() {
+    let app = TestApp::spawn(false);
+
+    let first = app.create_todo(KEY, "Water the plants @alice").await;
+    assert_eq!(first.status(), StatusCode::SEE_OTHER);
+    assert!(!first.headers().contains_key("Idempotent-Replayed"));
+
+    let retry = app.create_todo(KEY, "Water the plants @alice").await;
+    assert_eq!(retry.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&retry), location(&first));
+    assert_eq!(retry.headers()["Idempotent-Replayed"], "true");
+
+    assert_eq!(app.documents("todos").await.len(), 1);
+
+    // Keys are independent of each other.
+    let other = app
+        .create_todo("another-key", "Water the plants @alice")
+        .await;
+    assert!(!other.headers().contains_key("Idempotent-Replayed"));
+    assert_eq!(app.documents("todos").await.len(), 2);
+}
+
+#[actix_web::test]
+async fn reusing_a_key_for_a_different_request_is_unprocessable() {
+    let app = TestApp::spawn(false);
+
+    let first = app.create_todo(KEY, "Water the plants").await;
+    assert_eq!(first.status(), StatusCode::SEE_OTHER);
+
+    let other = app.create_todo(KEY, "Mow the lawn").await;
+    assert_eq!(other.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!other.headers().contains_key("Idempotent-Replayed"));
+
+    let todos = app.documents("todos").await;
+    assert_eq!(todos.len(), 1);
+    assert_eq!(todos[0]["content"], "Water the plants");
+}
+
+#[actix_web::test]
+async fn retries_while_the_first_request_is_handled_conflict() {
+    let app = TestApp::spawn(false);
+    let first = app.create_todo(KEY, "Water the plants").await;
+    assert_eq!(first.status(), StatusCode::SEE_OTHER);
+
+    // Put the record back the way it is while the first request is being handled.
+    let mut record = app.documents("idempotency_records").await.remove(0);
+    record.as_object_mut().unwrap().remove("response");
+    app.upsert("idempotency_records", record).await;
+
+    let retry = app.create_todo(KEY, "Water the plants").await;
+    assert_eq!(retry.status(), StatusCode::CONFLICT);
+    assert_eq!(app.documents("todos").await.len(), 1);
+}
+
+#[actix_web::test]
+async fn retries_after_a_server_error_are_handled_again() {
+    let app = TestApp::spawn(true);
+
+    let first = app.create_todo(KEY, "Water the plants @alice").await;
+    assert_eq!(first.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(app.documents("idempotency_records").await.is_empty());
+
+    app.upsert("list_members", alice_list_member()).await;
+
+    let retry = app.create_todo(KEY, "Water the plants @alice").await;
+    assert_eq!(retry.status(), StatusCode::SEE_OTHER);
+    assert!(!retry.headers().contains_key("Idempotent-Replayed"));
+    let todos = app.documents("todos").await;
+    assert_eq!(todos.len(), 1);
+    assert_eq!(todos[0]["created_by"], "alice");
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use common::StubCosmos;
+use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+const TOKEN: &str = "s3cr3t-feed-token";
+
+/// Collects the log lines the app writes.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Logs {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).to_string()
+    }
+}
+
+/// The logs of every app started by the tests of this binary. The workers of the app run
+/// on threads of their own, so the subscriber is installed for the whole process.
+fn logs() -> &'static Logs {
+    static LOGS: OnceLock<Logs> = OnceLock::new();
+    LOGS.get_or_init(|| {
+        let logs = Logs::default();
+        let writer = logs.clone();
+        let subscriber =
+            Registry::default()
+                .with(JsonStorageLayer)
+                .with(BunyanFormattingLayer::new("test".into(), move || {
+                    writer.clone()
+                }));
+        tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
+        logs
+    })
+}
+
+/// Requests the path from the app and returns the log lines mentioning `marker`, a part of
+/// the path that is not secret.
+async fn logs_of(path: &str, marker: &str) -> Vec<String> {
+    let logs = logs();
+    let cosmos = StubCosmos::spawn(HashMap::new());
+    let address = common::spawn_app(common::test_settings(&cosmos.url));
+
+    reqwest::get(format!("{}{}", address, path))
+        .await
+        .expect("Failed to send request");
+
+    logs.text()
+        .lines()
+        .filter(|line| line.contains(marker))
+        .map(str::to_string)
+        .collect()
+}
+
+#[actix_web::test]
+async fn calendar_feed_tokens_are_redacted_from_request_logs() {
+    let logs = logs_of(
+        &format!("/feeds/{}/todos.ics?list=feed-test", TOKEN),
+        "list=feed-test",
+    )
+    .await
+    .join("\n");
+
+    assert!(!logs.is_empty());
+    assert!(!logs.contains(TOKEN), "{}", logs);
+    assert!(
+        logs.contains(r#""http.target":"/feeds/[redacted]/todos.ics?list=feed-test""#),
+        "{}",
+        logs
+    );
+    assert!(
+        logs.contains(r#""http.route":"/feeds/{token}/todos.ics""#),
+        "{}",
+        logs
+    );
+}
+
+#[actix_web::test]
+async fn share_link_tokens_are_redacted_from_request_logs() {
+    let logs = logs_of(
+        &format!("/shared/{}?via=share-link-test", TOKEN),
+        "via=share-link-test",
+    )
+    .await
+    .join("\n");
+
+    assert!(!logs.is_empty());
+    assert!(!logs.contains(TOKEN), "{}", logs);
+    assert!(
+        logs.contains(r#""http.target":"/shared/[redacted]?via=share-link-test""#),
+        "{}",
+        logs
+    );
+}
+
+#[actix_web::test]
+async fn other_paths_are_logged_as_requested() {
+    let logs = logs_of("/healthcheck?probe=1", "probe=1").await.join("\n");
+
+    assert!(
+        logs.contains(r#""http.target":"/healthcheck?probe=1""#),
+        "{}",
+        logs
+    );
+}
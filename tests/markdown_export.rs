@@ -0,0 +1,140 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+const PERSONAL_ACCESS_TOKEN: &str = "todo_pat_markdown-export-test";
+
+fn todo(id: u128, owner: &str, content: &str, tags: &[&str], due_at: Option<&str>) -> Value {
+    json!({
+        "id": uuid::Uuid::from_u128(id),
+        "content": content,
+        "done": false,
+        "created_by": owner,
+        "created_at": "2026-10-01T08:00:00Z",
+        "due_at": due_at,
+        "tags": tags,
+    })
+}
+
+/// Alice exports her own list and the one Bob shared with her. Todos of other users are
+/// left out.
+fn spawn_app() -> String {
+    let cosmos = StubCosmos::spawn(HashMap::from([
+        (
+            "personal_access_tokens",
+            vec![json!({
+                "id": "token-hash",
+                "user_id": "alice",
+                "principal_name": "alice@example.com",
+                "name": "Weekly review",
+                "scopes": ["read"],
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+        (
+            "list_members",
+            vec![json!({
+                "id": "bob:alice",
+                "owner_id": "bob",
+                "owner_name": "bob@example.com",
+                "member_id": "alice",
+                "member_name": "alice@example.com",
+                "role": "viewer",
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+        (
+            "todos",
+            vec![
+                todo(
+                    1,
+                    "alice",
+                    "Paint the hall",
+                    &["home", "weekend"],
+                    Some("2026-10-18T10:00:00Z"),
+                ),
+                todo(
+                    2,
+                    "alice",
+                    "Fix the fence",
+                    &["weekend"],
+                    Some("2026-10-17T10:00:00Z"),
+                ),
+                todo(3, "alice", "Call the bank", &[], None),
+                todo(4, "bob", "Book the venue", &["party"], None),
+                todo(5, "carol", "Not shared with Alice", &["home"], None),
+            ],
+        ),
+    ]));
+
+    common::spawn_app(common::test_settings(&cosmos.url))
+}
+
+async fn export(address: &str, query: &str) -> (StatusCode, String) {
+    let response = reqwest::Client::new()
+        .get(format!("{}/me/todos.md{}", address, query))
+        .bearer_auth(PERSONAL_ACCESS_TOKEN)
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    (response.status(), response.text().await.unwrap())
+}
+
+#[actix_web::test]
+async fn todos_are_grouped_by_list_and_every_tag() {
+    let address = spawn_app();
+
+    let (status, markdown) = export(&address, "").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        markdown,
+        "# Todos\n\
+         \n## alice@example.com\n\
+         \n### home\n\n\
+         - [ ] Paint the hall (due 2026-10-18)\n\
+         \n### weekend\n\n\
+         - [ ] Fix the fence (due 2026-10-17)\n\
+         - [ ] Paint the hall (due 2026-10-18)\n\
+         \n### Untagged\n\n\
+         - [ ] Call the bank\n\
+         \n## bob@example.com\n\
+         \n### party\n\n\
+         - [ ] Book the venue\n"
+    );
+}
+
+#[actix_web::test]
+async fn lists_without_todos_in_the_range_are_left_out() {
+    let address = spawn_app();
+
+    let (status, markdown) = export(&address, "?from=2026-10-18&to=2026-10-18").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        markdown,
+        "# Todos\n\
+         \n_2026-10-18 – 2026-10-18_\n\
+         \n## alice@example.com\n\
+         \n### home\n\n\
+         - [ ] Paint the hall (due 2026-10-18)\n\
+         \n### weekend\n\n\
+         - [ ] Paint the hall (due 2026-10-18)\n"
+    );
+}
+
+#[actix_web::test]
+async fn nothing_in_the_range_is_said_so() {
+    let address = spawn_app();
+
+    let (status, markdown) = export(&address, "?from=2020-01-01&to=2020-01-31").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(markdown.ends_with("\nNo todos.\n"), "{}", markdown);
+    assert!(!markdown.contains("##"), "{}", markdown);
+}
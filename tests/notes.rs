@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use tera::Tera;
+use todo_app::markdown::{markdown_filter, render_html};
+
+#[test]
+fn markdown_is_rendered_as_html() {
+    let html = render_html("# Plan\n\nBuy **paint** and ~~brushes~~:\n\n- white\n- `#fff`\n");
+
+    assert_eq!(
+        html,
+        "<h1>Plan</h1>\n<p>Buy <strong>paint</strong> and <del>brushes</del>:</p>\n\
+         <ul>\n<li>white</li>\n<li><code>#fff</code></li>\n</ul>\n"
+    );
+}
+
+#[test]
+fn tables_are_rendered() {
+    let html = render_html("| Room | Colour |\n| --- | --- |\n| Hall | White |\n");
+
+    assert!(html.contains("<table>"), "{}", html);
+    assert!(html.contains("<td>Hall</td>"), "{}", html);
+}
+
+#[test]
+fn scripts_and_styles_are_dropped() {
+    let html = render_html(
+        "Before\n\n<script>alert('hi')</script>\n\n<style>body { display: none }</style>\n\nAfter",
+    );
+
+    assert!(!html.contains("<script"), "{}", html);
+    assert!(!html.contains("alert"), "{}", html);
+    assert!(!html.contains("<style"), "{}", html);
+    assert!(
+        html.contains("Before") && html.contains("After"),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn event_handlers_are_dropped() {
+    let html = render_html(
+        r#"<img src="https://example.com/a.png" onerror="alert(1)"> <b onclick="alert(2)">bold</b>"#,
+    );
+
+    assert!(!html.contains("onerror"), "{}", html);
+    assert!(!html.contains("onclick"), "{}", html);
+    assert!(!html.contains("alert"), "{}", html);
+    assert!(
+        html.contains(r#"src="https://example.com/a.png""#),
+        "{}",
+        html
+    );
+    assert!(html.contains("<b>bold</b>"), "{}", html);
+}
+
+#[test]
+fn unsafe_links_are_dropped() {
+    for markdown in [
+        "[click](javascript:alert(1))",
+        "[click](JavaScript:alert(1))",
+        "[click](vbscript:msgbox)",
+        "[click](data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==)",
+        r#"<a href="javascript:alert(1)">click</a>"#,
+        "![pixel](data:image/png;base64,iVBORw0KGgo=)",
+    ] {
+        let html = render_html(markdown);
+
+        assert!(!html.contains("href"), "{} rendered as {}", markdown, html);
+        assert!(!html.contains("src"), "{} rendered as {}", markdown, html);
+    }
+}
+
+#[test]
+fn safe_links_are_kept_and_do_not_expose_the_page() {
+    let html = render_html(
+        "[docs](https://example.com/docs), [mail](mailto:bob@example.com) and [list](/me/todos)",
+    );
+
+    assert!(
+        html.contains(r#"<a href="https://example.com/docs" rel="noopener noreferrer">docs</a>"#),
+        "{}",
+        html
+    );
+    assert!(
+        html.contains(r#"href="mailto:bob@example.com""#),
+        "{}",
+        html
+    );
+    assert!(html.contains(r#"href="/me/todos""#), "{}", html);
+}
+
+#[test]
+fn frames_and_forms_are_dropped() {
+    let html = render_html(
+        r#"<iframe src="https://example.com"></iframe><form action="/me/todos" method="post"><input name="content"><button>Go</button></form>"#,
+    );
+
+    assert!(!html.contains("<iframe"), "{}", html);
+    assert!(!html.contains("<form"), "{}", html);
+    assert!(!html.contains("<input"), "{}", html);
+    assert!(!html.contains("<button"), "{}", html);
+}
+
+#[test]
+fn unfinished_markup_cannot_escape_the_notes() {
+    let html = render_html("<div><p>Unclosed <b>tags</div></div></div><script>alert(1)</script>");
+
+    assert!(!html.contains("<script"), "{}", html);
+    assert_eq!(
+        html.matches("<div>").count(),
+        html.matches("</div>").count()
+    );
+}
+
+#[test]
+fn template_filter_renders_notes() {
+    let mut tera = Tera::default();
+    tera.register_filter("markdown", markdown_filter);
+    tera.add_raw_template("notes.html", "{{ notes | markdown | safe }}")
+        .unwrap();
+    let mut context = tera::Context::new();
+    context.insert("notes", "Call *Anna* <script>alert(1)</script>");
+
+    let html = tera.render("notes.html", &context).unwrap();
+
+    assert_eq!(html, "<p>Call <em>Anna</em> </p>\n");
+}
+
+#[test]
+fn template_filter_rejects_values_other_than_text() {
+    let result = markdown_filter(&tera::Value::from(42), &HashMap::new());
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn todo_page_shows_notes_rendered_and_raw_for_editing() {
+    let mut tera = Tera::new("templates/**/*").unwrap();
+    tera.register_filter("markdown", markdown_filter);
+    let mut context = tera::Context::new();
+    context.insert("csrf_token", "token");
+    context.insert(
+        "todo",
+        &serde_json::json!({
+            "id": "6f1f3bd4-4e4b-4bd4-9a3f-2d4f1e7b1c11",
+            "content": "Paint the hall",
+            "done": false,
+            "due_at": "2026-10-20T07:00:00Z",
+            "tags": ["home"],
+            "notes": "Buy **white** paint <script>alert(1)</script>",
+            "priority": "high",
+            "recurrence": null,
+        }),
+    );
+    context.insert("assignee_name", &Some("Anna"));
+    context.insert(
+        "list",
+        &serde_json::json!({
+            "owner_id": "owner",
+            "owner_name": "Bob",
+            "is_owner": true,
+            "can_edit": true,
+            "query": "",
+        }),
+    );
+
+    let html = tera.render("todo.html", &context).unwrap();
+
+    assert!(
+        html.contains("<p>Buy <strong>white</strong> paint </p>"),
+        "{}",
+        html
+    );
+    assert!(
+        html.contains("Buy **white** paint &lt;script&gt;alert(1)&lt;&#x2F;script&gt;</textarea>"),
+        "{}",
+        html
+    );
+    assert!(!html.contains("<script>alert"), "{}", html);
+    assert!(html.contains("Assigned to Anna"), "{}", html);
+}
@@ -0,0 +1,488 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use mail_parser::MessageParser;
+use tera::Tera;
+use todo_app::{
+    configuration::{NotificationSettings, SmtpSettings, SmtpTls},
+    notifications::{
+        DueTodoRepository, Notification, NotificationPreferences, NotificationRepository,
+        NotificationScheduler, SubscriberRepository, Todo, TodoContent, User, UserId,
+    },
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+
+/// An email received by the sink.
+struct SunkEmail {
+    to: String,
+    subject: String,
+    text: String,
+}
+
+/// A local SMTP server accepting every email, or rejecting them all while `reject` is set.
+struct SmtpSink {
+    port: u16,
+    emails: Arc<Mutex<Vec<SunkEmail>>>,
+    reject: Arc<AtomicBool>,
+}
+
+impl SmtpSink {
+    async fn spawn() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let emails = Arc::new(Mutex::new(Vec::new()));
+        let reject = Arc::new(AtomicBool::new(false));
+
+        let (received, rejecting) = (emails.clone(), reject.clone());
+        actix_web::rt::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (received, rejecting) = (received.clone(), rejecting.clone());
+                actix_web::rt::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+                    let mut to = String::new();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let verb = line.split(' ').next().unwrap_or_default().to_uppercase();
+                        let response = match verb.as_str() {
+                            "EHLO" | "HELO" => "250 sink",
+                            "RCPT" => {
+                                to = line[line.find('<').unwrap() + 1..line.find('>').unwrap()]
+                                    .to_string();
+                                "250 OK"
+                            }
+                            "DATA" => {
+                                writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+                                let mut data = String::new();
+                                while let Ok(Some(line)) = lines.next_line().await {
+                                    if line == "." {
+                                        break;
+                                    }
+                                    data.push_str(line.strip_prefix('.').unwrap_or(&line));
+                                    data.push_str("\r\n");
+                                }
+                                if rejecting.load(Ordering::SeqCst) {
+                                    "451 Try again later"
+                                } else {
+                                    let message =
+                                        MessageParser::default().parse(data.as_bytes()).unwrap();
+                                    received.lock().unwrap().push(SunkEmail {
+                                        to: to.clone(),
+                                        subject: message.subject().unwrap().to_string(),
+                                        text: message.body_text(0).unwrap().to_string(),
+                                    });
+                                    "250 Queued"
+                                }
+                            }
+                            "QUIT" => {
+                                writer.write_all(b"221 Bye\r\n").await.unwrap();
+                                return;
+                            }
+                            _ => "250 OK",
+                        };
+                        writer
+                            .write_all(format!("{}\r\n", response).as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        Self {
+            port,
+            emails,
+            reject,
+        }
+    }
+
+    fn take_emails(&self) -> Vec<SunkEmail> {
+        std::mem::take(&mut *self.emails.lock().unwrap())
+    }
+}
+
+/// Todos and users are not `Clone`, the fakes hand out copies of them.
+fn copy<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+}
+
+#[derive(Clone, Default)]
+struct FakeTodos {
+    todos: Rc<RefCell<Vec<Todo>>>,
+}
+
+impl DueTodoRepository for FakeTodos {
+    async fn get_open_due_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Todo>> {
+        Ok(self
+            .todos
+            .borrow()
+            .iter()
+            .filter(|todo| !todo.is_done())
+            .filter(|todo| {
+                todo.due_at()
+                    .is_some_and(|due_at| from <= due_at && due_at < to)
+            })
+            .map(copy)
+            .collect())
+    }
+
+    async fn get_open_for_user(&self, user_id: UserId) -> anyhow::Result<Vec<Todo>> {
+        Ok(self
+            .todos
+            .borrow()
+            .iter()
+            .filter(|todo| !todo.is_done() && todo.created_by() == user_id)
+            .map(copy)
+            .collect())
+    }
+}
+
+#[derive(Clone, Default)]
+struct FakeUsers {
+    users: Rc<RefCell<Vec<User>>>,
+}
+
+impl SubscriberRepository for FakeUsers {
+    async fn get_subscriber(&self, user_id: UserId) -> anyhow::Result<Option<User>> {
+        Ok(self
+            .users
+            .borrow()
+            .iter()
+            .find(|user| user.id() == user_id)
+            .map(copy))
+    }
+
+    async fn get_digest_subscribers(&self) -> anyhow::Result<Vec<User>> {
+        Ok(self
+            .users
+            .borrow()
+            .iter()
+            .filter(|user| user.notification_preferences().daily_digest)
+            .map(copy)
+            .collect())
+    }
+}
+
+/// Notifications recorded so far, shared by every scheduler like the container is.
+#[derive(Clone, Default)]
+struct FakeNotifications {
+    ids: Rc<RefCell<HashSet<(UserId, String)>>>,
+}
+
+impl NotificationRepository for FakeNotifications {
+    async fn create_if_absent(&self, notification: Notification) -> anyhow::Result<bool> {
+        Ok(self
+            .ids
+            .borrow_mut()
+            .insert((notification.user_id(), notification.id())))
+    }
+
+    async fn delete(&self, notification: Notification) -> anyhow::Result<()> {
+        self.ids
+            .borrow_mut()
+            .remove(&(notification.user_id(), notification.id()));
+        Ok(())
+    }
+
+    async fn delete_all_for_user(&self, user_id: UserId) -> anyhow::Result<()> {
+        self.ids.borrow_mut().retain(|(owner, _)| *owner != user_id);
+        Ok(())
+    }
+}
+
+struct TestApp {
+    sink: SmtpSink,
+    todos: FakeTodos,
+    users: FakeUsers,
+    notifications: FakeNotifications,
+}
+
+impl TestApp {
+    async fn spawn() -> Self {
+        let app = Self {
+            sink: SmtpSink::spawn().await,
+            todos: FakeTodos::default(),
+            users: FakeUsers::default(),
+            notifications: FakeNotifications::default(),
+        };
+        app.add_user(
+            ALICE,
+            "alice@example.com",
+            NotificationPreferences::default(),
+        );
+        app
+    }
+
+    /// A scheduler of its own, like one of several instances or one after a restart.
+    fn scheduler(&self) -> NotificationScheduler<FakeTodos, FakeUsers, FakeNotifications> {
+        let settings = NotificationSettings {
+            enabled: true,
+            base_url: "https://todo.example.com".to_string(),
+            smtp: SmtpSettings {
+                host: "127.0.0.1".to_string(),
+                port: Some(self.sink.port),
+                tls: SmtpTls::None,
+                from: "Todo App <todo@example.com>".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        NotificationScheduler::new(
+            self.todos.clone(),
+            self.users.clone(),
+            self.notifications.clone(),
+            Tera::new("templates/**/*").unwrap(),
+            settings,
+        )
+        .unwrap()
+    }
+
+    fn add_user(&self, id: &str, principal_name: &str, preferences: NotificationPreferences) {
+        let mut user = User::new(UserId::from(id.to_string()), principal_name.to_string());
+        user.update_notification_preferences(preferences);
+        let mut users = self.users.users.borrow_mut();
+        users.retain(|existing| existing.id() != user.id());
+        users.push(user);
+    }
+
+    fn add_todo(&self, content: &str, due_at: Option<DateTime<Utc>>) -> usize {
+        let mut todo = Todo::new(
+            TodoContent::try_from(content.to_string()).unwrap(),
+            UserId::from(ALICE.to_string()),
+        );
+        todo.update_due_at(due_at);
+        let mut todos = self.todos.todos.borrow_mut();
+        todos.push(todo);
+        todos.len() - 1
+    }
+
+    fn update_todo(&self, index: usize, update: impl FnOnce(&mut Todo)) {
+        update(&mut self.todos.todos.borrow_mut()[index]);
+    }
+}
+
+fn preferences(
+    time_zone: chrono_tz::Tz,
+    email_reminders: bool,
+    daily_digest: bool,
+) -> NotificationPreferences {
+    NotificationPreferences {
+        time_zone,
+        email_reminders,
+        daily_digest,
+    }
+}
+
+/// 2026-10-19 08:00 UTC, 10:00 in Warsaw and 04:00 in New York.
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 19, 8, 0, 0).unwrap()
+}
+
+#[actix_web::test]
+async fn todo_due_soon_is_reminded_of_once_in_the_owners_time_zone() {
+    let app = TestApp::spawn().await;
+    app.add_user(
+        ALICE,
+        "alice@example.com",
+        preferences(chrono_tz::Europe::Warsaw, true, false),
+    );
+    app.add_todo("Call the dentist", Some(now() + Duration::minutes(20)));
+    app.add_todo("Renew the passport", Some(now() + Duration::days(3)));
+    app.add_todo("Water the plants", None);
+
+    let first = app.scheduler().poll(now()).await.unwrap();
+    let again = app
+        .scheduler()
+        .poll(now() + Duration::minutes(1))
+        .await
+        .unwrap();
+
+    assert_eq!((first, again), (1, 0));
+    let emails = app.sink.take_emails();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "alice@example.com");
+    assert_eq!(emails[0].subject, "Reminder: Call the dentist");
+    assert!(
+        emails[0].text.contains("Due Mon 19 Oct 10:20 CEST"),
+        "{}",
+        emails[0].text
+    );
+    assert!(emails[0].text.contains("https://todo.example.com/me/todos"));
+}
+
+#[actix_web::test]
+async fn reminders_go_to_the_assignee() {
+    let app = TestApp::spawn().await;
+    app.add_user(BOB, "bob@example.com", NotificationPreferences::default());
+    let todo = app.add_todo("Fix the bike", Some(now() + Duration::minutes(10)));
+    app.update_todo(todo, |todo| {
+        todo.assign(
+            Some(UserId::from(BOB.to_string())),
+            UserId::from(ALICE.to_string()),
+        )
+    });
+
+    app.scheduler().poll(now()).await.unwrap();
+
+    let emails = app.sink.take_emails();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "bob@example.com");
+    assert!(emails[0].text.contains("/me/todos/assigned"));
+}
+
+#[actix_web::test]
+async fn done_todos_and_users_without_reminders_are_left_alone() {
+    let app = TestApp::spawn().await;
+    let done = app.add_todo("Call the dentist", Some(now() + Duration::minutes(10)));
+    app.update_todo(done, |todo| todo.mark_as_done());
+    app.add_user(
+        BOB,
+        "bob@example.com",
+        preferences(chrono_tz::UTC, false, false),
+    );
+    let assigned = app.add_todo("Fix the bike", Some(now() + Duration::minutes(10)));
+    app.update_todo(assigned, |todo| {
+        todo.assign(
+            Some(UserId::from(BOB.to_string())),
+            UserId::from(ALICE.to_string()),
+        )
+    });
+    // Without an email address there is nobody to write to.
+    app.add_user("carol", "carol", NotificationPreferences::default());
+    let other = app.add_todo("Book the flights", Some(now() + Duration::minutes(10)));
+    app.update_todo(other, |todo| {
+        todo.assign(
+            Some(UserId::from("carol".to_string())),
+            UserId::from(ALICE.to_string()),
+        )
+    });
+
+    let sent = app.scheduler().poll(now()).await.unwrap();
+
+    assert_eq!(sent, 0);
+    assert!(app.sink.take_emails().is_empty());
+}
+
+#[actix_web::test]
+async fn postponed_todo_is_reminded_of_again() {
+    let app = TestApp::spawn().await;
+    let todo = app.add_todo("Call the dentist", Some(now() + Duration::minutes(10)));
+    app.scheduler().poll(now()).await.unwrap();
+
+    let later = now() + Duration::days(1);
+    app.update_todo(todo, |todo| {
+        todo.update_due_at(Some(later + Duration::minutes(10)))
+    });
+    app.scheduler().poll(later).await.unwrap();
+
+    assert_eq!(app.sink.take_emails().len(), 2);
+}
+
+#[actix_web::test]
+async fn overdue_todo_missed_while_down_is_reminded_of_late() {
+    let app = TestApp::spawn().await;
+    app.add_todo("Call the dentist", Some(now() - Duration::minutes(30)));
+    app.add_todo("Pay the rent", Some(now() - Duration::days(2)));
+
+    app.scheduler().poll(now()).await.unwrap();
+
+    let emails = app.sink.take_emails();
+    assert_eq!(emails.len(), 1);
+    assert!(emails[0].text.contains("Overdue since"));
+}
+
+#[actix_web::test]
+async fn failed_reminder_is_retried_on_the_next_poll() {
+    let app = TestApp::spawn().await;
+    app.add_todo("Call the dentist", Some(now() + Duration::minutes(10)));
+    let scheduler = app.scheduler();
+
+    app.sink.reject.store(true, Ordering::SeqCst);
+    let failed = scheduler.poll(now()).await.unwrap();
+    app.sink.reject.store(false, Ordering::SeqCst);
+    let retried = scheduler.poll(now() + Duration::minutes(1)).await.unwrap();
+
+    assert_eq!((failed, retried), (0, 1));
+    assert_eq!(app.sink.take_emails().len(), 1);
+}
+
+#[actix_web::test]
+async fn daily_digest_is_sent_once_a_day_from_the_digest_hour_in_the_users_time_zone() {
+    let app = TestApp::spawn().await;
+    app.add_user(
+        ALICE,
+        "alice@example.com",
+        preferences(chrono_tz::America::New_York, false, true),
+    );
+    app.add_todo("Pay the rent", Some(now() - Duration::days(2)));
+    app.add_todo("Renew the passport", Some(now() + Duration::days(3)));
+    app.add_todo("Water the plants", None);
+    let done = app.add_todo("Call the dentist", None);
+    app.update_todo(done, |todo| todo.mark_as_done());
+
+    // 04:00 in New York, before the digest hour.
+    let early = app.scheduler().poll(now()).await.unwrap();
+    let morning = app
+        .scheduler()
+        .poll(now() + Duration::hours(3))
+        .await
+        .unwrap();
+    let afternoon = app
+        .scheduler()
+        .poll(now() + Duration::hours(9))
+        .await
+        .unwrap();
+    let next_morning = app
+        .scheduler()
+        .poll(now() + Duration::hours(27))
+        .await
+        .unwrap();
+
+    assert_eq!((early, morning, afternoon, next_morning), (0, 1, 0, 1));
+    let emails = app.sink.take_emails();
+    assert_eq!(emails[0].subject, "Your todos for Monday 19 October");
+    assert_eq!(emails[1].subject, "Your todos for Tuesday 20 October");
+    let text = &emails[0].text;
+    let overdue = text.find("Overdue:").unwrap();
+    let open = text.find("Open:").unwrap();
+    assert!(overdue < text.find("Pay the rent").unwrap());
+    assert!(open < text.find("Renew the passport").unwrap());
+    assert!(text.find("Renew the passport").unwrap() < text.find("Water the plants").unwrap());
+    assert!(!text.contains("Call the dentist"));
+    assert!(text.contains("https://todo.example.com/me/notifications"));
+}
+
+#[actix_web::test]
+async fn daily_digest_is_opt_in_and_skipped_without_open_todos() {
+    let app = TestApp::spawn().await;
+    app.add_todo("Water the plants", None);
+    app.add_user(
+        BOB,
+        "bob@example.com",
+        preferences(chrono_tz::UTC, true, true),
+    );
+
+    let sent = app.scheduler().poll(now()).await.unwrap();
+
+    assert_eq!(sent, 0);
+    assert!(app.sink.take_emails().is_empty());
+}
@@ -0,0 +1,386 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use actix_web::{
+    http::{header, StatusCode},
+    middleware, test, web, App, HttpResponse, HttpServer,
+};
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{get_current_timestamp, Algorithm, EncodingKey, Header};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde_json::{json, Value};
+use todo_app::{
+    auth::{self, AuthContext, Authenticator, JwtValidator},
+    configuration::{AuthorizationSettings, OidcSettings, RoleRule},
+};
+
+const ISSUER: &str = "https://issuer.example.com/";
+const AUDIENCE: &str = "todo-app";
+
+struct TestKey {
+    kid: String,
+    pkcs8: Vec<u8>,
+    jwk: Value,
+}
+
+impl TestKey {
+    /// Generates a fresh P-256 keypair, so no key material has to be checked in.
+    fn generate(kid: &str) -> Self {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .expect("Failed to generate keypair");
+        let keypair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                .expect("Failed to parse keypair");
+
+        // Uncompressed SEC1 point: 0x04 || x || y
+        let public_key = keypair.public_key().as_ref();
+        let jwk = json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "use": "sig",
+            "alg": "ES256",
+            "kid": kid,
+            "x": BASE64_URL_SAFE_NO_PAD.encode(&public_key[1..33]),
+            "y": BASE64_URL_SAFE_NO_PAD.encode(&public_key[33..65]),
+        });
+
+        Self {
+            kid: kid.to_string(),
+            pkcs8: pkcs8.as_ref().to_vec(),
+            jwk,
+        }
+    }
+
+    fn sign(&self, claims: &Value) -> String {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.kid.clone());
+        jsonwebtoken::encode(&header, claims, &EncodingKey::from_ec_der(&self.pkcs8))
+            .expect("Failed to sign token")
+    }
+}
+
+struct StubJwksServer {
+    url: String,
+    requests: Arc<AtomicUsize>,
+}
+
+impl StubJwksServer {
+    fn spawn(keys: Vec<Value>) -> Self {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let jwks = json!({ "keys": keys });
+
+        let counter = requests.clone();
+        let server = HttpServer::new(move || {
+            let counter = counter.clone();
+            let jwks = jwks.clone();
+            App::new().route(
+                "/jwks",
+                web::get().to(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let jwks = jwks.clone();
+                    async move { HttpResponse::Ok().json(jwks) }
+                }),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("Failed to bind stub JWKS server");
+
+        let port = server.addrs()[0].port();
+        tokio::spawn(server.run());
+
+        Self {
+            url: format!("http://127.0.0.1:{}/jwks", port),
+            requests,
+        }
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+fn valid_claims() -> Value {
+    json!({
+        "iss": ISSUER,
+        "aud": AUDIENCE,
+        "sub": "user-1",
+        "email": "user@example.com",
+        "roles": ["admin", "user"],
+        "iat": get_current_timestamp(),
+        "exp": get_current_timestamp() + 300,
+    })
+}
+
+async fn whoami(auth_ctx: web::ReqData<AuthContext>) -> HttpResponse {
+    let roles = auth_ctx
+        .claims
+        .claims
+        .iter()
+        .filter(|claim| claim.typ == "roles")
+        .map(|claim| claim.val.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    HttpResponse::Ok().body(format!(
+        "{}|{}|{}|{}",
+        auth_ctx.principal_id, auth_ctx.principal_name, auth_ctx.idp, roles
+    ))
+}
+
+macro_rules! test_app {
+    ($jwks_url:expr) => {
+        test_app!($jwks_url, AuthorizationSettings::default())
+    };
+    ($jwks_url:expr, $authorization:expr) => {{
+        let settings = OidcSettings {
+            issuer: ISSUER.to_string(),
+            audience: AUDIENCE.to_string(),
+            jwks_url: $jwks_url,
+            jwks_cache_ttl_seconds: 3600,
+            leeway_seconds: 0,
+        };
+
+        test::init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::Oidc(JwtValidator::new(
+                    settings,
+                ))))
+                .app_data(web::Data::new($authorization))
+                .service(
+                    web::scope("/me")
+                        .wrap(middleware::from_fn(auth::auth_middleware))
+                        .route("whoami", web::get().to(whoami)),
+                )
+                .service(
+                    web::scope("/admin")
+                        .wrap(auth::RequireRole::new(auth::Role::Admin))
+                        .wrap(middleware::from_fn(auth::auth_middleware))
+                        .route("whoami", web::get().to(whoami)),
+                ),
+        )
+        .await
+    }};
+}
+
+/// Calls the test app, turning errors raised by the auth middleware into their responses.
+macro_rules! call_with_token {
+    ($app:expr, $token:expr) => {
+        call_with_token!($app, $token, "/me/whoami")
+    };
+    ($app:expr, $token:expr, $uri:expr) => {{
+        let token: Option<&str> = $token;
+        let mut req = test::TestRequest::get().uri($uri);
+        if let Some(token) = token {
+            req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+        }
+
+        match test::try_call_service($app, req.to_request()).await {
+            Ok(resp) => {
+                let status = resp.status();
+                let body = test::read_body(resp).await;
+                (status, String::from_utf8_lossy(&body).into_owned())
+            }
+            Err(err) => (err.as_response_error().status_code(), err.to_string()),
+        }
+    }};
+}
+
+#[actix_web::test]
+async fn valid_token_is_mapped_to_auth_context() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone());
+
+    let (status, body) = call_with_token!(&app, Some(&key.sign(&valid_claims())));
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        format!("user-1|user@example.com|{}|admin,user", ISSUER)
+    );
+}
+
+#[actix_web::test]
+async fn jwks_is_cached_between_requests() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone());
+
+    for _ in 0..3 {
+        let (status, _) = call_with_token!(&app, Some(&key.sign(&valid_claims())));
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    assert_eq!(jwks.requests(), 1);
+}
+
+#[actix_web::test]
+async fn missing_token_is_rejected_with_challenge() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone());
+
+    let err = test::try_call_service(
+        &app,
+        test::TestRequest::get().uri("/me/whoami").to_request(),
+    )
+    .await
+    .expect_err("Request without token must be rejected");
+    let resp = err.error_response();
+
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers().contains_key(header::WWW_AUTHENTICATE));
+    assert_eq!(jwks.requests(), 0);
+}
+
+#[actix_web::test]
+async fn invalid_claims_are_rejected() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone());
+
+    let overrides = [
+        ("exp", json!(get_current_timestamp() - 60)),
+        ("aud", json!("another-app")),
+        ("iss", json!("https://evil.example.com/")),
+    ];
+
+    for (claim, value) in overrides {
+        let mut claims = valid_claims();
+        claims[claim] = value;
+
+        let (status, _) = call_with_token!(&app, Some(&key.sign(&claims)));
+
+        assert_eq!(
+            status,
+            StatusCode::UNAUTHORIZED,
+            "Accepted invalid {}",
+            claim
+        );
+    }
+
+    let mut claims = valid_claims();
+    claims.as_object_mut().unwrap().remove("sub");
+    let (status, _) = call_with_token!(&app, Some(&key.sign(&claims)));
+    assert_eq!(
+        status,
+        StatusCode::UNAUTHORIZED,
+        "Accepted token without sub"
+    );
+}
+
+#[actix_web::test]
+async fn tokens_not_signed_by_published_keys_are_rejected() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone());
+
+    // Same key id as the published key, different private key.
+    let forged = TestKey::generate("key-1");
+    let (status, _) = call_with_token!(&app, Some(&forged.sign(&valid_claims())));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let unknown = TestKey::generate("key-2");
+    let (status, _) = call_with_token!(&app, Some(&unknown.sign(&valid_claims())));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = call_with_token!(&app, Some("not-a-jwt"));
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn symmetric_tokens_are_rejected() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone());
+
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some(key.kid.clone());
+    let token = jsonwebtoken::encode(
+        &header,
+        &valid_claims(),
+        &EncodingKey::from_secret(key.jwk.to_string().as_bytes()),
+    )
+    .unwrap();
+
+    let (status, _) = call_with_token!(&app, Some(&token));
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+fn admins_by_email_domain() -> AuthorizationSettings {
+    AuthorizationSettings {
+        user: None,
+        admin: RoleRule {
+            email_domains: vec!["corp.example.com".to_string()],
+            ..RoleRule::default()
+        },
+    }
+}
+
+#[actix_web::test]
+async fn verified_emails_grant_roles_by_domain() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone(), admins_by_email_domain());
+
+    let mut claims = valid_claims();
+    claims["email"] = json!("boss@corp.example.com");
+    claims["email_verified"] = json!(true);
+
+    let (status, _) = call_with_token!(&app, Some(&key.sign(&claims)), "/admin/whoami");
+
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn unverified_emails_do_not_grant_roles_by_domain() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone(), admins_by_email_domain());
+
+    for email_verified in [None, Some(json!(false)), Some(json!("false"))] {
+        let mut claims = valid_claims();
+        claims["email"] = json!("boss@corp.example.com");
+        if let Some(email_verified) = &email_verified {
+            claims["email_verified"] = email_verified.clone();
+        }
+
+        let (status, _) = call_with_token!(&app, Some(&key.sign(&claims)), "/admin/whoami");
+
+        assert_eq!(
+            status,
+            StatusCode::FORBIDDEN,
+            "email_verified: {:?}",
+            email_verified
+        );
+    }
+}
+
+#[actix_web::test]
+async fn names_that_look_like_emails_do_not_grant_roles_by_domain() {
+    let key = TestKey::generate("key-1");
+    let jwks = StubJwksServer::spawn(vec![key.jwk.clone()]);
+    let app = test_app!(jwks.url.clone(), admins_by_email_domain());
+
+    for name_claim in ["preferred_username", "name"] {
+        let mut claims = valid_claims();
+        claims.as_object_mut().unwrap().remove("email");
+        claims[name_claim] = json!("boss@corp.example.com");
+        claims["email_verified"] = json!(true);
+
+        let (status, body) = call_with_token!(&app, Some(&key.sign(&claims)), "/me/whoami");
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("|boss@corp.example.com|"), "{}", body);
+
+        let (status, _) = call_with_token!(&app, Some(&key.sign(&claims)), "/admin/whoami");
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", name_claim);
+    }
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use std::collections::{BTreeSet, HashMap};
+
+use common::StubCosmos;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use todo_app::startup;
+
+const METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+];
+const CSRF_TOKEN: &str = "openapi-drift-test";
+const TODO_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// Starts the app with development authentication on a stub database holding a single
+/// todo, so that routed requests about it do not fail with `404 Not Found`.
+fn spawn_app() -> String {
+    let cosmos = StubCosmos::spawn(HashMap::from([(
+        "todos",
+        vec![json!({
+            "id": TODO_ID,
+            "content": "Check the OpenAPI specification",
+            "done": false,
+            "created_by": "local-user",
+            "created_at": "2026-10-01T08:00:00Z",
+        })],
+    )]));
+
+    common::spawn_app(common::test_settings(&cosmos.url))
+}
+
+async fn send(client: &reqwest::Client, method: Method, url: &str) -> StatusCode {
+    let request = client
+        .request(method.clone(), url)
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .header("X-CSRF-Token", CSRF_TOKEN);
+    let request = match method {
+        Method::POST => request.form(&[("content", "Check the OpenAPI specification")]),
+        Method::PATCH => request
+            .header("Content-Type", "application/json")
+            .body("{}"),
+        _ => request,
+    };
+
+    request
+        .send()
+        .await
+        .expect("Failed to send request")
+        .status()
+}
+
+fn is_routed(status: StatusCode) -> bool {
+    status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED
+}
+
+/// Every route of the documented API, as `startup::configure` registers it.
+fn routes() -> BTreeSet<(String, String)> {
+    startup::api_routes()
+        .into_iter()
+        .map(|route| (route.method.to_string(), format!("/me/{}", route.path)))
+        .collect()
+}
+
+#[actix_web::test]
+async fn specification_documents_every_route() {
+    let address = spawn_app();
+
+    let spec = reqwest::get(format!("{}/api/openapi.json", address))
+        .await
+        .expect("Failed to fetch specification")
+        .text()
+        .await
+        .expect("Failed to read specification");
+    let spec = serde_json::from_str::<Value>(&spec).expect("Specification is not JSON");
+    let documented = spec["paths"]
+        .as_object()
+        .expect("Specification has no paths")
+        .iter()
+        .flat_map(|(path, operations)| {
+            METHODS
+                .iter()
+                .filter(|method| operations.get(method.as_str().to_lowercase()).is_some())
+                .map(move |method| (method.to_string(), path.clone()))
+        })
+        .collect::<BTreeSet<_>>();
+
+    let routes = routes();
+    let undocumented = routes.difference(&documented).collect::<Vec<_>>();
+    let unknown = documented.difference(&routes).collect::<Vec<_>>();
+    assert!(
+        undocumented.is_empty() && unknown.is_empty(),
+        "Routes drifted from the specification, undocumented: {:?}, documented but not routed: {:?}",
+        undocumented,
+        unknown
+    );
+}
+
+#[actix_web::test]
+async fn routes_are_registered_for_exactly_their_methods() {
+    let address = spawn_app();
+    let client = reqwest::Client::new();
+    let routes = routes();
+    let paths = routes
+        .iter()
+        .map(|(_, path)| path.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let mut drift = Vec::new();
+    for path in paths {
+        let url = format!("{}{}", address, path.replace("{todo_id}", TODO_ID));
+        for method in METHODS {
+            let is_registered = routes.contains(&(method.to_string(), path.to_string()));
+            let status = send(&client, method.clone(), &url).await;
+
+            if is_registered != is_routed(status) {
+                drift.push(format!(
+                    "{} {} is {}registered, but answered {}",
+                    method,
+                    path,
+                    if is_registered { "" } else { "not " },
+                    status
+                ));
+            }
+        }
+    }
+
+    assert!(
+        drift.is_empty(),
+        "Routes answered other methods than they are registered for:\n{}",
+        drift.join("\n")
+    );
+}
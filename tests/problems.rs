@@ -0,0 +1,200 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::{Response, StatusCode};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+const CSRF_TOKEN: &str = "problems-test";
+const DAV_USERNAME: &str = "developer@localhost";
+const APP_PASSWORD: &str = "problems-test-app-password";
+
+/// The developer syncs their calendar with an app password. Alice's list is shared with
+/// them through a list member document missing its owner name, which fails every lookup
+/// of the developer's shared lists.
+fn spawn_app() -> String {
+    let cosmos = StubCosmos::spawn(HashMap::from([
+        (
+            "app_passwords",
+            vec![json!({
+                "id": format!("{:x}", Sha256::digest(APP_PASSWORD.as_bytes())),
+                "user_id": "local-user",
+                "principal_name": DAV_USERNAME,
+                "name": "Phone",
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+        (
+            "list_members",
+            vec![json!({
+                "id": uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, b"alice\nlocal-user"),
+                "owner_id": "alice",
+                "member_id": "local-user",
+                "role": "editor",
+            })],
+        ),
+    ]));
+    common::spawn_app(common::test_settings(&cosmos.url))
+}
+
+async fn create_todo(address: &str, content: &str, accept: &str) -> Response {
+    reqwest::Client::new()
+        .post(format!("{}/me/todos", address))
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .header("Accept", accept)
+        .form(&[("content", content), ("csrf_token", CSRF_TOKEN)])
+        .send()
+        .await
+        .expect("Failed to create todo")
+}
+
+fn request_id(response: &Response) -> String {
+    response
+        .headers()
+        .get("x-request-id")
+        .expect("No x-request-id header")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+fn content_type(response: &Response) -> String {
+    response
+        .headers()
+        .get("Content-Type")
+        .map(|content_type| content_type.to_str().unwrap().to_string())
+        .unwrap_or_default()
+}
+
+/// Checks the response is a problem of the status, identified by the request's id.
+async fn problem(response: Response, status: StatusCode) -> Value {
+    assert_eq!(response.status(), status);
+    assert_eq!(content_type(&response), "application/problem+json");
+    let request_id = request_id(&response);
+
+    let problem: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(problem["status"], status.as_u16());
+    assert_eq!(problem["title"], status.canonical_reason().unwrap());
+    assert_eq!(problem["request_id"], request_id);
+    problem
+}
+
+#[actix_web::test]
+async fn validation_errors_are_problems_listing_the_fields() {
+    let address = spawn_app();
+
+    let response = create_todo(&address, "", "application/json").await;
+
+    let problem = problem(response, StatusCode::BAD_REQUEST).await;
+    assert_eq!(problem["errors"][0]["field"], "content");
+    assert_eq!(problem["detail"], problem["errors"][0]["message"]);
+}
+
+#[actix_web::test]
+async fn server_errors_are_problems_without_details() {
+    let address = spawn_app();
+
+    let response = create_todo(&address, "Water the plants @alice", "application/json").await;
+
+    let problem = problem(response, StatusCode::INTERNAL_SERVER_ERROR).await;
+    assert_eq!(problem["detail"], "Something went wrong");
+    assert!(problem.get("errors").is_none());
+}
+
+#[actix_web::test]
+async fn errors_without_a_body_are_problems() {
+    let address = spawn_app();
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/no-such-page", address))
+        .send()
+        .await
+        .expect("Failed to execute request");
+
+    problem(response, StatusCode::NOT_FOUND).await;
+}
+
+#[actix_web::test]
+async fn browsers_get_an_error_page() {
+    let address = spawn_app();
+
+    let response = create_todo(&address, "", "text/html,application/xhtml+xml").await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(content_type(&response).starts_with("text/html"));
+    let request_id = request_id(&response);
+    assert!(response.text().await.unwrap().contains(&request_id));
+}
+
+#[actix_web::test]
+async fn every_response_carries_its_request_id() {
+    let address = spawn_app();
+    let client = reqwest::Client::new();
+
+    let first = client
+        .get(format!("{}/healthcheck", address))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    let second = client
+        .get(format!("{}/healthcheck", address))
+        .send()
+        .await
+        .expect("Failed to execute request");
+
+    assert!(first.status().is_success());
+    assert_ne!(request_id(&first), request_id(&second));
+}
+
+#[actix_web::test]
+async fn calendar_errors_are_passed_through() {
+    let address = spawn_app();
+    let client = reqwest::Client::new();
+    let dav = |method: &str, path: &str| {
+        client
+            .request(
+                reqwest::Method::from_bytes(method.as_bytes()).unwrap(),
+                format!("{}{}", address, path),
+            )
+            .basic_auth(DAV_USERNAME, Some(APP_PASSWORD))
+    };
+
+    let response = dav("REPORT", "/dav/calendars/todos/")
+        .body(
+            r#"<?xml version="1.0"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>garbage</D:sync-token>
+  <D:prop><D:getetag/></D:prop>
+</D:sync-collection>"#,
+        )
+        .send()
+        .await
+        .expect("Failed to sync calendar");
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_ne!(content_type(&response), "application/problem+json");
+    request_id(&response);
+    let body = response.text().await.unwrap();
+    assert!(body.contains("valid-sync-token"), "{}", body);
+
+    let response = dav("DELETE", "/dav/calendars/todos/missing.ics")
+        .send()
+        .await
+        .expect("Failed to delete calendar object");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_ne!(content_type(&response), "application/problem+json");
+
+    let response = client
+        .request(
+            reqwest::Method::from_bytes(b"PROPFIND").unwrap(),
+            format!("{}/dav/calendars/todos/", address),
+        )
+        .basic_auth(DAV_USERNAME, Some("wrong-password"))
+        .send()
+        .await
+        .expect("Failed to list calendar");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key("WWW-Authenticate"));
+    assert_ne!(content_type(&response), "application/problem+json");
+}
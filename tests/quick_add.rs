@@ -0,0 +1,371 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::{America::New_York, Europe::Warsaw, Tz};
+use todo_app::quick_add::{parse, Frequency, Priority, QuickAdd, Recurrence};
+
+/// Monday 2026-10-19 08:00 UTC, 10:00 in Warsaw and 04:00 in New York.
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 19, 8, 0, 0).unwrap()
+}
+
+fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+        .unwrap()
+}
+
+/// The text as typed in Warsaw.
+fn parse_in_warsaw(input: &str) -> QuickAdd {
+    parse(input, now(), Warsaw)
+}
+
+fn due_at(input: &str) -> Option<DateTime<Utc>> {
+    parse_in_warsaw(input).due_at
+}
+
+fn content(input: &str) -> String {
+    parse_in_warsaw(input).content
+}
+
+fn every(frequency: Frequency, interval: u32) -> Option<Recurrence> {
+    Some(Recurrence {
+        frequency,
+        interval,
+    })
+}
+
+#[test]
+fn all_details_are_read_out_of_the_text() {
+    let todo = parse_in_warsaw("Pay rent tomorrow 9am #home !high every month");
+
+    assert_eq!(
+        todo,
+        QuickAdd {
+            content: "Pay rent".to_string(),
+            // 09:00 summer time in Warsaw.
+            due_at: Some(utc(2026, 10, 20, 7, 0)),
+            tags: vec!["home".to_string()],
+            priority: Some(Priority::High),
+            list: None,
+            recurrence: every(Frequency::Monthly, 1),
+        }
+    );
+}
+
+#[test]
+fn text_without_details_is_left_alone() {
+    let todo = parse_in_warsaw("  Buy   milk and bread ");
+
+    assert_eq!(
+        todo,
+        QuickAdd {
+            content: "Buy milk and bread".to_string(),
+            due_at: None,
+            tags: Vec::new(),
+            priority: None,
+            list: None,
+            recurrence: None,
+        }
+    );
+}
+
+#[test]
+fn details_can_appear_anywhere_in_the_text() {
+    let todo = parse_in_warsaw("#work !low Send the report to Anna friday");
+
+    assert_eq!(todo.content, "Send the report to Anna");
+    assert_eq!(todo.tags, vec!["work"]);
+    assert_eq!(todo.priority, Some(Priority::Low));
+    assert_eq!(todo.due_at, Some(utc(2026, 10, 23, 7, 0)));
+}
+
+#[test]
+fn text_of_only_details_leaves_a_blank_content() {
+    let todo = parse_in_warsaw("tomorrow #home");
+
+    assert_eq!(todo.content, "");
+    assert_eq!(todo.tags, vec!["home"]);
+    assert!(todo.due_at.is_some());
+}
+
+#[test]
+fn dates_and_times_are_read_in_the_users_time_zone() {
+    let in_warsaw = parse("Call mom at 6pm", now(), Warsaw);
+    let in_new_york = parse("Call mom at 6pm", now(), New_York);
+    let in_utc = parse("Call mom at 6pm", now(), Tz::UTC);
+
+    assert_eq!(in_warsaw.due_at, Some(utc(2026, 10, 19, 16, 0)));
+    assert_eq!(in_new_york.due_at, Some(utc(2026, 10, 19, 22, 0)));
+    assert_eq!(in_utc.due_at, Some(utc(2026, 10, 19, 18, 0)));
+    assert_eq!(in_warsaw.content, "Call mom");
+}
+
+#[test]
+fn today_depends_on_the_time_zone() {
+    // 01:00 UTC on Monday is still Sunday evening in New York.
+    let now = utc(2026, 10, 19, 1, 0);
+
+    let in_new_york = parse("Water the plants tomorrow", now, New_York);
+    let in_warsaw = parse("Water the plants tomorrow", now, Warsaw);
+
+    assert_eq!(in_new_york.due_at, Some(utc(2026, 10, 19, 13, 0)));
+    assert_eq!(in_warsaw.due_at, Some(utc(2026, 10, 20, 7, 0)));
+}
+
+#[test]
+fn time_alone_is_the_next_time_the_clock_shows_it() {
+    // It is 10:00 in Warsaw.
+    assert_eq!(due_at("Stand-up 9:30"), Some(utc(2026, 10, 20, 7, 30)));
+    assert_eq!(due_at("Lunch at noon"), Some(utc(2026, 10, 19, 10, 0)));
+    assert_eq!(due_at("Backup at midnight"), Some(utc(2026, 10, 19, 22, 0)));
+}
+
+#[test]
+fn dates_without_a_time_are_due_in_the_morning() {
+    assert_eq!(
+        due_at("Renew the passport 2026-11-05"),
+        Some(utc(2026, 11, 5, 8, 0))
+    );
+    assert_eq!(due_at("Call the bank today"), Some(utc(2026, 10, 19, 7, 0)));
+    assert_eq!(
+        due_at("Take out the trash tonight"),
+        Some(utc(2026, 10, 19, 18, 0))
+    );
+    assert_eq!(
+        due_at("Take out the trash tonight at 10pm"),
+        Some(utc(2026, 10, 19, 20, 0))
+    );
+}
+
+#[test]
+fn relative_dates_are_counted_from_today() {
+    assert_eq!(due_at("Call back tomorrow"), Some(utc(2026, 10, 20, 7, 0)));
+    assert_eq!(due_at("Call back tmrw"), Some(utc(2026, 10, 20, 7, 0)));
+    assert_eq!(due_at("Call back in 3 days"), Some(utc(2026, 10, 22, 7, 0)));
+    assert_eq!(due_at("Call back in 2 weeks"), Some(utc(2026, 11, 2, 8, 0)));
+    assert_eq!(
+        due_at("Call back in a month"),
+        Some(utc(2026, 11, 19, 8, 0))
+    );
+    assert_eq!(
+        due_at("Call back in 1 year at 8am"),
+        Some(utc(2027, 10, 19, 6, 0))
+    );
+}
+
+#[test]
+fn relative_times_are_counted_from_now() {
+    assert_eq!(
+        due_at("Check the oven in 30 minutes"),
+        Some(now() + Duration::minutes(30))
+    );
+    assert_eq!(
+        due_at("Move the car in an hour"),
+        Some(now() + Duration::hours(1))
+    );
+
+    // A time cannot be added to them, it is left in the content.
+    let todo = parse_in_warsaw("Move the car in 2 hours at 5pm");
+    assert_eq!(todo.due_at, Some(now() + Duration::hours(2)));
+    assert_eq!(todo.content, "Move the car at 5pm");
+}
+
+#[test]
+fn weekdays_are_the_next_one_after_today() {
+    // Today is Monday.
+    assert_eq!(due_at("Gym wednesday"), Some(utc(2026, 10, 21, 7, 0)));
+    assert_eq!(due_at("Gym Friday 7pm"), Some(utc(2026, 10, 23, 17, 0)));
+    assert_eq!(due_at("Gym monday"), Some(utc(2026, 10, 26, 8, 0)));
+    assert_eq!(due_at("Gym sunday"), Some(utc(2026, 10, 25, 8, 0)));
+}
+
+#[test]
+fn next_is_read_as_in_the_coming_week_month_or_year() {
+    assert_eq!(due_at("Plan next week"), Some(utc(2026, 10, 26, 8, 0)));
+    assert_eq!(due_at("Plan next wednesday"), Some(utc(2026, 10, 28, 8, 0)));
+    assert_eq!(due_at("Plan next month"), Some(utc(2026, 11, 1, 8, 0)));
+    assert_eq!(due_at("Plan next year"), Some(utc(2027, 1, 1, 8, 0)));
+    assert_eq!(content("Plan the next step"), "Plan the next step");
+}
+
+#[test]
+fn days_of_months_are_the_next_time_they_come_around() {
+    assert_eq!(due_at("Dentist 5 nov"), Some(utc(2026, 11, 5, 8, 0)));
+    assert_eq!(due_at("Dentist November 5th"), Some(utc(2026, 11, 5, 8, 0)));
+    assert_eq!(due_at("Dentist on 5th of"), None);
+    assert_eq!(due_at("Dentist Nov 5, 2027"), Some(utc(2027, 11, 5, 8, 0)));
+    assert_eq!(due_at("Dentist 19 oct"), Some(utc(2026, 10, 19, 7, 0)));
+    assert_eq!(due_at("Dentist 1 jan"), Some(utc(2027, 1, 1, 8, 0)));
+    assert_eq!(due_at("Dentist sept 3rd"), Some(utc(2027, 9, 3, 7, 0)));
+    assert_eq!(due_at("Leap day feb 29"), Some(utc(2028, 2, 29, 8, 0)));
+    assert_eq!(due_at("Dentist 31 nov"), None);
+}
+
+#[test]
+fn clock_times_are_read_in_12_and_24_hour_formats() {
+    let at = |input| due_at(&format!("Meeting tomorrow {}", input));
+
+    assert_eq!(at("9am"), Some(utc(2026, 10, 20, 7, 0)));
+    assert_eq!(at("9:45am"), Some(utc(2026, 10, 20, 7, 45)));
+    assert_eq!(at("9 pm"), Some(utc(2026, 10, 20, 19, 0)));
+    assert_eq!(at("12am"), Some(utc(2026, 10, 19, 22, 0)));
+    assert_eq!(at("12pm"), Some(utc(2026, 10, 20, 10, 0)));
+    assert_eq!(at("at 21:15"), Some(utc(2026, 10, 20, 19, 15)));
+    assert_eq!(at("at 7"), Some(utc(2026, 10, 20, 5, 0)));
+}
+
+#[test]
+fn prepositions_are_only_read_before_dates_and_times() {
+    assert_eq!(content("Report due on friday"), "Report");
+    assert_eq!(content("Report by tomorrow at 5pm"), "Report");
+    assert_eq!(content("Meet on the roof"), "Meet on the roof");
+    assert_eq!(content("Look at the stars"), "Look at the stars");
+    assert_eq!(due_at("Meet on the roof"), None);
+}
+
+#[test]
+fn words_that_only_look_like_details_stay_in_the_content() {
+    for input in [
+        "Read 5 chapters",
+        "Buy may flowers",
+        "Pack sun cream",
+        "Put the milk in 2 bags",
+        "Fix issue #42",
+        "Email bob@example.com",
+        "Say hi!",
+        "Score 13pm",
+        "Move 0 days",
+        "Call at 25",
+    ] {
+        let todo = parse_in_warsaw(input);
+
+        assert_eq!(todo.content, input, "{} was read as details", input);
+        assert_eq!(todo.due_at, None, "{} was read as a date", input);
+        assert!(todo.tags.is_empty(), "{} was read as tagged", input);
+        assert_eq!(todo.priority, None, "{} was read as a priority", input);
+        assert_eq!(todo.list, None, "{} was read as a list", input);
+    }
+}
+
+#[test]
+fn only_the_first_date_is_read() {
+    let todo = parse_in_warsaw("Move the meeting from monday to friday");
+
+    assert_eq!(todo.due_at, Some(utc(2026, 10, 26, 8, 0)));
+    assert_eq!(todo.content, "Move the meeting from to friday");
+}
+
+#[test]
+fn escaped_words_are_kept_as_typed() {
+    let todo = parse_in_warsaw(r"Watch \tomorrow never dies \#1 \@home");
+
+    assert_eq!(todo.content, "Watch tomorrow never dies #1 @home");
+    assert_eq!(todo.due_at, None);
+    assert!(todo.tags.is_empty());
+    assert_eq!(todo.list, None);
+}
+
+#[test]
+fn tags_are_lowercase_and_listed_once() {
+    let todo = parse_in_warsaw("Plan the trip #Travel #family/kids, #travel #2026 #a.b");
+
+    assert_eq!(todo.tags, vec!["travel", "family/kids"]);
+    assert_eq!(todo.content, "Plan the trip #2026 #a.b");
+}
+
+#[test]
+fn tags_beyond_the_limit_stay_in_the_content() {
+    let tags = (1..=12).map(|i| format!("#tag{}", i)).collect::<Vec<_>>();
+
+    let todo = parse_in_warsaw(&format!("Sort {}", tags.join(" ")));
+
+    assert_eq!(todo.tags.len(), 10);
+    assert_eq!(todo.content, "Sort #tag11 #tag12");
+}
+
+#[test]
+fn priorities_have_short_forms_and_only_the_first_is_read() {
+    assert_eq!(parse_in_warsaw("Task !h").priority, Some(Priority::High));
+    assert_eq!(
+        parse_in_warsaw("Task !MED").priority,
+        Some(Priority::Medium)
+    );
+    assert_eq!(
+        parse_in_warsaw("Task !medium").priority,
+        Some(Priority::Medium)
+    );
+    assert_eq!(parse_in_warsaw("Task !l").priority, Some(Priority::Low));
+
+    let todo = parse_in_warsaw("Task !low !high");
+    assert_eq!(todo.priority, Some(Priority::Low));
+    assert_eq!(todo.content, "Task !high");
+}
+
+#[test]
+fn list_is_named_with_an_at_sign() {
+    let todo = parse_in_warsaw("Buy paint @bob, #diy");
+    let by_email = parse_in_warsaw("Buy paint @bob@example.com");
+
+    assert_eq!(todo.list.as_deref(), Some("bob"));
+    assert_eq!(todo.content, "Buy paint");
+    assert_eq!(by_email.list.as_deref(), Some("bob@example.com"));
+}
+
+#[test]
+fn recurrences_are_read_in_several_forms() {
+    let recurrence = |input| parse_in_warsaw(input).recurrence;
+
+    assert_eq!(recurrence("Stretch daily"), every(Frequency::Daily, 1));
+    assert_eq!(recurrence("Stretch every day"), every(Frequency::Daily, 1));
+    assert_eq!(recurrence("Clean weekly"), every(Frequency::Weekly, 1));
+    assert_eq!(
+        recurrence("Clean every other week"),
+        every(Frequency::Weekly, 2)
+    );
+    assert_eq!(recurrence("Water every 3 days"), every(Frequency::Daily, 3));
+    assert_eq!(recurrence("Invoice monthly"), every(Frequency::Monthly, 1));
+    assert_eq!(
+        recurrence("Invoice every 2 months"),
+        every(Frequency::Monthly, 2)
+    );
+    assert_eq!(recurrence("Taxes annually"), every(Frequency::Yearly, 1));
+    assert_eq!(recurrence("Taxes every year"), every(Frequency::Yearly, 1));
+    assert_eq!(recurrence("Wait every 0 days"), None);
+    assert_eq!(recurrence("Wait every 2 hours"), None);
+    assert_eq!(content("Stretch every day"), "Stretch");
+    assert_eq!(content("Wait every 0 days"), "Wait every 0 days");
+}
+
+#[test]
+fn recurring_weekday_sets_the_first_due_date() {
+    let todo = parse_in_warsaw("Take out the bins every thursday 7pm");
+    let with_date = parse_in_warsaw("Take out the bins every thursday starting 2026-11-05");
+
+    assert_eq!(todo.recurrence, every(Frequency::Weekly, 1));
+    assert_eq!(todo.due_at, Some(utc(2026, 10, 22, 17, 0)));
+    assert_eq!(todo.content, "Take out the bins");
+    assert_eq!(with_date.due_at, Some(utc(2026, 11, 5, 8, 0)));
+}
+
+#[test]
+fn recurrence_without_a_date_has_no_due_date() {
+    let todo = parse_in_warsaw("Stretch every day");
+
+    assert_eq!(todo.due_at, None);
+}
+
+#[test]
+fn times_skipped_by_daylight_saving_are_taken_an_hour_later() {
+    // Clocks in Warsaw go from 02:00 to 03:00 on 2027-03-28.
+    assert_eq!(
+        due_at("Check the clocks 2027-03-28 2:30am"),
+        Some(utc(2027, 3, 28, 1, 30))
+    );
+}
+
+#[test]
+fn times_repeated_by_daylight_saving_are_the_earlier_one() {
+    // Clocks in Warsaw go from 03:00 back to 02:00 on 2026-10-25.
+    assert_eq!(
+        due_at("Check the clocks sunday 2:30am"),
+        Some(utc(2026, 10, 25, 0, 30))
+    );
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::StubCosmos;
+use reqwest::StatusCode;
+use todo_app::configuration::Settings;
+
+/// Settings are only validated, the database behind this closed port is never reached.
+const UNREACHABLE_COSMOS: &str = "http://127.0.0.1:1/";
+
+/// Feeds are looked up in an empty database, so they are not found, but only after
+/// passing the rate limit.
+fn settings_with_one_anonymous_read(trusted_proxy_hops: usize) -> Settings {
+    let cosmos = StubCosmos::spawn(HashMap::new());
+    let mut settings = common::test_settings(&cosmos.url);
+    settings.limits.reads.burst = 1;
+    settings.limits.reads.per_second = 0.001;
+    settings.limits.trusted_proxy_hops = trusted_proxy_hops;
+    settings
+}
+
+async fn get_feed(address: &str, forwarded_for: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/feeds/some-token/todos.ics", address))
+        .header("X-Forwarded-For", forwarded_for)
+        .send()
+        .await
+        .expect("Failed to send request")
+}
+
+#[actix_web::test]
+async fn forwarded_addresses_are_ignored_without_trusted_proxies() {
+    let address = common::spawn_app(settings_with_one_anonymous_read(0));
+
+    let first = get_feed(&address, "198.51.100.1").await;
+    let second = get_feed(&address, "198.51.100.2").await;
+
+    assert_ne!(first.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_web::test]
+async fn trusted_proxies_tell_clients_apart() {
+    let address = common::spawn_app(settings_with_one_anonymous_read(1));
+
+    let first = get_feed(&address, "198.51.100.1").await;
+    let second = get_feed(&address, "198.51.100.2").await;
+    let spoofed = get_feed(&address, "203.0.113.9, 198.51.100.1").await;
+
+    assert_ne!(first.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_ne!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    // Only the entry appended by the proxy counts.
+    assert_eq!(spoofed.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[test]
+fn rates_must_be_positive() {
+    for per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let mut settings = common::test_settings(UNREACHABLE_COSMOS);
+        settings.limits.writes.per_second = per_second;
+
+        assert!(settings.validate().is_err(), "per_second: {}", per_second);
+    }
+}
+
+#[test]
+fn bursts_must_allow_a_request() {
+    let mut settings = common::test_settings(UNREACHABLE_COSMOS);
+    settings.limits.reads.burst = 0;
+
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn test_settings_are_valid() {
+    assert!(common::test_settings(UNREACHABLE_COSMOS).validate().is_ok());
+}
+
+#[actix_web::test]
+async fn full_lists_refuse_new_todos_with_a_conflict() {
+    let cosmos = StubCosmos::spawn(HashMap::from([(
+        "todos",
+        vec![serde_json::json!({
+            "id": uuid::Uuid::from_u128(1),
+            "content": "Paint the hall",
+            "done": false,
+            "created_by": "local-user",
+            "created_at": "2026-10-01T08:00:00Z",
+        })],
+    )]));
+    let mut settings = common::test_settings(&cosmos.url);
+    settings.limits.max_todos_per_user = 1;
+    let address = common::spawn_app(settings);
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/me/todos", address))
+        .header("Cookie", "csrf_token=rate-limit-test")
+        .header("Accept", "application/json")
+        .form(&[
+            ("content", "Fix the fence"),
+            ("csrf_token", "rate-limit-test"),
+        ])
+        .send()
+        .await
+        .expect("Failed to create todo");
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "application/problem+json"
+    );
+    let problem: serde_json::Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(problem["status"], 409);
+    assert_eq!(problem["detail"], "Todo lists are limited to 1 todos");
+}
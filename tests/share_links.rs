@@ -0,0 +1,178 @@
+mod common;
+
+use std::collections::HashMap;
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use common::StubCosmos;
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use secrecy::SecretString;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+const SIGNING_KEY: &str = "share-links-test-key";
+const CSRF_TOKEN: &str = "share-links-test";
+const SHARE_LINK_ID: u128 = 10;
+
+fn todo(id: u128, owner: &str, content: &str) -> Value {
+    json!({
+        "id": uuid::Uuid::from_u128(id),
+        "content": content,
+        "done": false,
+        "created_by": owner,
+        "created_at": "2026-10-01T08:00:00Z",
+        "tags": ["home"],
+    })
+}
+
+fn share_link(expires_at: Option<&str>) -> Value {
+    json!({
+        "id": uuid::Uuid::from_u128(SHARE_LINK_ID),
+        "user_id": "local-user",
+        "name": "Family chores",
+        "expires_at": expires_at,
+        "created_at": "2026-10-01T08:00:00Z",
+    })
+}
+
+/// Signs a share link token the way the app does, with the test key unless another one
+/// is given.
+fn token(key: &str, id: u128, expires_at: i64) -> String {
+    let payload = format!("{}.{}", uuid::Uuid::from_u128(id), expires_at);
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    let signature = BASE64_URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{payload}.{signature}")
+}
+
+/// The list of the signed in developer, with a share link to it, next to someone else's.
+fn spawn_app(share_links: Vec<Value>) -> String {
+    let cosmos = StubCosmos::spawn(HashMap::from([
+        (
+            "todos",
+            vec![
+                todo(1, "local-user", "Paint the hall"),
+                todo(2, "someone-else", "Not on the shared list"),
+            ],
+        ),
+        ("share_links", share_links),
+    ]));
+    let mut settings = common::test_settings(&cosmos.url);
+    settings.share_links.signing_key = Some(SecretString::from(SIGNING_KEY));
+    common::spawn_app(settings)
+}
+
+async fn open(address: &str, token: &str) -> (StatusCode, String) {
+    let response = reqwest::Client::new()
+        .get(format!("{}/shared/{}", address, token))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    (response.status(), response.text().await.unwrap())
+}
+
+#[actix_web::test]
+async fn created_links_show_the_list_read_only() {
+    let address = spawn_app(vec![]);
+    let client = reqwest::Client::new();
+
+    let html = client
+        .post(format!("{}/me/share-links", address))
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .form(&[
+            ("name", "Family chores"),
+            ("expires_in", "week"),
+            ("csrf_token", CSRF_TOKEN),
+        ])
+        .send()
+        .await
+        .expect("Failed to create share link")
+        .text()
+        .await
+        .unwrap()
+        .replace("&#x2F;", "/");
+    let start = html.find("/shared/").expect("No share link on the page") + "/shared/".len();
+    let token = html[start..]
+        .split(|c: char| c == '"' || c == '<' || c.is_whitespace())
+        .next()
+        .unwrap();
+
+    let (status, html) = open(&address, token).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("Family chores"));
+    assert!(html.contains("Paint the hall"));
+    assert!(html.contains("#home"));
+    assert!(!html.contains("Not on the shared list"));
+    assert!(!html.contains("local-user"));
+    assert!(!html.contains(&uuid::Uuid::from_u128(1).to_string()));
+    assert!(!html.contains("/me/"));
+    assert!(!html.contains("EventSource"));
+}
+
+#[actix_web::test]
+async fn links_signed_with_another_key_are_not_found() {
+    let address = spawn_app(vec![share_link(None)]);
+
+    let (status, _) = open(&address, &token(SIGNING_KEY, SHARE_LINK_ID, 0)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = open(&address, &token("another-key", SHARE_LINK_ID, 0)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn tampered_links_are_not_found() {
+    let address = spawn_app(vec![share_link(None)]);
+    let expires_at = (chrono::Utc::now() - chrono::Duration::days(1)).timestamp();
+    let expired = token(SIGNING_KEY, SHARE_LINK_ID, expires_at);
+    let (_, signature) = expired.rsplit_once('.').unwrap();
+
+    // Extending the expiry invalidates the signature.
+    let extended = format!("{}.0.{}", uuid::Uuid::from_u128(SHARE_LINK_ID), signature);
+    let (status, _) = open(&address, &extended).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for token in ["", "garbage", "a.b.c"] {
+        let (status, _) = open(&address, token).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "token: {:?}", token);
+    }
+}
+
+#[actix_web::test]
+async fn expired_links_are_not_found() {
+    let yesterday = chrono::Utc::now() - chrono::Duration::days(1);
+    let address = spawn_app(vec![share_link(Some(&yesterday.to_rfc3339()))]);
+
+    let (status, _) = open(
+        &address,
+        &token(SIGNING_KEY, SHARE_LINK_ID, yesterday.timestamp()),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn revoked_links_are_not_found() {
+    let address = spawn_app(vec![share_link(None)]);
+    let token = token(SIGNING_KEY, SHARE_LINK_ID, 0);
+    let (status, _) = open(&address, &token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let response = reqwest::Client::new()
+        .delete(format!(
+            "{}/me/share-links/{}",
+            address,
+            uuid::Uuid::from_u128(SHARE_LINK_ID)
+        ))
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .header("X-CSRF-Token", CSRF_TOKEN)
+        .send()
+        .await
+        .expect("Failed to revoke share link");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let (status, _) = open(&address, &token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
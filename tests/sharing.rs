@@ -0,0 +1,243 @@
+mod common;
+
+use std::collections::HashMap;
+
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use common::StubCosmos;
+use reqwest::{redirect::Policy, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+
+const CSRF_TOKEN: &str = "sharing-test";
+
+fn user(id: &str, principal_name: &str) -> Value {
+    json!({
+        "id": id,
+        "principal_name": principal_name,
+        "first_seen_at": "2026-10-01T08:00:00Z",
+        "last_seen_at": "2026-10-01T08:00:00Z",
+    })
+}
+
+struct TestApp {
+    address: String,
+    cosmos_url: String,
+    client: reqwest::Client,
+}
+
+impl TestApp {
+    /// The developer's list holds one todo. Bob and Carol have signed in before.
+    fn spawn() -> Self {
+        let cosmos = StubCosmos::spawn(HashMap::from([
+            (
+                "todos",
+                vec![json!({
+                    "id": uuid::Uuid::from_u128(1),
+                    "content": "Paint the hall",
+                    "done": false,
+                    "created_by": "local-user",
+                    "created_at": "2026-10-01T08:00:00Z",
+                })],
+            ),
+            (
+                "users",
+                vec![
+                    user("bob", "bob@example.com"),
+                    user("carol", "carol@example.com"),
+                ],
+            ),
+        ]));
+
+        Self {
+            address: common::spawn_app(common::test_settings(&cosmos.url)),
+            cosmos_url: cosmos.url.clone(),
+            client: reqwest::Client::builder()
+                .redirect(Policy::none())
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// A request of the signed in developer, or of the given user.
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        as_user: Option<&str>,
+    ) -> RequestBuilder {
+        let mut cookie = format!("csrf_token={}", CSRF_TOKEN);
+        if let Some(user_id) = as_user {
+            let principal = json!({
+                "principal_id": user_id,
+                "principal_name": format!("{}@example.com", user_id),
+            });
+            cookie.push_str(&format!(
+                "; dev_principal={}",
+                BASE64_URL_SAFE_NO_PAD.encode(principal.to_string())
+            ));
+        }
+
+        self.client
+            .request(method, format!("{}{}", self.address, path))
+            .header("Cookie", cookie)
+            .header("X-CSRF-Token", CSRF_TOKEN)
+            .header("Accept", "application/json")
+    }
+
+    async fn share(&self, principal_name: &str, role: &str) -> (StatusCode, String) {
+        let response = self
+            .request(reqwest::Method::POST, "/me/sharing", None)
+            .form(&[("principal_name", principal_name), ("role", role)])
+            .send()
+            .await
+            .expect("Failed to share list");
+        (response.status(), response.text().await.unwrap())
+    }
+
+    async fn revoke(&self, member_id: &str) -> StatusCode {
+        self.request(
+            reqwest::Method::DELETE,
+            &format!("/me/sharing/{}", member_id),
+            None,
+        )
+        .send()
+        .await
+        .expect("Failed to revoke access")
+        .status()
+    }
+
+    /// Reads the developer's list as the given user.
+    async fn read_list(&self, user_id: &str) -> StatusCode {
+        self.request(
+            reqwest::Method::GET,
+            "/me/todos?list=local-user",
+            Some(user_id),
+        )
+        .send()
+        .await
+        .expect("Failed to get todos")
+        .status()
+    }
+
+    /// Adds a todo to the developer's list as the given user.
+    async fn add_todo(&self, user_id: &str) -> StatusCode {
+        self.request(
+            reqwest::Method::POST,
+            "/me/todos?list=local-user",
+            Some(user_id),
+        )
+        .form(&[("content", "Fix the fence")])
+        .send()
+        .await
+        .expect("Failed to create todo")
+        .status()
+    }
+
+    async fn list_members(&self) -> Vec<Value> {
+        let response = self
+            .client
+            .post(format!(
+                "{}dbs/todoappdb/colls/list_members/docs",
+                self.cosmos_url
+            ))
+            .header("x-ms-documentdb-isquery", "true")
+            .body(json!({ "query": "SELECT * FROM c", "parameters": [] }).to_string())
+            .send()
+            .await
+            .expect("Failed to query the database");
+        let body: Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+        body["Documents"].as_array().unwrap().clone()
+    }
+}
+
+#[actix_web::test]
+async fn lists_are_shared_by_principal_name() {
+    let app = TestApp::spawn();
+
+    let (status, _) = app.share(" Bob@Example.com ", "viewer").await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let members = app.list_members().await;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0]["owner_id"], "local-user");
+    assert_eq!(members[0]["member_id"], "bob");
+    assert_eq!(members[0]["member_name"], "bob@example.com");
+    assert_eq!(members[0]["role"], "viewer");
+
+    // Sharing again changes the role.
+    let (status, _) = app.share("bob@example.com", "editor").await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let members = app.list_members().await;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0]["role"], "editor");
+
+    let page = app
+        .request(reqwest::Method::GET, "/me/sharing", None)
+        .send()
+        .await
+        .expect("Failed to get list members");
+    assert_eq!(page.status(), StatusCode::OK);
+    assert!(page.text().await.unwrap().contains("bob@example.com"));
+}
+
+#[actix_web::test]
+async fn lists_are_not_shared_with_unknown_users_or_their_owner() {
+    let app = TestApp::spawn();
+
+    let (status, problem) = app.share("dave@example.com", "viewer").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(problem.contains("principal_name"), "{}", problem);
+
+    let (status, problem) = app.share("developer@localhost", "editor").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(problem.contains("yourself"), "{}", problem);
+
+    assert!(app.list_members().await.is_empty());
+}
+
+#[actix_web::test]
+async fn members_act_on_the_list_as_their_role_allows() {
+    let app = TestApp::spawn();
+    app.share("bob@example.com", "viewer").await;
+    app.share("carol@example.com", "editor").await;
+
+    assert_eq!(app.read_list("bob").await, StatusCode::OK);
+    assert_eq!(app.add_todo("bob").await, StatusCode::FORBIDDEN);
+
+    assert_eq!(app.read_list("carol").await, StatusCode::OK);
+    assert_eq!(app.add_todo("carol").await, StatusCode::SEE_OTHER);
+
+    assert_eq!(app.read_list("dave").await, StatusCode::FORBIDDEN);
+    assert_eq!(app.add_todo("dave").await, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn revoked_members_lose_access() {
+    let app = TestApp::spawn();
+    app.share("carol@example.com", "editor").await;
+    assert_eq!(app.read_list("carol").await, StatusCode::OK);
+
+    assert_eq!(app.revoke("carol").await, StatusCode::NO_CONTENT);
+
+    assert!(app.list_members().await.is_empty());
+    assert_eq!(app.read_list("carol").await, StatusCode::FORBIDDEN);
+    assert_eq!(app.add_todo("carol").await, StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn members_cannot_revoke_other_members() {
+    let app = TestApp::spawn();
+    app.share("bob@example.com", "editor").await;
+    app.share("carol@example.com", "editor").await;
+
+    // Revoking only ever applies to the caller's own list.
+    let status = app
+        .request(reqwest::Method::DELETE, "/me/sharing/carol", Some("bob"))
+        .send()
+        .await
+        .expect("Failed to revoke access")
+        .status();
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    assert_eq!(app.list_members().await.len(), 2);
+    assert_eq!(app.read_list("carol").await, StatusCode::OK);
+}
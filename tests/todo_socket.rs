@@ -0,0 +1,219 @@
+mod common;
+
+use std::{collections::HashMap, time::Duration};
+
+use common::StubCosmos;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, protocol::frame::coding::CloseCode, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+const CSRF_TOKEN: &str = "todo-socket-test";
+/// Alice's token, the only one in the database.
+const PERSONAL_ACCESS_TOKEN: &str = "todo_pat_todo-socket-test";
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The list of the signed in developer, with one todo, shared with Alice as an editor.
+fn spawn_app() -> String {
+    let cosmos = StubCosmos::spawn(HashMap::from([
+        (
+            "todos",
+            vec![json!({
+                "id": uuid::Uuid::from_u128(1),
+                "content": "Paint the hall",
+                "done": false,
+                "created_by": "local-user",
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+        (
+            "list_members",
+            vec![json!({
+                "id": uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, b"local-user\nalice"),
+                "owner_id": "local-user",
+                "owner_name": "developer@localhost",
+                "member_id": "alice",
+                "member_name": "alice@example.com",
+                "role": "editor",
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+        (
+            "personal_access_tokens",
+            vec![json!({
+                "id": "token-hash",
+                "user_id": "alice",
+                "principal_name": "alice@example.com",
+                "name": "Kitchen display",
+                "scopes": ["read", "write"],
+                "created_at": "2026-10-01T08:00:00Z",
+            })],
+        ),
+    ]));
+    common::spawn_app(common::test_settings(&cosmos.url))
+}
+
+/// Opens a socket on the developer's list, as Alice when a token is given.
+async fn connect(address: &str, personal_access_token: Option<&str>) -> Socket {
+    let url = format!(
+        "{}/me/todos/ws?list=local-user",
+        address.replacen("http", "ws", 1)
+    );
+    let mut request = url.into_client_request().unwrap();
+    if let Some(token) = personal_access_token {
+        request.headers_mut().insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .expect("Failed to open socket");
+    socket
+}
+
+/// The next message of the given type, skipping presence updates and the events of
+/// the socket's own mutations.
+async fn next_message(socket: &mut Socket, kind: &str) -> Value {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let message = socket
+                .next()
+                .await
+                .expect("Socket closed")
+                .expect("Failed to read from socket");
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let message: Value = serde_json::from_str(&text).unwrap();
+            if message["type"] == kind {
+                return message;
+            }
+            assert!(
+                ["presence", "created"].contains(&message["type"].as_str().unwrap()),
+                "{}",
+                message
+            );
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("No {} message", kind))
+}
+
+async fn send(socket: &mut Socket, message: Value) {
+    socket
+        .send(Message::text(message.to_string()))
+        .await
+        .expect("Failed to send message");
+}
+
+async fn create_todo(address: &str, content: &str) {
+    let response = reqwest::Client::new()
+        .post(format!("{}/me/todos", address))
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .header("Accept", "application/json")
+        .form(&[("content", content), ("csrf_token", CSRF_TOKEN)])
+        .send()
+        .await
+        .expect("Failed to create todo");
+    assert!(response.status().is_success(), "{}", response.status());
+}
+
+fn contents(snapshot: &Value) -> Vec<&str> {
+    snapshot["todos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|todo| todo["content"].as_str().unwrap())
+        .collect()
+}
+
+#[actix_web::test]
+async fn resync_sends_a_fresh_snapshot() {
+    let address = spawn_app();
+    let mut socket = connect(&address, None).await;
+
+    let snapshot = next_message(&mut socket, "snapshot").await;
+    assert_eq!(snapshot["seq"], 0);
+    assert_eq!(contents(&snapshot), ["Paint the hall"]);
+
+    create_todo(&address, "Fix the fence").await;
+    let created = next_message(&mut socket, "created").await;
+    assert_eq!(created["seq"], 1);
+    assert_eq!(created["data"]["content"], "Fix the fence");
+
+    send(&mut socket, json!({ "type": "resync" })).await;
+    let snapshot = next_message(&mut socket, "snapshot").await;
+    assert_eq!(snapshot["seq"], 1);
+    let mut contents = contents(&snapshot);
+    contents.sort();
+    assert_eq!(contents, ["Fix the fence", "Paint the hall"]);
+}
+
+#[actix_web::test]
+async fn malformed_mutations_are_answered_with_an_error() {
+    let address = spawn_app();
+    let mut socket = connect(&address, None).await;
+    next_message(&mut socket, "snapshot").await;
+
+    send(&mut socket, json!({ "type": "create", "request_id": "1" })).await;
+    let error = next_message(&mut socket, "error").await;
+    assert_eq!(error["status"], 400);
+    assert!(error["detail"].as_str().unwrap().contains("content"));
+
+    send(&mut socket, json!({ "type": "archive" })).await;
+    assert_eq!(next_message(&mut socket, "error").await["status"], 400);
+}
+
+#[actix_web::test]
+async fn revoked_members_are_refused_and_disconnected() {
+    let address = spawn_app();
+    let mut socket = connect(&address, Some(PERSONAL_ACCESS_TOKEN)).await;
+    next_message(&mut socket, "snapshot").await;
+
+    send(
+        &mut socket,
+        json!({ "type": "create", "request_id": "1", "content": "Water the plants" }),
+    )
+    .await;
+    let ack = next_message(&mut socket, "ack").await;
+    assert_eq!(ack["request_id"], "1");
+
+    let response = reqwest::Client::new()
+        .delete(format!("{}/me/sharing/alice", address))
+        .header("Cookie", format!("csrf_token={}", CSRF_TOKEN))
+        .header("X-CSRF-Token", CSRF_TOKEN)
+        .send()
+        .await
+        .expect("Failed to revoke access");
+    assert!(response.status().is_success(), "{}", response.status());
+
+    // Every mutation is authorized on its own.
+    send(
+        &mut socket,
+        json!({ "type": "create", "request_id": "2", "content": "Mow the lawn" }),
+    )
+    .await;
+    let error = next_message(&mut socket, "error").await;
+    assert_eq!(error["request_id"], "2");
+    assert_eq!(error["status"], 403);
+
+    // Snapshots are only sent after checking access again.
+    send(&mut socket, json!({ "type": "resync" })).await;
+    let close = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match socket.next().await {
+                Some(Ok(Message::Close(frame))) => return frame,
+                Some(Ok(_)) => continue,
+                other => panic!("Socket ended without closing: {:?}", other),
+            }
+        }
+    })
+    .await
+    .expect("Socket was not closed");
+    assert_eq!(close.expect("No close frame").code, CloseCode::Policy);
+}